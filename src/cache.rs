@@ -1,74 +1,673 @@
-use std::{collections::HashMap, hash::Hash, sync::RwLock};
+#[cfg(not(target_arch = "wasm32"))]
+mod disk;
+#[cfg(target_arch = "wasm32")]
+mod local_storage;
+
+#[cfg(target_arch = "wasm32")]
+pub use local_storage::LocalStorageCache;
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::PathBuf;
+use std::{
+    any::Any,
+    collections::HashMap,
+    future::Future,
+    hash::Hash,
+    sync::{
+        Arc, Mutex, RwLock,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, SystemTime},
+};
 
 use anyhow::{Result, anyhow};
 use fancy_regex::Regex;
+use futures::lock::Mutex as AsyncMutex;
 use url::Url;
 
+#[cfg(not(target_arch = "wasm32"))]
+use crate::cache::disk::DiskCache;
+use crate::error::clone_error;
+use crate::utils::now;
+
+struct CacheEntry {
+    value: String,
+    /// Entries without an expiry live as long as the store does.
+    expires_at: Option<SystemTime>,
+    /// Tick of the store's clock at the last access, the entry with the lowest tick is evicted first.
+    last_used: AtomicU64,
+}
+
+impl CacheEntry {
+    fn is_expired(&self) -> bool {
+        self.expires_at
+            .is_some_and(|expires_at| expires_at <= now())
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CacheOptions {
+    /// TTL of entries inserted with `add`, entries never expire when missing.
+    pub default_ttl: Option<Duration>,
+    /// Maximum number of entries before the least recently used ones are evicted.
+    pub max_entries: Option<usize>,
+    /// Maximum total size of the cached values in bytes before the least recently used ones are evicted.
+    pub max_bytes: Option<usize>,
+    /// Directory to persist entries in, so they survive restarts and can be shared between processes.
+    /// Eviction only applies to the entries held in memory.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub dir: Option<PathBuf>,
+}
+
+/// Counters of a `CacheStore` since it was created.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub insertions: u64,
+    /// Entries dropped because they expired or didn't fit into the capacity of the store.
+    pub evictions: u64,
+}
+
+#[derive(Default)]
+struct CacheCounters {
+    hits: AtomicU64,
+    misses: AtomicU64,
+    insertions: AtomicU64,
+    evictions: AtomicU64,
+}
+
+impl CacheCounters {
+    fn record_lookup(&self, hit: bool) {
+        let counter = if hit { &self.hits } else { &self.misses };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn snapshot(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            insertions: self.insertions.load(Ordering::Relaxed),
+            evictions: self.evictions.load(Ordering::Relaxed),
+        }
+    }
+}
+
 pub struct CacheStore<T = String> {
-    cache: RwLock<HashMap<T, String>>,
+    cache: RwLock<HashMap<T, CacheEntry>>,
+    options: CacheOptions,
+    clock: AtomicU64,
+    counters: CacheCounters,
+    #[cfg(not(target_arch = "wasm32"))]
+    disk: Option<DiskCache>,
 }
 
 impl CacheStore {
+    /// Create an unbounded store whose entries never expire.
     pub fn new<T>() -> CacheStore<T> {
+        Self::with_options(CacheOptions::default())
+    }
+
+    /// Create a store whose entries expire `default_ttl` after being added.
+    pub fn with_ttl<T>(default_ttl: Duration) -> CacheStore<T> {
+        Self::with_options(CacheOptions {
+            default_ttl: Some(default_ttl),
+            ..Default::default()
+        })
+    }
+
+    /// Create a store that evicts the least recently used entries once it holds more than
+    /// `max_entries` entries or `max_bytes` bytes of values.
+    pub fn with_capacity<T>(max_entries: Option<usize>, max_bytes: Option<usize>) -> CacheStore<T> {
+        Self::with_options(CacheOptions {
+            max_entries,
+            max_bytes,
+            ..Default::default()
+        })
+    }
+
+    /// Create an unbounded store that persists its entries as files in `dir`.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn persistent<T>(dir: impl Into<PathBuf>) -> CacheStore<T> {
+        Self::with_options(CacheOptions {
+            dir: Some(dir.into()),
+            ..Default::default()
+        })
+    }
+
+    pub fn with_options<T>(options: CacheOptions) -> CacheStore<T> {
         CacheStore {
             cache: Default::default(),
+            #[cfg(not(target_arch = "wasm32"))]
+            disk: options.dir.clone().map(DiskCache::new),
+            options,
+            clock: AtomicU64::new(0),
+            counters: Default::default(),
         }
     }
 }
 
 pub trait CacheAccess<T> {
     fn add(&self, key: T, value: String) -> Result<()>;
+    /// Add an entry that is treated as missing once `ttl` has passed, overriding the default TTL of the store.
+    fn insert_with_ttl(&self, key: T, value: String, ttl: Duration) -> Result<()>;
     fn contains(&self, key: &T) -> Result<bool>;
     fn get(&self, key: &T) -> Result<Option<String>>;
+    /// Number of entries in the store, including expired ones that weren't cleaned up yet.
+    fn len(&self) -> Result<usize>;
+    fn is_empty(&self) -> Result<bool>;
+    /// Total size of the cached values in bytes.
+    fn approx_bytes(&self) -> Result<usize>;
+    fn stats(&self) -> CacheStats;
+    /// Remove the entry of `key`, returns whether there was one.
+    fn invalidate(&self, key: &T) -> Result<bool>;
+    /// Remove every entry, including the ones persisted to disk.
+    fn clear(&self) -> Result<()>;
+}
+
+/// Storage the player and code caches are kept in.
+///
+/// Implement this to back the caches with something like Redis, so multiple workers share
+/// one copy of the player instead of each downloading it, and pass it to `Tydle::with_cache_backend`.
+pub trait CacheBackend {
+    fn get(&self, key: &str) -> Result<Option<String>>;
+    fn insert(&self, key: &str, value: String) -> Result<()>;
+    /// Remove the entry of `key`, returns whether there was one.
+    fn remove(&self, key: &str) -> Result<bool>;
+    fn clear(&self) -> Result<()>;
+    /// Counters of the backend, backends that don't track them can keep the default of all zeroes.
+    fn stats(&self) -> CacheStats {
+        CacheStats::default()
+    }
+}
+
+impl CacheBackend for CacheStore {
+    fn get(&self, key: &str) -> Result<Option<String>> {
+        CacheAccess::get(self, &key.to_string())
+    }
+
+    fn insert(&self, key: &str, value: String) -> Result<()> {
+        self.add(key.to_string(), value)
+    }
+
+    fn remove(&self, key: &str) -> Result<bool> {
+        self.invalidate(&key.to_string())
+    }
+
+    fn clear(&self) -> Result<()> {
+        CacheAccess::clear(self)
+    }
+
+    fn stats(&self) -> CacheStats {
+        CacheAccess::stats(self)
+    }
+}
+
+struct TypedEntry {
+    value: Arc<dyn Any + Send + Sync>,
+    expires_at: Option<SystemTime>,
+}
+
+/// In-memory cache of values that were already parsed, so a cache hit doesn't have to parse a string again.
+///
+/// Values of any type can be stored. Getting a value as another type than it was inserted as is a miss.
+pub struct TypedCache<K = String> {
+    cache: RwLock<HashMap<K, TypedEntry>>,
+    default_ttl: Option<Duration>,
+    counters: CacheCounters,
+}
+
+impl<K> Default for TypedCache<K> {
+    fn default() -> Self {
+        Self {
+            cache: Default::default(),
+            default_ttl: None,
+            counters: Default::default(),
+        }
+    }
+}
+
+impl<K> TypedCache<K>
+where
+    K: Eq + Hash,
+{
+    /// Create a cache whose entries never expire.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Create a cache whose entries expire `default_ttl` after being inserted.
+    pub fn with_ttl(default_ttl: Duration) -> Self {
+        Self {
+            default_ttl: Some(default_ttl),
+            ..Default::default()
+        }
+    }
+
+    pub fn get<V: Any + Send + Sync>(&self, key: &K) -> Result<Option<Arc<V>>> {
+        let cache = self.cache.read().map_err(|e| anyhow!(e.to_string()))?;
+
+        let value = match cache.get(key) {
+            Some(entry)
+                if entry
+                    .expires_at
+                    .is_some_and(|expires_at| expires_at <= now()) =>
+            {
+                drop(cache);
+                self.cache
+                    .write()
+                    .map_err(|e| anyhow!(e.to_string()))?
+                    .remove(key);
+                self.counters.evictions.fetch_add(1, Ordering::Relaxed);
+                None
+            }
+            Some(entry) => entry.value.clone().downcast::<V>().ok(),
+            None => None,
+        };
+        self.counters.record_lookup(value.is_some());
+
+        Ok(value)
+    }
+
+    /// Insert `value` and return it as it's shared with the cache.
+    pub fn insert<V: Any + Send + Sync>(&self, key: K, value: V) -> Result<Arc<V>> {
+        self.insert_entry(key, value, self.default_ttl)
+    }
+
+    pub fn insert_with_ttl<V: Any + Send + Sync>(
+        &self,
+        key: K,
+        value: V,
+        ttl: Duration,
+    ) -> Result<Arc<V>> {
+        self.insert_entry(key, value, Some(ttl))
+    }
+
+    /// Remove the entry of `key`, returns whether there was one.
+    pub fn invalidate(&self, key: &K) -> Result<bool> {
+        Ok(self
+            .cache
+            .write()
+            .map_err(|e| anyhow!(e.to_string()))?
+            .remove(key)
+            .is_some())
+    }
+
+    pub fn clear(&self) -> Result<()> {
+        self.cache
+            .write()
+            .map_err(|e| anyhow!(e.to_string()))?
+            .clear();
+
+        Ok(())
+    }
+
+    pub fn len(&self) -> Result<usize> {
+        Ok(self.cache.read().map_err(|e| anyhow!(e.to_string()))?.len())
+    }
+
+    pub fn is_empty(&self) -> Result<bool> {
+        Ok(self.len()? == 0)
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        self.counters.snapshot()
+    }
+
+    fn insert_entry<V: Any + Send + Sync>(
+        &self,
+        key: K,
+        value: V,
+        ttl: Option<Duration>,
+    ) -> Result<Arc<V>> {
+        let value = Arc::new(value);
+        let entry = TypedEntry {
+            value: value.clone(),
+            expires_at: ttl.map(|ttl| now() + ttl),
+        };
+
+        self.cache
+            .write()
+            .map_err(|e| anyhow!(e.to_string()))?
+            .insert(key, entry);
+        self.counters.insertions.fetch_add(1, Ordering::Relaxed);
+
+        Ok(value)
+    }
+}
+
+type FlightSlot = Arc<AsyncMutex<Option<Result<String, Arc<anyhow::Error>>>>>;
+
+/// Deduplicates concurrent fetches of the same key, so a cold cache only downloads the player once
+/// even when many extractions need it at the same time.
+#[derive(Default)]
+pub struct SingleFlight {
+    in_flight: Mutex<HashMap<String, FlightSlot>>,
+}
+
+/// Removes the key from the in-flight map when the fetch completes or gets cancelled.
+struct FlightGuard<'a> {
+    single_flight: &'a SingleFlight,
+    key: &'a str,
+}
+
+impl Drop for FlightGuard<'_> {
+    fn drop(&mut self) {
+        if let Ok(mut in_flight) = self.single_flight.in_flight.lock() {
+            in_flight.remove(self.key);
+        }
+    }
+}
+
+impl SingleFlight {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Run `fetch` for `key` unless a fetch for it is already in progress,
+    /// in which case its result (or error) is awaited and returned instead.
+    pub async fn run<F, Fut>(&self, key: &str, fetch: F) -> Result<String>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<String>>,
+    {
+        let (slot, leader_guard) = {
+            let mut in_flight = self.in_flight.lock().map_err(|e| anyhow!(e.to_string()))?;
+
+            match in_flight.get(key) {
+                Some(slot) => (slot.clone(), None),
+                None => {
+                    let slot: FlightSlot = Default::default();
+                    // The slot was just created, so locking it can't fail. It's locked before being
+                    // shared so no other caller can observe it empty while the fetch is running.
+                    let guard = slot.try_lock_owned();
+                    in_flight.insert(key.to_string(), slot.clone());
+                    (slot, guard)
+                }
+            }
+        };
+
+        let Some(mut leader_guard) = leader_guard else {
+            let shared_result = slot.lock().await;
+
+            return match shared_result.as_ref() {
+                Some(Ok(value)) => Ok(value.clone()),
+                Some(Err(e)) => Err(clone_error(e)),
+                // The fetch was cancelled before finishing, so it's done again here.
+                None => fetch().await,
+            };
+        };
+
+        let _flight_guard = FlightGuard {
+            single_flight: self,
+            key,
+        };
+
+        // The leader keeps the original error, waiters get a copy that can still be downcast.
+        let result = fetch().await;
+        *leader_guard = Some(match &result {
+            Ok(value) => Ok(value.clone()),
+            Err(e) => Err(Arc::new(clone_error(e))),
+        });
+
+        result
+    }
 }
 
 pub trait PlayerCacheHandle {
-    fn get_player_id_and_path(&self, player_url: &String) -> Result<(String, String)>;
-    fn extract_player_info(&self, player_url: &String) -> Result<String>;
-    // fn store_player_data_from_cache(
-    //     &mut self,
-    //     name: &str,
-    //     player_url: String,
-    //     data: String,
-    // ) -> Result<()>;
-    fn player_js_cache_key(&self, player_url: &String) -> Result<String>;
+    fn get_player_id_and_path(&self, player_url: &str) -> Result<(String, String)>;
+    fn extract_player_info(&self, player_url: &str) -> Result<String>;
+    fn player_js_cache_key(&self, player_url: &str) -> Result<String>;
     fn load_player_data_from_cache(&self, name: &str, player_url: String)
     -> Result<Option<String>>;
+    fn store_player_data_to_cache(
+        &self,
+        name: &str,
+        player_url: String,
+        data: String,
+    ) -> Result<()>;
 }
 
 impl<T> CacheAccess<T> for CacheStore<T>
 where
-    T: Eq + Hash,
+    T: Eq + Hash + Clone + AsRef<str>,
 {
     fn get(&self, key: &T) -> Result<Option<String>> {
-        Ok(self
+        let value = self.lookup(key)?;
+        self.counters.record_lookup(value.is_some());
+
+        Ok(value)
+    }
+
+    fn add(&self, key: T, value: String) -> Result<()> {
+        self.insert_entry(key, value, self.options.default_ttl)
+    }
+
+    fn insert_with_ttl(&self, key: T, value: String, ttl: Duration) -> Result<()> {
+        self.insert_entry(key, value, Some(ttl))
+    }
+
+    fn contains(&self, key: &T) -> Result<bool> {
+        Ok(self.get(key)?.is_some())
+    }
+
+    fn len(&self) -> Result<usize> {
+        Ok(self.cache.read().map_err(|e| anyhow!(e.to_string()))?.len())
+    }
+
+    fn is_empty(&self) -> Result<bool> {
+        Ok(self.len()? == 0)
+    }
+
+    fn invalidate(&self, key: &T) -> Result<bool> {
+        #[allow(unused_mut)]
+        let mut removed = self
             .cache
-            .read()
+            .write()
             .map_err(|e| anyhow!(e.to_string()))?
-            .get(key)
-            .cloned())
+            .remove(key)
+            .is_some();
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(disk) = &self.disk {
+            removed |= disk.remove(&DiskCache::key_digest(key.as_ref()));
+        }
+
+        Ok(removed)
     }
 
-    fn add(&self, key: T, value: String) -> Result<()> {
+    fn clear(&self) -> Result<()> {
         self.cache
             .write()
             .map_err(|e| anyhow!(e.to_string()))?
-            .insert(key, value);
+            .clear();
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(disk) = &self.disk {
+            disk.clear()?;
+        }
 
         Ok(())
     }
 
-    fn contains(&self, key: &T) -> Result<bool> {
+    fn stats(&self) -> CacheStats {
+        self.counters.snapshot()
+    }
+
+    fn approx_bytes(&self) -> Result<usize> {
         Ok(self
             .cache
             .read()
             .map_err(|e| anyhow!(e.to_string()))?
-            .contains_key(key))
+            .values()
+            .map(|entry| entry.value.len())
+            .sum())
     }
 }
 
-impl PlayerCacheHandle for CacheStore<(String, String)> {
-    fn extract_player_info(&self, player_url: &String) -> Result<String> {
+impl<T> CacheStore<T>
+where
+    T: Eq + Hash + Clone + AsRef<str>,
+{
+    fn lookup(&self, key: &T) -> Result<Option<String>> {
+        let cache = self.cache.read().map_err(|e| anyhow!(e.to_string()))?;
+
+        match cache.get(key) {
+            Some(entry) if entry.is_expired() => {
+                drop(cache);
+                self.remove_expired(key)?;
+                Ok(None)
+            }
+            Some(entry) => {
+                entry.last_used.store(self.tick(), Ordering::Relaxed);
+                Ok(Some(entry.value.clone()))
+            }
+            None => {
+                drop(cache);
+                self.load_from_disk(key)
+            }
+        }
+    }
+
+    fn tick(&self) -> u64 {
+        self.clock.fetch_add(1, Ordering::Relaxed)
+    }
+
+    fn insert_entry(&self, key: T, value: String, ttl: Option<Duration>) -> Result<()> {
+        let expires_at = ttl.map(|ttl| now() + ttl);
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(disk) = &self.disk
+            && let Err(_e) = disk.write(&DiskCache::key_digest(key.as_ref()), &value, expires_at)
+        {
+            #[cfg(feature = "logging")]
+            log::warn!("Failed to persist cache entry: {}", _e);
+        }
+
+        let entry = CacheEntry {
+            value,
+            expires_at,
+            last_used: AtomicU64::new(self.tick()),
+        };
+
+        self.counters.insertions.fetch_add(1, Ordering::Relaxed);
+        self.insert_into_memory(key, entry)
+    }
+
+    fn insert_into_memory(&self, key: T, entry: CacheEntry) -> Result<()> {
+        let mut cache = self.cache.write().map_err(|e| anyhow!(e.to_string()))?;
+        cache.insert(key, entry);
+        self.evict(&mut cache);
+
+        Ok(())
+    }
+
+    /// Drop expired entries, then the least recently used ones until the store fits its capacity again.
+    fn evict(&self, cache: &mut HashMap<T, CacheEntry>) {
+        if self.options.max_entries.is_none() && self.options.max_bytes.is_none() {
+            return;
+        }
+
+        let entry_count = cache.len();
+        cache.retain(|_, entry| !entry.is_expired());
+        let mut evicted = entry_count - cache.len();
+
+        let mut total_bytes: usize = cache.values().map(|entry| entry.value.len()).sum();
+
+        loop {
+            let over_entries = self
+                .options
+                .max_entries
+                .is_some_and(|max_entries| cache.len() > max_entries);
+            let over_bytes = self
+                .options
+                .max_bytes
+                .is_some_and(|max_bytes| total_bytes > max_bytes);
+
+            if !over_entries && !over_bytes {
+                break;
+            }
+
+            // Ticks are unique, so this removes exactly one entry.
+            let Some(coldest) = cache
+                .values()
+                .map(|entry| entry.last_used.load(Ordering::Relaxed))
+                .min()
+            else {
+                break;
+            };
+
+            cache.retain(|_, entry| {
+                if entry.last_used.load(Ordering::Relaxed) == coldest {
+                    total_bytes -= entry.value.len();
+                    return false;
+                }
+
+                true
+            });
+            evicted += 1;
+        }
+
+        self.counters
+            .evictions
+            .fetch_add(evicted as u64, Ordering::Relaxed);
+    }
+
+    /// Lazily drop an entry found to be expired on access.
+    /// Checked again under the write lock since it might have been replaced in the meantime.
+    fn remove_expired(&self, key: &T) -> Result<()> {
+        let mut cache = self.cache.write().map_err(|e| anyhow!(e.to_string()))?;
+
+        if cache.get(key).is_some_and(|entry| entry.is_expired()) {
+            cache.remove(key);
+            self.counters.evictions.fetch_add(1, Ordering::Relaxed);
+
+            #[cfg(not(target_arch = "wasm32"))]
+            if let Some(disk) = &self.disk {
+                disk.remove(&DiskCache::key_digest(key.as_ref()));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Look up an entry missing in memory on disk, and keep it in memory if found.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn load_from_disk(&self, key: &T) -> Result<Option<String>> {
+        let Some(disk) = &self.disk else {
+            return Ok(None);
+        };
+
+        let key_digest = DiskCache::key_digest(key.as_ref());
+        let Some((value, expires_at)) = disk.read(&key_digest) else {
+            return Ok(None);
+        };
+
+        if expires_at.is_some_and(|expires_at| expires_at <= now()) {
+            disk.remove(&key_digest);
+            return Ok(None);
+        }
+
+        self.insert_into_memory(
+            key.clone(),
+            CacheEntry {
+                value: value.clone(),
+                expires_at,
+                last_used: AtomicU64::new(self.tick()),
+            },
+        )?;
+
+        Ok(Some(value))
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn load_from_disk(&self, _key: &T) -> Result<Option<String>> {
+        Ok(None)
+    }
+}
+
+impl PlayerCacheHandle for dyn CacheBackend + Send + Sync {
+    fn extract_player_info(&self, player_url: &str) -> Result<String> {
         const PLAYER_INFO_RE: [&str; 3] = [
             r"/s/player/(?P<id>[a-zA-Z0-9_-]{8,})/(?:tv-)?player",
             r"/(?P<id>[a-zA-Z0-9_-]{8,})/player(?:_ias\.vflset(?:/[a-zA-Z]{2,3}_[a-zA-Z]{2,3})?|-plasma-ias-(?:phone|tablet)-[a-z]{2}_[A-Z]{2}\.vflset)/base\.js$",
@@ -77,24 +676,24 @@ impl PlayerCacheHandle for CacheStore<(String, String)> {
 
         for player_info_re in PLAYER_INFO_RE {
             let re = Regex::new(player_info_re)?;
-            if let Ok(Some(caps)) = re.captures(player_url) {
-                if let Some(matched) = caps.name("id") {
-                    return Ok(matched.as_str().to_string());
-                }
+            if let Ok(Some(caps)) = re.captures(player_url)
+                && let Some(matched) = caps.name("id")
+            {
+                return Ok(matched.as_str().to_string());
             }
         }
 
         Err(anyhow!("Cannot identify player: {}", player_url))
     }
 
-    fn get_player_id_and_path(&self, player_url: &String) -> Result<(String, String)> {
+    fn get_player_id_and_path(&self, player_url: &str) -> Result<(String, String)> {
         let player_id = self.extract_player_info(player_url)?;
         let player_path = Url::parse(player_url)?.path().to_string();
 
         Ok((player_id, player_path))
     }
 
-    fn player_js_cache_key(&self, player_url: &String) -> Result<String> {
+    fn player_js_cache_key(&self, player_url: &str) -> Result<String> {
         let (player_id, player_path) = self.get_player_id_and_path(player_url)?;
 
         /*
@@ -110,39 +709,152 @@ impl PlayerCacheHandle for CacheStore<(String, String)> {
         name: &str,
         player_url: String,
     ) -> Result<Option<String>> {
-        let cache_id = (
-            format!("youtube-{}", name),
-            self.player_js_cache_key(&player_url)?,
+        let cache_id = format!(
+            "youtube-{}:{}",
+            name,
+            self.player_js_cache_key(&player_url)?
         );
 
-        if let Some(data) = self
-            .cache
-            .read()
-            .map_err(|e| anyhow!(e.to_string()))?
-            .get(&cache_id)
-        {
-            return Ok(Some(data.clone()));
+        self.get(&cache_id)
+    }
+
+    fn store_player_data_to_cache(
+        &self,
+        name: &str,
+        player_url: String,
+        data: String,
+    ) -> Result<()> {
+        let cache_id = format!(
+            "youtube-{}:{}",
+            name,
+            self.player_js_cache_key(&player_url)?
+        );
+
+        self.insert(&cache_id, data)
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use std::sync::atomic::AtomicUsize;
+
+    use super::*;
+    use crate::error::VideoUnavailable;
+
+    #[tokio::test]
+    async fn single_flight_runs_concurrent_fetches_once() {
+        let single_flight = SingleFlight::new();
+        let fetches = AtomicUsize::new(0);
+
+        let results = futures::future::join_all((0..10).map(|_| {
+            single_flight.run("player", || async {
+                fetches.fetch_add(1, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                Ok("var a = 1;".to_string())
+            })
+        }))
+        .await;
+
+        assert_eq!(fetches.load(Ordering::SeqCst), 1);
+        for result in results {
+            assert_eq!(result.unwrap(), "var a = 1;");
         }
+    }
 
-        Ok(None)
+    #[tokio::test]
+    async fn single_flight_shares_typed_errors() {
+        let single_flight = SingleFlight::new();
+        let fetches = AtomicUsize::new(0);
+
+        let results = futures::future::join_all((0..5).map(|_| {
+            single_flight.run("player", || async {
+                fetches.fetch_add(1, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                Err(anyhow::Error::new(VideoUnavailable::Private {
+                    reason: "This video is private.".into(),
+                })
+                .context("Failed to extract the manifest."))
+            })
+        }))
+        .await;
+
+        assert_eq!(fetches.load(Ordering::SeqCst), 1);
+        for result in results {
+            let error = result.unwrap_err();
+
+            assert_eq!(error.to_string(), "Failed to extract the manifest.");
+            assert!(matches!(
+                error.downcast_ref::<VideoUnavailable>(),
+                Some(VideoUnavailable::Private { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn stats_count_hits_misses_insertions_and_evictions() {
+        let store: CacheStore = CacheStore::with_capacity(Some(1), None);
+
+        assert_eq!(CacheAccess::get(&store, &"a".to_string()).unwrap(), None);
+        store.add("a".into(), "1".into()).unwrap();
+        assert_eq!(
+            CacheAccess::get(&store, &"a".to_string())
+                .unwrap()
+                .as_deref(),
+            Some("1")
+        );
+        store.add("b".into(), "2".into()).unwrap();
+
+        assert_eq!(
+            CacheAccess::stats(&store),
+            CacheStats {
+                hits: 1,
+                misses: 1,
+                insertions: 2,
+                evictions: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn recently_read_entries_survive_eviction() {
+        let store: CacheStore = CacheStore::with_capacity(Some(2), None);
+        store.add("a".into(), "1".into()).unwrap();
+        store.add("b".into(), "2".into()).unwrap();
+
+        CacheAccess::get(&store, &"a".to_string()).unwrap();
+        store.add("c".into(), "3".into()).unwrap();
+
+        assert!(store.contains(&"a".into()).unwrap());
+        assert!(!store.contains(&"b".into()).unwrap());
+        assert!(store.contains(&"c".into()).unwrap());
+        assert_eq!(store.len().unwrap(), 2);
     }
 
-    // fn store_player_data_from_cache(
-    //     &mut self,
-    //     name: &str,
-    //     player_url: String,
-    //     data: String,
-    // ) -> Result<()> {
-    //     let cache_id = (
-    //         format!("youtube-{}", name),
-    //         self.player_js_cache_key(&player_url)?,
-    //     );
-
-    //     if !self.cache.contains_key(&cache_id) {
-    //         self.cache.insert(cache_id, data);
-    //         return Ok(());
-    //     }
-
-    //     Ok(())
-    // }
+    #[test]
+    fn byte_capacity_evicts_the_least_recently_used_entries() {
+        let store: CacheStore = CacheStore::with_capacity(None, Some(10));
+        store.add("a".into(), "aaaa".into()).unwrap();
+        store.add("b".into(), "bbbb".into()).unwrap();
+
+        CacheAccess::get(&store, &"a".to_string()).unwrap();
+        store.add("c".into(), "cccccc".into()).unwrap();
+
+        assert!(store.contains(&"a".into()).unwrap());
+        assert!(!store.contains(&"b".into()).unwrap());
+        assert!(store.contains(&"c".into()).unwrap());
+        assert_eq!(store.approx_bytes().unwrap(), 10);
+        assert_eq!(CacheAccess::stats(&store).evictions, 1);
+    }
+
+    #[test]
+    fn typed_cache_wrong_type_is_a_miss() {
+        let cache: TypedCache = TypedCache::new();
+        cache.insert("plan".to_string(), vec![1u8, 2, 3]).unwrap();
+
+        assert!(cache.get::<String>(&"plan".to_string()).unwrap().is_none());
+        assert_eq!(
+            *cache.get::<Vec<u8>>(&"plan".to_string()).unwrap().unwrap(),
+            [1, 2, 3]
+        );
+    }
 }
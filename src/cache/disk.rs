@@ -0,0 +1,190 @@
+use std::{
+    path::PathBuf,
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+
+/// On-disk representation of a cache entry.
+#[derive(Serialize, Deserialize)]
+struct PersistedEntry {
+    /// Digest of the cache key, to detect files that were renamed or mixed up.
+    key: String,
+    /// SHA1 of `value`, to detect truncated or otherwise corrupted files.
+    checksum: String,
+    /// Expiry as seconds since the unix epoch, missing for entries that never expire.
+    expires_at: Option<u64>,
+    value: String,
+}
+
+fn sha1_hex(data: &[u8]) -> String {
+    format!("{:x}", Sha1::digest(data))
+}
+
+/// Directory of cache entries stored as one JSON file per key.
+pub(crate) struct DiskCache {
+    dir: PathBuf,
+    temp_counter: AtomicU64,
+}
+
+impl DiskCache {
+    pub fn new(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            temp_counter: AtomicU64::new(0),
+        }
+    }
+
+    /// SHA1 of the UTF-8 bytes of `key`, so file names stay the same across processes and versions.
+    pub fn key_digest(key: &str) -> String {
+        sha1_hex(key.as_bytes())
+    }
+
+    fn entry_path(&self, key_digest: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", key_digest))
+    }
+
+    /// Read the value and expiry stored for `key_digest`.
+    /// Corrupted files are removed and treated as missing, so the value gets fetched again.
+    pub fn read(&self, key_digest: &str) -> Option<(String, Option<SystemTime>)> {
+        let path = self.entry_path(key_digest);
+        let contents = std::fs::read_to_string(&path).ok()?;
+
+        match serde_json::from_str::<PersistedEntry>(&contents) {
+            Ok(entry)
+                if entry.key == key_digest
+                    && entry.checksum == sha1_hex(entry.value.as_bytes()) =>
+            {
+                // An expiry too far out to represent is treated as never expiring.
+                let expires_at = entry
+                    .expires_at
+                    .and_then(|expires_at| UNIX_EPOCH.checked_add(Duration::from_secs(expires_at)));
+
+                Some((entry.value, expires_at))
+            }
+            _ => {
+                #[cfg(feature = "logging")]
+                log::warn!("Removing corrupted cache file {}.", path.display());
+
+                let _ = std::fs::remove_file(&path);
+                None
+            }
+        }
+    }
+
+    /// Write an entry to a temporary file first and then rename it,
+    /// so other processes sharing the directory never read a partially written file.
+    pub fn write(
+        &self,
+        key_digest: &str,
+        value: &str,
+        expires_at: Option<SystemTime>,
+    ) -> Result<()> {
+        let entry = PersistedEntry {
+            key: key_digest.to_string(),
+            checksum: sha1_hex(value.as_bytes()),
+            expires_at: expires_at
+                .and_then(|expires_at| expires_at.duration_since(UNIX_EPOCH).ok())
+                .map(|expires_at| expires_at.as_secs()),
+            value: value.to_string(),
+        };
+
+        std::fs::create_dir_all(&self.dir)?;
+
+        let temp_path = self.dir.join(format!(
+            "{}.{}.{}.tmp",
+            key_digest,
+            std::process::id(),
+            self.temp_counter.fetch_add(1, Ordering::Relaxed)
+        ));
+
+        std::fs::write(&temp_path, serde_json::to_string(&entry)?)?;
+        std::fs::rename(&temp_path, self.entry_path(key_digest))?;
+
+        Ok(())
+    }
+
+    /// Remove the file of `key_digest`, returns whether there was one.
+    pub fn remove(&self, key_digest: &str) -> bool {
+        std::fs::remove_file(self.entry_path(key_digest)).is_ok()
+    }
+
+    /// Remove every entry file in the directory, leaving anything else in it alone.
+    pub fn clear(&self) -> Result<()> {
+        let entries = match std::fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e.into()),
+        };
+
+        for entry in entries {
+            let path = entry?.path();
+
+            if path
+                .extension()
+                .is_some_and(|extension| extension == "json")
+            {
+                let _ = std::fs::remove_file(path);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::{CacheAccess, CacheStore};
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("tydle-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn key_digest_is_the_sha1_of_the_key() {
+        assert_eq!(
+            DiskCache::key_digest("abc"),
+            "a9993e364706816aba3e25717850c26c9cd0d89d"
+        );
+    }
+
+    #[test]
+    fn entries_survive_a_new_store() {
+        let dir = temp_dir("disk-reload");
+
+        let store: CacheStore = CacheStore::persistent(&dir);
+        store.add("player".into(), "var a = 1;".into()).unwrap();
+
+        let reopened: CacheStore = CacheStore::persistent(&dir);
+        let value = reopened.get(&"player".to_string()).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(value.as_deref(), Some("var a = 1;"));
+    }
+
+    #[test]
+    fn corrupted_files_are_misses_and_removed() {
+        let dir = temp_dir("disk-corrupt");
+
+        let store: CacheStore = CacheStore::persistent(&dir);
+        store.add("player".into(), "var a = 1;".into()).unwrap();
+
+        let path = dir.join(format!("{}.json", DiskCache::key_digest("player")));
+        let contents = std::fs::read_to_string(&path).unwrap();
+        std::fs::write(&path, contents.replace("var a = 1;", "var a = 2;")).unwrap();
+
+        let reopened: CacheStore = CacheStore::persistent(&dir);
+        let value = reopened.get(&"player".to_string()).unwrap();
+        let still_exists = path.exists();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(value, None);
+        assert!(!still_exists);
+    }
+}
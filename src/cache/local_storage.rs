@@ -0,0 +1,255 @@
+use std::{
+    sync::atomic::Ordering,
+    time::{Duration, UNIX_EPOCH},
+};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::Storage;
+
+use crate::{
+    cache::{CacheAccess, CacheBackend, CacheCounters, CacheOptions, CacheStats, CacheStore},
+    utils::now,
+};
+
+const KEY_PREFIX: &str = "tydle:";
+/// Bump when the format of stored entries changes, entries of other versions are removed on startup.
+const FORMAT_VERSION: u32 = 1;
+
+/// Representation of a cache entry in `localStorage`.
+#[derive(Serialize, Deserialize)]
+struct StoredEntry {
+    /// Seconds since the unix epoch, the oldest entries are evicted first once the size cap is reached.
+    stored_at: u64,
+    /// Expiry as seconds since the unix epoch, missing for entries that never expire.
+    expires_at: Option<u64>,
+    value: String,
+}
+
+impl StoredEntry {
+    fn is_expired(&self, now_secs: u64) -> bool {
+        self.expires_at
+            .is_some_and(|expires_at| expires_at <= now_secs)
+    }
+}
+
+fn now_secs() -> u64 {
+    now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Get `localStorage` if the environment has it, Node.js and web workers don't.
+fn local_storage() -> Option<Storage> {
+    // Some private browsing modes throw on accessing it rather than leaving it undefined.
+    js_sys::Reflect::get(&js_sys::global(), &JsValue::from_str("localStorage"))
+        .ok()?
+        .dyn_into::<Storage>()
+        .ok()
+}
+
+fn storage_keys(storage: &Storage) -> Vec<String> {
+    let length = storage.length().unwrap_or_default();
+
+    (0..length)
+        .filter_map(|index| storage.key(index).ok().flatten())
+        .collect()
+}
+
+/// Cache backend for browsers that persists its entries in `localStorage`, so the downloaded player
+/// survives page loads. Used by default when compiled to WebAssembly.
+///
+/// Entries are kept in memory as well, which is all that's used when `localStorage` is unavailable
+/// or full, like outside of browsers or in some private browsing modes.
+pub struct LocalStorageCache {
+    prefix: String,
+    default_ttl: Option<Duration>,
+    max_bytes: usize,
+    memory: CacheStore,
+    counters: CacheCounters,
+}
+
+impl LocalStorageCache {
+    /// Create a cache that keeps its entries under `namespace`, with at most `max_bytes` of values in `localStorage`.
+    /// Values larger than that are only cached in memory.
+    pub fn new(namespace: &str, default_ttl: Option<Duration>, max_bytes: usize) -> Self {
+        let cache = Self {
+            prefix: format!("{}v{}:{}:", KEY_PREFIX, FORMAT_VERSION, namespace),
+            default_ttl,
+            max_bytes,
+            memory: CacheStore::with_options(CacheOptions {
+                default_ttl,
+                ..Default::default()
+            }),
+            counters: Default::default(),
+        };
+
+        if let Some(storage) = local_storage() {
+            cache.remove_outdated_entries(&storage);
+        }
+
+        cache
+    }
+
+    fn storage_key(&self, key: &str) -> String {
+        format!("{}{}", self.prefix, key)
+    }
+
+    /// Remove the entries written by versions with another format.
+    fn remove_outdated_entries(&self, storage: &Storage) {
+        let current_prefix = format!("{}v{}:", KEY_PREFIX, FORMAT_VERSION);
+
+        for key in storage_keys(storage) {
+            if key.starts_with(KEY_PREFIX) && !key.starts_with(&current_prefix) {
+                let _ = storage.remove_item(&key);
+            }
+        }
+    }
+
+    fn read(&self, storage: &Storage, key: &str) -> Option<StoredEntry> {
+        let storage_key = self.storage_key(key);
+        let contents = storage.get_item(&storage_key).ok()??;
+
+        match serde_json::from_str::<StoredEntry>(&contents) {
+            Ok(entry) if !entry.is_expired(now_secs()) => Some(entry),
+            _ => {
+                let _ = storage.remove_item(&storage_key);
+                None
+            }
+        }
+    }
+
+    fn write(&self, storage: &Storage, key: &str, value: &str) {
+        if value.len() > self.max_bytes {
+            return;
+        }
+
+        self.make_room(storage, value.len());
+
+        let now_secs = now_secs();
+        let entry = StoredEntry {
+            stored_at: now_secs,
+            expires_at: self.default_ttl.map(|ttl| now_secs + ttl.as_secs()),
+            value: value.to_string(),
+        };
+
+        let Ok(contents) = serde_json::to_string(&entry) else {
+            return;
+        };
+
+        // Fails when the origin's quota is exhausted, the entry then only lives in memory.
+        if let Err(_e) = storage.set_item(&self.storage_key(key), &contents) {
+            #[cfg(feature = "logging")]
+            log::warn!("Failed to persist cache entry to localStorage: {:?}", _e);
+        }
+    }
+
+    /// Drop expired entries, then the oldest ones until `incoming_bytes` more fit into `max_bytes`.
+    fn make_room(&self, storage: &Storage, incoming_bytes: usize) {
+        let now_secs = now_secs();
+        let mut entries = Vec::new();
+
+        for key in storage_keys(storage) {
+            if !key.starts_with(&self.prefix) {
+                continue;
+            }
+
+            let Some(entry) = storage
+                .get_item(&key)
+                .ok()
+                .flatten()
+                .and_then(|contents| serde_json::from_str::<StoredEntry>(&contents).ok())
+                .filter(|entry| !entry.is_expired(now_secs))
+            else {
+                let _ = storage.remove_item(&key);
+                self.counters.evictions.fetch_add(1, Ordering::Relaxed);
+                continue;
+            };
+
+            entries.push((entry.stored_at, entry.value.len(), key));
+        }
+
+        entries.sort_unstable();
+
+        let mut total_bytes: usize = entries.iter().map(|(_, size, _)| size).sum();
+
+        for (_, size, key) in entries {
+            if total_bytes + incoming_bytes <= self.max_bytes {
+                break;
+            }
+
+            let _ = storage.remove_item(&key);
+            self.counters.evictions.fetch_add(1, Ordering::Relaxed);
+            total_bytes -= size;
+        }
+    }
+}
+
+impl CacheBackend for LocalStorageCache {
+    fn get(&self, key: &str) -> Result<Option<String>> {
+        let mut value = self.memory.get(&key.to_string())?;
+
+        if value.is_none()
+            && let Some(storage) = local_storage()
+            && let Some(entry) = self.read(&storage, key)
+        {
+            // Keep the expiry it was stored with, rather than restarting the TTL of the memory store.
+            match entry.expires_at {
+                Some(expires_at) => self.memory.insert_with_ttl(
+                    key.to_string(),
+                    entry.value.clone(),
+                    Duration::from_secs(expires_at.saturating_sub(now_secs())),
+                )?,
+                None => self.memory.add(key.to_string(), entry.value.clone())?,
+            }
+
+            value = Some(entry.value);
+        }
+
+        self.counters.record_lookup(value.is_some());
+
+        Ok(value)
+    }
+
+    fn insert(&self, key: &str, value: String) -> Result<()> {
+        if let Some(storage) = local_storage() {
+            self.write(&storage, key, &value);
+        }
+
+        self.counters.insertions.fetch_add(1, Ordering::Relaxed);
+        self.memory.add(key.to_string(), value)
+    }
+
+    fn remove(&self, key: &str) -> Result<bool> {
+        let mut removed = self.memory.invalidate(&key.to_string())?;
+
+        if let Some(storage) = local_storage() {
+            let storage_key = self.storage_key(key);
+
+            removed |= storage.get_item(&storage_key).ok().flatten().is_some();
+            let _ = storage.remove_item(&storage_key);
+        }
+
+        Ok(removed)
+    }
+
+    fn clear(&self) -> Result<()> {
+        self.memory.clear()?;
+
+        if let Some(storage) = local_storage() {
+            for key in storage_keys(&storage) {
+                if key.starts_with(&self.prefix) {
+                    let _ = storage.remove_item(&key);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn stats(&self) -> CacheStats {
+        self.counters.snapshot()
+    }
+}
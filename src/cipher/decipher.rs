@@ -1,13 +1,27 @@
-use std::sync::Arc;
+#[cfg(test)]
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::{borrow::Cow, collections::HashMap, sync::Arc};
 
-use anyhow::{Result, bail};
+use anyhow::{Context, Result, anyhow, bail};
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+use url::Url;
 
+#[cfg(feature = "js-interp")]
+use crate::cipher::interp;
 use crate::{
-    cache::{CacheAccess, CacheStore, PlayerCacheHandle},
-    cipher::js::SignatureJsHandle,
-    utils::{parse_query_string, replace_n_sig_query_param},
+    cache::{CacheBackend, PlayerCacheHandle, SingleFlight, TypedCache},
+    cipher::{
+        js::{PreprocessedPlayer, SignatureJsHandle},
+        strategy::{SignatureStrategy, StrategyOutput, build_n_script, snippet},
+    },
+    error::DecipherError,
+    tydle::DEFAULT_CACHE_TTL,
+    utils::replace_n_sig_query_param,
+    yt_interface::{SignatureCipher, YtStream, YtStreamSource},
 };
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SignatureType {
     Nsignature,
     Signature,
@@ -20,32 +34,161 @@ impl SignatureType {
             Self::Signature => "sig",
         }
     }
+
+    fn description(&self) -> &str {
+        match self {
+            Self::Nsignature => "n parameter",
+            Self::Signature => "signature",
+        }
+    }
+}
+
+/// The player.js challenges are solved with.
+#[derive(Debug, Clone, Copy)]
+pub enum PlayerSource<'a> {
+    /// The player at this URL, which has been downloaded into the code cache.
+    Url(&'a str),
+    /// The code of a player the caller provided, which is used without any network request.
+    Code(&'a str),
+}
+
+/// Put the deciphered signature into the stream URL under the parameter named by `sp`, `signature` if missing,
+/// percent-encoding characters like `=` it can contain.
+///
+/// A signature the URL already carries is replaced rather than appended a second time,
+/// which YouTube rejects for some itags only.
+fn append_signature(signature_cipher: &SignatureCipher, signature: &str) -> Result<String> {
+    let mut url = Url::parse(&signature_cipher.url)?;
+    let signature_param = signature_cipher.signature_param();
+
+    // Rebuilding the query also drops the empty pair of a query ending with `&`.
+    let query_pairs: Vec<(String, String)> = url
+        .query_pairs()
+        .into_owned()
+        .filter(|(key, _)| {
+            !key.is_empty() && key != "sig" && key != "signature" && key != signature_param
+        })
+        .collect();
+    url.query_pairs_mut()
+        .clear()
+        .extend_pairs(query_pairs)
+        .append_pair(signature_param, signature);
+
+    Ok(url.to_string())
+}
+
+fn get_n_param(url: &str) -> Result<Option<String>> {
+    Ok(Url::parse(url)?
+        .query_pairs()
+        .find(|(key, _)| key == "n")
+        .map(|(_, n)| n.into_owned()))
+}
+
+/// Bump when the format of exported plans changes, plans of other versions are ignored on import.
+const PLAN_FORMAT_VERSION: u32 = 1;
+
+/// Everything extracted from a player to decipher with it, see `SignatureDecipherHandle::export_plan`.
+#[derive(Serialize, Deserialize)]
+struct DecipherPlan {
+    format_version: u32,
+    player_version: String,
+    player_js_cache_key: String,
+    /// SHA1 of `preprocessed`, to detect truncated or otherwise corrupted plans.
+    checksum: String,
+    preprocessed: String,
+    /// Name of the `SignatureStrategy` that worked for the player.
+    signature_strategy: Option<String>,
+}
+
+fn strategy_key(signature_type: SignatureType, player_js_cache_key: &str) -> String {
+    format!("{}-{}", signature_type.as_str(), player_js_cache_key)
+}
+
+fn sha1_hex(data: &[u8]) -> String {
+    format!("{:x}", Sha1::digest(data))
 }
 
 pub struct SignatureDecipher {
-    pub player_cache: Arc<CacheStore<(String, String)>>,
-    pub code_cache: Arc<CacheStore>,
+    pub player_cache: Arc<dyn CacheBackend + Send + Sync>,
+    pub code_cache: Arc<dyn CacheBackend + Send + Sync>,
+    /// Players preprocessed by the solver, keyed like the player.js in `code_cache`.
+    pub preprocessed_players: TypedCache,
+    /// Players currently being preprocessed.
+    pub preprocess_flights: SingleFlight,
+    /// The `SignatureStrategy` that last worked for a player and `SignatureType`, tried first for them from then on.
+    /// Keyed by `strategy_key`.
+    pub signature_strategies: TypedCache,
+    /// Keys of the preprocessed players by their version, to export their plans.
+    pub player_keys: TypedCache,
+    /// Times a player.js was parsed, by the solver or to extract the function of a strategy.
+    #[cfg(test)]
+    pub(crate) parses: AtomicUsize,
 }
 
 impl SignatureDecipher {
     pub fn new(
-        player_cache: Arc<CacheStore<(String, String)>>,
-        code_cache: Arc<CacheStore>,
+        player_cache: Arc<dyn CacheBackend + Send + Sync>,
+        code_cache: Arc<dyn CacheBackend + Send + Sync>,
     ) -> Self {
         Self {
             player_cache,
             code_cache,
+            preprocessed_players: TypedCache::with_ttl(DEFAULT_CACHE_TTL),
+            preprocess_flights: SingleFlight::new(),
+            signature_strategies: TypedCache::with_ttl(DEFAULT_CACHE_TTL),
+            player_keys: TypedCache::with_ttl(DEFAULT_CACHE_TTL),
+            #[cfg(test)]
+            parses: AtomicUsize::new(0),
         }
     }
 }
 
 pub trait SignatureDecipherHandle {
-    async fn extract_signature_function(
+    /// Get the player preprocessed by the solver, parsing the player.js only if no
+    /// cache has it, so deciphering many signatures of one player only parses it once.
+    async fn get_preprocessed_player(&self, player_url: String) -> Result<Arc<PreprocessedPlayer>>;
+    /// Solve challenges with a single `SignatureStrategy`.
+    async fn solve_with_strategy(
         &self,
+        strategy: SignatureStrategy,
+        signature_type: SignatureType,
+        challenges: &[String],
+        player: PlayerSource<'_>,
+    ) -> Result<HashMap<String, String>>;
+    /// Solve challenges with the first `SignatureStrategy` that solves all of them.
+    async fn solve_with_strategies(
+        &self,
+        signature_type: SignatureType,
+        challenges: Vec<String>,
+        player: PlayerSource<'_>,
+    ) -> Result<HashMap<String, String>>;
+    /// Put a player.js the caller already has into the code cache, so deciphering with `player_url` doesn't download it.
+    fn seed_player(&self, player_url: String, player_js: String) -> Result<()>;
+    /// Decipher a `signatureCipher` with a player.js the caller provides, without any network request.
+    async fn decipher_with_player_source(&self, signature: &str, player_js: &str)
+    -> Result<String>;
+    /// Transform an `n` parameter with a player.js the caller provides, without any network request.
+    async fn decipher_n_with_player_source(&self, n: &str, player_js: &str) -> Result<String>;
+    /// Serialize what was extracted from the player of `player_version`, so it doesn't have to be parsed
+    /// again after a restart. Fails if nothing was deciphered with that player yet.
+    fn export_plan(&self, player_version: &str) -> Result<Vec<u8>>;
+    /// Restore a plan from `export_plan`. Fails for plans of another player than `player_version`,
+    /// corrupted plans are ignored and return `false`, so the player gets parsed again like without a plan.
+    fn import_plan(&self, bytes: &[u8], player_version: &str) -> Result<bool>;
+    /// Decrypt challenges of one player, only running the solver for the ones that aren't cached.
+    /// Challenges the solver failed on are `None`.
+    async fn decrypt_signatures(
+        &self,
+        signature_type: SignatureType,
+        encrypted_signatures: Vec<String>,
         player_url: String,
-        example_sig: String,
+    ) -> Result<Vec<Option<String>>>;
+    /// Decrypt `(index, challenge, player_url)` triples, solving the challenges of each player in one run.
+    async fn decrypt_indexed(
+        &self,
         signature_type: SignatureType,
-    ) -> Result<String>;
+        challenges: Vec<(usize, String, String)>,
+    ) -> Result<HashMap<usize, String>>;
     async fn decrypt_signature(
         &self,
         signature_type: SignatureType,
@@ -53,27 +196,536 @@ pub trait SignatureDecipherHandle {
         player_url: String,
     ) -> Result<String>;
     async fn decipher(&self, signature: String, player_url: String) -> Result<String>;
+    async fn decipher_cipher(
+        &self,
+        signature_cipher: &SignatureCipher,
+        player_url: String,
+    ) -> Result<String>;
+    /// Decipher `(signature, player_url)` pairs, returning the URLs in the same order.
+    /// Each player is only asked to solve once for all of its signatures.
+    async fn decipher_all(&self, signatures: Vec<(String, String)>) -> Result<Vec<String>>;
+    async fn decipher_ciphers(
+        &self,
+        signature_ciphers: Vec<(SignatureCipher, String)>,
+    ) -> Result<Vec<String>>;
+    /// Transform the `n` parameter of a stream URL with the player's nsig function.
+    /// YouTube throttles downloads of URLs with an untransformed `n`.
+    async fn decipher_n(&self, n: String, player_url: String) -> Result<String>;
+    /// Transform `(n, player_url)` pairs, returning the values in the same order.
+    async fn decipher_n_all(&self, ns: Vec<(String, String)>) -> Result<Vec<String>>;
+    /// Replace the `n` parameter of `url` with its transformed value, returns `url` unchanged if it has none.
+    async fn transform_n_param(&self, url: String, player_url: String) -> Result<String>;
+    /// Replace the `n` parameters of `(url, player_url)` pairs, returning the URLs in the same order.
+    async fn transform_n_params(&self, urls: Vec<(String, String)>) -> Result<Vec<String>>;
+    /// Get the playable URL of a stream, deciphering its signature and `n` parameter as needed.
+    async fn resolve_stream_url(
+        &self,
+        source: &YtStreamSource,
+        player_url: String,
+    ) -> Result<String>;
+    /// Get the playable URLs of streams of one player, in the same order.
+    async fn resolve_stream_urls(
+        &self,
+        streams: &[YtStream],
+        player_url: String,
+    ) -> Result<Vec<String>>;
+    /// Forget the player along with what was learned about it, so it's downloaded and solved anew.
+    fn invalidate_player(&self, player_url: String) -> Result<()>;
+    /// Forget what was deciphered for `source` with the player, so resolving it again solves it anew.
+    fn invalidate_source(&self, source: &YtStreamSource, player_url: String) -> Result<()>;
 }
 
 impl SignatureDecipherHandle for SignatureDecipher {
-    async fn extract_signature_function(
+    async fn get_preprocessed_player(&self, player_url: String) -> Result<Arc<PreprocessedPlayer>> {
+        let player_js_code_key = self.player_cache.player_js_cache_key(&player_url)?;
+
+        if let Some(preprocessed) = self
+            .preprocessed_players
+            .get::<PreprocessedPlayer>(&player_js_code_key)?
+        {
+            return Ok(preprocessed);
+        }
+
+        // Also kept in the code cache, so it's persisted and shared like the player.js and expires with it.
+        let preprocessed_key = format!("preprocessed-{}", player_js_code_key);
+        let preprocessed = self
+            .preprocess_flights
+            .run(&preprocessed_key, || async {
+                if let Some(preprocessed) = self.code_cache.get(&preprocessed_key)? {
+                    return Ok(preprocessed);
+                }
+
+                let Some(code) = self.code_cache.get(&player_js_code_key)? else {
+                    // Downloading the player failed, otherwise the extraction would have cached it.
+                    return Err(DecipherError::PlayerFetchFailed {
+                        url: player_url.to_string(),
+                        status: None,
+                    }
+                    .into());
+                };
+
+                #[cfg(feature = "logging")]
+                log::debug!("Preprocessing player {}.", player_js_code_key);
+
+                #[cfg(test)]
+                self.parses.fetch_add(1, Ordering::SeqCst);
+
+                let preprocessed = self.preprocess_player(code).await?;
+                self.code_cache
+                    .insert(&preprocessed_key, preprocessed.0.clone())?;
+
+                Ok(preprocessed.0)
+            })
+            .await?;
+
+        self.player_keys.insert(
+            self.player_cache.extract_player_info(&player_url)?,
+            player_js_code_key.clone(),
+        )?;
+
+        self.preprocessed_players
+            .insert(player_js_code_key, PreprocessedPlayer(preprocessed))
+    }
+
+    async fn solve_with_strategy(
         &self,
-        player_url: String,
-        example_sig: String,
+        strategy: SignatureStrategy,
         signature_type: SignatureType,
-    ) -> Result<String> {
+        challenges: &[String],
+        player: PlayerSource<'_>,
+    ) -> Result<HashMap<String, String>> {
+        let code = match (strategy, player) {
+            (SignatureStrategy::Solver, PlayerSource::Url(player_url)) => {
+                let preprocessed = self.get_preprocessed_player(player_url.to_string()).await?;
+
+                return self
+                    .solve_challenges(&preprocessed, challenges.to_vec(), signature_type)
+                    .await;
+            }
+            (SignatureStrategy::Solver, PlayerSource::Code(code)) => {
+                // Downloading the solver modules would defeat the point of providing the player.
+                if !self.has_js_modules()? {
+                    bail!("The solver modules are not cached, seed them with `Tydle::seed_solver`.")
+                }
+
+                let preprocessed = self.preprocess_player(code.to_string()).await?;
+
+                return self
+                    .solve_challenges(&preprocessed, challenges.to_vec(), signature_type)
+                    .await;
+            }
+            (_, PlayerSource::Code(code)) => Cow::Borrowed(code),
+            (_, PlayerSource::Url(player_url)) => {
+                let player_js_code_key = self.player_cache.player_js_cache_key(player_url)?;
+                let Some(code) = self.code_cache.get(&player_js_code_key)? else {
+                    // Downloading the player failed, otherwise the extraction would have cached it.
+                    return Err(DecipherError::PlayerFetchFailed {
+                        url: player_url.to_string(),
+                        status: None,
+                    }
+                    .into());
+                };
+
+                Cow::Owned(code)
+            }
+        };
+
+        #[cfg(test)]
+        self.parses.fetch_add(1, Ordering::SeqCst);
+
+        let strategy_script = match signature_type {
+            SignatureType::Signature => strategy.build_script(&code, challenges)?,
+            SignatureType::Nsignature => build_n_script(&code, challenges)?,
+        };
+        let Some(strategy_script) = strategy_script else {
+            bail!(
+                "The {} function was not found.",
+                signature_type.description()
+            )
+        };
+
+        let output = match strategy {
+            #[cfg(feature = "js-interp")]
+            SignatureStrategy::Interpreter => interp::eval(&strategy_script.script)?,
+            _ => self.eval_js(strategy_script.script).await?,
+        };
+        let output: StrategyOutput = serde_json::from_str(&output)?;
+
+        if let Some(op_index) = output.failed_op {
+            return Err(DecipherError::TransformFailed {
+                op_index,
+                op: strategy_script
+                    .operations
+                    .get(op_index)
+                    .cloned()
+                    .unwrap_or_default(),
+            }
+            .into());
+        }
+
+        Ok(output.solved)
+    }
+
+    async fn solve_with_strategies(
+        &self,
+        signature_type: SignatureType,
+        challenges: Vec<String>,
+        player: PlayerSource<'_>,
+    ) -> Result<HashMap<String, String>> {
+        // Only players from a URL have a version to remember the working strategy by.
+        let player_js_code_key = match player {
+            PlayerSource::Url(player_url) => {
+                Some(self.player_cache.player_js_cache_key(player_url)?)
+            }
+            PlayerSource::Code(_) => None,
+        };
+
+        let strategy_key = player_js_code_key
+            .as_deref()
+            .map(|player_js_code_key| strategy_key(signature_type, player_js_code_key));
+        let recorded = match &strategy_key {
+            Some(strategy_key) => self
+                .signature_strategies
+                .get::<SignatureStrategy>(strategy_key)?
+                .map(|strategy| *strategy),
+            None => None,
+        };
+        let candidates = match signature_type {
+            SignatureType::Nsignature => SignatureStrategy::N_PARAM,
+            SignatureType::Signature => SignatureStrategy::ALL,
+        };
+
+        let strategies: Vec<SignatureStrategy> = recorded
+            .into_iter()
+            .chain(
+                candidates
+                    .iter()
+                    .copied()
+                    .filter(|strategy| Some(*strategy) != recorded),
+            )
+            .collect();
+
+        let player_name = player_js_code_key
+            .clone()
+            .unwrap_or_else(|| "provided by the caller".into());
+        let mut failures: Vec<(SignatureStrategy, anyhow::Error)> =
+            Vec::with_capacity(strategies.len());
+
+        for strategy in &strategies {
+            match self
+                .solve_with_strategy(*strategy, signature_type, &challenges, player)
+                .await
+            {
+                Ok(solved) if challenges.iter().all(|c| solved.contains_key(c)) => {
+                    #[cfg(feature = "logging")]
+                    log::debug!(
+                        "Deciphered {} with the {} strategy for player {}.",
+                        signature_type.description(),
+                        strategy.name(),
+                        player_name
+                    );
+
+                    if let Some(strategy_key) = strategy_key {
+                        self.signature_strategies.insert(strategy_key, *strategy)?;
+                    }
+
+                    return Ok(solved);
+                }
+                Ok(_) => failures.push((*strategy, anyhow!("returned no result"))),
+                Err(e) => failures.push((*strategy, e)),
+            }
+        }
+
+        // Errors telling more than that the function wasn't found are returned as they are.
+        if let Some(index) = failures.iter().position(|(_, e)| {
+            matches!(
+                e.downcast_ref::<DecipherError>(),
+                Some(
+                    DecipherError::PlayerFetchFailed { .. } | DecipherError::TransformFailed { .. }
+                )
+            )
+        }) {
+            return Err(failures.swap_remove(index).1);
+        }
+
+        let failures: Vec<String> = failures
+            .iter()
+            .map(|(strategy, e)| format!("{}: {}", strategy.name(), e))
+            .collect();
+
+        if signature_type == SignatureType::Nsignature {
+            bail!(
+                "All {} strategies failed to decipher the {} with player {} ({}).",
+                strategies.len(),
+                signature_type.description(),
+                player_name,
+                failures.join("; ")
+            )
+        }
+
+        #[cfg(feature = "logging")]
+        log::warn!(
+            "All strategies failed to decipher the signature with player {} ({}).",
+            player_name,
+            failures.join("; ")
+        );
+
+        let (player_version, code) = match player {
+            PlayerSource::Url(player_url) => (
+                self.player_cache.extract_player_info(player_url).ok(),
+                player_js_code_key
+                    .map(|player_js_code_key| self.code_cache.get(&player_js_code_key))
+                    .transpose()?
+                    .flatten(),
+            ),
+            PlayerSource::Code(code) => (None, Some(code.to_string())),
+        };
+
+        Err(DecipherError::SigFunctionNotFound {
+            player_version,
+            strategies_tried: strategies
+                .iter()
+                .map(|strategy| strategy.name().to_string())
+                .collect(),
+            snippet: code.as_deref().and_then(snippet),
+        }
+        .into())
+    }
+
+    fn seed_player(&self, player_url: String, player_js: String) -> Result<()> {
         let player_js_code_key = self.player_cache.player_js_cache_key(&player_url)?;
+        self.code_cache.insert(&player_js_code_key, player_js)
+    }
+
+    async fn decipher_with_player_source(
+        &self,
+        signature: &str,
+        player_js: &str,
+    ) -> Result<String> {
+        let signature_cipher: SignatureCipher = signature.parse()?;
+
+        let mut solved = self
+            .solve_with_strategies(
+                SignatureType::Signature,
+                vec![signature_cipher.s.clone()],
+                PlayerSource::Code(player_js),
+            )
+            .await?;
+        let url = append_signature(
+            &signature_cipher,
+            &solved.remove(&signature_cipher.s).unwrap_or_default(),
+        )?;
+
+        let Some(n) = get_n_param(&url)? else {
+            return Ok(url);
+        };
+
+        let deciphered_n = self.decipher_n_with_player_source(&n, player_js).await?;
+        Ok(replace_n_sig_query_param(&url, deciphered_n)?)
+    }
+
+    async fn decipher_n_with_player_source(&self, n: &str, player_js: &str) -> Result<String> {
+        let mut solved = self
+            .solve_with_strategies(
+                SignatureType::Nsignature,
+                vec![n.to_string()],
+                PlayerSource::Code(player_js),
+            )
+            .await?;
+
+        Ok(solved.remove(n).unwrap_or_default())
+    }
+
+    fn export_plan(&self, player_version: &str) -> Result<Vec<u8>> {
+        let Some(player_js_cache_key) = self.player_keys.get::<String>(&player_version.into())?
+        else {
+            bail!(
+                "No signature was deciphered with player {} yet, there is no plan to export.",
+                player_version
+            )
+        };
+
+        let Some(preprocessed) = self
+            .preprocessed_players
+            .get::<PreprocessedPlayer>(&player_js_cache_key)?
+        else {
+            bail!("The plan of player {} has expired.", player_version)
+        };
+
+        let signature_strategy = self
+            .signature_strategies
+            .get::<SignatureStrategy>(&strategy_key(
+                SignatureType::Signature,
+                &player_js_cache_key,
+            ))?
+            .map(|strategy| strategy.name().to_string());
 
-        if let Some(code) = self.code_cache.get(&player_js_code_key)? {
-            let res = self
-                .parse_signature_js(code, example_sig, signature_type)
-                .await?;
-            return Ok(res);
+        Ok(serde_json::to_vec(&DecipherPlan {
+            format_version: PLAN_FORMAT_VERSION,
+            player_version: player_version.to_string(),
+            player_js_cache_key: player_js_cache_key.to_string(),
+            checksum: sha1_hex(preprocessed.0.as_bytes()),
+            preprocessed: preprocessed.0.clone(),
+            signature_strategy,
+        })?)
+    }
+
+    fn import_plan(&self, bytes: &[u8], player_version: &str) -> Result<bool> {
+        let plan = match serde_json::from_slice::<DecipherPlan>(bytes) {
+            Ok(plan)
+                if plan.format_version == PLAN_FORMAT_VERSION
+                    && plan.checksum == sha1_hex(plan.preprocessed.as_bytes()) =>
+            {
+                plan
+            }
+            _ => {
+                #[cfg(feature = "logging")]
+                log::warn!(
+                    "Ignoring corrupted decipher plan for player {}.",
+                    player_version
+                );
+
+                return Ok(false);
+            }
+        };
+
+        // The key starts with the version, a plan claiming otherwise was tampered with.
+        if plan.player_version != player_version
+            || !plan
+                .player_js_cache_key
+                .starts_with(&format!("{}-", player_version))
+        {
+            bail!(
+                "The decipher plan is for player {}, not {}.",
+                plan.player_version,
+                player_version
+            )
         }
 
-        bail!(
-            "The player.js was not downloaded before, deciphering failed because the code was not found."
-        )
+        if let Some(strategy) = plan
+            .signature_strategy
+            .as_deref()
+            .and_then(SignatureStrategy::from_name)
+        {
+            self.signature_strategies.insert(
+                strategy_key(SignatureType::Signature, &plan.player_js_cache_key),
+                strategy,
+            )?;
+        }
+
+        self.code_cache.insert(
+            &format!("preprocessed-{}", plan.player_js_cache_key),
+            plan.preprocessed.clone(),
+        )?;
+        self.player_keys
+            .insert(plan.player_version, plan.player_js_cache_key.clone())?;
+        self.preprocessed_players.insert(
+            plan.player_js_cache_key,
+            PreprocessedPlayer(plan.preprocessed),
+        )?;
+
+        Ok(true)
+    }
+
+    async fn decrypt_signatures(
+        &self,
+        signature_type: SignatureType,
+        encrypted_signatures: Vec<String>,
+        player_url: String,
+    ) -> Result<Vec<Option<String>>> {
+        let player_js_cache_key = self.player_cache.player_js_cache_key(&player_url)?;
+        // Keyed by the player version rather than its URL, the same player is served from multiple URLs.
+        let cache_ids: Vec<String> = encrypted_signatures
+            .iter()
+            .map(|encrypted_signature| {
+                format!(
+                    "{}-{}:{}",
+                    signature_type.as_str(),
+                    player_js_cache_key,
+                    encrypted_signature
+                )
+            })
+            .collect();
+
+        let mut decrypted = cache_ids
+            .iter()
+            .map(|cache_id| self.player_cache.get(cache_id))
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut missing: Vec<String> = encrypted_signatures
+            .iter()
+            .zip(&decrypted)
+            .filter(|(_, decrypted)| decrypted.is_none())
+            .map(|(encrypted_signature, _)| encrypted_signature.clone())
+            .collect();
+        missing.sort_unstable();
+        missing.dedup();
+
+        if missing.is_empty() {
+            return Ok(decrypted);
+        }
+
+        let solved = self
+            .solve_with_strategies(signature_type, missing, PlayerSource::Url(&player_url))
+            .await?;
+
+        for (index, encrypted_signature) in encrypted_signatures.iter().enumerate() {
+            if decrypted[index].is_none()
+                && let Some(solution) = solved.get(encrypted_signature)
+            {
+                self.player_cache
+                    .insert(&cache_ids[index], solution.clone())?;
+                decrypted[index] = Some(solution.clone());
+            }
+        }
+
+        Ok(decrypted)
+    }
+
+    async fn decrypt_indexed(
+        &self,
+        signature_type: SignatureType,
+        challenges: Vec<(usize, String, String)>,
+    ) -> Result<HashMap<usize, String>> {
+        let mut by_player: HashMap<String, Vec<(usize, String)>> = HashMap::new();
+
+        for (index, challenge, player_url) in challenges {
+            by_player
+                .entry(player_url)
+                .or_default()
+                .push((index, challenge));
+        }
+
+        let mut decrypted = HashMap::new();
+
+        for (player_url, challenges) in by_player {
+            let (indices, encrypted_signatures): (Vec<usize>, Vec<String>) =
+                challenges.into_iter().unzip();
+            let solutions = self
+                .decrypt_signatures(signature_type, encrypted_signatures, player_url)
+                .await
+                .with_context(|| {
+                    format!(
+                        "Failed to decipher the {} at index {}.",
+                        signature_type.description(),
+                        indices[0]
+                    )
+                })?;
+
+            for (index, solution) in indices.into_iter().zip(solutions) {
+                let Some(solution) = solution else {
+                    bail!(
+                        "Failed to decipher the {} at index {}, the solver returned no result for it.",
+                        signature_type.description(),
+                        index
+                    )
+                };
+
+                decrypted.insert(index, solution);
+            }
+        }
+
+        Ok(decrypted)
     }
 
     async fn decrypt_signature(
@@ -82,51 +734,590 @@ impl SignatureDecipherHandle for SignatureDecipher {
         encrypted_signature: String,
         player_url: String,
     ) -> Result<String> {
-        let cache_id = (
-            format!("{}-{}", signature_type.as_str(), player_url),
-            encrypted_signature.clone(),
-        );
+        let Some(Some(decrypted)) = self
+            .decrypt_signatures(signature_type, vec![encrypted_signature], player_url)
+            .await?
+            .pop()
+        else {
+            bail!(
+                "Signature deciphering failed because ytcore returned no result for the {}.",
+                signature_type.description()
+            )
+        };
+
+        Ok(decrypted)
+    }
+
+    async fn decipher(&self, signature: String, player_url: String) -> Result<String> {
+        self.decipher_cipher(&signature.parse()?, player_url).await
+    }
+
+    async fn decipher_cipher(
+        &self,
+        signature_cipher: &SignatureCipher,
+        player_url: String,
+    ) -> Result<String> {
+        let decrypted_signature = self
+            .decrypt_signature(
+                SignatureType::Signature,
+                signature_cipher.s.clone(),
+                player_url.clone(),
+            )
+            .await?;
+
+        self.transform_n_param(
+            append_signature(signature_cipher, &decrypted_signature)?,
+            player_url,
+        )
+        .await
+    }
+
+    async fn decipher_all(&self, signatures: Vec<(String, String)>) -> Result<Vec<String>> {
+        let mut signature_ciphers = Vec::with_capacity(signatures.len());
+
+        for (index, (signature, player_url)) in signatures.into_iter().enumerate() {
+            let signature_cipher = signature
+                .parse()
+                .with_context(|| format!("Failed to decipher the signature at index {}.", index))?;
 
-        if let Some(cached_deciphered_value) = self.player_cache.get(&cache_id)? {
-            return Ok(cached_deciphered_value);
+            signature_ciphers.push((signature_cipher, player_url));
         }
 
-        let extracted_signature = self
-            .extract_signature_function(player_url, encrypted_signature, signature_type)
+        self.decipher_ciphers(signature_ciphers).await
+    }
+
+    async fn decipher_ciphers(
+        &self,
+        signature_ciphers: Vec<(SignatureCipher, String)>,
+    ) -> Result<Vec<String>> {
+        let challenges = signature_ciphers
+            .iter()
+            .enumerate()
+            .map(|(index, (signature_cipher, player_url))| {
+                (index, signature_cipher.s.clone(), player_url.clone())
+            })
+            .collect();
+
+        let mut decrypted = self
+            .decrypt_indexed(SignatureType::Signature, challenges)
             .await?;
-        Ok(extracted_signature)
+
+        let mut urls = Vec::with_capacity(signature_ciphers.len());
+
+        for (index, (signature_cipher, player_url)) in signature_ciphers.into_iter().enumerate() {
+            let decrypted_signature = decrypted.remove(&index).unwrap_or_default();
+            urls.push((
+                append_signature(&signature_cipher, &decrypted_signature)?,
+                player_url,
+            ));
+        }
+
+        self.transform_n_params(urls).await
     }
 
-    async fn decipher(&self, signature: String, player_url: String) -> Result<String> {
-        let sc = parse_query_string(&signature).unwrap_or_default();
+    async fn decipher_n(&self, n: String, player_url: String) -> Result<String> {
+        self.decrypt_signature(SignatureType::Nsignature, n, player_url)
+            .await
+    }
 
-        let (Some(fmt_url), Some(encrypted_sig)) = (sc.get("url").cloned(), sc.get("s").cloned())
-        else {
-            bail!("The provided signature cannot be deciphered because it is missing `url`.")
+    async fn decipher_n_all(&self, ns: Vec<(String, String)>) -> Result<Vec<String>> {
+        let count = ns.len();
+        let challenges = ns
+            .into_iter()
+            .enumerate()
+            .map(|(index, (n, player_url))| (index, n, player_url))
+            .collect();
+
+        let mut decrypted = self
+            .decrypt_indexed(SignatureType::Nsignature, challenges)
+            .await?;
+
+        Ok((0..count)
+            .map(|index| decrypted.remove(&index).unwrap_or_default())
+            .collect())
+    }
+
+    async fn transform_n_param(&self, url: String, player_url: String) -> Result<String> {
+        let Some(n) = get_n_param(&url)? else {
+            return Ok(url);
         };
 
-        let decrypted_signature = self
-            .decrypt_signature(SignatureType::Signature, encrypted_sig, player_url.clone())
+        let deciphered_n = self.decipher_n(n, player_url).await?;
+        Ok(replace_n_sig_query_param(&url, deciphered_n)?)
+    }
+
+    async fn transform_n_params(&self, urls: Vec<(String, String)>) -> Result<Vec<String>> {
+        let mut challenges = Vec::new();
+
+        for (index, (url, player_url)) in urls.iter().enumerate() {
+            let n = get_n_param(url).with_context(|| {
+                format!("Failed to decipher the n parameter at index {}.", index)
+            })?;
+
+            if let Some(n) = n {
+                challenges.push((index, n, player_url.clone()));
+            }
+        }
+
+        let mut decrypted = self
+            .decrypt_indexed(SignatureType::Nsignature, challenges)
+            .await?;
+
+        urls.into_iter()
+            .enumerate()
+            .map(|(index, (url, _))| match decrypted.remove(&index) {
+                Some(deciphered_n) => Ok(replace_n_sig_query_param(&url, deciphered_n)?),
+                None => Ok(url),
+            })
+            .collect()
+    }
+
+    async fn resolve_stream_url(
+        &self,
+        source: &YtStreamSource,
+        player_url: String,
+    ) -> Result<String> {
+        match source {
+            YtStreamSource::URL(url) => self.transform_n_param(url.clone(), player_url).await,
+            YtStreamSource::Signature(signature_cipher) => {
+                self.decipher_cipher(signature_cipher, player_url).await
+            }
+        }
+    }
+
+    async fn resolve_stream_urls(
+        &self,
+        streams: &[YtStream],
+        player_url: String,
+    ) -> Result<Vec<String>> {
+        let mut urls = Vec::with_capacity(streams.len());
+        let mut pending_signatures = Vec::new();
+        let mut challenges = Vec::new();
+
+        for (index, stream) in streams.iter().enumerate() {
+            match &stream.source {
+                YtStreamSource::URL(url) => urls.push((url.clone(), player_url.clone())),
+                YtStreamSource::Signature(signature_cipher) => {
+                    challenges.push((index, signature_cipher.s.clone(), player_url.clone()));
+                    pending_signatures.push((index, signature_cipher));
+                    // Filled in once the signatures are deciphered.
+                    urls.push((String::new(), player_url.clone()));
+                }
+            }
+        }
+
+        let mut decrypted = self
+            .decrypt_indexed(SignatureType::Signature, challenges)
             .await?;
-        let url_with_sig = format!(
-            "{}&{}={}",
-            fmt_url,
-            sc.get("sp").map(String::as_str).unwrap_or("signature"),
-            decrypted_signature,
+
+        for (index, signature_cipher) in pending_signatures {
+            let decrypted_signature = decrypted.remove(&index).unwrap_or_default();
+            urls[index].0 = append_signature(signature_cipher, &decrypted_signature)?;
+        }
+
+        self.transform_n_params(urls).await
+    }
+
+    fn invalidate_player(&self, player_url: String) -> Result<()> {
+        let player_js_cache_key = self.player_cache.player_js_cache_key(&player_url)?;
+
+        self.code_cache.remove(&player_js_cache_key)?;
+        self.code_cache
+            .remove(&format!("preprocessed-{}", player_js_cache_key))?;
+        self.preprocessed_players.invalidate(&player_js_cache_key)?;
+        for signature_type in [SignatureType::Signature, SignatureType::Nsignature] {
+            self.signature_strategies
+                .invalidate(&strategy_key(signature_type, &player_js_cache_key))?;
+        }
+
+        Ok(())
+    }
+
+    fn invalidate_source(&self, source: &YtStreamSource, player_url: String) -> Result<()> {
+        let player_js_cache_key = self.player_cache.player_js_cache_key(&player_url)?;
+
+        let url = match source {
+            YtStreamSource::URL(url) => url,
+            YtStreamSource::Signature(signature_cipher) => {
+                self.player_cache.remove(&format!(
+                    "{}-{}:{}",
+                    SignatureType::Signature.as_str(),
+                    player_js_cache_key,
+                    signature_cipher.s
+                ))?;
+
+                &signature_cipher.url
+            }
+        };
+
+        if let Some(n) = get_n_param(url)? {
+            self.player_cache.remove(&format!(
+                "{}-{}:{}",
+                SignatureType::Nsignature.as_str(),
+                player_js_cache_key,
+                n
+            ))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use crate::cache::CacheStore;
+
+    /// The `Kx` signature function of `player_split_anchor.js`: reverse, swap the first and fourth character, drop two.
+    #[cfg(feature = "js-interp")]
+    pub(crate) fn split_anchor_signature(signature: &str) -> String {
+        let mut chars: Vec<char> = signature.chars().rev().collect();
+        let index = 3 % chars.len();
+        chars.swap(0, index);
+
+        chars[2..].iter().collect()
+    }
+
+    const PLAYER_URL: &str =
+        "https://www.youtube.com/s/player/0123abcd/player_ias.vflset/en_US/base.js";
+
+    fn decipher() -> (SignatureDecipher, Arc<CacheStore>) {
+        let code_cache = Arc::new(CacheStore::new());
+        let decipher = SignatureDecipher::new(Arc::new(CacheStore::new()), code_cache.clone());
+
+        (decipher, code_cache)
+    }
+
+    fn signature_cipher(sp: Option<&str>, url: &str) -> SignatureCipher {
+        SignatureCipher {
+            s: "encrypted".into(),
+            sp: sp.map(str::to_string),
+            url: url.into(),
+        }
+    }
+
+    #[test]
+    fn signature_is_appended_under_sp() {
+        let url = "https://example.com/videoplayback?itag=18";
+
+        assert_eq!(
+            append_signature(&signature_cipher(Some("sig"), url), "AB=C").unwrap(),
+            "https://example.com/videoplayback?itag=18&sig=AB%3DC"
+        );
+        assert_eq!(
+            append_signature(&signature_cipher(Some("signature"), url), "ABC").unwrap(),
+            "https://example.com/videoplayback?itag=18&signature=ABC"
+        );
+        assert_eq!(
+            append_signature(&signature_cipher(Some("lsig"), url), "ABC").unwrap(),
+            "https://example.com/videoplayback?itag=18&lsig=ABC"
         );
+    }
 
-        Ok(
-            match parse_query_string(&url_with_sig)
-                .unwrap_or_default()
-                .get("n")
-            {
-                Some(nsig) => replace_n_sig_query_param(
-                    &url_with_sig,
-                    self.decrypt_signature(SignatureType::Nsignature, nsig.clone(), player_url)
-                        .await?,
-                )?,
-                None => url_with_sig,
-            },
+    #[test]
+    fn signature_defaults_to_the_signature_param() {
+        let cipher = signature_cipher(None, "https://example.com/videoplayback?itag=18");
+
+        assert_eq!(
+            append_signature(&cipher, "ABC").unwrap(),
+            "https://example.com/videoplayback?itag=18&signature=ABC"
+        );
+    }
+
+    #[test]
+    fn signature_already_in_the_url_is_replaced() {
+        let cipher = signature_cipher(
+            Some("sig"),
+            "https://example.com/videoplayback?sig=OLD&itag=18&signature=OLD",
+        );
+
+        assert_eq!(
+            append_signature(&cipher, "ABC").unwrap(),
+            "https://example.com/videoplayback?itag=18&sig=ABC"
+        );
+    }
+
+    #[test]
+    fn query_ending_with_ampersand_gets_no_empty_pair() {
+        let cipher = signature_cipher(Some("sig"), "https://example.com/videoplayback?itag=18&");
+
+        assert_eq!(
+            append_signature(&cipher, "ABC").unwrap(),
+            "https://example.com/videoplayback?itag=18&sig=ABC"
+        );
+    }
+
+    #[tokio::test]
+    async fn preprocessed_player_is_not_parsed_again() {
+        let (decipher, code_cache) = decipher();
+        let key = decipher
+            .player_cache
+            .player_js_cache_key(PLAYER_URL)
+            .unwrap();
+        // Like a player another instance preprocessed into a shared or persistent code cache.
+        CacheBackend::insert(
+            code_cache.as_ref(),
+            &format!("preprocessed-{}", key),
+            "var preprocessed;".into(),
         )
+        .unwrap();
+
+        let first = decipher
+            .get_preprocessed_player(PLAYER_URL.into())
+            .await
+            .unwrap();
+        let hits = CacheBackend::stats(code_cache.as_ref()).hits;
+        let second = decipher
+            .get_preprocessed_player(PLAYER_URL.into())
+            .await
+            .unwrap();
+
+        assert_eq!(first.0, "var preprocessed;");
+        assert!(Arc::ptr_eq(&first, &second));
+        // The second call neither reads the code cache nor parses the player.
+        assert_eq!(CacheBackend::stats(code_cache.as_ref()).hits, hits);
+        assert_eq!(decipher.parses.load(Ordering::SeqCst), 0);
+    }
+
+    #[cfg(feature = "js-interp")]
+    #[tokio::test]
+    async fn signatures_of_one_player_are_extracted_once() {
+        let (decipher, _) = decipher();
+        decipher
+            .seed_player(
+                PLAYER_URL.into(),
+                include_str!("../../tests/fixtures/player_split_anchor.js").into(),
+            )
+            .unwrap();
+        // The solver needs the JS runtime, the interpreter evaluates the same function.
+        let key = decipher
+            .player_cache
+            .player_js_cache_key(PLAYER_URL)
+            .unwrap();
+        decipher
+            .signature_strategies
+            .insert(
+                strategy_key(SignatureType::Signature, &key),
+                SignatureStrategy::Interpreter,
+            )
+            .unwrap();
+
+        let signatures: Vec<String> = (0..100)
+            .map(|i| format!("{:03}ABCDEFGHIJKLMNOPQRSTUVWXYZ", i))
+            .collect();
+        let deciphered = decipher
+            .decrypt_signatures(
+                SignatureType::Signature,
+                signatures.clone(),
+                PLAYER_URL.into(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(decipher.parses.load(Ordering::SeqCst), 1);
+        for (signature, deciphered) in signatures.iter().zip(deciphered) {
+            assert_eq!(deciphered.unwrap(), split_anchor_signature(signature));
+        }
+
+        // Deciphered signatures are cached, deciphering them again doesn't parse the player.
+        decipher
+            .decrypt_signatures(SignatureType::Signature, signatures, PLAYER_URL.into())
+            .await
+            .unwrap();
+        assert_eq!(decipher.parses.load(Ordering::SeqCst), 1);
+    }
+
+    #[cfg(feature = "js-interp")]
+    #[tokio::test]
+    async fn cipher_of_a_fixture_player_resolves_to_the_known_url() {
+        let (decipher, _) = decipher();
+        decipher
+            .seed_player(
+                PLAYER_URL.into(),
+                include_str!("../../tests/fixtures/player_usage_site.js").into(),
+            )
+            .unwrap();
+        let key = decipher
+            .player_cache
+            .player_js_cache_key(PLAYER_URL)
+            .unwrap();
+        for signature_type in [SignatureType::Signature, SignatureType::Nsignature] {
+            decipher
+                .signature_strategies
+                .insert(
+                    strategy_key(signature_type, &key),
+                    SignatureStrategy::Interpreter,
+                )
+                .unwrap();
+        }
+
+        let signature_cipher: SignatureCipher = "s=0123456789ABCDEFGHIJ&sp=sig&url=https%3A%2F%2Frr1---sn-abc.googlevideo.com%2Fvideoplayback%3Fitag%3D18%26n%3Dabcdef"
+            .parse()
+            .unwrap();
+        let url = decipher
+            .decipher_cipher(&signature_cipher, PLAYER_URL.into())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            url,
+            "https://rr1---sn-abc.googlevideo.com/videoplayback?itag=18&n=bcdefa&sig=HIJGFEDCBA9876543210"
+        );
+    }
+
+    #[tokio::test]
+    async fn mangled_player_error_lists_strategies_and_snippet() {
+        let (decipher, _) = decipher();
+
+        let error = decipher
+            .decipher_with_player_source(
+                "s=abc&url=https%3A%2F%2Fexample.com%2Fvideoplayback",
+                include_str!("../../tests/fixtures/player_mangled.js"),
+            )
+            .await
+            .unwrap_err();
+
+        let Some(DecipherError::SigFunctionNotFound {
+            player_version,
+            strategies_tried,
+            snippet,
+        }) = error.downcast_ref::<DecipherError>()
+        else {
+            panic!("unexpected error: {:?}", error);
+        };
+        let names: Vec<&str> = SignatureStrategy::ALL
+            .iter()
+            .map(|strategy| strategy.name())
+            .collect();
+
+        assert_eq!(player_version, &None);
+        assert_eq!(strategies_tried, &names);
+        assert!(
+            snippet
+                .as_deref()
+                .unwrap()
+                .contains(r#"Mq=function(z,w){z=z.split("");"#)
+        );
+        assert!(error.to_string().contains(&names.join(", ")));
+        assert!(error.to_string().contains("Player code: "));
+    }
+
+    /// A plan of `PLAYER_URL` exported from a decipher that preprocessed it, along with its player version.
+    async fn exported_plan() -> (Vec<u8>, String) {
+        let (decipher, code_cache) = decipher();
+        let key = decipher
+            .player_cache
+            .player_js_cache_key(PLAYER_URL)
+            .unwrap();
+        CacheBackend::insert(
+            code_cache.as_ref(),
+            &format!("preprocessed-{}", key),
+            "var preprocessed;".into(),
+        )
+        .unwrap();
+        decipher
+            .signature_strategies
+            .insert(
+                strategy_key(SignatureType::Signature, &key),
+                SignatureStrategy::SplitAnchor,
+            )
+            .unwrap();
+        decipher
+            .get_preprocessed_player(PLAYER_URL.into())
+            .await
+            .unwrap();
+        let player_version = decipher
+            .player_cache
+            .extract_player_info(PLAYER_URL)
+            .unwrap();
+
+        (
+            decipher.export_plan(&player_version).unwrap(),
+            player_version,
+        )
+    }
+
+    /// Whether `decipher` holds anything about the player of `PLAYER_URL`.
+    fn knows_player(decipher: &SignatureDecipher) -> bool {
+        let key = decipher
+            .player_cache
+            .player_js_cache_key(PLAYER_URL)
+            .unwrap();
+
+        !decipher.preprocessed_players.is_empty().unwrap()
+            || !decipher.signature_strategies.is_empty().unwrap()
+            || decipher
+                .code_cache
+                .get(&format!("preprocessed-{}", key))
+                .unwrap()
+                .is_some()
+    }
+
+    #[tokio::test]
+    async fn imported_plan_is_not_parsed_again() {
+        let (plan, player_version) = exported_plan().await;
+        let (decipher, _) = decipher();
+
+        assert!(decipher.import_plan(&plan, &player_version).unwrap());
+        let preprocessed = decipher
+            .get_preprocessed_player(PLAYER_URL.into())
+            .await
+            .unwrap();
+        let key = decipher
+            .player_cache
+            .player_js_cache_key(PLAYER_URL)
+            .unwrap();
+
+        assert_eq!(preprocessed.0, "var preprocessed;");
+        assert_eq!(decipher.parses.load(Ordering::SeqCst), 0);
+        assert_eq!(
+            decipher
+                .signature_strategies
+                .get::<SignatureStrategy>(&strategy_key(SignatureType::Signature, &key))
+                .unwrap()
+                .as_deref(),
+            Some(&SignatureStrategy::SplitAnchor)
+        );
+    }
+
+    #[tokio::test]
+    async fn plan_of_another_player_version_is_rejected() {
+        let (plan, player_version) = exported_plan().await;
+        let (decipher, _) = decipher();
+
+        let error = decipher.import_plan(&plan, "89abcdef").unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            format!(
+                "The decipher plan is for player {}, not 89abcdef.",
+                player_version
+            )
+        );
+        assert!(!knows_player(&decipher));
+    }
+
+    #[tokio::test]
+    async fn corrupted_plans_are_ignored() {
+        let (plan, player_version) = exported_plan().await;
+        let truncated = plan[..plan.len() / 2].to_vec();
+        let tampered = String::from_utf8(plan.clone())
+            .unwrap()
+            .replace("var preprocessed;", "var tampered;")
+            .into_bytes();
+        let future_format = String::from_utf8(plan)
+            .unwrap()
+            .replace(
+                &format!("\"format_version\":{}", PLAN_FORMAT_VERSION),
+                &format!("\"format_version\":{}", PLAN_FORMAT_VERSION + 1),
+            )
+            .into_bytes();
+
+        for corrupted in [truncated, tampered, future_format, b"not a plan".to_vec()] {
+            let (decipher, _) = decipher();
+
+            assert!(!decipher.import_plan(&corrupted, &player_version).unwrap());
+            assert!(!knows_player(&decipher));
+        }
     }
 }
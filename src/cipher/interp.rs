@@ -0,0 +1,17 @@
+use anyhow::{Result, anyhow};
+use boa_engine::{Context, Source};
+
+/// Evaluate `script` with the boa interpreter and return the string it evaluates to.
+///
+/// Much slower than the JS runtime, so it's only used once the other strategies failed.
+pub fn eval(script: &str) -> Result<String> {
+    let mut context = Context::default();
+    let value = context
+        .eval(Source::from_bytes(script))
+        .map_err(|e| anyhow!("Interpreting the player failed: {}", e))?;
+
+    value
+        .as_string()
+        .map(|value| value.to_std_string_escaped())
+        .ok_or_else(|| anyhow!("The interpreted script didn't return a string."))
+}
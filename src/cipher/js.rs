@@ -1,42 +1,51 @@
-#[cfg(not(target_arch = "wasm32"))]
 use std::collections::HashMap;
 
 #[cfg(target_arch = "wasm32")]
-use anyhow::{Result, anyhow};
-#[cfg(not(target_arch = "wasm32"))]
+use anyhow::anyhow;
 use anyhow::{Result, bail};
 #[cfg(not(target_arch = "wasm32"))]
 use deno_core::JsRuntime;
 #[cfg(target_arch = "wasm32")]
 use js_sys::{Function, eval};
-#[cfg(not(target_arch = "wasm32"))]
-use serde_json::json;
+use serde_json::{Value, json};
 #[cfg(target_arch = "wasm32")]
 use wasm_bindgen::prelude::*;
 
-use crate::{
-    cache::CacheAccess,
-    cipher::decipher::{SignatureDecipher, SignatureType},
-};
+use crate::cipher::decipher::{SignatureDecipher, SignatureType};
+
+const YT_DLP_YT_SOLVER_PKG_LIB_URL: &str =
+    "https://github.com/yt-dlp/ejs/releases/download/0.3.1/yt.solver.lib.min.js";
+const YT_DLP_YT_SOLVER_PKG_CORE_URL: &str =
+    "https://github.com/yt-dlp/ejs/releases/download/0.3.1/yt.solver.core.min.js";
+
+/// The player.js as preprocessed by the solver, the plan it solves challenges with without parsing the player again.
+pub struct PreprocessedPlayer(pub String);
 
 pub trait SignatureJsHandle {
     async fn get_js_modules(&self) -> Result<(String, String)>;
-    async fn parse_signature_js(
+    /// Whether the solver modules are cached, so solving doesn't need a network request.
+    fn has_js_modules(&self) -> Result<bool>;
+    /// Put the solver modules into the code cache, for when they can't be downloaded from GitHub.
+    fn seed_js_modules(&self, lib_code: String, core_code: String) -> Result<()>;
+    /// Run the solver's `jsc` entrypoint with `input` and return its output.
+    async fn run_solver(&self, input: Value) -> Result<Value>;
+    /// Evaluate `script` in a fresh JS context and return the string it evaluates to.
+    async fn eval_js(&self, script: String) -> Result<String>;
+    /// Parse the player.js once into the form the solver solves challenges with.
+    async fn preprocess_player(&self, code: String) -> Result<PreprocessedPlayer>;
+    /// Solve many challenges of one type in a single solver run.
+    /// Challenges the solver failed on are missing from the returned map.
+    async fn solve_challenges(
         &self,
-        code: String,
-        example_sig: String,
+        preprocessed: &PreprocessedPlayer,
+        challenges: Vec<String>,
         signature_type: SignatureType,
-    ) -> Result<String>;
+    ) -> Result<HashMap<String, String>>;
 }
 
 impl SignatureJsHandle for SignatureDecipher {
     async fn get_js_modules(&self) -> Result<(String, String)> {
-        const YT_DLP_YT_SOLVER_PKG_LIB_URL: &str =
-            "https://github.com/yt-dlp/ejs/releases/download/0.3.1/yt.solver.lib.min.js";
-        const YT_DLP_YT_SOLVER_PKG_CORE_URL: &str =
-            "https://github.com/yt-dlp/ejs/releases/download/0.3.1/yt.solver.core.min.js";
-
-        let lib_code = match self.code_cache.get(&YT_DLP_YT_SOLVER_PKG_LIB_URL.into())? {
+        let lib_code = match self.code_cache.get(YT_DLP_YT_SOLVER_PKG_LIB_URL)? {
             Some(cached_lib_code) => cached_lib_code,
             None => {
                 let fetched_lib = reqwest::get(YT_DLP_YT_SOLVER_PKG_LIB_URL)
@@ -45,13 +54,13 @@ impl SignatureJsHandle for SignatureDecipher {
                     .await?;
 
                 self.code_cache
-                    .add(YT_DLP_YT_SOLVER_PKG_LIB_URL.into(), fetched_lib.clone())?;
+                    .insert(YT_DLP_YT_SOLVER_PKG_LIB_URL, fetched_lib.clone())?;
 
                 fetched_lib
             }
         };
 
-        let core_code = match self.code_cache.get(&YT_DLP_YT_SOLVER_PKG_CORE_URL.into())? {
+        let core_code = match self.code_cache.get(YT_DLP_YT_SOLVER_PKG_CORE_URL)? {
             Some(cached_lib_code) => cached_lib_code,
             None => {
                 let fetched_lib = reqwest::get(YT_DLP_YT_SOLVER_PKG_CORE_URL)
@@ -60,7 +69,7 @@ impl SignatureJsHandle for SignatureDecipher {
                     .await?;
 
                 self.code_cache
-                    .add(YT_DLP_YT_SOLVER_PKG_CORE_URL.into(), fetched_lib.clone())?;
+                    .insert(YT_DLP_YT_SOLVER_PKG_CORE_URL, fetched_lib.clone())?;
 
                 fetched_lib
             }
@@ -69,15 +78,25 @@ impl SignatureJsHandle for SignatureDecipher {
         Ok((lib_code, core_code))
     }
 
+    fn has_js_modules(&self) -> Result<bool> {
+        Ok(self.code_cache.get(YT_DLP_YT_SOLVER_PKG_LIB_URL)?.is_some()
+            && self
+                .code_cache
+                .get(YT_DLP_YT_SOLVER_PKG_CORE_URL)?
+                .is_some())
+    }
+
+    fn seed_js_modules(&self, lib_code: String, core_code: String) -> Result<()> {
+        self.code_cache
+            .insert(YT_DLP_YT_SOLVER_PKG_LIB_URL, lib_code)?;
+        self.code_cache
+            .insert(YT_DLP_YT_SOLVER_PKG_CORE_URL, core_code)
+    }
+
     // Taken from `youtube_explode_dart`'s implementation with `yt-dlp`'s ejs cipher library.
     // See: https://github.com/Hexer10/youtube_explode_dart/blob/a993b3d463713b0aabd945f07a7e6a1635bcf1e7/lib/src/reverse_engineering/challenges/ejs/ejs.dart
     #[cfg(not(target_arch = "wasm32"))]
-    async fn parse_signature_js(
-        &self,
-        code: String,
-        example_sig: String,
-        signature_type: SignatureType,
-    ) -> Result<String> {
+    async fn run_solver(&self, input: Value) -> Result<Value> {
         let (lib_code, core_code) = self.get_js_modules().await?;
 
         let js_env = format!(
@@ -89,13 +108,6 @@ impl SignatureJsHandle for SignatureDecipher {
 
         deno.execute_script("<setup_environment>", js_env)?;
 
-        let input = json!({
-            "type": "player",
-            "player": code,
-            "requests": [{"type": signature_type.as_str(), "challenges": [example_sig]}],
-            "output_preprocessed": true
-        });
-
         let set_input_js = format!("globalThis.__input = {};", input.to_string());
         deno.execute_script("<set_input>", set_input_js)?;
 
@@ -112,27 +124,32 @@ impl SignatureJsHandle for SignatureDecipher {
         let mut scope = deno.handle_scope();
         let result_str = local_value.to_rust_string_lossy(&mut scope);
 
-        let result: HashMap<String, serde_json::Value> = serde_json::from_str(&result_str)?;
-        let Some(deciphered_sig) = result
-            .get("responses")
-            .and_then(|r| r.get(0))
-            .and_then(|r| r.get("data"))
-            .and_then(|r| r.get(example_sig))
-            .and_then(|v| v.as_str())
-        else {
-            bail!("Signature deciphering failed because ytcore returned an invalid response.")
-        };
+        Ok(serde_json::from_str(&result_str)?)
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    async fn eval_js(&self, script: String) -> Result<String> {
+        let mut deno = JsRuntime::new(Default::default());
+
+        let global_value = deno.execute_script("<eval>", script)?;
+        deno.run_event_loop(Default::default()).await?;
+
+        let local_value = global_value.open(deno.v8_isolate());
 
-        Ok(deciphered_sig.into())
+        let mut scope = deno.handle_scope();
+        Ok(local_value.to_rust_string_lossy(&mut scope))
     }
 
     #[cfg(target_arch = "wasm32")]
-    async fn parse_signature_js(
-        &self,
-        code: String,
-        example_sig: String,
-        signature_type: SignatureType,
-    ) -> Result<String> {
+    async fn eval_js(&self, script: String) -> Result<String> {
+        eval(&script)
+            .map_err(|err| anyhow!("JS eval failed: {:?}", err))?
+            .as_string()
+            .ok_or_else(|| anyhow!("The evaluated script didn't return a string."))
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    async fn run_solver(&self, input: Value) -> Result<Value> {
         let (lib_code, core_code) = self.get_js_modules().await?;
 
         let js_env = format!(
@@ -147,13 +164,6 @@ impl SignatureJsHandle for SignatureDecipher {
             .dyn_into::<Function>()
             .map_err(|_| anyhow!("Failed to defined `jsc` in the JS context."))?;
 
-        let input = serde_json::json!({
-            "type": "player",
-            "player": code,
-            "requests": [{"type": signature_type.as_str(), "challenges": [example_sig]}],
-            "output_preprocessed": true
-        });
-
         let js_input = serde_wasm_bindgen::to_value(&input).map_err(|_| {
             anyhow!("Signature deciphering failed due to the failure of serializing input for the JS context.")
         })?;
@@ -161,15 +171,56 @@ impl SignatureJsHandle for SignatureDecipher {
             .call1(&JsValue::NULL, &js_input)
             .map_err(|e| anyhow!("jsc() call failed: {:?}", e))?;
 
-        let result: serde_json::Value =
-            serde_wasm_bindgen::from_value(result_val).map_err(|_| {
-                anyhow!("Signature deciphering failed because the JS bridge returned an error.")
-            })?;
-        let deciphered = result["responses"][0]["data"][&example_sig]
-            .as_str()
-            .unwrap_or_default()
-            .to_string();
+        serde_wasm_bindgen::from_value(result_val).map_err(|_| {
+            anyhow!("Signature deciphering failed because the JS bridge returned an error.")
+        })
+    }
+
+    async fn preprocess_player(&self, code: String) -> Result<PreprocessedPlayer> {
+        let result = self
+            .run_solver(json!({
+                "type": "player",
+                "player": code,
+                "requests": [],
+                "output_preprocessed": true
+            }))
+            .await?;
+
+        let Some(preprocessed) = result.get("preprocessed_player").and_then(|p| p.as_str()) else {
+            bail!("Preprocessing the player failed because ytcore returned an invalid response.")
+        };
+
+        Ok(PreprocessedPlayer(preprocessed.to_string()))
+    }
+
+    async fn solve_challenges(
+        &self,
+        preprocessed: &PreprocessedPlayer,
+        challenges: Vec<String>,
+        signature_type: SignatureType,
+    ) -> Result<HashMap<String, String>> {
+        let result = self
+            .run_solver(json!({
+                "type": "preprocessed",
+                "preprocessed_player": preprocessed.0,
+                "requests": [{"type": signature_type.as_str(), "challenges": challenges}]
+            }))
+            .await?;
+
+        let Some(solved) = result
+            .get("responses")
+            .and_then(|r| r.get(0))
+            .and_then(|r| r.get("data"))
+            .and_then(|r| r.as_object())
+        else {
+            bail!("Signature deciphering failed because ytcore returned an invalid response.")
+        };
 
-        Ok(deciphered)
+        Ok(solved
+            .iter()
+            .filter_map(|(challenge, solution)| {
+                Some((challenge.clone(), solution.as_str()?.to_string()))
+            })
+            .collect())
     }
 }
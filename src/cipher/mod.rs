@@ -1,3 +1,5 @@
-mod js;
-
 pub mod decipher;
+#[cfg(feature = "js-interp")]
+pub mod interp;
+pub mod js;
+pub mod strategy;
@@ -0,0 +1,581 @@
+use std::collections::{HashMap, HashSet};
+
+use anyhow::Result;
+use fancy_regex::Regex;
+use serde::Deserialize;
+
+/// Definitions are followed this many levels deep, e.g. the signature function, its helper object
+/// and the global lookup array the helper object indexes.
+const MAX_DEPENDENCY_DEPTH: usize = 4;
+/// Length of the player code included in errors, enough to see what the signature function looks like now.
+const MAX_SNIPPET_CHARS: usize = 300;
+
+/// Ways of deciphering signatures, tried in order until one succeeds.
+///
+/// The solver handles the current player, the others locate the signature function in the player.js
+/// with patterns of older player versions and evaluate only that function with its dependencies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignatureStrategy {
+    /// yt-dlp's ejs solver.
+    Solver,
+    /// The function name taken from where the player calls it with the `s` parameter.
+    UsageSite,
+    /// A function starting with `a=a.split("")`, the traditional form of the signature function.
+    SplitAnchor,
+    /// A function calling its operations through a global lookup array, like `a=a[XY[12]](XY[3])`.
+    GlobalLookup,
+    /// The function found by any of the patterns above, or the `n` function, run with the boa
+    /// interpreter rather than the JS runtime.
+    #[cfg(feature = "js-interp")]
+    Interpreter,
+}
+
+impl SignatureStrategy {
+    pub const ALL: &[Self] = &[
+        Self::Solver,
+        Self::UsageSite,
+        Self::SplitAnchor,
+        Self::GlobalLookup,
+        #[cfg(feature = "js-interp")]
+        Self::Interpreter,
+    ];
+    /// The strategies able to transform the `n` parameter.
+    pub const N_PARAM: &[Self] = &[
+        Self::Solver,
+        #[cfg(feature = "js-interp")]
+        Self::Interpreter,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Solver => "solver",
+            Self::UsageSite => "usage site",
+            Self::SplitAnchor => "split anchor",
+            Self::GlobalLookup => "global lookup",
+            #[cfg(feature = "js-interp")]
+            Self::Interpreter => "interpreter",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|strategy| strategy.name() == name)
+    }
+
+    /// Patterns capturing the name of the signature function as `sig`.
+    fn function_name_patterns(&self) -> &'static [&'static str] {
+        match self {
+            Self::Solver => &[],
+            Self::UsageSite => &[
+                r"\b(?P<var>[a-zA-Z0-9_$]+)&&\((?P=var)=(?P<sig>[a-zA-Z0-9_$]{2,})\(decodeURIComponent\((?P=var)\)\)",
+                r"\b[cs]\s*&&\s*[adf]\.set\([^,]+\s*,\s*encodeURIComponent\s*\(\s*(?P<sig>[a-zA-Z0-9$]+)\(",
+                r"\bm=(?P<sig>[a-zA-Z0-9$]{2,})\(decodeURIComponent\(h\.s\)\)",
+            ],
+            Self::SplitAnchor => &[
+                r#"(?P<sig>[a-zA-Z0-9_$]+)\s*=\s*function\(\s*(?P<arg>[a-zA-Z0-9_$]+)\s*\)\s*\{\s*(?P=arg)\s*=\s*(?P=arg)\.split\(\s*""\s*\)\s*;\s*[^}]+;\s*return\s+(?P=arg)\.join\(\s*""\s*\)"#,
+                r#"(?:\b|[^a-zA-Z0-9_$])(?P<sig>[a-zA-Z0-9_$]{2,})\s*=\s*function\(\s*a\s*\)\s*\{\s*a\s*=\s*a\.split\(\s*""\s*\)"#,
+            ],
+            Self::GlobalLookup => &[
+                r"(?P<sig>[a-zA-Z0-9_$]+)\s*=\s*function\(\s*(?P<arg>[a-zA-Z0-9_$]+)\s*\)\s*\{\s*(?P=arg)\s*=\s*(?P=arg)\[(?P<gvar>[a-zA-Z0-9_$]+)\[\d+\]\]\(\s*(?P=gvar)\[\d+\]\s*\)",
+            ],
+            // Tries the patterns of the other strategies in `find_function_name`.
+            #[cfg(feature = "js-interp")]
+            Self::Interpreter => &[],
+        }
+    }
+
+    /// Build a script that applies the signature function found by this strategy to every challenge
+    /// and evaluates to the JSON of a `StrategyOutput`.
+    /// Returns `None` if the function or one of its dependencies can't be found in `code`.
+    pub fn build_script(
+        &self,
+        code: &str,
+        challenges: &[String],
+    ) -> Result<Option<StrategyScript>> {
+        let Some(function_name) = self.find_function_name(code)? else {
+            return Ok(None);
+        };
+
+        build_function_script(code, &function_name, challenges)
+    }
+
+    fn find_function_name(&self, code: &str) -> Result<Option<String>> {
+        #[cfg(feature = "js-interp")]
+        if *self == Self::Interpreter {
+            for strategy in [Self::UsageSite, Self::SplitAnchor, Self::GlobalLookup] {
+                if let Some(name) = strategy.find_function_name(code)? {
+                    return Ok(Some(name));
+                }
+            }
+
+            return Ok(None);
+        }
+
+        for pattern in self.function_name_patterns() {
+            if let Some(captures) = Regex::new(pattern)?.captures(code)?
+                && let Some(name) = captures.name("sig")
+            {
+                return Ok(Some(name.as_str().to_string()));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+/// Build a script like `SignatureStrategy::build_script` does, for the `n` function instead.
+pub fn build_n_script(code: &str, challenges: &[String]) -> Result<Option<StrategyScript>> {
+    let Some(function_name) = find_n_function_name(code)? else {
+        return Ok(None);
+    };
+
+    build_function_script(code, &function_name, challenges)
+}
+
+/// Patterns of where the player calls the `n` function, capturing its name as `nfunc`
+/// and `idx` if it's called through an array.
+const N_FUNCTION_PATTERNS: [&str; 3] = [
+    r#"\.get\("n"\)\)&&\(b=(?P<nfunc>[a-zA-Z0-9_$]+)(?:\[(?P<idx>\d+)\])?\([a-zA-Z0-9]\)"#,
+    r"b=String\.fromCharCode\(110\),c=a\.get\(b\)\)&&\(c=(?P<nfunc>[a-zA-Z0-9_$]+)(?:\[(?P<idx>\d+)\])?\([a-zA-Z0-9]\)",
+    r#"[;,]\s*(?P<nfunc>[a-zA-Z0-9_$]+)\s*=\s*function\([a-zA-Z0-9_$]+\)\s*\{(?:(?!\}[;,])[\s\S])+?\}\s*catch\(\s*[a-zA-Z0-9_$]+\s*\)\s*\{\s*return\s+"enhanced_except_"#,
+];
+
+fn find_n_function_name(code: &str) -> Result<Option<String>> {
+    for pattern in N_FUNCTION_PATTERNS {
+        let Some(captures) = Regex::new(pattern)?.captures(code)? else {
+            continue;
+        };
+        let Some(name) = captures.name("nfunc") else {
+            continue;
+        };
+
+        let Some(index) = captures.name("idx") else {
+            return Ok(Some(name.as_str().to_string()));
+        };
+
+        // Called like `XY[0](a)`, where `var XY=[nfunc]`.
+        let array_re = Regex::new(&format!(
+            r"var\s+{}\s*=\s*\[(?P<list>[^\]]+)\]",
+            escape_identifier(name.as_str())
+        ))?;
+
+        if let Some(array) = array_re.captures(code)?
+            && let Some(list) = array.name("list")
+            && let Ok(index) = index.as_str().parse::<usize>()
+            && let Some(name) = list.as_str().split(',').nth(index)
+        {
+            return Ok(Some(name.trim().to_string()));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Build a script applying `function_name` to every challenge, see `SignatureStrategy::build_script`.
+fn build_function_script(
+    code: &str,
+    function_name: &str,
+    challenges: &[String],
+) -> Result<Option<StrategyScript>> {
+    let Some(definitions) = collect_definitions(code, function_name)? else {
+        return Ok(None);
+    };
+
+    // The function itself is declared after its dependencies.
+    let (argument, operations) = definitions
+        .last()
+        .and_then(|definition| split_operations(definition))
+        .unwrap_or_default();
+
+    // Each operation is wrapped into a function of its own, so the one that throws can be told apart.
+    let wrapped_operations: Vec<String> = operations
+        .iter()
+        .map(|operation| {
+            if operation.starts_with("return") {
+                format!("function({}) {{ {}; }}", argument, operation)
+            } else {
+                format!("function({0}) {{ {1}; return {0}; }}", argument, operation)
+            }
+        })
+        .collect();
+
+    let script = format!(
+        r#"(function() {{
+{}
+var __tydleOps = [{}];
+function __tydleFailedOp(c) {{
+  var a = c;
+  for (var i = 0; i < __tydleOps.length; i++) {{
+    try {{ a = __tydleOps[i](a); }} catch (e) {{ return i; }}
+  }}
+  return null;
+}}
+var __tydleSolved = {{}};
+var __tydleFailed = null;
+{}.forEach(function(c) {{
+  try {{ __tydleSolved[c] = {}(c); }} catch (e) {{ if (__tydleFailed === null) __tydleFailed = __tydleFailedOp(c); }}
+}});
+return JSON.stringify({{ solved: __tydleSolved, failed_op: __tydleFailed }});
+}})();"#,
+        definitions.join("\n"),
+        wrapped_operations.join(", "),
+        serde_json::to_string(challenges)?,
+        function_name
+    );
+
+    Ok(Some(StrategyScript { script, operations }))
+}
+
+/// A script built by a `SignatureStrategy`, with the operations of the signature function it applies.
+pub struct StrategyScript {
+    pub script: String,
+    /// Statements of the signature function's body, like `a=a.split("")` or `XY.ab(a,3)`.
+    pub operations: Vec<String>,
+}
+
+/// What a `StrategyScript` evaluates to.
+#[derive(Deserialize)]
+pub struct StrategyOutput {
+    pub solved: HashMap<String, String>,
+    /// Index of the operation that threw while solving a challenge.
+    pub failed_op: Option<usize>,
+}
+
+/// Get the code around where the signature function usually is, for error messages.
+pub fn snippet(code: &str) -> Option<String> {
+    const ANCHORS: [&str; 3] = [".split(\"\")", "decodeURIComponent(", ".join(\"\")"];
+
+    let anchor = ANCHORS.iter().find_map(|anchor| code.find(anchor))?;
+    let mut start = anchor.saturating_sub(MAX_SNIPPET_CHARS / 2);
+    let mut end = (start + MAX_SNIPPET_CHARS).min(code.len());
+
+    while !code.is_char_boundary(start) {
+        start -= 1;
+    }
+    while !code.is_char_boundary(end) {
+        end -= 1;
+    }
+
+    Some(code[start..end].to_string())
+}
+
+/// Split a function definition into its argument and the statements of its body.
+fn split_operations(definition: &str) -> Option<(String, Vec<String>)> {
+    let arguments_start = definition.find('(')?;
+    let arguments_end = arguments_start + definition[arguments_start..].find(')')?;
+    let argument = definition[arguments_start + 1..arguments_end].trim();
+
+    // Only single argument functions are applied operation by operation.
+    if argument.is_empty() || argument.contains(',') {
+        return None;
+    }
+
+    let body_start = arguments_end + definition[arguments_end..].find('{')? + 1;
+    let mut rest = &definition[body_start..];
+    let mut operations = Vec::new();
+
+    loop {
+        let operation = read_expression(rest);
+        rest = &rest[operation.len()..];
+
+        if !operation.trim().is_empty() {
+            operations.push(operation.trim().to_string());
+        }
+
+        // The body ends at the closing brace `read_expression` stops at.
+        if !rest.starts_with([';', ',']) {
+            break;
+        }
+
+        rest = &rest[1..];
+    }
+
+    Some((argument.to_string(), operations))
+}
+
+fn escape_identifier(name: &str) -> String {
+    name.replace('$', r"\$")
+}
+
+/// Find the definitions of `function_name` and everything it references, in the order they have to be declared.
+fn collect_definitions(code: &str, function_name: &str) -> Result<Option<Vec<String>>> {
+    let Some(function_definition) = find_definition(code, function_name)? else {
+        return Ok(None);
+    };
+
+    let mut seen = HashSet::from([function_name.to_string()]);
+    let mut definitions = vec![function_definition];
+    let mut pending = vec![(definitions[0].clone(), 0)];
+
+    while let Some((definition, depth)) = pending.pop() {
+        if depth >= MAX_DEPENDENCY_DEPTH {
+            continue;
+        }
+
+        for name in referenced_objects(&definition)? {
+            if !seen.insert(name.clone()) {
+                continue;
+            }
+
+            if let Some(dependency) = find_definition(code, &name)? {
+                // Dependencies are declared before whatever uses them.
+                definitions.insert(0, dependency.clone());
+                pending.push((dependency, depth + 1));
+            }
+        }
+    }
+
+    Ok(Some(definitions))
+}
+
+/// Names of the objects `code` accesses members of, like `XY` in `XY.ab(a,3)` or `XY[12]`.
+fn referenced_objects(code: &str) -> Result<Vec<String>> {
+    let re = Regex::new(r"(?<![a-zA-Z0-9_$.])([a-zA-Z_$][a-zA-Z0-9_$]*)\s*[.\[]")?;
+    let mut names = Vec::new();
+
+    for captures in re.captures_iter(code) {
+        if let Some(name) = captures?.get(1) {
+            let name = name.as_str();
+
+            if !matches!(name, "a" | "b" | "c" | "this" | "String" | "Math" | "Array")
+                && !names.iter().any(|n| n == name)
+            {
+                names.push(name.to_string());
+            }
+        }
+    }
+
+    Ok(names)
+}
+
+/// Find the definition of `name` in `code` and return it as a `var` declaration.
+fn find_definition(code: &str, name: &str) -> Result<Option<String>> {
+    let escaped = escape_identifier(name);
+
+    let function_re = Regex::new(&format!(r"\bfunction\s+{}\s*\(", escaped))?;
+    if let Some(m) = function_re.find(code)? {
+        return Ok(read_function(&code[m.start()..]).map(str::to_string));
+    }
+
+    let assignment_re = Regex::new(&format!(r"(?<![a-zA-Z0-9_$.]){}\s*=(?![=>])", escaped))?;
+    if let Some(m) = assignment_re.find(code)? {
+        let value = read_expression(&code[m.end()..]);
+
+        if !value.trim().is_empty() {
+            return Ok(Some(format!("var {}={};", name, value)));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Read a `function name(...) {...}` declaration at the start of `code`.
+fn read_function(code: &str) -> Option<&str> {
+    let body_start = code.find('{')?;
+    let body_length = read_expression(&code[body_start..]).len();
+
+    Some(&code[..body_start + body_length])
+}
+
+/// Read the expression at the start of `code` up to the `;` or `,` ending it,
+/// skipping over brackets and string literals.
+fn read_expression(code: &str) -> &str {
+    let mut depth = 0usize;
+    let mut quote: Option<char> = None;
+    let mut escaped = false;
+
+    for (index, char) in code.char_indices() {
+        if let Some(q) = quote {
+            match char {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                _ if char == q => quote = None,
+                _ => {}
+            }
+            continue;
+        }
+
+        match char {
+            '"' | '\'' | '`' => quote = Some(char),
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => {
+                if depth == 0 {
+                    return &code[..index];
+                }
+
+                depth -= 1;
+
+                // A function body or object ends the expression unless something is chained onto it.
+                if depth == 0 && char == '}' {
+                    let rest = code[index + 1..].trim_start();
+
+                    if !rest.starts_with(['.', '(', '[']) {
+                        return &code[..index + 1];
+                    }
+                }
+            }
+            ';' | ',' if depth == 0 => return &code[..index],
+            _ => {}
+        }
+    }
+
+    code
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SPLIT_ANCHOR_PLAYER: &str = include_str!("../../tests/fixtures/player_split_anchor.js");
+    const GLOBAL_LOOKUP_PLAYER: &str = include_str!("../../tests/fixtures/player_global_lookup.js");
+    const USAGE_SITE_PLAYER: &str = include_str!("../../tests/fixtures/player_usage_site.js");
+    const MANGLED_PLAYER: &str = include_str!("../../tests/fixtures/player_mangled.js");
+
+    #[test]
+    fn strategies_find_the_signature_function_of_their_player_version() {
+        let cases = [
+            (
+                SignatureStrategy::UsageSite,
+                SPLIT_ANCHOR_PLAYER,
+                Some("Kx"),
+            ),
+            (
+                SignatureStrategy::SplitAnchor,
+                SPLIT_ANCHOR_PLAYER,
+                Some("Kx"),
+            ),
+            (SignatureStrategy::GlobalLookup, SPLIT_ANCHOR_PLAYER, None),
+            (
+                SignatureStrategy::UsageSite,
+                GLOBAL_LOOKUP_PLAYER,
+                Some("Jh"),
+            ),
+            (SignatureStrategy::SplitAnchor, GLOBAL_LOOKUP_PLAYER, None),
+            (
+                SignatureStrategy::GlobalLookup,
+                GLOBAL_LOOKUP_PLAYER,
+                Some("Jh"),
+            ),
+            (SignatureStrategy::UsageSite, USAGE_SITE_PLAYER, Some("Yb")),
+            (SignatureStrategy::SplitAnchor, USAGE_SITE_PLAYER, None),
+            (SignatureStrategy::GlobalLookup, USAGE_SITE_PLAYER, None),
+        ];
+
+        for (strategy, player, function_name) in cases {
+            assert_eq!(
+                strategy.find_function_name(player).unwrap().as_deref(),
+                function_name,
+                "{} strategy",
+                strategy.name()
+            );
+        }
+    }
+
+    #[test]
+    fn no_strategy_finds_the_function_of_a_mangled_player() {
+        for strategy in SignatureStrategy::ALL {
+            assert!(
+                strategy
+                    .build_script(MANGLED_PLAYER, &["abc".into()])
+                    .unwrap()
+                    .is_none(),
+                "{} strategy",
+                strategy.name()
+            );
+        }
+    }
+
+    #[test]
+    fn scripts_declare_dependencies_before_the_function() {
+        let script = SignatureStrategy::GlobalLookup
+            .build_script(GLOBAL_LOOKUP_PLAYER, &["abc".into()])
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(
+            script.operations,
+            [
+                "a=a[Zq[0]](Zq[4])",
+                "Wd.Rt(a,3)",
+                "Wd.Pm(a,1)",
+                "return a[Zq[3]](Zq[4])"
+            ]
+        );
+
+        let function = script.script.find("var Jh=function(a)").unwrap();
+        let lookup = script
+            .script
+            .find(r#"var Zq="split;reverse;splice;join;".split(";");"#)
+            .unwrap();
+        let helpers = script.script.find("var Wd={Rt:function(a)").unwrap();
+        assert!(lookup < function && helpers < function);
+    }
+
+    #[test]
+    fn n_function_is_found_through_its_array() {
+        assert_eq!(
+            find_n_function_name(USAGE_SITE_PLAYER).unwrap().as_deref(),
+            Some("Ow")
+        );
+        assert!(find_n_function_name(SPLIT_ANCHOR_PLAYER).unwrap().is_none());
+    }
+
+    #[test]
+    fn snippet_is_bounded_around_the_anchor() {
+        let padding = "é".repeat(MAX_SNIPPET_CHARS);
+        let code = format!("{0}a=a.split(\"\");{0}", padding);
+        let snippet = snippet(&code).unwrap();
+
+        assert!(snippet.contains(r#"a=a.split("")"#));
+        assert!(snippet.len() <= MAX_SNIPPET_CHARS);
+        assert!(super::snippet("var a=1;").is_none());
+    }
+
+    #[cfg(feature = "js-interp")]
+    fn solve(script: Option<StrategyScript>, challenge: &str) -> String {
+        let output = crate::cipher::interp::eval(&script.unwrap().script).unwrap();
+        let mut output: StrategyOutput = serde_json::from_str(&output).unwrap();
+
+        assert_eq!(output.failed_op, None);
+        output.solved.remove(challenge).unwrap()
+    }
+
+    #[cfg(feature = "js-interp")]
+    #[test]
+    fn fixture_players_decipher_with_their_strategy() {
+        let challenge = "0123456789ABCDEFGHIJ";
+        let challenges = [challenge.to_string()];
+        // What the signature function of each player does, spelled out.
+        let reversed: String = challenge.chars().rev().collect();
+        let mut swapped: Vec<char> = reversed.chars().collect();
+        swapped.swap(0, 2);
+        let swapped: String = swapped.into_iter().collect();
+        let mut split_anchor: Vec<char> = reversed.chars().collect();
+        split_anchor.swap(0, 3);
+        let split_anchor: String = split_anchor[2..].iter().collect();
+
+        let cases = [
+            (
+                SignatureStrategy::SplitAnchor,
+                SPLIT_ANCHOR_PLAYER,
+                split_anchor,
+            ),
+            (
+                SignatureStrategy::GlobalLookup,
+                GLOBAL_LOOKUP_PLAYER,
+                reversed[1..].to_string(),
+            ),
+            (SignatureStrategy::UsageSite, USAGE_SITE_PLAYER, swapped),
+        ];
+
+        for (strategy, player, deciphered) in cases {
+            let script = strategy.build_script(player, &challenges).unwrap();
+            assert_eq!(solve(script, challenge), deciphered, "{}", strategy.name());
+        }
+
+        let script = build_n_script(USAGE_SITE_PLAYER, &challenges).unwrap();
+        assert_eq!(solve(script, challenge), "123456789ABCDEFGHIJ0");
+    }
+}
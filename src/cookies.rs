@@ -1,55 +1,760 @@
-use std::{collections::HashMap, sync::RwLock};
+use std::{
+    collections::HashMap,
+    fmt::Write,
+    net::IpAddr,
+    path::Path,
+    sync::RwLock,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
-use anyhow::{Result, anyhow};
+use anyhow::{Result, anyhow, bail};
+use reqwest::header::{HeaderMap, SET_COOKIE};
+use serde::{Deserialize, Serialize};
 use url::Url;
 
-pub(crate) type Cookies = HashMap<String, String>;
-pub(crate) type DomainMap = HashMap<Url, Cookies>;
+use crate::utils::parse_http_date;
 
-#[derive(Debug)]
-pub(crate) struct CookieJar {
+#[cfg(all(feature = "browser-cookies", not(target_arch = "wasm32")))]
+mod browser;
+
+#[cfg(all(feature = "browser-cookies", not(target_arch = "wasm32")))]
+pub use browser::Browser;
+
+pub type Cookies = HashMap<String, String>;
+/// Cookies bucketed by the registrable domain they belong to, keyed by their name, domain and path.
+pub(crate) type DomainMap = HashMap<String, HashMap<(String, String, String), Cookie>>;
+
+/// Attributes a cookie can be set with, as sent in a `Set-Cookie` header.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CookieAttributes {
+    /// Domain the cookie is valid for, including all of its subdomains.
+    /// The cookie is only sent to the exact host it was set from when missing.
+    pub domain: Option<String>,
+    /// Path prefix the cookie is valid for, defaults to the directory of the request path.
+    pub path: Option<String>,
+    /// Absolute expiry of the cookie.
+    pub expires: Option<SystemTime>,
+    /// Lifetime of the cookie in seconds, takes precedence over `expires`.
+    pub max_age: Option<i64>,
+    /// Only send the cookie over HTTPS.
+    pub secure: bool,
+    /// Hide the cookie from JavaScript, kept to round-trip browser exports.
+    pub http_only: bool,
+}
+
+/// Latest expiry a cookie can have, 9999-12-31T23:59:59Z, which longer lifetimes are cut down to. (RFC 6265 section 5.2.2)
+const MAX_EXPIRY: Duration = Duration::from_secs(253_402_300_799);
+
+impl CookieAttributes {
+    fn expiry(&self) -> Option<SystemTime> {
+        let max_expiry = UNIX_EPOCH + MAX_EXPIRY;
+
+        match self.max_age {
+            Some(max_age) if max_age <= 0 => Some(UNIX_EPOCH),
+            Some(max_age) => Some(
+                SystemTime::now()
+                    .checked_add(Duration::from_secs(max_age as u64))
+                    .map_or(max_expiry, |expiry| expiry.min(max_expiry)),
+            ),
+            None => self.expires,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cookie {
+    pub name: String,
+    pub value: String,
+    /// Domain of the cookie, without a leading dot.
+    pub domain: String,
+    pub path: String,
+    /// Host-only cookies are not sent to subdomains of `domain`.
+    pub host_only: bool,
+    /// Point in time after which the cookie must not be sent anymore.
+    pub expires: Option<SystemTime>,
+    /// Session cookies have no expiry and live as long as the jar does.
+    pub session: bool,
+    /// Secure cookies are never sent along with plain `http://` requests.
+    pub secure: bool,
+    pub http_only: bool,
+}
+
+impl Cookie {
+    pub fn is_expired(&self) -> bool {
+        match self.expires {
+            Some(expires) => expires <= SystemTime::now(),
+            None => false,
+        }
+    }
+
+    /// Check whether the cookie should be sent along with a request to `host` and `path`. (RFC 6265 section 5.4)
+    pub fn matches(&self, host: &str, path: &str) -> bool {
+        let domain_matches = match self.host_only {
+            true => host == self.domain,
+            false => domain_match(host, &self.domain),
+        };
+
+        domain_matches && path_match(path, &self.path)
+    }
+}
+
+const NETSCAPE_HTTP_ONLY_PREFIX: &str = "#HttpOnly_";
+
+/// On-disk representation of a cookie used by `CookieJar::save` and `CookieJar::load`.
+/// New fields must be optional so files written by older versions keep loading.
+#[derive(Debug, Serialize, Deserialize)]
+struct PersistedCookie {
+    value: String,
+    /// Expiry as seconds since the unix epoch, missing for session cookies.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    expires: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    path: Option<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    secure: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    http_only: bool,
+}
+
+/// Cookies sharing a name on one domain, which can only differ by their path.
+/// A single cookie is stored as an object, which is also the only form older versions wrote.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+enum PersistedCookieEntry {
+    Single(PersistedCookie),
+    Multiple(Vec<PersistedCookie>),
+}
+
+impl PersistedCookieEntry {
+    fn into_vec(self) -> Vec<PersistedCookie> {
+        match self {
+            Self::Single(cookie) => vec![cookie],
+            Self::Multiple(cookies) => cookies,
+        }
+    }
+}
+
+/// Domains of cookies valid for subdomains too are prefixed with a dot, like in the Netscape format.
+type PersistedCookies = HashMap<String, HashMap<String, PersistedCookieEntry>>;
+
+#[derive(Debug, Default)]
+pub struct CookieJar {
     cookies: RwLock<DomainMap>,
 }
 
 impl CookieJar {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
     pub fn new_from_domain(domain: &str, cookies: Cookies) -> Result<Self> {
-        let mut domain_map = HashMap::new();
-        domain_map.insert(Url::parse(domain)?, cookies);
+        let jar = Self::new();
 
-        Ok(Self {
-            cookies: RwLock::new(domain_map),
-        })
+        for (name, value) in cookies {
+            jar.set(domain, &name, &value)?;
+        }
+
+        Ok(jar)
+    }
+
+    /// Read a Netscape formatted `cookies.txt` file, like the ones exported by browser extensions or yt-dlp.
+    pub fn from_netscape_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)?;
+        Self::from_netscape_str(&contents)
+    }
+
+    /// Parse the contents of a Netscape formatted `cookies.txt` file.
+    /// Malformed lines are skipped instead of failing the whole import.
+    pub fn from_netscape_str(contents: &str) -> Result<Self> {
+        let jar = Self::new();
+
+        for line in contents.lines() {
+            // Only the line ending goes, a cookie with an empty value ends in a tab.
+            let line = line.trim_end_matches('\r');
+            let (line, http_only) = match line.strip_prefix(NETSCAPE_HTTP_ONLY_PREFIX) {
+                Some(http_only_line) => (http_only_line, true),
+                None if line.trim().is_empty() || line.starts_with('#') => continue,
+                None => (line, false),
+            };
+
+            let parts: Vec<&str> = line.split('\t').collect();
+
+            if parts.len() != 7 || parts[0].is_empty() || parts[5].is_empty() {
+                #[cfg(feature = "logging")]
+                log::warn!("Skipping malformed line in Netscape cookie file: {}", line);
+                continue;
+            }
+
+            let domain = parts[0].trim_start_matches('.');
+            let include_subdomains = parts[1].eq_ignore_ascii_case("TRUE");
+
+            // An expiry of 0 marks a session cookie.
+            let expires = match parts[4].parse::<u64>() {
+                Ok(0) | Err(_) => None,
+                Ok(expiry) => match UNIX_EPOCH.checked_add(Duration::from_secs(expiry)) {
+                    Some(expires) => Some(expires),
+                    None => {
+                        #[cfg(feature = "logging")]
+                        log::warn!("Skipping cookie with out of range expiry: {}", line);
+                        continue;
+                    }
+                },
+            };
+
+            let attributes = CookieAttributes {
+                domain: include_subdomains.then(|| domain.to_string()),
+                path: Some(parts[2].to_string()),
+                expires,
+                max_age: None,
+                secure: parts[3].eq_ignore_ascii_case("TRUE"),
+                http_only,
+            };
+
+            if jar
+                .set_with_attributes(
+                    &format!("https://{}", domain),
+                    parts[5],
+                    parts[6],
+                    &attributes,
+                )
+                .is_err()
+            {
+                #[cfg(feature = "logging")]
+                log::warn!("Skipping cookie with invalid domain \"{}\".", parts[0]);
+            }
+        }
+
+        Ok(jar)
+    }
+
+    /// Load a cookie jar previously written with `CookieJar::save`.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)?;
+        let persisted: PersistedCookies = serde_json::from_str(&contents)?;
+        let jar = Self::new();
+
+        for (domain, domain_cookies) in persisted {
+            let host = domain.trim_start_matches('.');
+
+            for (name, cookie) in domain_cookies.into_iter().flat_map(|(name, entry)| {
+                entry.into_vec().into_iter().map(move |c| (name.clone(), c))
+            }) {
+                // A cookie expiring too far out to represent is dropped rather than kept forever.
+                let expires = match cookie.expires {
+                    Some(expiry) => match UNIX_EPOCH.checked_add(Duration::from_secs(expiry)) {
+                        Some(expires) => Some(expires),
+                        None => continue,
+                    },
+                    None => None,
+                };
+
+                let attributes = CookieAttributes {
+                    domain: domain.starts_with('.').then(|| host.to_string()),
+                    path: cookie.path.or_else(|| Some("/".into())),
+                    expires,
+                    max_age: None,
+                    secure: cookie.secure,
+                    http_only: cookie.http_only,
+                };
+
+                jar.set_with_attributes(
+                    &format!("https://{}", host),
+                    &name,
+                    &cookie.value,
+                    &attributes,
+                )?;
+            }
+        }
+
+        jar.purge_expired()?;
+        Ok(jar)
+    }
+
+    /// Persist every cookie in the jar as JSON so it can be restored with `CookieJar::load`.
+    ///
+    /// The file is written to a temporary path first and then renamed,
+    /// so a crash midway never leaves a corrupted jar behind.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        save_cookies(self.snapshot()?, path)
+    }
+
+    /// Every unexpired cookie that should be sent along with a request to `url`,
+    /// ordered from the most to the least specific path and domain.
+    fn matching_cookies(&self, url: &str) -> Result<Vec<Cookie>> {
+        self.purge_expired()?;
+
+        let request_url = Url::parse(url)?;
+        let host = request_url.host_str().unwrap_or_default().to_lowercase();
+        let cookies = self.cookies.read().map_err(|e| anyhow!(e.to_string()))?;
+
+        let Some(bucket) = cookies.get(registrable_domain(&host)) else {
+            return Ok(vec![]);
+        };
+
+        let mut matching: Vec<Cookie> = bucket
+            .values()
+            .filter(|cookie| cookie.matches(&host, request_url.path()))
+            .filter(|cookie| !cookie.secure || request_url.scheme() == "https")
+            .cloned()
+            .collect();
+
+        matching.sort_by(|a, b| {
+            (b.path.len(), b.host_only, b.domain.len())
+                .cmp(&(a.path.len(), a.host_only, a.domain.len()))
+                .then_with(|| a.name.cmp(&b.name))
+        });
+
+        Ok(matching)
+    }
+
+    /// Write every cookie in the jar to a Netscape formatted `cookies.txt` file.
+    pub fn to_netscape_file<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        std::fs::write(path, self.to_netscape_string()?)?;
+        Ok(())
+    }
+
+    /// Serialize every cookie in the jar into the Netscape `cookies.txt` format.
+    pub fn to_netscape_string(&self) -> Result<String> {
+        let cookies = self.cookies.read().map_err(|e| anyhow!(e.to_string()))?;
+        let mut netscape = String::from("# Netscape HTTP Cookie File\n\n");
+
+        for cookie in cookies.values().flat_map(|bucket| bucket.values()) {
+            if cookie.is_expired() {
+                continue;
+            }
+
+            let (domain, include_subdomains) = match cookie.host_only {
+                true => (cookie.domain.clone(), "FALSE"),
+                false => (format!(".{}", cookie.domain), "TRUE"),
+            };
+            let expiry = cookie
+                .expires
+                .and_then(|expires| expires.duration_since(UNIX_EPOCH).ok())
+                .map(|expires| expires.as_secs())
+                .unwrap_or_default();
+            let http_only_prefix = match cookie.http_only {
+                true => NETSCAPE_HTTP_ONLY_PREFIX,
+                false => "",
+            };
+            let secure = match cookie.secure {
+                true => "TRUE",
+                false => "FALSE",
+            };
+
+            writeln!(
+                netscape,
+                "{}{}\t{}\t{}\t{}\t{}\t{}\t{}",
+                http_only_prefix,
+                domain,
+                include_subdomains,
+                cookie.path,
+                secure,
+                expiry,
+                cookie.name,
+                cookie.value
+            )?;
+        }
+
+        Ok(netscape)
     }
 }
 
-pub(crate) trait CookieStore {
-    fn get_all(&self, domain: &str) -> Result<Option<Cookies>>;
-    fn set(&self, domain: &str, name: &str, value: &str) -> Result<()>;
+pub trait CookieStore {
+    /// Get every cookie that should be sent along with a request to `url`.
+    /// Secure cookies are left out unless `url` is HTTPS.
+    fn get_all(&self, url: &str) -> Result<Option<Cookies>>;
+    /// Build the value of the `Cookie` header to send along with a request to `url`, `None` when no cookie matches.
+    ///
+    /// Cookies with longer paths come first (RFC 6265 section 5.4) and values are sent exactly as stored.
+    /// The default implementation is built on `get_all`, so it can't contain multiple cookies with the same name.
+    fn cookie_header(&self, url: &str) -> Result<Option<String>> {
+        let Some(cookies) = self.get_all(url)? else {
+            return Ok(None);
+        };
+
+        let mut pairs: Vec<(String, String)> = cookies.into_iter().collect();
+        pairs.sort();
+
+        Ok(Some(
+            pairs
+                .iter()
+                .map(|(name, value)| format!("{}={}", name, value))
+                .collect::<Vec<_>>()
+                .join("; "),
+        ))
+    }
+    fn set(&self, url: &str, name: &str, value: &str) -> Result<()>;
+    /// Set a cookie from `url` with the attributes of a `Set-Cookie` header, like `Domain` or `Secure`.
+    fn set_with_attributes(
+        &self,
+        url: &str,
+        name: &str,
+        value: &str,
+        attributes: &CookieAttributes,
+    ) -> Result<()>;
+    /// Remove every cookie that is past its expiry.
+    fn purge_expired(&self) -> Result<()>;
+    /// Store every `Set-Cookie` header of a response received from `url`.
+    fn store_response_cookies(&self, url: &str, headers: &HeaderMap) -> Result<()>;
+    /// Remove every cookie called `name` set for exactly `domain`, regardless of its path.
+    fn remove(&self, domain: &str, name: &str) -> Result<()>;
+    /// Remove every cookie set for exactly `domain`.
+    fn clear(&self, domain: &str) -> Result<()>;
+    fn clear_all(&self) -> Result<()>;
+    /// Every domain the store holds cookies for, without a leading dot.
+    fn domains(&self) -> Result<Vec<String>>;
+    /// Copy of every cookie in the store, including ones not yet purged after expiring.
+    fn snapshot(&self) -> Result<Vec<Cookie>>;
 }
 
 impl CookieStore for CookieJar {
-    fn get_all(&self, domain: &str) -> Result<Option<Cookies>> {
-        let domain_url = Url::parse(domain)?;
+    fn get_all(&self, url: &str) -> Result<Option<Cookies>> {
+        let matching = self.matching_cookies(url)?;
+
+        if matching.is_empty() {
+            return Ok(None);
+        }
+
+        // The most specific cookie is inserted last, so it wins on name collisions.
+        Ok(Some(
+            matching
+                .into_iter()
+                .rev()
+                .map(|cookie| (cookie.name, cookie.value))
+                .collect(),
+        ))
+    }
+
+    fn cookie_header(&self, url: &str) -> Result<Option<String>> {
+        let matching = self.matching_cookies(url)?;
+
+        if matching.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some(
+            matching
+                .iter()
+                .map(|cookie| format!("{}={}", cookie.name, cookie.value))
+                .collect::<Vec<_>>()
+                .join("; "),
+        ))
+    }
+
+    fn set(&self, url: &str, name: &str, value: &str) -> Result<()> {
+        self.set_with_attributes(url, name, value, &CookieAttributes::default())
+    }
+
+    fn set_with_attributes(
+        &self,
+        url: &str,
+        name: &str,
+        value: &str,
+        attributes: &CookieAttributes,
+    ) -> Result<()> {
+        let request_url = Url::parse(url)?;
+        let host = request_url
+            .host_str()
+            .ok_or_else(|| anyhow!("Cannot set a cookie for \"{}\" since it has no host.", url))?
+            .to_lowercase();
+
+        let (domain, host_only) = match &attributes.domain {
+            Some(domain) => {
+                let domain = normalize_domain(domain);
+
+                if !domain_match(&host, &domain) {
+                    bail!(
+                        "Cannot set a cookie for domain \"{}\" from \"{}\".",
+                        domain,
+                        host
+                    );
+                }
+
+                // A cookie for a public suffix would be sent to every site under it. (RFC 6265 section 5.3)
+                match is_public_suffix(&domain) {
+                    true if domain == host => (host, true),
+                    true => bail!(
+                        "Cannot set a cookie for the public suffix \"{}\" from \"{}\".",
+                        domain,
+                        host
+                    ),
+                    false => (domain, false),
+                }
+            }
+            None => (host, true),
+        };
+
+        let path = match &attributes.path {
+            Some(path) if path.starts_with('/') => path.clone(),
+            _ => default_path(request_url.path()),
+        };
+
+        let expires = attributes.expiry();
+        let cookie = Cookie {
+            name: name.into(),
+            value: value.into(),
+            domain: domain.clone(),
+            path: path.clone(),
+            host_only,
+            expires,
+            session: expires.is_none(),
+            secure: attributes.secure,
+            http_only: attributes.http_only,
+        };
+
+        let mut cookies = self.cookies.write().map_err(|e| anyhow!(e.to_string()))?;
+        cookies
+            .entry(registrable_domain(&domain).to_string())
+            .or_default()
+            .insert((name.into(), domain, path), cookie);
+
+        Ok(())
+    }
+
+    fn purge_expired(&self) -> Result<()> {
+        let mut cookies = self.cookies.write().map_err(|e| anyhow!(e.to_string()))?;
+
+        for bucket in cookies.values_mut() {
+            bucket.retain(|_, cookie| !cookie.is_expired());
+        }
+        cookies.retain(|_, bucket| !bucket.is_empty());
+
+        Ok(())
+    }
+
+    fn store_response_cookies(&self, url: &str, headers: &HeaderMap) -> Result<()> {
+        for set_cookie in headers.get_all(SET_COOKIE) {
+            let Ok(set_cookie) = set_cookie.to_str() else {
+                continue;
+            };
+
+            if let Some((name, value, attributes)) = parse_set_cookie(set_cookie) {
+                // Cookies for a domain the response isn't allowed to set are ignored, like browsers do.
+                let _ = self.set_with_attributes(url, &name, &value, &attributes);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn remove(&self, domain: &str, name: &str) -> Result<()> {
+        let domain = normalize_domain(domain);
+        let mut cookies = self.cookies.write().map_err(|e| anyhow!(e.to_string()))?;
+
+        if let Some(bucket) = cookies.get_mut(registrable_domain(&domain)) {
+            bucket.retain(|_, cookie| cookie.domain != domain || cookie.name != name);
+        }
+        cookies.retain(|_, bucket| !bucket.is_empty());
+
+        Ok(())
+    }
+
+    fn clear(&self, domain: &str) -> Result<()> {
+        let domain = normalize_domain(domain);
+        let mut cookies = self.cookies.write().map_err(|e| anyhow!(e.to_string()))?;
+
+        if let Some(bucket) = cookies.get_mut(registrable_domain(&domain)) {
+            bucket.retain(|_, cookie| cookie.domain != domain);
+        }
+        cookies.retain(|_, bucket| !bucket.is_empty());
+
+        Ok(())
+    }
+
+    fn clear_all(&self) -> Result<()> {
+        self.cookies
+            .write()
+            .map_err(|e| anyhow!(e.to_string()))?
+            .clear();
+
+        Ok(())
+    }
+
+    fn domains(&self) -> Result<Vec<String>> {
+        let mut domains: Vec<String> = self
+            .snapshot()?
+            .into_iter()
+            .map(|cookie| cookie.domain)
+            .collect();
+
+        domains.sort();
+        domains.dedup();
+
+        Ok(domains)
+    }
+
+    fn snapshot(&self) -> Result<Vec<Cookie>> {
         Ok(self
             .cookies
-            .write()
+            .read()
             .map_err(|e| anyhow!(e.to_string()))?
-            .get(&domain_url)
-            .cloned())
+            .values()
+            .flat_map(|bucket| bucket.values().cloned())
+            .collect())
     }
+}
 
-    fn set(&self, domain: &str, name: &str, value: &str) -> Result<()> {
-        let domain_url = Url::parse(domain)?;
-        let mut cookies = self.cookies.write().map_err(|e| anyhow!(e.to_string()))?;
+/// Write `cookies` in the JSON format read by `CookieJar::load`, through a temporary file.
+pub(crate) fn save_cookies<P: AsRef<Path>>(cookies: Vec<Cookie>, path: P) -> Result<()> {
+    let mut grouped: HashMap<String, HashMap<String, Vec<PersistedCookie>>> = HashMap::new();
 
-        if let Some(cookies) = cookies.get_mut(&domain_url) {
-            cookies.insert(name.into(), value.into());
+    for cookie in cookies {
+        if cookie.is_expired() {
+            continue;
         }
 
-        Ok(())
+        let domain = match cookie.host_only {
+            true => cookie.domain.clone(),
+            false => format!(".{}", cookie.domain),
+        };
+
+        grouped
+            .entry(domain)
+            .or_default()
+            .entry(cookie.name)
+            .or_default()
+            .push(PersistedCookie {
+                value: cookie.value,
+                expires: cookie
+                    .expires
+                    .and_then(|expires| expires.duration_since(UNIX_EPOCH).ok())
+                    .map(|expires| expires.as_secs()),
+                path: Some(cookie.path),
+                secure: cookie.secure,
+                http_only: cookie.http_only,
+            });
+    }
+
+    let persisted: PersistedCookies = grouped
+        .into_iter()
+        .map(|(domain, domain_cookies)| {
+            let entries = domain_cookies
+                .into_iter()
+                .map(|(name, mut cookies)| {
+                    let entry = match cookies.len() {
+                        1 => PersistedCookieEntry::Single(cookies.remove(0)),
+                        _ => {
+                            cookies.sort_by(|a, b| a.path.cmp(&b.path));
+                            PersistedCookieEntry::Multiple(cookies)
+                        }
+                    };
+
+                    (name, entry)
+                })
+                .collect();
+
+            (domain, entries)
+        })
+        .collect();
+
+    let path = path.as_ref();
+    let mut temp_path = path.as_os_str().to_owned();
+    temp_path.push(".tmp");
+
+    std::fs::write(&temp_path, serde_json::to_string_pretty(&persisted)?)?;
+    std::fs::rename(&temp_path, path)?;
+
+    Ok(())
+}
+
+fn normalize_domain(domain: &str) -> String {
+    domain.trim_start_matches('.').to_lowercase()
+}
+
+/// Second-level labels used as public suffixes under country code TLDs, like `co.uk` or `com.au`,
+/// which is where the regional Google and YouTube hosts live.
+const SECOND_LEVEL_SUFFIXES: [&str; 10] = [
+    "ac", "co", "com", "edu", "gov", "go", "ne", "net", "or", "org",
+];
+
+/// Whether `domain` is a public suffix that cookies must never be scoped to, like `com` or `co.uk`.
+/// This is a small approximation of the Public Suffix List that covers the hosts the crate talks to.
+fn is_public_suffix(domain: &str) -> bool {
+    let mut labels = domain.rsplit('.');
+    let (Some(tld), second_level, rest) = (labels.next(), labels.next(), labels.next()) else {
+        return true;
+    };
+
+    match (second_level, rest) {
+        (None, _) => true,
+        (Some(second_level), None) => {
+            tld.len() == 2 && SECOND_LEVEL_SUFFIXES.contains(&second_level)
+        }
+        _ => false,
+    }
+}
+
+/// Registrable domain (eTLD+1) of `host` which is used to bucket cookies, like `youtube.com`
+/// for `www.youtube.com` or `google.co.uk` for `accounts.google.co.uk`.
+/// Lookups still check every cookie in the bucket with RFC 6265 domain matching.
+fn registrable_domain(host: &str) -> &str {
+    if host.parse::<IpAddr>().is_ok() {
+        return host;
+    }
+
+    // Suffixes of `host` from the shortest to the longest, the first that isn't public is registrable.
+    host.rmatch_indices('.')
+        .map(|(index, _)| &host[index + 1..])
+        .chain([host])
+        .find(|suffix| !is_public_suffix(suffix))
+        .unwrap_or(host)
+}
+
+/// Domain matching as described by RFC 6265 section 5.1.3.
+fn domain_match(host: &str, domain: &str) -> bool {
+    host == domain
+        || (host.ends_with(domain)
+            && host[..host.len() - domain.len()].ends_with('.')
+            && host.parse::<IpAddr>().is_err())
+}
+
+/// Path matching as described by RFC 6265 section 5.1.4.
+fn path_match(request_path: &str, cookie_path: &str) -> bool {
+    request_path == cookie_path
+        || (request_path.starts_with(cookie_path)
+            && (cookie_path.ends_with('/') || request_path[cookie_path.len()..].starts_with('/')))
+}
+
+/// Default path of a cookie set without a `Path` attribute. (RFC 6265 section 5.1.4)
+fn default_path(request_path: &str) -> String {
+    match request_path.rfind('/') {
+        Some(0) | None => "/".into(),
+        Some(index) => request_path[..index].into(),
     }
 }
 
+/// Parse the `name=value` pair of a `Set-Cookie` header value along with its attributes.
+pub(crate) fn parse_set_cookie(set_cookie: &str) -> Option<(String, String, CookieAttributes)> {
+    let mut parts = set_cookie.split(';');
+    let (name, value) = parts.next()?.split_once('=')?;
+    let name = name.trim();
+
+    if name.is_empty() {
+        return None;
+    }
+
+    let mut attributes = CookieAttributes::default();
+
+    for attribute in parts {
+        let (key, attribute_value) = attribute.split_once('=').unwrap_or((attribute, ""));
+        let attribute_value = attribute_value.trim();
+
+        match key.trim().to_ascii_lowercase().as_str() {
+            "domain" if !attribute_value.is_empty() => {
+                attributes.domain = Some(attribute_value.to_string())
+            }
+            "path" => attributes.path = Some(attribute_value.to_string()),
+            "expires" => attributes.expires = parse_http_date(attribute_value),
+            "max-age" => attributes.max_age = attribute_value.parse().ok(),
+            "secure" => attributes.secure = true,
+            "httponly" => attributes.http_only = true,
+            _ => {}
+        }
+    }
+
+    Some((name.to_string(), value.trim().to_string(), attributes))
+}
+
 /// Parse a Netscape formatted cookie file into a `HashMap`
 pub fn read_from_cookie_file(path: &str) -> Result<HashMap<String, String>> {
     let cookie_file_contents = std::fs::read_to_string(path)?;
@@ -76,3 +781,611 @@ pub fn read_from_cookie_file(path: &str) -> Result<HashMap<String, String>> {
 
     Ok(cookies)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn set_then_get_on_fresh_jar() {
+        let jar = CookieJar::new();
+        jar.set("https://www.youtube.com", "PREF", "hl=en").unwrap();
+
+        let cookies = jar.get_all("https://www.youtube.com").unwrap().unwrap();
+        assert_eq!(cookies.get("PREF").map(String::as_str), Some("hl=en"));
+    }
+
+    #[test]
+    fn set_overwrites_existing_name() {
+        let jar = CookieJar::new();
+        jar.set("https://www.youtube.com", "PREF", "hl=en").unwrap();
+        jar.set("https://www.youtube.com", "PREF", "hl=de").unwrap();
+
+        let cookies = jar.get_all("https://www.youtube.com").unwrap().unwrap();
+        assert_eq!(cookies.len(), 1);
+        assert_eq!(cookies.get("PREF").map(String::as_str), Some("hl=de"));
+    }
+
+    #[test]
+    fn set_for_two_domains() {
+        let jar = CookieJar::new();
+        jar.set("https://www.youtube.com", "PREF", "hl=en").unwrap();
+        jar.set("https://accounts.google.com", "SID", "abc")
+            .unwrap();
+
+        let youtube = jar.get_all("https://www.youtube.com").unwrap().unwrap();
+        let google = jar.get_all("https://accounts.google.com").unwrap().unwrap();
+
+        assert_eq!(youtube.get("PREF").map(String::as_str), Some("hl=en"));
+        assert!(!youtube.contains_key("SID"));
+        assert_eq!(google.get("SID").map(String::as_str), Some("abc"));
+        assert!(!google.contains_key("PREF"));
+    }
+
+    #[test]
+    fn max_age_takes_precedence_over_expires() {
+        let jar = CookieJar::new();
+        let expired = CookieAttributes {
+            expires: Some(SystemTime::now() + Duration::from_secs(3600)),
+            max_age: Some(0),
+            ..Default::default()
+        };
+        let alive = CookieAttributes {
+            expires: Some(UNIX_EPOCH + Duration::from_secs(1)),
+            max_age: Some(3600),
+            ..Default::default()
+        };
+
+        jar.set_with_attributes("https://www.youtube.com", "GONE", "1", &expired)
+            .unwrap();
+        jar.set_with_attributes("https://www.youtube.com", "KEPT", "1", &alive)
+            .unwrap();
+
+        let cookies = jar.get_all("https://www.youtube.com").unwrap().unwrap();
+        assert!(!cookies.contains_key("GONE"));
+        assert!(cookies.contains_key("KEPT"));
+    }
+
+    #[test]
+    fn huge_max_age_is_clamped_to_a_persistent_cookie() {
+        let jar = CookieJar::new();
+        let attributes = CookieAttributes {
+            max_age: Some(i64::MAX),
+            ..Default::default()
+        };
+
+        jar.set_with_attributes("https://www.youtube.com", "PREF", "1", &attributes)
+            .unwrap();
+
+        let cookie = jar.snapshot().unwrap().remove(0);
+        assert!(!cookie.is_expired());
+        assert!(!cookie.session);
+        assert_eq!(cookie.expires, Some(UNIX_EPOCH + MAX_EXPIRY));
+
+        // Unlike a session cookie, it's kept when the jar is saved and loaded again.
+        let path = std::env::temp_dir().join(format!("tydle-max-age-{}.json", std::process::id()));
+        jar.save(&path).unwrap();
+        let loaded = CookieJar::load(&path)
+            .unwrap()
+            .snapshot()
+            .unwrap()
+            .remove(0);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.expires, Some(UNIX_EPOCH + MAX_EXPIRY));
+        assert!(!loaded.session);
+    }
+
+    #[test]
+    fn session_cookies_never_expire_on_time() {
+        let jar = CookieJar::new();
+        jar.set("https://www.youtube.com", "YSC", "abc").unwrap();
+        jar.purge_expired().unwrap();
+
+        let cookie = jar.snapshot().unwrap().remove(0);
+        assert!(cookie.session);
+        assert_eq!(cookie.expires, None);
+        assert!(!cookie.is_expired());
+    }
+
+    #[test]
+    fn netscape_skips_out_of_range_expiry() {
+        let jar = CookieJar::from_netscape_str(&format!(
+            ".youtube.com\tTRUE\t/\tTRUE\t{}\tBAD\t1\n.youtube.com\tTRUE\t/\tTRUE\t0\tGOOD\t1\n",
+            u64::MAX
+        ))
+        .unwrap();
+
+        let cookies = jar.get_all("https://www.youtube.com").unwrap().unwrap();
+        assert!(!cookies.contains_key("BAD"));
+        assert!(cookies.contains_key("GOOD"));
+    }
+
+    /// The cookies of `jar` ordered by name, to compare jars with.
+    fn sorted_cookies(jar: &CookieJar) -> Vec<Cookie> {
+        let mut cookies = jar.snapshot().unwrap();
+        cookies.sort_by(|a, b| a.name.cmp(&b.name));
+        cookies
+    }
+
+    #[test]
+    fn netscape_export_of_youtube_round_trips() {
+        let export = include_str!("../tests/fixtures/cookies_youtube.txt");
+        let jar = CookieJar::from_netscape_str(export).unwrap();
+        let cookies = sorted_cookies(&jar);
+
+        assert_eq!(cookies.len(), 14);
+
+        let cookie = |name: &str| cookies.iter().find(|cookie| cookie.name == name).unwrap();
+        assert_eq!(cookie("wide").value, "");
+        assert!(cookie("wide").host_only);
+        assert_eq!(cookie("ST-1b").value, "");
+        assert!(cookie("ST-1b").http_only);
+        assert!(cookie("LOGIN_INFO").http_only && cookie("LOGIN_INFO").secure);
+        assert!(cookie("YSC").session);
+        assert!(!cookie("HSID").http_only && !cookie("HSID").secure);
+        assert_eq!(cookie("PREF").value, "f6=40000000&tz=Europe.Berlin&f7=100");
+
+        let exported = jar.to_netscape_string().unwrap();
+        assert!(exported.contains("www.youtube.com\tFALSE\t/\tFALSE\t1856000000\twide\t\n"));
+        assert!(exported.contains("#HttpOnly_.youtube.com\tTRUE\t/\tTRUE\t0\tYSC\tdQw4w9WgXcQ\n"));
+        assert_eq!(
+            sorted_cookies(&CookieJar::from_netscape_str(&exported).unwrap()),
+            cookies
+        );
+
+        // Files written on Windows end their lines in `\r\n`.
+        let crlf = export.replace('\n', "\r\n");
+        assert_eq!(
+            sorted_cookies(&CookieJar::from_netscape_str(&crlf).unwrap()),
+            cookies
+        );
+    }
+
+    fn domain_attributes(domain: &str, path: &str) -> CookieAttributes {
+        CookieAttributes {
+            domain: Some(domain.into()),
+            path: Some(path.into()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn subdomains_get_parent_domain_cookies() {
+        let jar = CookieJar::new();
+        jar.set_with_attributes(
+            "https://youtube.com",
+            "DOTTED",
+            "1",
+            &domain_attributes(".youtube.com", "/"),
+        )
+        .unwrap();
+        jar.set_with_attributes(
+            "https://youtube.com",
+            "PARENT",
+            "1",
+            &domain_attributes("youtube.com", "/"),
+        )
+        .unwrap();
+        jar.set("https://youtube.com/", "HOST_ONLY", "1").unwrap();
+        jar.set("https://www.youtube.com/", "WWW", "1").unwrap();
+
+        let cookies = jar
+            .get_all("https://www.youtube.com/youtubei/v1/player")
+            .unwrap()
+            .unwrap();
+
+        let mut names: Vec<&str> = cookies.keys().map(String::as_str).collect();
+        names.sort();
+        assert_eq!(names, ["DOTTED", "PARENT", "WWW"]);
+    }
+
+    #[test]
+    fn more_specific_paths_shadow_others() {
+        let jar = CookieJar::new();
+        jar.set_with_attributes(
+            "https://www.youtube.com",
+            "PREF",
+            "root",
+            &domain_attributes("youtube.com", "/"),
+        )
+        .unwrap();
+        jar.set_with_attributes(
+            "https://www.youtube.com",
+            "PREF",
+            "api",
+            &domain_attributes("youtube.com", "/youtubei"),
+        )
+        .unwrap();
+
+        let api = jar
+            .get_all("https://www.youtube.com/youtubei/v1/player")
+            .unwrap()
+            .unwrap();
+        let watch = jar
+            .get_all("https://www.youtube.com/watch")
+            .unwrap()
+            .unwrap();
+        let lookalike = jar
+            .get_all("https://www.youtube.com/youtubeiv")
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(api.get("PREF").map(String::as_str), Some("api"));
+        assert_eq!(watch.get("PREF").map(String::as_str), Some("root"));
+        assert_eq!(lookalike.get("PREF").map(String::as_str), Some("root"));
+    }
+
+    #[test]
+    fn googlevideo_cookies_stay_on_googlevideo() {
+        let jar = CookieJar::new();
+        jar.set_with_attributes(
+            "https://rr1---sn-abc.googlevideo.com",
+            "GV",
+            "1",
+            &domain_attributes("googlevideo.com", "/"),
+        )
+        .unwrap();
+        jar.set("https://www.youtube.com", "PREF", "1").unwrap();
+
+        assert!(
+            jar.set_with_attributes(
+                "https://rr1---sn-abc.googlevideo.com",
+                "CROSS",
+                "1",
+                &domain_attributes("youtube.com", "/"),
+            )
+            .is_err()
+        );
+
+        let youtube = jar.get_all("https://www.youtube.com").unwrap().unwrap();
+        assert!(!youtube.contains_key("GV"));
+        assert!(!youtube.contains_key("CROSS"));
+        assert!(jar.get_all("https://youtube.com").unwrap().is_none());
+        assert!(
+            jar.get_all("https://rr5---sn-xyz.googlevideo.com/videoplayback")
+                .unwrap()
+                .unwrap()
+                .contains_key("GV")
+        );
+    }
+
+    #[test]
+    fn secure_cookies_are_only_sent_over_https() {
+        let jar = CookieJar::new();
+        let secure = CookieAttributes {
+            secure: true,
+            ..domain_attributes(".youtube.com", "/")
+        };
+        jar.set_with_attributes(
+            "https://www.youtube.com",
+            "__Secure-3PSID",
+            "g.a000",
+            &secure,
+        )
+        .unwrap();
+        jar.set_with_attributes(
+            "https://www.youtube.com",
+            "PREF",
+            "hl=en",
+            &domain_attributes(".youtube.com", "/"),
+        )
+        .unwrap();
+
+        assert_eq!(
+            jar.cookie_header("https://www.youtube.com/watch").unwrap(),
+            Some("PREF=hl=en; __Secure-3PSID=g.a000".into())
+        );
+        assert_eq!(
+            jar.cookie_header("http://www.youtube.com/watch").unwrap(),
+            Some("PREF=hl=en".into())
+        );
+        let cookies = jar
+            .get_all("http://www.youtube.com/watch")
+            .unwrap()
+            .unwrap();
+        assert!(!cookies.contains_key("__Secure-3PSID"));
+    }
+
+    #[test]
+    fn remove_drops_every_path_of_the_named_cookie_on_that_domain_only() {
+        let jar = CookieJar::new();
+        for (url, name, domain, path) in [
+            ("https://www.youtube.com", "GPS", ".youtube.com", "/"),
+            ("https://www.youtube.com", "GPS", ".youtube.com", "/feed"),
+            ("https://www.youtube.com", "PREF", ".youtube.com", "/"),
+            ("https://www.google.com", "GPS", ".google.com", "/"),
+        ] {
+            jar.set_with_attributes(url, name, "1", &domain_attributes(domain, path))
+                .unwrap();
+        }
+
+        jar.remove(".youtube.com", "GPS").unwrap();
+
+        assert_eq!(
+            jar.cookie_header("https://www.youtube.com/feed").unwrap(),
+            Some("PREF=1".into())
+        );
+        assert_eq!(
+            jar.cookie_header("https://www.google.com/").unwrap(),
+            Some("GPS=1".into())
+        );
+    }
+
+    #[test]
+    fn clear_and_clear_all_update_domains() {
+        let jar = CookieJar::new();
+        jar.set("https://www.youtube.com", "PREF", "hl=en").unwrap();
+        jar.set_with_attributes(
+            "https://www.youtube.com",
+            "VISITOR_INFO1_LIVE",
+            "abc",
+            &domain_attributes(".youtube.com", "/"),
+        )
+        .unwrap();
+        jar.set("https://accounts.google.com", "SID", "abc")
+            .unwrap();
+        jar.set("https://www.google.com", "NID", "1").unwrap();
+
+        assert_eq!(
+            jar.domains().unwrap(),
+            [
+                "accounts.google.com",
+                "www.google.com",
+                "www.youtube.com",
+                "youtube.com"
+            ]
+        );
+
+        jar.clear("www.youtube.com").unwrap();
+        jar.clear(".google.com").unwrap();
+
+        assert_eq!(
+            jar.domains().unwrap(),
+            ["accounts.google.com", "www.google.com", "youtube.com"]
+        );
+        assert_eq!(
+            jar.cookie_header("https://www.youtube.com/").unwrap(),
+            Some("VISITOR_INFO1_LIVE=abc".into())
+        );
+
+        jar.clear_all().unwrap();
+
+        assert!(jar.domains().unwrap().is_empty());
+        assert!(jar.snapshot().unwrap().is_empty());
+        assert_eq!(jar.cookie_header("https://www.youtube.com/").unwrap(), None);
+    }
+
+    #[test]
+    fn registrable_domains() {
+        assert_eq!(registrable_domain("www.youtube.com"), "youtube.com");
+        assert_eq!(registrable_domain("youtube.com"), "youtube.com");
+        assert_eq!(
+            registrable_domain("rr1---sn-abc.googlevideo.com"),
+            "googlevideo.com"
+        );
+        assert_eq!(registrable_domain("accounts.google.co.uk"), "google.co.uk");
+        assert_eq!(registrable_domain("www.google.com.au"), "google.com.au");
+        assert_eq!(registrable_domain("co.uk"), "co.uk");
+        assert_eq!(registrable_domain("localhost"), "localhost");
+        assert_eq!(registrable_domain("127.0.0.1"), "127.0.0.1");
+    }
+
+    #[test]
+    fn cookies_cannot_be_set_for_public_suffixes() {
+        let jar = CookieJar::new();
+
+        assert!(
+            jar.set_with_attributes(
+                "https://www.google.co.uk",
+                "SID",
+                "1",
+                &domain_attributes("co.uk", "/"),
+            )
+            .is_err()
+        );
+        assert!(
+            jar.set_with_attributes(
+                "https://www.youtube.com",
+                "SID",
+                "1",
+                &domain_attributes("com", "/"),
+            )
+            .is_err()
+        );
+        assert!(jar.get_all("https://www.bbc.co.uk").unwrap().is_none());
+    }
+
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn cookie_jar_and_tydle_are_send_sync() {
+        assert_send_sync::<CookieJar>();
+        assert_send_sync::<crate::tydle::Tydle>();
+    }
+
+    #[test]
+    fn concurrent_set_and_get_all() {
+        const THREADS: usize = 16;
+        const ITERATIONS: usize = 500;
+
+        let jar = std::sync::Arc::new(CookieJar::new());
+        let handles: Vec<_> = (0..THREADS)
+            .map(|thread| {
+                let jar = jar.clone();
+
+                std::thread::spawn(move || {
+                    for iteration in 0..ITERATIONS {
+                        let name = format!("C{}", thread);
+                        jar.set("https://www.youtube.com", &name, &iteration.to_string())
+                            .unwrap();
+
+                        let cookies = jar.get_all("https://www.youtube.com").unwrap().unwrap();
+                        assert!(cookies.contains_key(&name));
+                        jar.cookie_header("https://www.youtube.com").unwrap();
+                    }
+                })
+            })
+            .collect();
+
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        let cookies = jar.get_all("https://www.youtube.com").unwrap().unwrap();
+        assert_eq!(cookies.len(), THREADS);
+        assert!(
+            cookies
+                .values()
+                .all(|value| value == &(ITERATIONS - 1).to_string())
+        );
+    }
+
+    #[test]
+    fn save_and_load_keeps_cookies_with_the_same_name_on_different_paths() {
+        let path = std::env::temp_dir().join(format!("tydle-cookies-{}.json", std::process::id()));
+        let jar = CookieJar::new();
+        jar.set_with_attributes(
+            "https://www.youtube.com",
+            "PREF",
+            "root",
+            &domain_attributes("youtube.com", "/"),
+        )
+        .unwrap();
+        jar.set_with_attributes(
+            "https://www.youtube.com",
+            "PREF",
+            "api",
+            &domain_attributes("youtube.com", "/youtubei"),
+        )
+        .unwrap();
+        jar.set("https://www.youtube.com/", "PREF", "host").unwrap();
+
+        jar.save(&path).unwrap();
+        let loaded = CookieJar::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let mut cookies = loaded.snapshot().unwrap();
+        cookies.sort_by(|a, b| (&a.path, a.host_only).cmp(&(&b.path, b.host_only)));
+        let cookies: Vec<(&str, &str, bool)> = cookies
+            .iter()
+            .map(|c| (c.path.as_str(), c.value.as_str(), c.host_only))
+            .collect();
+
+        assert_eq!(
+            cookies,
+            [
+                ("/", "root", false),
+                ("/", "host", true),
+                ("/youtubei", "api", false)
+            ]
+        );
+    }
+
+    #[test]
+    fn load_reads_older_single_cookie_files() {
+        let path =
+            std::env::temp_dir().join(format!("tydle-cookies-old-{}.json", std::process::id()));
+        std::fs::write(
+            &path,
+            r#"{".youtube.com": {"PREF": {"value": "hl=en"}, "SID": {"value": "abc", "expires": 4102444800}}}"#,
+        )
+        .unwrap();
+
+        let jar = CookieJar::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let cookies = jar.get_all("https://www.youtube.com").unwrap().unwrap();
+        assert_eq!(cookies.get("PREF").map(String::as_str), Some("hl=en"));
+        assert_eq!(cookies.get("SID").map(String::as_str), Some("abc"));
+    }
+
+    #[test]
+    fn cookie_header_keeps_special_characters() {
+        let jar = CookieJar::new();
+        jar.set("https://www.youtube.com/", "PREF", "f6=40000000&hl=en%2DUS")
+            .unwrap();
+        jar.set("https://www.youtube.com/", "TOKEN", "a+b/c==")
+            .unwrap();
+
+        assert_eq!(
+            jar.cookie_header("https://www.youtube.com/").unwrap(),
+            Some("PREF=f6=40000000&hl=en%2DUS; TOKEN=a+b/c==".into())
+        );
+    }
+
+    #[test]
+    fn cookie_header_orders_same_name_by_path() {
+        let jar = CookieJar::new();
+        jar.set_with_attributes(
+            "https://www.youtube.com",
+            "PREF",
+            "root",
+            &domain_attributes("youtube.com", "/"),
+        )
+        .unwrap();
+        jar.set_with_attributes(
+            "https://www.youtube.com",
+            "PREF",
+            "api",
+            &domain_attributes("youtube.com", "/youtubei/v1"),
+        )
+        .unwrap();
+
+        assert_eq!(
+            jar.cookie_header("https://www.youtube.com/youtubei/v1/player")
+                .unwrap(),
+            Some("PREF=api; PREF=root".into())
+        );
+        assert_eq!(
+            jar.cookie_header("https://www.youtube.com/watch").unwrap(),
+            Some("PREF=root".into())
+        );
+        assert_eq!(jar.cookie_header("https://www.google.com/").unwrap(), None);
+    }
+
+    #[test]
+    fn response_cookies_keep_their_path_and_expiry() {
+        let jar = CookieJar::new();
+        let mut headers = HeaderMap::new();
+        for set_cookie in [
+            "YSC=yIy5oLQkHNg; Domain=.youtube.com; Path=/; Secure; HttpOnly; SameSite=none",
+            "VISITOR_INFO1_LIVE=Z8sWbp0Wnl4; Domain=.youtube.com; Expires=Thu, 15-Apr-2027 10:00:00 GMT; Path=/; Secure; HttpOnly",
+            "__Secure-ROLLOUT_TOKEN=CPq; Domain=youtube.com; Expires=Thu, 15-Apr-2027 10:00:00 GMT; Path=/youtubei; Secure",
+        ] {
+            headers.append(SET_COOKIE, set_cookie.parse().unwrap());
+        }
+
+        jar.store_response_cookies("https://www.youtube.com/watch?v=dQw4w9WgXcQ", &headers)
+            .unwrap();
+
+        let mut cookies = jar.snapshot().unwrap();
+        cookies.sort_by(|a, b| a.name.cmp(&b.name));
+        let expires = Some(UNIX_EPOCH + Duration::from_secs(1_807_783_200));
+        let attributes: Vec<_> = cookies
+            .iter()
+            .map(|c| (c.name.as_str(), c.path.as_str(), c.expires, c.session))
+            .collect();
+
+        assert_eq!(
+            attributes,
+            [
+                ("VISITOR_INFO1_LIVE", "/", expires, false),
+                ("YSC", "/", None, true),
+                ("__Secure-ROLLOUT_TOKEN", "/youtubei", expires, false),
+            ]
+        );
+        assert_eq!(
+            jar.cookie_header("https://www.youtube.com/youtubei/v1/player")
+                .unwrap(),
+            Some(
+                "__Secure-ROLLOUT_TOKEN=CPq; VISITOR_INFO1_LIVE=Z8sWbp0Wnl4; YSC=yIy5oLQkHNg"
+                    .into()
+            )
+        );
+        assert_eq!(
+            jar.cookie_header("https://www.youtube.com/watch").unwrap(),
+            Some("VISITOR_INFO1_LIVE=Z8sWbp0Wnl4; YSC=yIy5oLQkHNg".into())
+        );
+    }
+}
@@ -0,0 +1,413 @@
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{Result, anyhow, bail};
+use rusqlite::{Connection, OpenFlags, params_from_iter};
+
+use crate::cookies::{CookieAttributes, CookieJar, CookieStore};
+
+/// Domains whose cookies are imported from the browser.
+const COOKIE_DOMAINS: [&str; 2] = ["youtube.com", "google.com"];
+/// Seconds between the Windows epoch (1601-01-01) Chromium stores timestamps in and the unix epoch.
+const CHROMIUM_EPOCH_OFFSET_SECS: u64 = 11_644_473_600;
+/// Chromium prepends a SHA256 of the cookie domain to decrypted values since database version 24.
+const CHROMIUM_DOMAIN_HASH_VERSION: i64 = 24;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Browser {
+    Firefox,
+    Chrome,
+    Chromium,
+    Brave,
+    Edge,
+}
+
+impl fmt::Display for Browser {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Firefox => "Firefox",
+            Self::Chrome => "Chrome",
+            Self::Chromium => "Chromium",
+            Self::Brave => "Brave",
+            Self::Edge => "Edge",
+        };
+
+        f.write_str(name)
+    }
+}
+
+impl Browser {
+    /// Directory containing the profiles of the browser.
+    fn profiles_root(&self) -> Result<PathBuf> {
+        #[cfg(target_os = "windows")]
+        let (config_dir, local_data_dir) = {
+            let app_data =
+                std::env::var_os("APPDATA").ok_or_else(|| anyhow!("APPDATA is not set."))?;
+            let local_app_data = std::env::var_os("LOCALAPPDATA")
+                .ok_or_else(|| anyhow!("LOCALAPPDATA is not set."))?;
+            (PathBuf::from(app_data), PathBuf::from(local_app_data))
+        };
+        #[cfg(target_os = "macos")]
+        let (config_dir, local_data_dir) = {
+            let home = std::env::var_os("HOME").ok_or_else(|| anyhow!("HOME is not set."))?;
+            let support = PathBuf::from(home).join("Library/Application Support");
+            (support.clone(), support)
+        };
+        #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+        let (config_dir, local_data_dir) = {
+            let home =
+                PathBuf::from(std::env::var_os("HOME").ok_or_else(|| anyhow!("HOME is not set."))?);
+            let config = std::env::var_os("XDG_CONFIG_HOME")
+                .map(PathBuf::from)
+                .unwrap_or_else(|| home.join(".config"));
+            (config, home)
+        };
+
+        let root = match self {
+            #[cfg(target_os = "windows")]
+            Self::Firefox => config_dir.join("Mozilla/Firefox/Profiles"),
+            #[cfg(target_os = "macos")]
+            Self::Firefox => config_dir.join("Firefox/Profiles"),
+            #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+            Self::Firefox => local_data_dir.join(".mozilla/firefox"),
+            #[cfg(target_os = "windows")]
+            Self::Chrome => local_data_dir.join("Google/Chrome/User Data"),
+            #[cfg(target_os = "macos")]
+            Self::Chrome => local_data_dir.join("Google/Chrome"),
+            #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+            Self::Chrome => config_dir.join("google-chrome"),
+            #[cfg(target_os = "windows")]
+            Self::Chromium => local_data_dir.join("Chromium/User Data"),
+            #[cfg(target_os = "macos")]
+            Self::Chromium => local_data_dir.join("Chromium"),
+            #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+            Self::Chromium => config_dir.join("chromium"),
+            #[cfg(target_os = "windows")]
+            Self::Brave => local_data_dir.join("BraveSoftware/Brave-Browser/User Data"),
+            #[cfg(not(target_os = "windows"))]
+            Self::Brave => config_dir.join("BraveSoftware/Brave-Browser"),
+            #[cfg(target_os = "windows")]
+            Self::Edge => local_data_dir.join("Microsoft/Edge/User Data"),
+            #[cfg(target_os = "macos")]
+            Self::Edge => local_data_dir.join("Microsoft Edge"),
+            #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+            Self::Edge => config_dir.join("microsoft-edge"),
+        };
+        Ok(root)
+    }
+
+    /// Find the cookie database of `profile`, which is either a profile name or a path to the profile directory.
+    /// Firefox picks the most recently used profile when none is given, Chromium based browsers use `Default`.
+    fn cookie_database(&self, profile: Option<&str>) -> Result<PathBuf> {
+        let profile_dir = match profile {
+            Some(profile) if Path::new(profile).is_absolute() => PathBuf::from(profile),
+            Some(profile) => self.profiles_root()?.join(profile),
+            None if *self == Self::Firefox => latest_firefox_profile(&self.profiles_root()?)?,
+            None => self.profiles_root()?.join("Default"),
+        };
+
+        let candidates = match self {
+            Self::Firefox => vec![profile_dir.join("cookies.sqlite")],
+            _ => vec![
+                profile_dir.join("Network/Cookies"),
+                profile_dir.join("Cookies"),
+            ],
+        };
+
+        candidates
+            .into_iter()
+            .find(|candidate| candidate.is_file())
+            .ok_or_else(|| {
+                anyhow!(
+                    "No {} cookie database found in the profile at \"{}\".",
+                    self,
+                    profile_dir.display()
+                )
+            })
+    }
+}
+
+impl CookieJar {
+    /// Import the YouTube and Google cookies of a browser profile, like yt-dlp's `--cookies-from-browser`.
+    ///
+    /// `profile` is either the name of a profile or the path to its directory.
+    /// Encrypted Chromium cookies can only be decrypted on Linux for now.
+    pub fn from_browser(browser: Browser, profile: Option<&str>) -> Result<Self> {
+        let database_path = browser.cookie_database(profile)?;
+
+        // Browsers keep the database locked while running, so a copy is read instead.
+        let copy_path = std::env::temp_dir().join(format!(
+            "tydle-{}-cookies-{}.sqlite",
+            browser.to_string().to_lowercase(),
+            std::process::id()
+        ));
+        std::fs::copy(&database_path, &copy_path)?;
+
+        let jar = Self::new();
+        let imported = match browser {
+            Browser::Firefox => import_firefox_cookies(&jar, &copy_path),
+            _ => import_chromium_cookies(&jar, &copy_path),
+        };
+        let _ = std::fs::remove_file(&copy_path);
+
+        if imported? == 0 {
+            bail!(
+                "No YouTube cookies found in the {} cookie database at \"{}\".",
+                browser,
+                database_path.display()
+            );
+        }
+
+        Ok(jar)
+    }
+}
+
+fn latest_firefox_profile(profiles_root: &Path) -> Result<PathBuf> {
+    let mut latest: Option<(SystemTime, PathBuf)> = None;
+
+    for entry in std::fs::read_dir(profiles_root)
+        .map_err(|e| anyhow!("Failed to read \"{}\": {}", profiles_root.display(), e))?
+    {
+        let profile_dir = entry?.path();
+        let Ok(metadata) = std::fs::metadata(profile_dir.join("cookies.sqlite")) else {
+            continue;
+        };
+        let modified = metadata.modified().unwrap_or(UNIX_EPOCH);
+
+        if latest
+            .as_ref()
+            .is_none_or(|(latest_modified, _)| modified > *latest_modified)
+        {
+            latest = Some((modified, profile_dir));
+        }
+    }
+
+    latest.map(|(_, profile_dir)| profile_dir).ok_or_else(|| {
+        anyhow!(
+            "No Firefox profile with cookies found in \"{}\".",
+            profiles_root.display()
+        )
+    })
+}
+
+fn open_database(path: &Path) -> Result<Connection> {
+    Ok(Connection::open_with_flags(
+        path,
+        OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+    )?)
+}
+
+/// `WHERE` condition on `column` matching `COOKIE_DOMAINS` and all of their subdomains,
+/// but not lookalikes such as `notyoutube.com`. Bound to the values of `domain_params`.
+fn domain_condition(column: &str) -> String {
+    (0..COOKIE_DOMAINS.len())
+        .map(|index| {
+            format!(
+                "{column} = ?{} OR {column} LIKE ?{}",
+                index * 2 + 1,
+                index * 2 + 2
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(" OR ")
+}
+
+fn domain_params() -> Vec<String> {
+    COOKIE_DOMAINS
+        .iter()
+        .flat_map(|domain| [domain.to_string(), format!("%.{}", domain)])
+        .collect()
+}
+
+fn add_browser_cookie(
+    jar: &CookieJar,
+    host: &str,
+    name: &str,
+    value: &str,
+    attributes: CookieAttributes,
+) -> bool {
+    let domain = host.trim_start_matches('.');
+    let attributes = CookieAttributes {
+        domain: host.starts_with('.').then(|| domain.to_string()),
+        ..attributes
+    };
+
+    jar.set_with_attributes(&format!("https://{}", domain), name, value, &attributes)
+        .is_ok()
+}
+
+fn import_firefox_cookies(jar: &CookieJar, database_path: &Path) -> Result<usize> {
+    let connection = open_database(database_path)?;
+    let mut statement = connection.prepare(&format!(
+        "SELECT host, name, value, path, expiry, isSecure, isHttpOnly FROM moz_cookies WHERE {}",
+        domain_condition("host")
+    ))?;
+    let mut rows = statement.query(params_from_iter(domain_params()))?;
+    let mut imported = 0;
+
+    while let Some(row) = rows.next()? {
+        let host: String = row.get(0)?;
+        let name: String = row.get(1)?;
+        let value: String = row.get(2)?;
+        let expiry: i64 = row.get(4)?;
+
+        let attributes = CookieAttributes {
+            path: Some(row.get(3)?),
+            expires: u64::try_from(expiry)
+                .ok()
+                .filter(|expiry| *expiry > 0)
+                .and_then(|expiry| UNIX_EPOCH.checked_add(Duration::from_secs(expiry))),
+            secure: row.get::<_, i64>(5)? != 0,
+            http_only: row.get::<_, i64>(6)? != 0,
+            ..Default::default()
+        };
+
+        if add_browser_cookie(jar, &host, &name, &value, attributes) {
+            imported += 1;
+        }
+    }
+
+    Ok(imported)
+}
+
+fn import_chromium_cookies(jar: &CookieJar, database_path: &Path) -> Result<usize> {
+    let connection = open_database(database_path)?;
+    let meta_version: i64 = connection
+        .query_row("SELECT value FROM meta WHERE key = 'version'", [], |row| {
+            row.get::<_, String>(0)
+        })
+        .ok()
+        .and_then(|version| version.parse().ok())
+        .unwrap_or_default();
+
+    let mut statement = connection.prepare(&format!(
+        "SELECT host_key, name, value, encrypted_value, path, expires_utc, is_secure, is_httponly FROM cookies WHERE {}",
+        domain_condition("host_key")
+    ))?;
+    let mut rows = statement.query(params_from_iter(domain_params()))?;
+    let mut imported = 0;
+
+    while let Some(row) = rows.next()? {
+        let host: String = row.get(0)?;
+        let name: String = row.get(1)?;
+        let mut value: String = row.get(2)?;
+        let encrypted_value: Vec<u8> = row.get(3)?;
+        let expires_utc: i64 = row.get(5)?;
+
+        if value.is_empty() && !encrypted_value.is_empty() {
+            let Some(decrypted) = decrypt_chromium_value(
+                &encrypted_value,
+                meta_version >= CHROMIUM_DOMAIN_HASH_VERSION,
+            ) else {
+                #[cfg(feature = "logging")]
+                log::warn!("Failed to decrypt the \"{}\" cookie of \"{}\".", name, host);
+                continue;
+            };
+            value = decrypted;
+        }
+
+        // Chromium stores microseconds since 1601-01-01, 0 marks a session cookie.
+        let expires = u64::try_from(expires_utc)
+            .ok()
+            .map(|expires| expires / 1_000_000)
+            .filter(|expires| *expires > CHROMIUM_EPOCH_OFFSET_SECS)
+            .and_then(|expires| {
+                UNIX_EPOCH.checked_add(Duration::from_secs(expires - CHROMIUM_EPOCH_OFFSET_SECS))
+            });
+
+        let attributes = CookieAttributes {
+            path: Some(row.get(4)?),
+            expires,
+            secure: row.get::<_, i64>(6)? != 0,
+            http_only: row.get::<_, i64>(7)? != 0,
+            ..Default::default()
+        };
+
+        if add_browser_cookie(jar, &host, &name, &value, attributes) {
+            imported += 1;
+        }
+    }
+
+    Ok(imported)
+}
+
+/// Decrypt a Chromium cookie value encrypted with the `v10`/`v11` scheme used on Linux.
+///
+/// `v10` values use the hardcoded `peanuts` password. `v11` values use a password from the system keyring,
+/// which isn't read here, so only the empty password Chromium falls back to without a keyring is tried.
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn decrypt_chromium_value(encrypted_value: &[u8], has_domain_hash: bool) -> Option<String> {
+    use aes::cipher::{BlockDecryptMut, KeyIvInit, block_padding::Pkcs7};
+
+    let (version, ciphertext) = encrypted_value.split_at_checked(3)?;
+    let passwords: &[&[u8]] = match version {
+        b"v10" => &[b"peanuts", b""],
+        b"v11" => &[b""],
+        _ => return None,
+    };
+
+    passwords.iter().find_map(|password| {
+        let mut key = [0u8; 16];
+        pbkdf2::pbkdf2_hmac::<sha1::Sha1>(password, b"saltysalt", 1, &mut key);
+
+        let decrypted = cbc::Decryptor::<aes::Aes128>::new(&key.into(), &[b' '; 16].into())
+            .decrypt_padded_vec_mut::<Pkcs7>(ciphertext)
+            .ok()?;
+        let decrypted = match has_domain_hash {
+            true => decrypted.get(32..)?.to_vec(),
+            false => decrypted,
+        };
+
+        String::from_utf8(decrypted).ok()
+    })
+}
+
+#[cfg(any(target_os = "windows", target_os = "macos"))]
+fn decrypt_chromium_value(_encrypted_value: &[u8], _has_domain_hash: bool) -> Option<String> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cookies::CookieStore;
+
+    #[test]
+    fn firefox_import_skips_lookalike_domains() {
+        let path =
+            std::env::temp_dir().join(format!("tydle-firefox-{}.sqlite", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let connection = Connection::open(&path).unwrap();
+        connection
+            .execute_batch(
+                "CREATE TABLE moz_cookies (host TEXT, name TEXT, value TEXT, path TEXT, expiry INTEGER, isSecure INTEGER, isHttpOnly INTEGER);
+                INSERT INTO moz_cookies VALUES ('.youtube.com', 'DOTTED', '1', '/', 0, 1, 0);
+                INSERT INTO moz_cookies VALUES ('youtube.com', 'EXACT', '1', '/', 0, 1, 0);
+                INSERT INTO moz_cookies VALUES ('www.youtube.com', 'WWW', '1', '/', 0, 1, 0);
+                INSERT INTO moz_cookies VALUES ('.google.com', 'SID', '1', '/', 0, 1, 0);
+                INSERT INTO moz_cookies VALUES ('.notyoutube.com', 'EVIL', '1', '/', 0, 1, 0);
+                INSERT INTO moz_cookies VALUES ('evilgoogle.com', 'EVIL', '1', '/', 0, 1, 0);",
+            )
+            .unwrap();
+        drop(connection);
+
+        let jar = CookieJar::new();
+        let imported = import_firefox_cookies(&jar, &path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let mut names: Vec<String> = jar
+            .snapshot()
+            .unwrap()
+            .into_iter()
+            .map(|cookie| cookie.name)
+            .collect();
+        names.sort();
+
+        assert_eq!(imported, 4);
+        assert_eq!(names, ["DOTTED", "EXACT", "SID", "WWW"]);
+    }
+}
@@ -0,0 +1,1536 @@
+use std::{
+    ffi::OsString,
+    fmt,
+    io::SeekFrom,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use anyhow::{Context, Result, anyhow, bail};
+use bytes::Bytes;
+use futures::{StreamExt, stream};
+use serde::{Deserialize, Serialize};
+use tokio::{
+    fs,
+    io::{AsyncSeekExt, AsyncWriteExt},
+};
+
+use crate::{
+    error::DecipherError,
+    utils::get_url_query_param,
+    yt_interface::{VideoId, YtStream},
+};
+
+/// How yt-dlp splits up downloads too, small enough for googlevideo not to throttle them.
+const DEFAULT_CHUNK_SIZE: u64 = 10 * 1024 * 1024;
+/// How many times a range of a parallel download is requested before the download fails.
+const MAX_RANGE_ATTEMPTS: u32 = 3;
+/// Lowest `DownloadOptions::max_rate`, anything slower takes hours for a few minutes of video.
+const MIN_RATE: u64 = 8 * 1024;
+
+/// How `Tydle::download` requests the stream and reports its progress.
+pub struct DownloadOptions {
+    /// Called every `progress_interval` while the stream is downloading, and once more when it's done.
+    pub on_progress: Option<Box<dyn FnMut(DownloadProgress) + Send>>,
+    pub progress_interval: Duration,
+    /// Request the stream in parts of this many bytes one after another, since googlevideo throttles
+    /// requests that go on for long. 0 requests all of it at once. 10 MiB by default.
+    pub chunk_size: u64,
+    /// How many ranges of `chunk_size` bytes are downloaded at the same time. 1 by default, which
+    /// asks googlevideo for one range after another. Only streams whose `file_size` is known are
+    /// downloaded in parallel, and parallel downloads aren't resumed.
+    pub parallelism: usize,
+    /// Highest speed in bytes per second, shared by every range of the download. Downloads set
+    /// to less than 8 KiB/s fail right away.
+    pub max_rate: Option<u64>,
+    pub resume_policy: ResumePolicy,
+    /// Check with `Cipher::verify_stream` that googlevideo serves as many bytes as YouTube declared
+    /// before downloading anything, and fail with `tydle::error::StreamLengthMismatch` if it doesn't.
+    /// A URL that's refused right away is handled like during the download. Off by default, since it
+    /// costs a request.
+    pub verify_length: bool,
+}
+
+/// What `Tydle::download` does when googlevideo refuses the URL of the stream, which happens
+/// once its signature has expired, for example when resuming a download the next day.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum ResumePolicy {
+    /// Throw away the `.part` file, so the next download starts over, and fail with
+    /// `DecipherError::UrlRejected`.
+    #[default]
+    Restart,
+    /// Get a fresh URL for the stream of this video with `Cipher::refresh_stream` once and carry on
+    /// from where the download stopped.
+    Refresh(VideoId),
+}
+
+impl Default for DownloadOptions {
+    fn default() -> Self {
+        Self {
+            on_progress: None,
+            progress_interval: Duration::from_millis(500),
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            parallelism: 1,
+            max_rate: None,
+            resume_policy: ResumePolicy::default(),
+            verify_length: false,
+        }
+    }
+}
+
+/// How far along a download is, see `DownloadOptions::on_progress`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DownloadProgress {
+    pub bytes_done: u64,
+    /// Size of the whole stream in bytes, missing if googlevideo didn't say.
+    pub total: Option<u64>,
+    /// Average speed since the download started, in bytes per second. Bytes that were already in the
+    /// `.part` file of a resumed download don't count.
+    pub speed_bps: f64,
+}
+
+impl DownloadProgress {
+    /// How much of the stream is downloaded, from 0 to 1, missing if its size isn't known.
+    pub fn fraction(&self) -> Option<f64> {
+        self.total
+            .filter(|total| *total > 0)
+            .map(|total| self.bytes_done as f64 / total as f64)
+    }
+}
+
+/// What `Tydle::download` did.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DownloadSummary {
+    /// Size of the downloaded file in bytes.
+    pub bytes: u64,
+    /// How many of `bytes` were already in the `.part` file, 0 unless the download was resumed.
+    pub resumed_from: u64,
+    pub elapsed: Duration,
+    /// In bytes per second.
+    pub average_speed_bps: f64,
+}
+
+#[cfg(feature = "mux")]
+impl DownloadSummary {
+    /// Both downloads together, as if they were one after the other.
+    pub(crate) fn merged(self, other: DownloadSummary) -> DownloadSummary {
+        let bytes = self.bytes + other.bytes;
+        let resumed_from = self.resumed_from + other.resumed_from;
+        let elapsed = self.elapsed + other.elapsed;
+
+        DownloadSummary {
+            bytes,
+            resumed_from,
+            elapsed,
+            average_speed_bps: bytes_per_second(bytes - resumed_from, elapsed),
+        }
+    }
+}
+
+fn bytes_per_second(bytes: u64, elapsed: Duration) -> f64 {
+    match elapsed.is_zero() {
+        true => 0.0,
+        false => bytes as f64 / elapsed.as_secs_f64(),
+    }
+}
+
+/// `dest` with `.part` after its extension, like `video.mp4.part`.
+fn part_path(dest: &Path) -> PathBuf {
+    let mut part_path = OsString::from(dest.as_os_str());
+    part_path.push(".part");
+    PathBuf::from(part_path)
+}
+
+/// `dest` with `.part.json` after its extension, where the `PartInfo` of its `.part` file is kept.
+fn part_info_path(dest: &Path) -> PathBuf {
+    let mut part_info_path = OsString::from(dest.as_os_str());
+    part_info_path.push(".part.json");
+    PathBuf::from(part_info_path)
+}
+
+/// Remove the `.part` file of `dest` along with its `PartInfo`, so downloading it starts over.
+pub(crate) async fn discard_part(dest: &Path) {
+    let _ = fs::remove_file(part_path(dest)).await;
+    let _ = fs::remove_file(part_info_path(dest)).await;
+}
+
+/// Token bucket holding a tenth of a second worth of `rate` at most, so a download is held back
+/// in short waits after every few chunks rather than in stops of a whole second.
+struct RateLimiter {
+    /// In bytes per second.
+    rate: f64,
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl RateLimiter {
+    fn new(rate: u64) -> Self {
+        Self {
+            rate: rate as f64,
+            tokens: rate as f64 / 10.0,
+            refilled_at: Instant::now(),
+        }
+    }
+
+    /// Take `bytes` out of the bucket and return how long to wait before downloading more.
+    fn take(&mut self, bytes: u64) -> Duration {
+        let now = Instant::now();
+        let refill = now.duration_since(self.refilled_at).as_secs_f64() * self.rate;
+        self.tokens = (self.tokens + refill).min(self.rate / 10.0) - bytes as f64;
+        self.refilled_at = now;
+
+        match self.tokens < 0.0 {
+            true => Duration::from_secs_f64(-self.tokens / self.rate),
+            false => Duration::ZERO,
+        }
+    }
+}
+
+/// Kept next to a `.part` file, to tell whether it can be resumed with the URL of a stream.
+#[derive(Serialize, Deserialize)]
+struct PartInfo {
+    url: String,
+    itag: Option<String>,
+    content_length: u64,
+}
+
+impl PartInfo {
+    /// Whether the `.part` file is of the same stream as `url`. Stream URLs change with every extraction,
+    /// so they're compared by the `lmt` googlevideo gives the file and fall back to the whole URL without it.
+    fn is_same_stream(&self, url: &str, itag: &Option<String>) -> bool {
+        if self.itag != *itag {
+            return false;
+        }
+
+        match (
+            get_url_query_param(&self.url, "lmt"),
+            get_url_query_param(url, "lmt"),
+        ) {
+            (Some(lmt), Some(other_lmt)) => lmt == other_lmt,
+            _ => self.url == url,
+        }
+    }
+}
+
+/// How many bytes of `stream` the `.part` file left behind by an earlier download of `dest` already has,
+/// along with the size of the whole stream. `None` when there's nothing that can be resumed.
+async fn resumable_part(dest: &Path, url: &str, stream: &YtStream) -> Option<(u64, u64)> {
+    let part_info = fs::read_to_string(part_info_path(dest)).await.ok()?;
+    let part_info = serde_json::from_str::<PartInfo>(&part_info).ok()?;
+
+    if !part_info.is_same_stream(url, &stream.itag) {
+        return None;
+    }
+
+    if let Some(file_size) = stream.file_size
+        && file_size != part_info.content_length
+    {
+        #[cfg(feature = "logging")]
+        log::warn!(
+            "The stream is {} bytes now instead of {}, downloading it again.",
+            file_size,
+            part_info.content_length
+        );
+        return None;
+    }
+
+    let part_len = fs::metadata(part_path(dest)).await.ok()?.len();
+
+    if part_len > part_info.content_length {
+        #[cfg(feature = "logging")]
+        log::warn!("The .part file is larger than the stream it's of, downloading it again.",);
+        return None;
+    }
+
+    Some((part_len, part_info.content_length))
+}
+
+/// Parse a `Content-Range` like `bytes 0-1023/146515` into the first and last byte and the size
+/// of the whole stream, which is `*` when the server doesn't know it.
+fn parse_content_range(content_range: &str) -> Option<(u64, u64, Option<u64>)> {
+    let (range, total) = content_range
+        .trim()
+        .strip_prefix("bytes ")?
+        .split_once('/')?;
+    let (start, end) = range.split_once('-')?;
+
+    Some((
+        start.parse().ok()?,
+        end.parse().ok()?,
+        match total {
+            "*" => None,
+            total => Some(total.parse().ok()?),
+        },
+    ))
+}
+
+/// The parsed `Content-Range` of a part of the stream, see `parse_content_range`.
+fn content_range(response: &reqwest::Response) -> Result<(u64, u64, Option<u64>)> {
+    let content_range = response
+        .headers()
+        .get("Content-Range")
+        .and_then(|content_range| content_range.to_str().ok())
+        .ok_or_else(|| anyhow!("The part of the stream came without a `Content-Range`."))?;
+
+    parse_content_range(content_range)
+        .ok_or_else(|| anyhow!("Failed to parse the `Content-Range` {}.", content_range))
+}
+
+/// Fail with `DecipherError::UrlRejected` if googlevideo refused the URL.
+fn check_rejected(response: &reqwest::Response) -> Result<()> {
+    let status = response.status().as_u16();
+
+    if matches!(status, 403 | 410) {
+        return Err(DecipherError::UrlRejected { status }.into());
+    }
+
+    Ok(())
+}
+
+/// The stream is a different size than it was when the download started, or than the `.part` file
+/// being resumed says.
+#[derive(Debug)]
+struct SizeChanged {
+    expected: u64,
+    actual: u64,
+}
+
+impl fmt::Display for SizeChanged {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "The stream is {} bytes now instead of {}.",
+            self.actual, self.expected
+        )
+    }
+}
+
+impl std::error::Error for SizeChanged {}
+
+/// How far along a download is, reported through `DownloadOptions::on_progress` and held back
+/// to `DownloadOptions::max_rate`. Shared by every range of a download.
+pub(crate) struct Progress {
+    options: DownloadOptions,
+    bytes_done: u64,
+    resumed_from: u64,
+    total: Option<u64>,
+    started_at: Instant,
+    reported_at: Instant,
+    rate_limiter: Option<RateLimiter>,
+}
+
+impl Progress {
+    pub fn new(options: DownloadOptions) -> Result<Self> {
+        if let Some(max_rate) = options.max_rate
+            && max_rate < MIN_RATE
+        {
+            bail!(
+                "Failed to download the stream at {} bytes per second, `max_rate` can't be lower than {}.",
+                max_rate,
+                MIN_RATE
+            )
+        }
+
+        let started_at = Instant::now();
+
+        Ok(Self {
+            bytes_done: 0,
+            resumed_from: 0,
+            total: None,
+            started_at,
+            reported_at: started_at,
+            rate_limiter: options.max_rate.map(RateLimiter::new),
+            options,
+        })
+    }
+
+    pub fn resume_policy(&self) -> &ResumePolicy {
+        &self.options.resume_policy
+    }
+
+    pub fn verify_length(&self) -> bool {
+        self.options.verify_length
+    }
+
+    /// Start counting over from `resumed_from` bytes of a stream of `total` bytes.
+    fn restart(&mut self, resumed_from: u64, total: Option<u64>) {
+        self.bytes_done = resumed_from;
+        self.resumed_from = resumed_from;
+        self.total = total;
+    }
+
+    /// Count `bytes` as downloaded, report the progress if it's time to and return how long to wait
+    /// before downloading more to stay under `DownloadOptions::max_rate`.
+    fn count(&mut self, bytes: u64) -> Duration {
+        self.bytes_done += bytes;
+
+        if self.reported_at.elapsed() >= self.options.progress_interval {
+            self.report();
+        }
+
+        self.rate_limiter
+            .as_mut()
+            .map(|rate_limiter| rate_limiter.take(bytes))
+            .unwrap_or_default()
+    }
+
+    /// `count` the bytes and wait for as long as it says.
+    async fn wait(&mut self, bytes: u64) {
+        let wait = self.count(bytes);
+
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    pub fn report(&mut self) {
+        if let Some(on_progress) = &mut self.options.on_progress {
+            on_progress(DownloadProgress {
+                bytes_done: self.bytes_done,
+                total: self.total,
+                speed_bps: bytes_per_second(
+                    self.bytes_done - self.resumed_from,
+                    self.started_at.elapsed(),
+                ),
+            });
+        }
+
+        self.reported_at = Instant::now();
+    }
+
+    fn summary(&self) -> DownloadSummary {
+        let elapsed = self.started_at.elapsed();
+
+        DownloadSummary {
+            bytes: self.bytes_done,
+            resumed_from: self.resumed_from,
+            elapsed,
+            average_speed_bps: bytes_per_second(self.bytes_done - self.resumed_from, elapsed),
+        }
+    }
+}
+
+/// Reads a stream from `offset` on, in ranges of `DownloadOptions::chunk_size` requested one after
+/// another. Every part has to come with the `Content-Range` that was asked for.
+pub(crate) struct RangeReader {
+    http_client: reqwest::Client,
+    url: String,
+    chunk_size: u64,
+    /// The next byte of the stream to read.
+    offset: u64,
+    response: Option<reqwest::Response>,
+    /// Last byte of the part being read, missing when the server sent all of the stream instead.
+    part_end: Option<u64>,
+    /// Last byte that was asked for, missing when the request was open-ended.
+    requested_end: Option<u64>,
+    /// Bytes to drop from the start of a response that has all of the stream, when it was asked for
+    /// the rest of it.
+    skip: u64,
+    is_done: bool,
+}
+
+impl RangeReader {
+    pub fn new(http_client: reqwest::Client, url: String, chunk_size: u64, offset: u64) -> Self {
+        Self {
+            http_client,
+            url,
+            chunk_size,
+            offset,
+            response: None,
+            part_end: None,
+            requested_end: None,
+            skip: 0,
+            is_done: false,
+        }
+    }
+
+    /// Carry on reading from the same byte with another URL of the stream.
+    pub fn set_url(&mut self, url: String) {
+        self.url = url;
+        self.response = None;
+        self.skip = 0;
+    }
+
+    /// The next chunk of the stream, `None` once all of it is read. Chunks are counted in `progress`,
+    /// which may hold the reader back for a bit to stay under `DownloadOptions::max_rate`.
+    pub async fn next_chunk(&mut self, progress: &mut Progress) -> Result<Option<Bytes>> {
+        loop {
+            let Some(response) = &mut self.response else {
+                if self.is_done || progress.total == Some(self.offset) {
+                    break;
+                }
+
+                self.request_part(progress).await?;
+                continue;
+            };
+
+            let Some(mut chunk) = response.chunk().await.with_context(|| {
+                format!("Failed to download the stream after {} bytes.", self.offset)
+            })?
+            else {
+                self.end_part(progress)?;
+                continue;
+            };
+
+            if self.skip > 0 {
+                let skipped = self.skip.min(chunk.len() as u64);
+                chunk = chunk.split_off(skipped as usize);
+                self.skip -= skipped;
+
+                if chunk.is_empty() {
+                    continue;
+                }
+            }
+
+            if let Some(part_end) = self.part_end
+                && self.offset + chunk.len() as u64 > part_end + 1
+            {
+                bail!(
+                    "Bytes {}-{} of the stream came with more bytes than that.",
+                    self.offset,
+                    part_end
+                )
+            }
+
+            self.offset += chunk.len() as u64;
+            progress.wait(chunk.len() as u64).await;
+
+            return Ok(Some(chunk));
+        }
+
+        if let Some(total) = progress.total
+            && self.offset != total
+        {
+            bail!(
+                "The stream ended after {} of its {} bytes.",
+                self.offset,
+                total
+            )
+        }
+
+        Ok(None)
+    }
+
+    /// Request the next part of the stream.
+    async fn request_part(&mut self, progress: &mut Progress) -> Result<()> {
+        let start = self.offset;
+        // Open-ended without chunks, `bytes=<start>-`.
+        let end = match progress.total {
+            _ if self.chunk_size == 0 => None,
+            Some(total) => Some((start + self.chunk_size - 1).min(total.saturating_sub(1))),
+            None => Some(start + self.chunk_size - 1),
+        };
+
+        let mut request = self.http_client.get(&self.url);
+        if self.chunk_size > 0 || start > 0 {
+            request = request.header(
+                "Range",
+                format!(
+                    "bytes={}-{}",
+                    start,
+                    end.map(|end| end.to_string()).unwrap_or_default()
+                ),
+            );
+        }
+
+        let response = request.send().await?;
+        check_rejected(&response)?;
+
+        let (part_end, total) = match response.status().as_u16() {
+            // The server ignored the range and sent all of the stream.
+            200 => {
+                if start > 0 {
+                    #[cfg(feature = "logging")]
+                    log::warn!(
+                        "The server doesn't resume downloads, skipping the first {} bytes of the stream.",
+                        start
+                    );
+                    self.skip = start;
+                }
+
+                (None, response.content_length())
+            }
+            206 => {
+                let (part_start, part_end, total) = content_range(&response)?;
+
+                if part_start != start
+                    || end.is_some_and(|end| part_end > end)
+                    || part_end < part_start
+                {
+                    bail!(
+                        "Asked for bytes {}-{} of the stream, but got {}-{}.",
+                        start,
+                        end.map(|end| end.to_string()).unwrap_or_default(),
+                        part_start,
+                        part_end
+                    )
+                }
+
+                (Some(part_end), total)
+            }
+            // Nothing is left after the end of a stream whose size isn't known.
+            416 if progress.total.is_none() => {
+                self.is_done = true;
+                return Ok(());
+            }
+            status => bail!(
+                "Asked for bytes {}-{} of the stream, but got HTTP {}.",
+                start,
+                end.map(|end| end.to_string()).unwrap_or_default(),
+                status
+            ),
+        };
+
+        if let (Some(expected), Some(actual)) = (progress.total, total)
+            && expected != actual
+        {
+            return Err(SizeChanged { expected, actual }.into());
+        }
+
+        progress.total = total.or(progress.total);
+        self.part_end = part_end;
+        self.requested_end = end;
+        self.response = Some(response);
+
+        Ok(())
+    }
+
+    /// Check that the part that was read has all of its bytes and find out whether it's the last one.
+    fn end_part(&mut self, progress: &Progress) -> Result<()> {
+        self.response = None;
+
+        let Some(part_end) = self.part_end else {
+            self.is_done = true;
+            return Ok(());
+        };
+
+        if self.offset != part_end + 1 {
+            bail!(
+                "The part of the stream up to byte {} ended at byte {}.",
+                part_end,
+                self.offset
+            )
+        }
+
+        // Without a total, a part shorter than asked for is the last one.
+        self.is_done = match progress.total {
+            Some(total) => self.offset >= total,
+            None => self
+                .requested_end
+                .is_none_or(|requested_end| part_end < requested_end),
+        };
+
+        Ok(())
+    }
+}
+
+/// The `.part` file a stream is downloaded into.
+struct PartFile {
+    file: fs::File,
+    path: PathBuf,
+    info_path: PathBuf,
+    /// Whether the `PartInfo` of the file is written, which needs the size of the stream to be known.
+    has_info: bool,
+    itag: Option<String>,
+}
+
+impl PartFile {
+    /// Write the `PartInfo` of the file once the size of the stream is known, so it can be resumed.
+    async fn write_info(&mut self, url: &str, total: Option<u64>) -> Result<()> {
+        let Some(content_length) = total.filter(|_| !self.has_info) else {
+            return Ok(());
+        };
+
+        let part_info = serde_json::to_string(&PartInfo {
+            url: url.to_string(),
+            itag: self.itag.clone(),
+            content_length,
+        })?;
+        fs::write(&self.info_path, part_info)
+            .await
+            .with_context(|| format!("Failed to write {}.", self.info_path.display()))?;
+        self.has_info = true;
+
+        Ok(())
+    }
+
+    /// Empty the file to download the stream from the start again.
+    async fn restart(&mut self) -> Result<()> {
+        self.file.set_len(0).await?;
+        self.file.rewind().await?;
+        self.has_info = false;
+
+        Ok(())
+    }
+
+    /// Check that all of the stream is there and move the file to `dest`.
+    async fn finish(mut self, progress: &mut Progress, dest: &Path) -> Result<DownloadSummary> {
+        self.file.flush().await?;
+
+        if let Some(total) = progress.total
+            && progress.bytes_done != total
+        {
+            bail!(
+                "The stream ended after {} of its {} bytes, {} was left as it is.",
+                progress.bytes_done,
+                total,
+                self.path.display()
+            )
+        }
+
+        let summary = progress.summary();
+        progress.report();
+
+        drop(self.file);
+        fs::rename(&self.path, dest).await.with_context(|| {
+            format!(
+                "Failed to move {} to {}.",
+                self.path.display(),
+                dest.display()
+            )
+        })?;
+        let _ = fs::remove_file(self.info_path).await;
+
+        Ok(summary)
+    }
+}
+
+/// Download `url` of `stream` into a `.part` file next to `dest` and move it to `dest` once all of it is there.
+/// A download that fails midway leaves the `.part` file behind, and picks up from where it stopped
+/// when it's tried again.
+pub(crate) async fn download_to_file(
+    http_client: &reqwest::Client,
+    url: &str,
+    stream: &YtStream,
+    dest: &Path,
+    progress: &mut Progress,
+) -> Result<DownloadSummary> {
+    if let Some(parent) = dest
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        fs::create_dir_all(parent)
+            .await
+            .with_context(|| format!("Failed to create directory {}.", parent.display()))?;
+    }
+
+    if progress.options.parallelism > 1
+        && let Some(total) = stream.file_size.filter(|total| *total > 0)
+        && let Some(summary) =
+            download_in_parallel(http_client, url, stream, dest, total, progress).await?
+    {
+        return Ok(summary);
+    }
+
+    let path = part_path(dest);
+    let resumable_part = resumable_part(dest, url, stream).await;
+    let file = match resumable_part {
+        Some(_) => {
+            let mut file = fs::OpenOptions::new()
+                .write(true)
+                .open(&path)
+                .await
+                .with_context(|| format!("Failed to open {}.", path.display()))?;
+            file.seek(SeekFrom::End(0)).await?;
+            file
+        }
+        None => {
+            let _ = fs::remove_file(part_info_path(dest)).await;
+            fs::File::create(&path)
+                .await
+                .with_context(|| format!("Failed to create {}.", path.display()))?
+        }
+    };
+
+    let (resumed_from, total) = match resumable_part {
+        Some((resumed_from, total)) => (resumed_from, Some(total)),
+        None => (0, None),
+    };
+    progress.restart(resumed_from, total);
+
+    let mut part_file = PartFile {
+        file,
+        path,
+        info_path: part_info_path(dest),
+        has_info: resumable_part.is_some(),
+        itag: stream.itag.clone(),
+    };
+    let mut reader = RangeReader::new(
+        http_client.clone(),
+        url.to_string(),
+        progress.options.chunk_size,
+        resumed_from,
+    );
+
+    loop {
+        match reader.next_chunk(progress).await {
+            Ok(Some(chunk)) => {
+                part_file.write_info(url, progress.total).await?;
+                part_file
+                    .file
+                    .write_all(&chunk)
+                    .await
+                    .with_context(|| format!("Failed to write to {}.", part_file.path.display()))?;
+            }
+            Ok(None) => break,
+            // What's in the `.part` file is of an older version of the stream.
+            Err(e) if progress.resumed_from > 0 && e.is::<SizeChanged>() => {
+                #[cfg(feature = "logging")]
+                log::warn!("{} Downloading it again.", e);
+
+                part_file.restart().await?;
+                progress.restart(0, None);
+                reader = RangeReader::new(
+                    http_client.clone(),
+                    url.to_string(),
+                    progress.options.chunk_size,
+                    0,
+                );
+            }
+            Err(e) => return Err(e),
+        }
+    }
+
+    part_file.write_info(url, progress.total).await?;
+    part_file.finish(progress, dest).await
+}
+
+/// Download `stream` with `DownloadOptions::parallelism` ranges at a time, each written where it belongs
+/// in the `.part` file. `None` when the server sends all of the stream instead of a range.
+async fn download_in_parallel(
+    http_client: &reqwest::Client,
+    url: &str,
+    stream: &YtStream,
+    dest: &Path,
+    total: u64,
+    progress: &mut Progress,
+) -> Result<Option<DownloadSummary>> {
+    discard_part(dest).await;
+
+    let path = part_path(dest);
+    let file = fs::File::create(&path)
+        .await
+        .with_context(|| format!("Failed to create {}.", path.display()))?;
+    file.set_len(total)
+        .await
+        .with_context(|| format!("Failed to allocate {} bytes for {}.", total, path.display()))?;
+
+    let parallelism = progress.options.parallelism;
+    let range_size = match progress.options.chunk_size {
+        0 => total.div_ceil(parallelism as u64),
+        chunk_size => chunk_size,
+    };
+    progress.restart(0, Some(total));
+
+    let part_file = PartFile {
+        file,
+        path: path.clone(),
+        info_path: part_info_path(dest),
+        has_info: false,
+        itag: stream.itag.clone(),
+    };
+    let shared_progress = Mutex::new(&mut *progress);
+
+    let mut ranges = stream::iter(0..total.div_ceil(range_size))
+        .map(|i| {
+            let start = i * range_size;
+            let end = (start + range_size).min(total) - 1;
+            download_range(http_client, url, &path, start, end, &shared_progress)
+        })
+        .buffer_unordered(parallelism);
+
+    // Returning drops the ranges that are still downloading.
+    while let Some(served) = ranges.next().await {
+        if !served? {
+            #[cfg(feature = "logging")]
+            log::warn!("The server doesn't serve ranges of the stream, downloading it in one go.");
+            return Ok(None);
+        }
+    }
+    drop(ranges);
+
+    part_file.finish(progress, dest).await.map(Some)
+}
+
+/// Download bytes `start` to `end` of the stream into the `.part` file at `path`, requesting them again
+/// up to `MAX_RANGE_ATTEMPTS` times. `false` when the server sent all of the stream instead.
+async fn download_range(
+    http_client: &reqwest::Client,
+    url: &str,
+    path: &Path,
+    start: u64,
+    end: u64,
+    progress: &Mutex<&mut Progress>,
+) -> Result<bool> {
+    let mut attempt = 1;
+
+    loop {
+        let mut written = 0;
+
+        match write_range(http_client, url, path, start, end, progress, &mut written).await {
+            Err(e)
+                if attempt < MAX_RANGE_ATTEMPTS
+                    && !matches!(
+                        e.downcast_ref::<DecipherError>(),
+                        Some(DecipherError::UrlRejected { .. })
+                    ) =>
+            {
+                #[cfg(feature = "logging")]
+                log::warn!(
+                    "Bytes {}-{} of the stream failed, requesting them again: {}",
+                    start,
+                    end,
+                    e
+                );
+
+                progress
+                    .lock()
+                    .map_err(|_| anyhow!("Download progress lock poisoned."))?
+                    .bytes_done -= written;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Request bytes `start` to `end` of the stream once and write them to the `.part` file at `path`,
+/// counting what's been written so far in `written`.
+async fn write_range(
+    http_client: &reqwest::Client,
+    url: &str,
+    path: &Path,
+    start: u64,
+    end: u64,
+    progress: &Mutex<&mut Progress>,
+    written: &mut u64,
+) -> Result<bool> {
+    let mut response = http_client
+        .get(url)
+        .header("Range", format!("bytes={}-{}", start, end))
+        .send()
+        .await?;
+    check_rejected(&response)?;
+
+    match response.status().as_u16() {
+        200 => return Ok(false),
+        206 => {}
+        status => bail!(
+            "Asked for bytes {}-{} of the stream, but got HTTP {}.",
+            start,
+            end,
+            status
+        ),
+    }
+
+    let (part_start, part_end, _) = content_range(&response)?;
+
+    if part_start != start || part_end != end {
+        bail!(
+            "Asked for bytes {}-{} of the stream, but got {}-{}.",
+            start,
+            end,
+            part_start,
+            part_end
+        )
+    }
+
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .open(path)
+        .await
+        .with_context(|| format!("Failed to open {}.", path.display()))?;
+    file.seek(SeekFrom::Start(start)).await?;
+
+    let range_len = end - start + 1;
+
+    while let Some(chunk) = response.chunk().await? {
+        if *written + chunk.len() as u64 > range_len {
+            bail!(
+                "Bytes {}-{} of the stream came with more bytes than that.",
+                start,
+                end
+            )
+        }
+
+        file.write_all(&chunk)
+            .await
+            .with_context(|| format!("Failed to write to {}.", path.display()))?;
+        *written += chunk.len() as u64;
+
+        let wait = progress
+            .lock()
+            .map_err(|_| anyhow!("Download progress lock poisoned."))?
+            .count(chunk.len() as u64);
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    file.flush().await?;
+
+    if *written != range_len {
+        bail!(
+            "Bytes {}-{} of the stream ended after {} bytes.",
+            start,
+            end,
+            written
+        )
+    }
+
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    };
+
+    use sha1::{Digest, Sha1};
+
+    use super::*;
+    use crate::{
+        test_server::{TestResponse, TestServer},
+        yt_interface::YtStreamSource,
+    };
+
+    const MIB: usize = 1024 * 1024;
+
+    /// `len` bytes that don't repeat in any way a misplaced range could hide in.
+    fn random_body(len: usize) -> Vec<u8> {
+        let mut state: u64 = 0x9e3779b97f4a7c15;
+
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state as u8
+            })
+            .collect()
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("tydle-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    /// Serve `body` at `/videoplayback` honoring ranges, like googlevideo.
+    async fn stream_server(body: Vec<u8>) -> TestServer {
+        TestServer::start(move |request| TestResponse::ranged(request, &body)).await
+    }
+
+    fn served_stream(server: &TestServer, file_size: Option<u64>) -> (String, YtStream) {
+        let url = server.url("/videoplayback?itag=137&lmt=1706000000000001");
+        let stream = YtStream::new(
+            None,
+            file_size,
+            Some("137".into()),
+            Some("hd1080".into()),
+            YtStreamSource::URL(url.clone()),
+            2959154.0,
+        );
+
+        (url, stream)
+    }
+
+    /// Every `DownloadProgress` the download reports, collected as it goes.
+    fn recorded_progress() -> (DownloadOptions, Arc<Mutex<Vec<DownloadProgress>>>) {
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let options = DownloadOptions {
+            on_progress: Some(Box::new({
+                let reports = reports.clone();
+                move |progress| reports.lock().unwrap().push(progress)
+            })),
+            progress_interval: Duration::ZERO,
+            ..Default::default()
+        };
+
+        (options, reports)
+    }
+
+    async fn download(
+        url: &str,
+        stream: &YtStream,
+        dest: &Path,
+        options: DownloadOptions,
+    ) -> Result<DownloadSummary> {
+        let mut progress = Progress::new(options)?;
+        download_to_file(&reqwest::Client::new(), url, stream, dest, &mut progress).await
+    }
+
+    #[tokio::test]
+    async fn stream_is_downloaded_in_chunks_into_a_new_directory() {
+        let body = random_body(3 * MIB + 123);
+        let server = stream_server(body.clone()).await;
+        let (url, stream) = served_stream(&server, None);
+        let dir = temp_dir("download-chunks");
+        let dest = dir.join("nested").join("video.mp4");
+        let (options, reports) = recorded_progress();
+
+        let summary = download(
+            &url,
+            &stream,
+            &dest,
+            DownloadOptions {
+                chunk_size: MIB as u64,
+                ..options
+            },
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(std::fs::read(&dest).unwrap(), body);
+        assert!(!part_path(&dest).exists());
+        assert!(!part_info_path(&dest).exists());
+        assert_eq!(summary.bytes, body.len() as u64);
+        assert_eq!(summary.resumed_from, 0);
+
+        let ranges: Vec<String> = server
+            .requests()
+            .iter()
+            .map(|request| request.headers["range"].clone())
+            .collect();
+        assert_eq!(
+            ranges,
+            [
+                "bytes=0-1048575",
+                "bytes=1048576-2097151",
+                "bytes=2097152-3145727",
+                "bytes=3145728-3145850",
+            ]
+        );
+
+        let reports = reports.lock().unwrap();
+        assert!(
+            reports
+                .windows(2)
+                .all(|pair| pair[0].bytes_done <= pair[1].bytes_done)
+        );
+        assert_eq!(
+            reports.last().map(|last| (last.bytes_done, last.total)),
+            Some((body.len() as u64, Some(body.len() as u64)))
+        );
+        assert_eq!(reports.last().and_then(|last| last.fraction()), Some(1.0));
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn rejected_url_fails_with_url_rejected() {
+        let server = TestServer::start(|_| TestResponse::status(403)).await;
+        let (url, stream) = served_stream(&server, Some(1000));
+        let dir = temp_dir("download-rejected");
+        let dest = dir.join("video.mp4");
+
+        let error = download(&url, &stream, &dest, DownloadOptions::default())
+            .await
+            .unwrap_err();
+
+        assert!(matches!(
+            error.downcast_ref::<DecipherError>(),
+            Some(DecipherError::UrlRejected { status: 403 })
+        ));
+        assert!(!dest.exists());
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    /// Wait for the writes of a download that was dropped midway to land, and get the size of its `.part` file.
+    async fn settled_part_len(dest: &Path, expected: u64) -> u64 {
+        for _ in 0..50 {
+            if fs::metadata(part_path(dest)).await.unwrap().len() == expected {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+
+        fs::metadata(part_path(dest)).await.unwrap().len()
+    }
+
+    #[tokio::test]
+    async fn killed_download_is_resumed_from_its_part_file() {
+        let body = random_body(3 * MIB);
+        // The second chunk never comes, so the download is killed after the first.
+        let stalled = TestServer::start({
+            let body = body.clone();
+            move |request| match request.range() {
+                Some((0, _)) => TestResponse::ranged(request, &body),
+                _ => TestResponse::ranged(request, &body).delay(Duration::from_secs(60)),
+            }
+        })
+        .await;
+        let (url, stream) = served_stream(&stalled, Some(body.len() as u64));
+        let dir = temp_dir("download-killed");
+        let dest = dir.join("video.mp4");
+        let options = || DownloadOptions {
+            chunk_size: MIB as u64,
+            ..Default::default()
+        };
+
+        let killed = tokio::time::timeout(
+            Duration::from_millis(500),
+            download(&url, &stream, &dest, options()),
+        )
+        .await;
+        assert!(killed.is_err());
+        assert_eq!(settled_part_len(&dest, MIB as u64).await, MIB as u64);
+        assert!(part_info_path(&dest).exists());
+
+        // The next extraction has another URL on another host, but the same `lmt`.
+        let server = stream_server(body.clone()).await;
+        let (url, stream) = served_stream(&server, Some(body.len() as u64));
+        let summary = download(&url, &stream, &dest, options()).await.unwrap();
+
+        assert_eq!(summary.resumed_from, MIB as u64);
+        assert_eq!(summary.bytes, body.len() as u64);
+        assert_eq!(
+            server.requests()[0].range(),
+            Some((MIB as u64, Some(2 * MIB as u64 - 1)))
+        );
+        assert_eq!(std::fs::read(&dest).unwrap(), body);
+        assert!(!part_path(&dest).exists());
+        assert!(!part_info_path(&dest).exists());
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn truncated_part_is_resumed_with_an_open_range() {
+        let body = random_body(2 * MIB);
+        let dropping = TestServer::start({
+            let body = body.clone();
+            move |request| TestResponse::ranged(request, &body).truncate_after(MIB + 4321)
+        })
+        .await;
+        let (url, stream) = served_stream(&dropping, None);
+        let dir = temp_dir("download-truncated");
+        let dest = dir.join("video.mp4");
+        let options = || DownloadOptions {
+            chunk_size: 0,
+            ..Default::default()
+        };
+
+        assert!(download(&url, &stream, &dest, options()).await.is_err());
+        let part_len = settled_part_len(&dest, (MIB + 4321) as u64).await;
+        assert!(part_len > 0 && part_len <= (MIB + 4321) as u64);
+
+        let server = stream_server(body.clone()).await;
+        let (url, stream) = served_stream(&server, None);
+        let summary = download(&url, &stream, &dest, options()).await.unwrap();
+
+        assert_eq!(summary.resumed_from, part_len);
+        assert_eq!(server.requests()[0].range(), Some((part_len, None)));
+        assert_eq!(std::fs::read(&dest).unwrap(), body);
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn part_is_resumed_from_a_server_that_ignores_ranges() {
+        let body = random_body(MIB);
+        let server = TestServer::start({
+            let body = body.clone();
+            move |_| TestResponse::ok(body.clone())
+        })
+        .await;
+        let (url, stream) = served_stream(&server, Some(body.len() as u64));
+        let dir = temp_dir("download-no-ranges");
+        let dest = dir.join("video.mp4");
+
+        write_part(&dest, &url, &body[..1000], body.len() as u64);
+        let summary = download(&url, &stream, &dest, DownloadOptions::default())
+            .await
+            .unwrap();
+
+        // The first 1000 bytes are skipped instead of written again.
+        assert_eq!(summary.resumed_from, 1000);
+        assert_eq!(std::fs::read(&dest).unwrap(), body);
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    /// Leave a `.part` file with `bytes` behind for `dest`, as a download of `url` that stopped would.
+    fn write_part(dest: &Path, url: &str, bytes: &[u8], content_length: u64) {
+        std::fs::create_dir_all(dest.parent().unwrap()).unwrap();
+        std::fs::write(part_path(dest), bytes).unwrap();
+        std::fs::write(
+            part_info_path(dest),
+            serde_json::to_string(&PartInfo {
+                url: url.to_string(),
+                itag: Some("137".into()),
+                content_length,
+            })
+            .unwrap(),
+        )
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn part_that_cant_be_of_the_stream_is_downloaded_again() {
+        let body = random_body(MIB);
+        let len = body.len() as u64;
+        let server = stream_server(body.clone()).await;
+        let (url, stream) = served_stream(&server, Some(len));
+        let dir = temp_dir("download-restart");
+        let dest = dir.join("video.mp4");
+        let other_url = url.replace("lmt=1706000000000001", "lmt=1706000000000002");
+
+        let parts = [
+            // Larger than the stream, so it's corrupt.
+            (url.clone(), random_body(MIB + 1), len),
+            // Of a stream that was another size.
+            (url.clone(), body[..1000].to_vec(), len + 10),
+            // Of another version of the stream.
+            (other_url, body[..1000].to_vec(), len),
+        ];
+
+        for (part_url, part, content_length) in parts {
+            write_part(&dest, &part_url, &part, content_length);
+            let requests = server.requests().len();
+
+            let summary = download(&url, &stream, &dest, DownloadOptions::default())
+                .await
+                .unwrap();
+
+            assert_eq!(summary.resumed_from, 0);
+            assert_eq!(
+                server.requests()[requests].range().map(|(start, _)| start),
+                Some(0)
+            );
+            assert_eq!(std::fs::read(&dest).unwrap(), body);
+        }
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    fn sha1_of(bytes: &[u8]) -> Vec<u8> {
+        Sha1::digest(bytes).to_vec()
+    }
+
+    fn parallel_options(parallelism: usize) -> DownloadOptions {
+        DownloadOptions {
+            chunk_size: 512 * 1024,
+            parallelism,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn parallel_ranges_are_written_where_they_belong() {
+        let body = random_body(5 * MIB + 777);
+        let ranges = body.len().div_ceil(512 * 1024) as u64;
+        // Later ranges are answered sooner, so they finish before the ones in front of them.
+        let server = TestServer::start({
+            let body = body.clone();
+            move |request| {
+                let index = request.range().map(|(start, _)| start / (512 * 1024));
+                let delay = (ranges - index.unwrap_or_default()) * 15;
+
+                TestResponse::ranged(request, &body).delay(Duration::from_millis(delay))
+            }
+        })
+        .await;
+        let (url, stream) = served_stream(&server, Some(body.len() as u64));
+        let dir = temp_dir("download-parallel");
+        let dest = dir.join("video.mp4");
+        let (options, reports) = recorded_progress();
+
+        let summary = download(
+            &url,
+            &stream,
+            &dest,
+            DownloadOptions {
+                on_progress: options.on_progress,
+                progress_interval: Duration::ZERO,
+                ..parallel_options(4)
+            },
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(sha1_of(&std::fs::read(&dest).unwrap()), sha1_of(&body));
+        assert_eq!(summary.bytes, body.len() as u64);
+
+        let mut requested: Vec<_> = server
+            .requests()
+            .iter()
+            .filter_map(|request| request.range())
+            .collect();
+        requested.sort();
+        let expected: Vec<_> = (0..ranges)
+            .map(|i| {
+                let start = i * 512 * 1024;
+                (start, Some((start + 512 * 1024).min(body.len() as u64) - 1))
+            })
+            .collect();
+        assert_eq!(requested, expected);
+
+        // Progress of every range adds up in the one callback.
+        let reports = reports.lock().unwrap();
+        assert_eq!(
+            reports.last().map(|last| last.bytes_done),
+            Some(body.len() as u64)
+        );
+        assert!(
+            reports
+                .iter()
+                .all(|report| report.total == Some(body.len() as u64))
+        );
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn failed_range_is_requested_again_alone() {
+        let body = random_body(2 * MIB);
+        let failed = Arc::new(AtomicBool::new(false));
+        let server = TestServer::start({
+            let body = body.clone();
+            let failed = failed.clone();
+            move |request| {
+                let response = TestResponse::ranged(request, &body);
+
+                // The connection of the third range drops the first time around.
+                match request.range() {
+                    Some((start, _))
+                        if start == 1024 * 1024 && !failed.swap(true, Ordering::SeqCst) =>
+                    {
+                        response.truncate_after(1000)
+                    }
+                    _ => response,
+                }
+            }
+        })
+        .await;
+        let (url, stream) = served_stream(&server, Some(body.len() as u64));
+        let dir = temp_dir("download-parallel-retry");
+        let dest = dir.join("video.mp4");
+
+        let summary = download(&url, &stream, &dest, parallel_options(3))
+            .await
+            .unwrap();
+
+        assert_eq!(sha1_of(&std::fs::read(&dest).unwrap()), sha1_of(&body));
+        assert_eq!(summary.bytes, body.len() as u64);
+
+        let requests_of = |start: u64| {
+            server
+                .requests()
+                .iter()
+                .filter(|request| request.range().map(|(s, _)| s) == Some(start))
+                .count()
+        };
+        assert_eq!(requests_of(1024 * 1024), 2);
+        for start in [0, 512 * 1024, 1536 * 1024] {
+            assert_eq!(requests_of(start), 1);
+        }
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn parallel_download_falls_back_when_ranges_arent_served() {
+        let body = random_body(MIB + 5);
+        let server = TestServer::start({
+            let body = body.clone();
+            move |_| TestResponse::ok(body.clone())
+        })
+        .await;
+        let (url, stream) = served_stream(&server, Some(body.len() as u64));
+        let dir = temp_dir("download-parallel-fallback");
+        let dest = dir.join("video.mp4");
+
+        download(&url, &stream, &dest, parallel_options(4))
+            .await
+            .unwrap();
+
+        assert_eq!(sha1_of(&std::fs::read(&dest).unwrap()), sha1_of(&body));
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    /// Download 1.5 MiB capped at 512 KiB/s from a server that's much faster than that, and check
+    /// the average speed isn't above the cap. It takes about three seconds, so the tenth of a second
+    /// the bucket starts with adds a few percent at most. Only an absurdly slow download fails the
+    /// lower bound, since a busy machine can always be slower.
+    async fn assert_rate_is_capped(name: &str, parallelism: usize) {
+        const MAX_RATE: u64 = 512 * 1024;
+        const SIZE: usize = 3 * MIB / 2;
+
+        let body = random_body(SIZE);
+        let server = stream_server(body.clone()).await;
+        let (url, stream) = served_stream(&server, Some(body.len() as u64));
+        let dir = temp_dir(name);
+        let dest = dir.join("video.mp4");
+
+        let summary = download(
+            &url,
+            &stream,
+            &dest,
+            DownloadOptions {
+                chunk_size: 256 * 1024,
+                parallelism,
+                max_rate: Some(MAX_RATE),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+
+        let burst = MAX_RATE as f64 / 10.0;
+        let fastest = MAX_RATE as f64 * SIZE as f64 / (SIZE as f64 - burst);
+        assert!(
+            summary.average_speed_bps <= fastest * 1.01,
+            "{} bytes per second is over the cap of {}",
+            summary.average_speed_bps,
+            MAX_RATE
+        );
+        assert!(
+            summary.average_speed_bps >= MAX_RATE as f64 / 4.0,
+            "{} bytes per second is far below the cap of {}",
+            summary.average_speed_bps,
+            MAX_RATE
+        );
+        assert_eq!(std::fs::read(&dest).unwrap(), body);
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn max_rate_caps_the_average_speed() {
+        assert_rate_is_capped("download-rate", 1).await;
+    }
+
+    #[tokio::test]
+    async fn max_rate_is_shared_by_parallel_ranges() {
+        assert_rate_is_capped("download-rate-parallel", 4).await;
+    }
+
+    #[tokio::test]
+    async fn max_rate_below_the_minimum_fails_right_away() {
+        let server = stream_server(random_body(1000)).await;
+        let (url, stream) = served_stream(&server, Some(1000));
+        let dir = temp_dir("download-rate-minimum");
+        let dest = dir.join("video.mp4");
+
+        let options = DownloadOptions {
+            max_rate: Some(MIN_RATE - 1),
+            ..Default::default()
+        };
+        assert!(download(&url, &stream, &dest, options).await.is_err());
+        assert!(server.requests().is_empty());
+
+        let options = DownloadOptions {
+            max_rate: Some(MIN_RATE),
+            ..Default::default()
+        };
+        assert!(Progress::new(options).is_ok());
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
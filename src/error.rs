@@ -0,0 +1,474 @@
+//! The errors worth telling apart from the others. They're returned inside the `anyhow::Error` of the
+//! methods, use `downcast_ref` to match on one. The reasons YouTube gives for refusing a video end the
+//! message of a `VideoUnavailable` as `(YouTube said: ...)`.
+
+use core::fmt;
+#[cfg(feature = "mux")]
+use std::path::PathBuf;
+
+use crate::yt_interface::YtClient;
+
+/// Reasons YouTube refuses to serve a video, returned by the extraction methods.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VideoUnavailable {
+    /// The video is age-restricted and can only be extracted with the cookies of a logged-in account.
+    AgeRestricted { reason: String },
+    /// The uploader made the video private.
+    Private { reason: String },
+    /// The video was removed or never existed.
+    Deleted { reason: String },
+    /// The account that uploaded the video was terminated.
+    AccountTerminated { reason: String },
+    /// The video isn't available in the country the request came from.
+    GeoBlocked {
+        reason: String,
+        /// Codes of the countries the video is available in, like `US`, empty if YouTube didn't say.
+        allowed_countries: Vec<String>,
+    },
+    /// The video is a perk for the members of the channel.
+    MembersOnly { reason: String },
+    /// The video was blocked on copyright grounds.
+    Copyright {
+        reason: String,
+        /// Who claimed the copyright, if YouTube named them.
+        claimant: Option<String>,
+    },
+    /// The video is a premiere or a live stream that hasn't started yet.
+    Upcoming {
+        reason: String,
+        /// When it's scheduled to start, in seconds since the unix epoch.
+        scheduled_start_time: Option<u64>,
+    },
+    /// YouTube suspects the requests come from a bot, which usually happens to datacenter IP addresses.
+    /// Pass a PO token with `TydleOptions::po_token` along with the `visitor_data` it was minted for.
+    BotCheck { reason: String },
+    /// YouTube refused the video for a reason not covered by the other variants.
+    Unknown {
+        /// Status of the `playabilityStatus`, like `UNPLAYABLE` or `LOGIN_REQUIRED`.
+        status: String,
+        reason: String,
+    },
+}
+
+impl VideoUnavailable {
+    /// What YouTube said about the video.
+    fn reason(&self) -> &str {
+        match self {
+            Self::AgeRestricted { reason }
+            | Self::Private { reason }
+            | Self::Deleted { reason }
+            | Self::AccountTerminated { reason }
+            | Self::GeoBlocked { reason, .. }
+            | Self::MembersOnly { reason }
+            | Self::Copyright { reason, .. }
+            | Self::Upcoming { reason, .. }
+            | Self::BotCheck { reason }
+            | Self::Unknown { reason, .. } => reason,
+        }
+    }
+
+    /// Whether the video stays unavailable no matter how it is requested, unlike age restrictions and
+    /// members-only videos which can be lifted by authenticating, or geo-blocks which depend on the location.
+    pub fn is_permanent(&self) -> bool {
+        matches!(
+            self,
+            Self::Private { .. }
+                | Self::Deleted { .. }
+                | Self::AccountTerminated { .. }
+                | Self::Copyright { .. }
+        )
+    }
+}
+
+impl fmt::Display for VideoUnavailable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::AgeRestricted { .. } => write!(
+                f,
+                "The video is age-restricted, provide the cookies of a logged-in account and enable `authenticate` to extract it."
+            ),
+            Self::Private { .. } => write!(f, "The video is private."),
+            Self::Deleted { .. } => write!(f, "The video has been removed or does not exist."),
+            Self::AccountTerminated { .. } => {
+                write!(f, "The account of the uploader has been terminated.")
+            }
+            Self::GeoBlocked {
+                allowed_countries, ..
+            } if !allowed_countries.is_empty() => write!(
+                f,
+                "The video is not available in your country, only in {}.",
+                allowed_countries.join(", ")
+            ),
+            Self::GeoBlocked { .. } => write!(f, "The video is not available in your country."),
+            Self::MembersOnly { .. } => write!(
+                f,
+                "The video is only available to members of the channel, provide the cookies of a member's account and enable `authenticate` to extract it."
+            ),
+            Self::Copyright {
+                claimant: Some(claimant),
+                ..
+            } => write!(
+                f,
+                "The video was blocked on copyright grounds by {}.",
+                claimant
+            ),
+            Self::Copyright { .. } => write!(f, "The video was blocked on copyright grounds."),
+            Self::Upcoming { .. } => write!(
+                f,
+                "The video is a premiere or live stream that hasn't started yet."
+            ),
+            Self::BotCheck { .. } => write!(
+                f,
+                "YouTube asked to confirm this isn't a bot, provide a PO token and the visitor data it was minted for with `po_token` and `visitor_data`."
+            ),
+            Self::Unknown { status, .. } => {
+                write!(f, "The video is unavailable with status {}.", status)
+            }
+        }?;
+
+        write!(f, " (YouTube said: {})", self.reason())
+    }
+}
+
+impl std::error::Error for VideoUnavailable {}
+
+/// YouTube redirected to its cookie consent page, even after accepting consent with the `SOCS` cookie.
+/// Usually happens for requests from the EU with `disable_consent_bypass` set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConsentRequired {
+    /// URL of the consent page YouTube redirected to.
+    pub url: String,
+}
+
+impl fmt::Display for ConsentRequired {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "YouTube requires accepting cookie consent before continuing. (Redirected to {})",
+            self.url
+        )
+    }
+}
+
+impl std::error::Error for ConsentRequired {}
+
+/// None of the clients the player response was requested from returned any streams.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientsFailed {
+    /// Every client that was tried, in order, along with why it failed.
+    pub failures: Vec<(YtClient, String)>,
+}
+
+impl fmt::Display for ClientsFailed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "None of the clients returned any streams:")?;
+
+        for (client, reason) in &self.failures {
+            write!(f, "\n  {}: {}", client.as_str(), reason)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl std::error::Error for ClientsFailed {}
+
+/// Some of the streams of a response couldn't be resolved by `Cipher::resolve_streams`.
+/// The others were, and the ones listed here were left as they were.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StreamsUnresolved {
+    /// The `itag` of every stream that failed, along with why.
+    pub failures: Vec<(Option<String>, String)>,
+}
+
+impl fmt::Display for StreamsUnresolved {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} streams couldn't be resolved:", self.failures.len())?;
+
+        for (itag, reason) in &self.failures {
+            write!(
+                f,
+                "\n  {}: {}",
+                itag.as_deref().unwrap_or("unknown itag"),
+                reason
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+impl std::error::Error for StreamsUnresolved {}
+
+/// A format that `Cipher::refresh_streams` was asked for isn't offered for the video anymore.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormatGone {
+    pub itag: u32,
+}
+
+impl fmt::Display for FormatGone {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Format {} isn't offered for the video anymore.",
+            self.itag
+        )
+    }
+}
+
+impl std::error::Error for FormatGone {}
+
+/// googlevideo serves a stream with another size than YouTube declared for it, see `DownloadOptions::verify_length`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StreamLengthMismatch {
+    pub itag: Option<String>,
+    /// Size of the stream in bytes according to YouTube.
+    pub declared: u64,
+    /// Size of the stream in bytes according to googlevideo.
+    pub actual: u64,
+}
+
+impl fmt::Display for StreamLengthMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Stream {} is {} bytes according to YouTube but googlevideo serves {} bytes, the download would be cut short or corrupted.",
+            self.itag.as_deref().unwrap_or("?"),
+            self.declared,
+            self.actual
+        )
+    }
+}
+
+impl std::error::Error for StreamLengthMismatch {}
+
+/// `tydle::mux` couldn't find the `ffmpeg` binary to merge the streams with.
+#[cfg(feature = "mux")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FfmpegNotFound {
+    /// The name of the binary that was looked for, like `ffmpeg` or `ffmpeg.exe`.
+    pub binary: String,
+    /// Every directory it was looked for in, in order.
+    pub searched: Vec<PathBuf>,
+}
+
+#[cfg(feature = "mux")]
+impl fmt::Display for FfmpegNotFound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Failed to find `{}` to merge the streams with, looked in:",
+            self.binary
+        )?;
+
+        for dir in &self.searched {
+            write!(f, "\n  {}", dir.display())?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "mux")]
+impl std::error::Error for FfmpegNotFound {}
+
+/// A stream can't be muxed into the container that was asked for without encoding it again.
+#[cfg(feature = "mux")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnsupportedCodec {
+    /// Like `opus` or `vp9`.
+    pub codec: String,
+    /// Like `mp4` or `webm`.
+    pub container: String,
+}
+
+#[cfg(feature = "mux")]
+impl fmt::Display for UnsupportedCodec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} can't be muxed into {}, pick another stream or mux into mkv instead.",
+            self.codec, self.container
+        )
+    }
+}
+
+#[cfg(feature = "mux")]
+impl std::error::Error for UnsupportedCodec {}
+
+/// The uploader turned off the comments of the video.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommentsDisabled {
+    pub video_id: String,
+}
+
+impl fmt::Display for CommentsDisabled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Comments are turned off for video {}.", self.video_id)
+    }
+}
+
+impl std::error::Error for CommentsDisabled {}
+
+/// Reasons the oEmbed endpoint refuses a video, returned by `get_oembed`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OEmbedError {
+    /// The video doesn't exist or was removed.
+    NotFound { video_id: String },
+    /// The video exists, but its uploader disabled embedding or made it private.
+    NotEmbeddable { video_id: String },
+}
+
+impl fmt::Display for OEmbedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotFound { video_id } => {
+                write!(f, "Video {} does not exist or has been removed.", video_id)
+            }
+            Self::NotEmbeddable { video_id } => write!(
+                f,
+                "Video {} can't be embedded, it is private or its uploader disabled embedding.",
+                video_id
+            ),
+        }
+    }
+}
+
+impl std::error::Error for OEmbedError {}
+
+/// A clip or its URL was passed where a video was expected.
+/// Clips are parts of videos, `resolve_clip` gets the video and the part of it the clip is.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IsClip {
+    /// The ID or URL that was passed.
+    pub input: String,
+}
+
+impl fmt::Display for IsClip {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} is a clip rather than a video, resolve it to its video with `resolve_clip`.",
+            self.input
+        )
+    }
+}
+
+impl std::error::Error for IsClip {}
+
+/// A continuation token is malformed, belongs to another kind of listing, or YouTube doesn't accept it anymore.
+/// The listing has to be requested again from its first page.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContinuationExpired {
+    pub token: String,
+}
+
+impl fmt::Display for ContinuationExpired {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "The continuation {} is invalid or has expired, request the first page again.",
+            self.token
+        )
+    }
+}
+
+impl std::error::Error for ContinuationExpired {}
+
+/// Reasons deciphering a signature or `n` parameter failed, returned by the `Cipher` methods.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecipherError {
+    /// The `player.js` couldn't be downloaded, `status` is missing if YouTube didn't respond at all.
+    PlayerFetchFailed { url: String, status: Option<u16> },
+    /// None of the strategies found the signature function in the player.
+    SigFunctionNotFound {
+        /// Version of the player, missing for players provided by the caller.
+        player_version: Option<String>,
+        /// Names of the strategies that were tried, in order.
+        strategies_tried: Vec<String>,
+        /// The player code around where the signature function usually is, to include in bug reports.
+        snippet: Option<String>,
+    },
+    /// The signature function was found, but one of its operations threw.
+    TransformFailed {
+        /// Index of the statement in the signature function's body.
+        op_index: usize,
+        /// The statement, like `XY.ab(a,3)`.
+        op: String,
+    },
+    /// googlevideo refused a deciphered URL, see `TydleOptions::validate_stream_urls`.
+    UrlRejected { status: u16 },
+}
+
+impl fmt::Display for DecipherError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::PlayerFetchFailed {
+                url,
+                status: Some(status),
+            } => write!(f, "Failed to fetch the player {} (HTTP {}).", url, status),
+            Self::PlayerFetchFailed { url, status: None } => {
+                write!(f, "Failed to fetch the player {}.", url)
+            }
+            Self::SigFunctionNotFound {
+                player_version,
+                strategies_tried,
+                snippet,
+            } => {
+                write!(
+                    f,
+                    "The signature function was not found in player {} with the {} strategies.",
+                    player_version
+                        .as_deref()
+                        .unwrap_or("provided by the caller"),
+                    strategies_tried.join(", ")
+                )?;
+
+                match snippet {
+                    Some(snippet) => write!(f, " Player code: {}", snippet),
+                    None => Ok(()),
+                }
+            }
+            Self::TransformFailed { op_index, op } => write!(
+                f,
+                "Operation {} of the signature function threw: {}",
+                op_index, op
+            ),
+            Self::UrlRejected { status } => write!(
+                f,
+                "The stream URL was rejected with HTTP {}, the signature was probably deciphered with an outdated player.",
+                status
+            ),
+        }
+    }
+}
+
+impl std::error::Error for DecipherError {}
+
+/// Copy of `error` for every caller sharing the result of one request, like the waiters of a `SingleFlight`.
+/// The typed error it carries is cloned so `downcast_ref` keeps working, its message is kept as context.
+pub(crate) fn clone_error(error: &anyhow::Error) -> anyhow::Error {
+    fn clone_typed<E>(error: &anyhow::Error) -> Option<anyhow::Error>
+    where
+        E: std::error::Error + Clone + Send + Sync + 'static,
+    {
+        let typed = error.downcast_ref::<E>()?;
+
+        Some(match typed.to_string() == error.to_string() {
+            true => anyhow::Error::new(typed.clone()),
+            false => anyhow::Error::new(typed.clone()).context(error.to_string()),
+        })
+    }
+
+    clone_typed::<VideoUnavailable>(error)
+        .or_else(|| clone_typed::<DecipherError>(error))
+        .or_else(|| clone_typed::<ConsentRequired>(error))
+        .or_else(|| clone_typed::<ClientsFailed>(error))
+        .or_else(|| clone_typed::<StreamsUnresolved>(error))
+        .or_else(|| clone_typed::<FormatGone>(error))
+        .or_else(|| clone_typed::<CommentsDisabled>(error))
+        .or_else(|| clone_typed::<OEmbedError>(error))
+        .or_else(|| clone_typed::<IsClip>(error))
+        .or_else(|| clone_typed::<ContinuationExpired>(error))
+        .unwrap_or_else(|| anyhow::anyhow!(error.to_string()))
+}
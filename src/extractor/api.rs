@@ -6,8 +6,8 @@ use serde_json::{Value, json};
 
 use crate::{
     extractor::{
-        auth::ExtractorAuthHandle, client::INNERTUBE_CLIENTS, extract::YtExtractor,
-        ytcfg::ExtractorYtCfgHandle,
+        auth::ExtractorAuthHandle, client::INNERTUBE_CLIENTS, cookies::ExtractorCookieHandle,
+        extract::YtExtractor, ytcfg::ExtractorYtCfgHandle,
     },
     yt_interface::{DEFAULT_YT_CLIENT, YtClient, YtEndpoint},
 };
@@ -62,10 +62,15 @@ impl ExtractorApiHandle for YtExtractor {
 
         headers.insert("Origin", origin.clone());
 
-        if let Some(available_visitor_id) = visitor_id {
-            headers.insert("X-Goog-Visitor-Id", available_visitor_id);
-        } else if let Some(selected_visitor_id) = self.select_visitor_data(&[&ytcfg]) {
-            headers.insert("X-Goog-Visitor-Id", selected_visitor_id);
+        if let Some(visitor_id) = self
+            .tydle_options
+            .visitor_data
+            .clone()
+            .or(visitor_id)
+            .or_else(|| self.select_visitor_data(&[&ytcfg]))
+            .or_else(|| self.get_session_visitor_data())
+        {
+            headers.insert("X-Goog-Visitor-Id", visitor_id);
         }
 
         let innertube_client_context = innertube_client.innertube_context.get("client").unwrap();
@@ -106,7 +111,6 @@ impl ExtractorApiHandle for YtExtractor {
         #[cfg(feature = "logging")]
         log::info!("Requesting YouTube API at {}", api_url);
 
-        let http_client = reqwest::Client::new();
         let mut real_headers =
             self.generate_api_headers(Default::default(), None, None, None, None, Some(client))?;
         let mut data: HashMap<String, Value> = HashMap::new();
@@ -133,7 +137,20 @@ impl ExtractorApiHandle for YtExtractor {
             real_headers.extend(availabe_headers);
         }
 
-        let mut request_builder = http_client
+        if let Some(cookie_header) = self.get_cookie_header(yt_url.as_str())? {
+            real_headers.insert("Cookie", cookie_header);
+        }
+
+        // The OAuth token replaces the `SAPISIDHASH` authorization of the cookies.
+        #[cfg(feature = "oauth")]
+        let use_oauth = self.tydle_options.authenticate && self.oauth.has_token();
+        #[cfg(feature = "oauth")]
+        if use_oauth {
+            real_headers.remove("Authorization");
+        }
+
+        let mut request_builder = self
+            .cookie_client
             .post(yt_url)
             .json(&data)
             .query(&[("prettyPrint", "false")]);
@@ -148,7 +165,25 @@ impl ExtractorApiHandle for YtExtractor {
 
         request_builder = request_builder.header("Content-Type", "application/json");
 
-        let response = request_builder.send().await?;
-        Ok(response.json().await?)
+        let request = request_builder.build()?;
+        let headers = request.headers().clone();
+
+        #[cfg(feature = "oauth")]
+        let response = match use_oauth {
+            true => {
+                let request_builder =
+                    reqwest::RequestBuilder::from_parts(self.cookie_client.clone(), request);
+                self.oauth.send(&self.http_client, request_builder).await?
+            }
+            false => self.cookie_client.execute(request).await?,
+        };
+        #[cfg(not(feature = "oauth"))]
+        let response = self.cookie_client.execute(request).await?;
+        let response = self.follow_redirects(response, headers).await?;
+
+        let response: HashMap<String, Value> = response.json().await?;
+        self.remember_visitor_data(&response);
+
+        Ok(response)
     }
 }
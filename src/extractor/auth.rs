@@ -4,15 +4,17 @@ use anyhow::{Result, anyhow};
 use serde_json::Value;
 
 use crate::{
-    cookies::CookieStore,
+    cookies::CookieAttributes,
     extractor::{cookies::ExtractorCookieHandle, extract::YtExtractor, json::ExtractorJsonHandle},
     utils::{convert_to_query_string, parse_query_string},
-    yt_interface::{PREFERRED_LOCALE, YT_URL},
+    yt_interface::{PREFERRED_LOCALE, YT_DOMAIN, YT_URL},
 };
 
 pub trait ExtractorAuthHandle {
     fn initialize_cookie_auth(&self) -> Result<()>;
     fn initialize_consent(&self) -> Result<()>;
+    /// Accept YouTube's cookie consent for every YouTube subdomain.
+    fn set_consent_cookies(&self) -> Result<()>;
     fn initialize_pref(&self) -> Result<()>;
     fn is_authenticated(&self) -> Result<bool>;
     fn has_auth_cookies(&self) -> Result<bool>;
@@ -45,14 +47,20 @@ impl ExtractorAuthHandle for YtExtractor {
         if self.has_auth_cookies()? {
             self.passed_auth_cookies.store(true, Ordering::Relaxed);
             #[cfg(feature = "logging")]
-            log::info!("Found YouTube account cookies.");
+            if self.tydle_options.authenticate {
+                log::info!("Found YouTube account cookies.");
+            } else {
+                log::warn!(
+                    "Found YouTube account cookies, but they won't be sent since `authenticate` is disabled."
+                );
+            }
         }
 
         Ok(())
     }
 
     fn initialize_consent(&self) -> Result<()> {
-        if self.has_auth_cookies()? {
+        if self.tydle_options.disable_consent_bypass || self.has_auth_cookies()? {
             return Ok(());
         }
 
@@ -64,7 +72,20 @@ impl ExtractorAuthHandle for YtExtractor {
             }
         }
 
-        self.cookie_jar.set(YT_URL, "SOCS", "CAI")?;
+        self.set_consent_cookies()
+    }
+
+    fn set_consent_cookies(&self) -> Result<()> {
+        let attributes = CookieAttributes {
+            domain: Some(YT_DOMAIN.into()),
+            path: Some("/".into()),
+            ..Default::default()
+        };
+
+        self.cookie_store
+            .set_with_attributes(YT_URL, "SOCS", "CAI", &attributes)?;
+        self.cookie_store
+            .set_with_attributes(YT_URL, "CONSENT", "YES+", &attributes)?;
         Ok(())
     }
 
@@ -79,17 +100,32 @@ impl ExtractorAuthHandle for YtExtractor {
             }
         }
 
-        pref.insert("hl".into(), PREFERRED_LOCALE.into());
+        pref.insert(
+            "hl".into(),
+            self.tydle_options
+                .hl
+                .clone()
+                .unwrap_or_else(|| PREFERRED_LOCALE.into()),
+        );
+
+        if let Some(gl) = &self.tydle_options.gl {
+            pref.insert("gl".into(), gl.clone());
+        }
         pref.insert("tz".into(), "UTC".into());
 
         let pref_qs = convert_to_query_string(&pref);
 
-        self.cookie_jar.set(YT_URL, "PREF", pref_qs.as_str())?;
+        self.cookie_store.set(YT_URL, "PREF", pref_qs.as_str())?;
         Ok(())
     }
 
     fn is_authenticated(&self) -> Result<bool> {
-        self.has_auth_cookies()
+        #[cfg(feature = "oauth")]
+        if self.tydle_options.authenticate && self.oauth.has_token() {
+            return Ok(true);
+        }
+
+        Ok(self.tydle_options.authenticate && self.has_auth_cookies()?)
     }
 
     fn has_auth_cookies(&self) -> Result<bool> {
@@ -220,15 +256,18 @@ impl ExtractorAuthHandle for YtExtractor {
             None => self.get_user_session_id(&[&ytcfg]),
         };
 
-        if let Some(auth) = self.get_sid_authorization_header(Some(origin.clone()), user_sess_id)? {
+        if self.is_authenticated()?
+            && let Some(auth) =
+                self.get_sid_authorization_header(Some(origin.clone()), user_sess_id)?
+        {
             headers.insert("Authorization", auth);
             headers.insert("X-Origin", origin);
         }
 
-        if let Some(logged_in) = ytcfg.get("LOGGED_IN") {
-            if logged_in.as_bool().unwrap_or_default() {
-                headers.insert("X-Youtube-Bootstrap-Logged-In", "true".into());
-            }
+        if let Some(logged_in) = ytcfg.get("LOGGED_IN")
+            && logged_in.as_bool().unwrap_or_default()
+        {
+            headers.insert("X-Youtube-Bootstrap-Logged-In", "true".into());
         }
 
         Ok(headers)
@@ -0,0 +1,246 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use futures::{StreamExt, stream};
+use serde_json::Value;
+
+use crate::{
+    error::{OEmbedError, VideoUnavailable},
+    extractor::{
+        api::ExtractorApiHandle, extract::YtExtractor, oembed::ExtractorOEmbedHandle,
+        player::ExtractorPlayerHandle,
+    },
+    yt_interface::{VideoId, YtAvailability, YtClient, YtEndpoint},
+};
+
+/// How many videos `check_availability_many` probes at the same time.
+const MAX_CONCURRENT_PROBES: usize = 8;
+
+pub trait ExtractorAvailabilityHandle {
+    /// Find out whether a video can be played with an oEmbed request and, unless it's gone,
+    /// a player request from a client that doesn't need the player JS.
+    async fn check_availability(&self, video_id: &VideoId) -> Result<YtAvailability>;
+    /// `check_availability` of every video, in the order they were given.
+    async fn check_availability_many(&self, video_ids: &[VideoId]) -> Vec<Result<YtAvailability>>;
+    fn classify_availability(
+        &self,
+        player_response: &HashMap<String, Value>,
+    ) -> Result<YtAvailability>;
+}
+
+impl ExtractorAvailabilityHandle for YtExtractor {
+    async fn check_availability(&self, video_id: &VideoId) -> Result<YtAvailability> {
+        // oEmbed only answers for public videos, but a 404 is enough to know one is gone.
+        if let Err(err) = self.extract_oembed(video_id).await
+            && let Some(OEmbedError::NotFound { .. }) = err.downcast_ref::<OEmbedError>()
+        {
+            return Ok(YtAvailability::Deleted);
+        }
+
+        let mut query = HashMap::new();
+        query.insert("videoId".into(), video_id.as_str().into());
+
+        // No signature timestamp, the streams aren't going to be deciphered.
+        let player_response = self
+            .call_api(
+                YtEndpoint::Player,
+                query,
+                None,
+                None,
+                None,
+                Some(&YtClient::AndroidVr),
+            )
+            .await?;
+
+        self.classify_availability(&player_response)
+    }
+
+    async fn check_availability_many(&self, video_ids: &[VideoId]) -> Vec<Result<YtAvailability>> {
+        stream::iter(video_ids)
+            .map(|video_id| self.check_availability(video_id))
+            .buffered(MAX_CONCURRENT_PROBES)
+            .collect()
+            .await
+    }
+
+    fn classify_availability(
+        &self,
+        player_response: &HashMap<String, Value>,
+    ) -> Result<YtAvailability> {
+        if self.is_upcoming(player_response) {
+            return Ok(YtAvailability::Upcoming);
+        }
+
+        if self.is_age_gated(player_response) {
+            return Ok(YtAvailability::AgeRestricted);
+        }
+
+        if let Some(unavailability) = self.get_unavailability(player_response) {
+            return match unavailability {
+                // Says nothing about the video.
+                VideoUnavailable::BotCheck { .. } => Err(unavailability.into()),
+                VideoUnavailable::Private { .. } => Ok(YtAvailability::Private),
+                VideoUnavailable::Deleted { .. } | VideoUnavailable::AccountTerminated { .. } => {
+                    Ok(YtAvailability::Deleted)
+                }
+                VideoUnavailable::GeoBlocked { .. } => Ok(YtAvailability::GeoBlocked),
+                VideoUnavailable::AgeRestricted { .. } => Ok(YtAvailability::AgeRestricted),
+                VideoUnavailable::Upcoming { .. } => Ok(YtAvailability::Upcoming),
+                VideoUnavailable::MembersOnly { .. }
+                | VideoUnavailable::Copyright { .. }
+                | VideoUnavailable::Unknown { .. } => Ok(YtAvailability::Unavailable),
+            };
+        }
+
+        let is_live = player_response
+            .get("videoDetails")
+            .and_then(|vd| vd.get("isLive"))
+            .and_then(|is_live| is_live.as_bool())
+            .unwrap_or_default();
+
+        if is_live {
+            return Ok(YtAvailability::LiveNow);
+        }
+
+        Ok(YtAvailability::Playable)
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::{
+        test_server::{TestResponse, TestServer, test_extractor},
+        tydle::TydleOptions,
+    };
+
+    fn classify(status: &str) -> Result<YtAvailability> {
+        let mut fixtures: HashMap<String, HashMap<String, Value>> = serde_json::from_str(
+            include_str!("../../tests/fixtures/playability_statuses.json"),
+        )
+        .unwrap();
+        let player_response = fixtures.remove(status).unwrap();
+
+        test_extractor(TydleOptions::default(), reqwest::Client::new())
+            .classify_availability(&player_response)
+    }
+
+    /// Extractor sending every request through `server`, the player requests as `CONNECT` tunnels it refuses.
+    fn proxied_extractor(server: &TestServer) -> YtExtractor {
+        let options = TydleOptions {
+            prefer_insecure: true,
+            ..Default::default()
+        };
+        let http_client = reqwest::Client::builder()
+            .proxy(reqwest::Proxy::all(server.url("")).unwrap())
+            .build()
+            .unwrap();
+
+        test_extractor(options, http_client)
+    }
+
+    /// Requests that tried to open a tunnel for the player API instead of going to oEmbed.
+    fn player_requests(server: &TestServer) -> usize {
+        server.requests().len() - server.count("http://www.youtube.com/oembed")
+    }
+
+    #[test]
+    fn playability_statuses_map_to_their_availability() {
+        let cases = [
+            ("private", YtAvailability::Private),
+            ("deleted", YtAvailability::Deleted),
+            ("account_terminated", YtAvailability::Deleted),
+            ("geo_blocked", YtAvailability::GeoBlocked),
+            ("members_only", YtAvailability::Unavailable),
+            ("copyright", YtAvailability::Unavailable),
+            ("age_restricted", YtAvailability::AgeRestricted),
+            ("unknown", YtAvailability::Unavailable),
+            ("upcoming", YtAvailability::Upcoming),
+            ("live_now", YtAvailability::LiveNow),
+            ("playable", YtAvailability::Playable),
+        ];
+
+        for (status, availability) in cases {
+            assert_eq!(classify(status).unwrap(), availability, "{}", status);
+        }
+    }
+
+    #[test]
+    fn bot_check_is_an_error_and_not_an_availability() {
+        let err = classify("bot_check").unwrap_err();
+
+        assert!(matches!(
+            err.downcast_ref::<VideoUnavailable>(),
+            Some(VideoUnavailable::BotCheck { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn oembed_not_found_is_deleted_without_a_player_request() {
+        let server = TestServer::start(|_| TestResponse::status(404)).await;
+        let extractor = proxied_extractor(&server);
+        let video_id = VideoId::new("dQw4w9WgXcQ").unwrap();
+
+        let availability = extractor.check_availability(&video_id).await.unwrap();
+
+        assert_eq!(availability, YtAvailability::Deleted);
+        assert_eq!(server.count("http://www.youtube.com/oembed"), 1);
+        assert_eq!(player_requests(&server), 0);
+    }
+
+    #[tokio::test]
+    async fn oembed_refusal_still_asks_the_player() {
+        let server = TestServer::start(|req| match req.path.starts_with("http://") {
+            true => TestResponse::status(401),
+            false => TestResponse::status(403),
+        })
+        .await;
+        let extractor = proxied_extractor(&server);
+        let video_id = VideoId::new("dQw4w9WgXcQ").unwrap();
+
+        // Private and unembeddable videos also get a 401 from oEmbed, only the player can tell.
+        assert!(extractor.check_availability(&video_id).await.is_err());
+        assert_eq!(player_requests(&server), 1);
+    }
+
+    #[tokio::test]
+    async fn many_keeps_the_order_it_was_given() {
+        // The earlier videos answer last, so they finish in the reverse order.
+        let gone = [
+            ("aaaaaaaaaaa", Some(300)),
+            ("bbbbbbbbbbb", None),
+            ("ccccccccccc", Some(200)),
+            ("ddddddddddd", None),
+            ("eeeeeeeeeee", Some(0)),
+        ];
+        let server = TestServer::start(move |req| {
+            if !req.path.starts_with("http://") {
+                return TestResponse::status(403);
+            }
+
+            let (_, delay) = gone.iter().find(|(id, _)| req.path.contains(id)).unwrap();
+
+            match delay {
+                Some(delay) => TestResponse::status(404).delay(Duration::from_millis(*delay)),
+                None => TestResponse::status(401).delay(Duration::from_millis(100)),
+            }
+        })
+        .await;
+        let extractor = proxied_extractor(&server);
+        let video_ids: Vec<VideoId> = gone
+            .iter()
+            .map(|(id, _)| VideoId::new(*id).unwrap())
+            .collect();
+
+        let results = extractor.check_availability_many(&video_ids).await;
+
+        let deleted: Vec<bool> = results
+            .iter()
+            .map(|result| matches!(result, Ok(YtAvailability::Deleted)))
+            .collect();
+        assert_eq!(deleted, [true, false, true, false, true]);
+        assert!(results[1].is_err() && results[3].is_err());
+    }
+}
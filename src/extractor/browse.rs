@@ -0,0 +1,137 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use serde_json::Value;
+
+use crate::{
+    error::ContinuationExpired,
+    extractor::{api::ExtractorApiHandle, extract::YtExtractor, json::ExtractorJsonHandle},
+    yt_interface::{YtEndpoint, YtThumbnail},
+};
+
+pub trait ExtractorBrowseHandle {
+    /// Request a listing like a playlist or a channel tab from the browse endpoint.
+    async fn browse(&self, browse_id: &str, params: Option<&str>) -> Result<Value>;
+    /// Request the next page of a listing of `endpoint` with the token from `get_continuation_token`.
+    async fn browse_continuation(&self, endpoint: YtEndpoint, token: &str) -> Result<Value>;
+    /// Collect the values of every key named `renderer` in `data`, like all `playlistVideoRenderer`s of a page.
+    fn find_renderers<'a>(&self, data: &'a Value, renderer: &str) -> Vec<&'a Value>;
+    /// Get the token of the next page of a listing, missing on the last page.
+    fn get_continuation_token(&self, data: &Value) -> Option<String>;
+    /// Parse the `thumbnails` of a `thumbnail` object.
+    fn extract_thumbnails(&self, thumbnail: &Value) -> Vec<YtThumbnail>;
+    /// Parse the thumbnail of a `lockupViewModel`, the newer layout of videos and playlists in listings.
+    fn extract_lockup_thumbnails(&self, lockup: &Value) -> Vec<YtThumbnail>;
+}
+
+impl ExtractorBrowseHandle for YtExtractor {
+    async fn browse(&self, browse_id: &str, params: Option<&str>) -> Result<Value> {
+        let mut query = HashMap::new();
+        query.insert("browseId".into(), browse_id.into());
+
+        if let Some(params) = params {
+            query.insert("params".into(), params.into());
+        }
+
+        let response = self
+            .call_api(YtEndpoint::Browse, query, None, None, None, None)
+            .await?;
+
+        Ok(Value::Object(response.into_iter().collect()))
+    }
+
+    async fn browse_continuation(&self, endpoint: YtEndpoint, token: &str) -> Result<Value> {
+        let mut query = HashMap::new();
+        query.insert("continuation".into(), token.into());
+
+        let response = self
+            .call_api(endpoint, query, None, None, None, None)
+            .await?;
+
+        // YouTube answers tokens it doesn't accept anymore with an `INVALID_ARGUMENT` error.
+        if response
+            .get("error")
+            .and_then(|e| e.get("code"))
+            .and_then(|c| c.as_u64())
+            == Some(400)
+        {
+            return Err(ContinuationExpired {
+                token: token.to_string(),
+            }
+            .into());
+        }
+
+        Ok(Value::Object(response.into_iter().collect()))
+    }
+
+    fn find_renderers<'a>(&self, data: &'a Value, renderer: &str) -> Vec<&'a Value> {
+        let mut renderers = Vec::new();
+        let mut pending = vec![data];
+
+        while let Some(value) = pending.pop() {
+            match value {
+                Value::Object(map) => {
+                    for (key, value) in map.iter().rev() {
+                        if key == renderer {
+                            renderers.push(value);
+                        } else {
+                            pending.push(value);
+                        }
+                    }
+                }
+                // Pushed in reverse, so the items are visited in the order of the listing.
+                Value::Array(items) => pending.extend(items.iter().rev()),
+                _ => {}
+            }
+        }
+
+        renderers
+    }
+
+    fn get_continuation_token(&self, data: &Value) -> Option<String> {
+        self.find_renderers(data, "continuationItemRenderer")
+            .into_iter()
+            .find_map(|renderer| self.find_key(renderer, "token"))
+    }
+
+    fn extract_thumbnails(&self, thumbnail: &Value) -> Vec<YtThumbnail> {
+        thumbnail
+            .get("thumbnails")
+            .and_then(|t| t.as_array())
+            .map(|thumbnails| {
+                thumbnails
+                    .iter()
+                    .filter_map(|t| {
+                        t.get("url")
+                            .and_then(|v| v.as_str())
+                            .map(|url| YtThumbnail {
+                                url: url.to_string(),
+                                height: t.get("height").and_then(|h| h.as_u64()),
+                                width: t.get("width").and_then(|w| w.as_u64()),
+                            })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    fn extract_lockup_thumbnails(&self, lockup: &Value) -> Vec<YtThumbnail> {
+        self.find_renderers(lockup, "thumbnailViewModel")
+            .into_iter()
+            .next()
+            .and_then(|thumbnail| thumbnail.get("image")?.get("sources")?.as_array())
+            .map(|sources| {
+                sources
+                    .iter()
+                    .filter_map(|source| {
+                        Some(YtThumbnail {
+                            url: source.get("url")?.as_str()?.to_string(),
+                            height: source.get("height").and_then(|h| h.as_u64()),
+                            width: source.get("width").and_then(|w| w.as_u64()),
+                        })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
@@ -0,0 +1,148 @@
+use anyhow::{Result, bail};
+use fancy_regex::Regex;
+use serde_json::Value;
+
+use crate::{
+    extractor::{cookies::ExtractorCookieHandle, extract::YtExtractor},
+    utils::unescape_xml,
+    yt_interface::{YtCaptionCue, YtCaptionTrack},
+};
+
+pub trait ExtractorCaptionsHandle {
+    /// Download the timedtext of a caption track and parse it into cues.
+    async fn fetch_caption_track(&self, track: &YtCaptionTrack) -> Result<Vec<YtCaptionCue>>;
+    /// Parse timedtext in either of the formats YouTube serves it in, decided by the `fmt` parameter of the URL.
+    fn parse_timedtext(&self, timedtext: &str) -> Result<Vec<YtCaptionCue>>;
+    /// Parse the `json3` format, whose events hold the text in segments.
+    fn parse_timedtext_json3(&self, timedtext: &str) -> Result<Vec<YtCaptionCue>>;
+    /// Parse the XML formats, `<text start="1.5" dur="2">` in seconds by default
+    /// and `<p t="1500" d="2000">` in milliseconds with `fmt=srv3`.
+    fn parse_timedtext_xml(&self, timedtext: &str) -> Result<Vec<YtCaptionCue>>;
+}
+
+impl ExtractorCaptionsHandle for YtExtractor {
+    async fn fetch_caption_track(&self, track: &YtCaptionTrack) -> Result<Vec<YtCaptionCue>> {
+        #[cfg(feature = "logging")]
+        log::info!("Downloading {} captions", track.language_code);
+
+        let mut request_builder = self.http_client.get(&track.base_url);
+
+        if let Some(cookie_header) = self.get_cookie_header(&track.base_url)? {
+            request_builder = request_builder.header("Cookie", cookie_header);
+        }
+
+        let timedtext = request_builder
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+
+        if timedtext.trim().is_empty() {
+            bail!(
+                "YouTube returned no timedtext for the {} captions, the track might require a PO token.",
+                track.language_code
+            )
+        }
+
+        self.parse_timedtext(&timedtext)
+    }
+
+    fn parse_timedtext(&self, timedtext: &str) -> Result<Vec<YtCaptionCue>> {
+        if timedtext.trim_start().starts_with('{') {
+            self.parse_timedtext_json3(timedtext)
+        } else {
+            self.parse_timedtext_xml(timedtext)
+        }
+    }
+
+    fn parse_timedtext_json3(&self, timedtext: &str) -> Result<Vec<YtCaptionCue>> {
+        let data: Value = serde_json::from_str(timedtext)?;
+
+        let cues = data
+            .get("events")
+            .and_then(|e| e.as_array())
+            .map(|events| {
+                events
+                    .iter()
+                    .filter_map(|event| {
+                        // Events without segments only position or style the ones around them.
+                        let text = event
+                            .get("segs")?
+                            .as_array()?
+                            .iter()
+                            .filter_map(|seg| seg.get("utf8").and_then(|u| u.as_str()))
+                            .collect::<String>();
+
+                        if text.trim().is_empty() {
+                            return None;
+                        }
+
+                        Some(YtCaptionCue {
+                            start_ms: event.get("tStartMs").and_then(|t| t.as_u64())?,
+                            duration_ms: event
+                                .get("dDurationMs")
+                                .and_then(|d| d.as_u64())
+                                .unwrap_or_default(),
+                            text,
+                        })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Ok(cues)
+    }
+
+    fn parse_timedtext_xml(&self, timedtext: &str) -> Result<Vec<YtCaptionCue>> {
+        let cue_re = Regex::new(r"(?s)<(text|p)\b([^>]*)>(.*?)</\1>")?;
+        let attribute_re = Regex::new(r#"\b(start|dur|t|d)="([\d.]+)""#)?;
+        let tag_re = Regex::new(r"<[^>]+>")?;
+
+        let mut cues = Vec::new();
+
+        for captures in cue_re.captures_iter(timedtext) {
+            let captures = captures?;
+            let is_srv3 = &captures[1] == "p";
+
+            let mut start_ms = None;
+            let mut duration_ms = 0;
+
+            for attribute in attribute_re.captures_iter(&captures[2]) {
+                let attribute = attribute?;
+                let Ok(value) = attribute[2].parse::<f64>() else {
+                    continue;
+                };
+                let ms = if is_srv3 { value } else { value * 1000.0 }.round() as u64;
+
+                match &attribute[1] {
+                    "start" | "t" => start_ms = Some(ms),
+                    "dur" | "d" => duration_ms = ms,
+                    _ => {}
+                }
+            }
+
+            // srv3 wraps words in `<s>` tags, the default format escapes its text as HTML a second time,
+            // including markup like `<font>`.
+            let mut text = unescape_xml(&tag_re.replace_all(&captures[3], ""));
+
+            if !is_srv3 {
+                text = unescape_xml(&tag_re.replace_all(&text, ""));
+            }
+
+            let text = text.trim().to_string();
+
+            if let Some(start_ms) = start_ms
+                && !text.is_empty()
+            {
+                cues.push(YtCaptionCue {
+                    start_ms,
+                    duration_ms,
+                    text,
+                });
+            }
+        }
+
+        Ok(cues)
+    }
+}
@@ -0,0 +1,183 @@
+use std::collections::HashMap;
+
+use anyhow::{Result, anyhow, bail};
+use serde_json::Value;
+
+use crate::{
+    extractor::{
+        api::ExtractorApiHandle, browse::ExtractorBrowseHandle, extract::YtExtractor,
+        json::ExtractorJsonHandle,
+    },
+    utils::{parse_count, parse_duration},
+    yt_interface::{
+        ChannelId, Continuation, VideoId, YtChannelVideo, YtChannelVideos, YtChannelVideosOrder,
+        YtChannelVideosPage, YtEndpoint,
+    },
+};
+
+/// Selects the Videos tab of a channel.
+const VIDEOS_TAB_PARAMS: &str = "EgZ2aWRlb3PyBgQKAjoA";
+
+pub trait ExtractorChannelHandle {
+    /// Resolve the `UC...` ID of a channel from one of its other forms.
+    async fn resolve_channel_id(&self, channel_id: &ChannelId) -> Result<String>;
+    async fn extract_channel_videos(
+        &self,
+        channel_id: &ChannelId,
+        order: YtChannelVideosOrder,
+    ) -> Result<YtChannelVideos>;
+    async fn extract_channel_videos_continuation(
+        &self,
+        continuation: &str,
+    ) -> Result<YtChannelVideosPage>;
+    /// Parse the videos and the continuation of a page of the Videos tab.
+    fn extract_channel_videos_page(&self, data: &Value) -> YtChannelVideosPage;
+}
+
+impl ExtractorChannelHandle for YtExtractor {
+    async fn resolve_channel_id(&self, channel_id: &ChannelId) -> Result<String> {
+        if let Some(id) = channel_id.as_canonical() {
+            return Ok(id.to_string());
+        }
+
+        let mut query = HashMap::new();
+        query.insert("url".into(), channel_id.get_url().into());
+
+        let response = self
+            .call_api(YtEndpoint::ResolveUrl, query, None, None, None, None)
+            .await?;
+
+        response
+            .get("endpoint")
+            .and_then(|e| e.get("browseEndpoint"))
+            .and_then(|e| e.get("browseId"))
+            .and_then(|id| id.as_str())
+            .filter(|id| id.starts_with("UC"))
+            .map(|id| id.to_string())
+            .ok_or_else(|| anyhow!("Failed to resolve channel {} to its ID.", channel_id))
+    }
+
+    async fn extract_channel_videos(
+        &self,
+        channel_id: &ChannelId,
+        order: YtChannelVideosOrder,
+    ) -> Result<YtChannelVideos> {
+        let id = self.resolve_channel_id(channel_id).await?;
+        let data = self.browse(&id, Some(VIDEOS_TAB_PARAMS)).await?;
+
+        let Some(metadata) = data
+            .get("metadata")
+            .and_then(|m| m.get("channelMetadataRenderer"))
+        else {
+            bail!(
+                "Failed to extract channel {} because YouTube didn't return its metadata.",
+                channel_id
+            )
+        };
+
+        let name = metadata
+            .get("title")
+            .and_then(|t| t.as_str())
+            .unwrap_or_default()
+            .to_string();
+        let avatar = self.extract_thumbnails(metadata.get("avatar").unwrap_or_default());
+
+        // The older header has a field for it, the newer one only lists it among the other metadata.
+        let subscriber_text = self
+            .find_renderers(&data, "subscriberCountText")
+            .into_iter()
+            .find_map(|text| self.get_text(text, None, None))
+            .or_else(|| {
+                self.find_renderers(&data, "metadataParts")
+                    .into_iter()
+                    .filter_map(|parts| parts.as_array())
+                    .flatten()
+                    .filter_map(|part| part.get("text").and_then(|t| t.get("content")))
+                    .filter_map(|content| content.as_str())
+                    .find(|content| content.contains("subscriber"))
+                    .map(|content| content.to_string())
+            });
+
+        let videos = match order {
+            YtChannelVideosOrder::Latest => self.extract_channel_videos_page(&data),
+            _ => {
+                // Sorting is done by the chips above the videos, which are in the order of `YtChannelVideosOrder`.
+                let mut chips = self.find_renderers(&data, "chipCloudChipRenderer");
+
+                if chips.is_empty() {
+                    chips = self.find_renderers(&data, "chipViewModel");
+                }
+
+                let Some(token) = chips
+                    .get(order as usize)
+                    .and_then(|chip| self.find_key(chip, "token"))
+                else {
+                    bail!(
+                        "Failed to sort the videos of channel {} by {:?}, it has no sorting options.",
+                        channel_id,
+                        order
+                    )
+                };
+
+                self.extract_channel_videos_continuation(&token).await?
+            }
+        };
+
+        Ok(YtChannelVideos {
+            channel_id: id,
+            name,
+            avatar,
+            subscriber_text,
+            videos,
+        })
+    }
+
+    async fn extract_channel_videos_continuation(
+        &self,
+        continuation: &str,
+    ) -> Result<YtChannelVideosPage> {
+        let data = self
+            .browse_continuation(YtEndpoint::Browse, continuation)
+            .await?;
+
+        Ok(self.extract_channel_videos_page(&data))
+    }
+
+    fn extract_channel_videos_page(&self, data: &Value) -> YtChannelVideosPage {
+        let videos = self
+            .find_renderers(data, "videoRenderer")
+            .into_iter()
+            .filter_map(|renderer| {
+                let video_id = renderer
+                    .get("videoId")
+                    .and_then(|v| v.as_str())
+                    .and_then(|v| VideoId::new(v).ok())?;
+
+                Some(YtChannelVideo {
+                    video_id,
+                    title: self
+                        .get_text(renderer, Some(vec![vec!["title"]]), None)
+                        .unwrap_or_default(),
+                    published_text: self.get_text(
+                        renderer,
+                        Some(vec![vec!["publishedTimeText"]]),
+                        None,
+                    ),
+                    view_count: self
+                        .get_text(renderer, Some(vec![vec!["viewCountText"]]), None)
+                        .and_then(|views| parse_count(&views)),
+                    duration: self
+                        .get_text(renderer, Some(vec![vec!["lengthText"]]), None)
+                        .and_then(|length| parse_duration(&length)),
+                    thumbnails: self
+                        .extract_thumbnails(renderer.get("thumbnail").unwrap_or_default()),
+                })
+            })
+            .collect();
+
+        YtChannelVideosPage {
+            videos,
+            continuation: self.get_continuation_token(data).map(Continuation::new),
+        }
+    }
+}
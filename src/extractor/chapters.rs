@@ -0,0 +1,196 @@
+use std::collections::HashMap;
+
+use fancy_regex::Regex;
+use serde_json::Value;
+
+use crate::{
+    extractor::{browse::ExtractorBrowseHandle, extract::YtExtractor, json::ExtractorJsonHandle},
+    utils::parse_duration,
+    yt_interface::YtChapter,
+};
+
+/// YouTube only shows chapters from the description when there are at least this many.
+const MIN_DESCRIPTION_CHAPTERS: usize = 3;
+
+pub trait ExtractorChaptersHandle {
+    /// Get the chapters of a video, preferring the ones YouTube has parsed or generated
+    /// over parsing the timestamps of the description.
+    fn extract_chapters(
+        &self,
+        initial_data: &HashMap<String, Value>,
+        description: &str,
+        duration: u64,
+    ) -> Vec<YtChapter>;
+    /// Parse the chapters of the markers of the player bar in the initial data, as starts in milliseconds and titles.
+    fn extract_structured_chapters(
+        &self,
+        initial_data: &HashMap<String, Value>,
+    ) -> Vec<(u64, String)>;
+    /// Parse lines like `0:00 Intro` or `- 1:02:03 Outro` of a description as starts in milliseconds and titles.
+    fn extract_description_chapters(&self, description: &str) -> Vec<(u64, String)>;
+}
+
+impl ExtractorChaptersHandle for YtExtractor {
+    fn extract_chapters(
+        &self,
+        initial_data: &HashMap<String, Value>,
+        description: &str,
+        duration: u64,
+    ) -> Vec<YtChapter> {
+        let mut starts = self.extract_structured_chapters(initial_data);
+
+        if starts.is_empty() {
+            starts = self.extract_description_chapters(description);
+        }
+
+        let duration_ms = (duration > 0).then_some(duration * 1000);
+        let ends = starts
+            .iter()
+            .skip(1)
+            .map(|(start_ms, _)| Some(*start_ms))
+            .chain([duration_ms])
+            .collect::<Vec<_>>();
+
+        starts
+            .into_iter()
+            .zip(ends)
+            .map(|((start_ms, title), end_ms)| YtChapter {
+                title,
+                start_ms,
+                end_ms,
+            })
+            .collect()
+    }
+
+    fn extract_structured_chapters(
+        &self,
+        initial_data: &HashMap<String, Value>,
+    ) -> Vec<(u64, String)> {
+        let Some(player_overlays) = initial_data.get("playerOverlays") else {
+            return vec![];
+        };
+
+        self.find_renderers(player_overlays, "chapterRenderer")
+            .into_iter()
+            .filter_map(|chapter| {
+                Some((
+                    chapter.get("timeRangeStartMillis")?.as_u64()?,
+                    self.get_text(chapter, Some(vec![vec!["title"]]), None)?,
+                ))
+            })
+            .collect()
+    }
+
+    fn extract_description_chapters(&self, description: &str) -> Vec<(u64, String)> {
+        let Ok(chapter_re) = Regex::new(
+            r"^[\s\-–—•*·▶►>]*[(\[]?(\d{1,2}(?::\d{1,2})?:\d{2})[)\]]?[\s\-–—:|.]*(.+)$",
+        ) else {
+            return vec![];
+        };
+
+        let mut chapters: Vec<(u64, String)> = Vec::new();
+
+        for line in description.lines() {
+            let Ok(Some(captures)) = chapter_re.captures(line.trim()) else {
+                continue;
+            };
+            let Some(start_ms) = parse_duration(&captures[1]).map(|start| start * 1000) else {
+                continue;
+            };
+            let title = captures[2].trim().to_string();
+
+            // Timestamps out of order point to moments in the video rather than starting chapters.
+            if title.is_empty()
+                || chapters
+                    .last()
+                    .is_some_and(|(previous_start_ms, _)| *previous_start_ms >= start_ms)
+            {
+                continue;
+            }
+
+            chapters.push((start_ms, title));
+        }
+
+        // YouTube only trusts timestamps as chapters when the first one starts the video.
+        if chapters.len() < MIN_DESCRIPTION_CHAPTERS || chapters[0].0 != 0 {
+            return vec![];
+        }
+
+        chapters
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use crate::{test_server::test_extractor, tydle::TydleOptions};
+
+    const DESCRIPTION: &str = include_str!("../../tests/fixtures/description_chapters.txt");
+
+    fn extractor() -> YtExtractor {
+        test_extractor(TydleOptions::default(), reqwest::Client::new())
+    }
+
+    fn chapter(title: &str, start_ms: u64, end_ms: Option<u64>) -> YtChapter {
+        YtChapter {
+            title: title.into(),
+            start_ms,
+            end_ms,
+        }
+    }
+
+    #[test]
+    fn structured_chapters_are_preferred_over_the_description() {
+        let initial_data: HashMap<String, Value> =
+            serde_json::from_str(include_str!("../../tests/fixtures/next_chapters.json")).unwrap();
+
+        assert_eq!(
+            extractor().extract_chapters(&initial_data, DESCRIPTION, 4000),
+            [
+                chapter("Intro", 0, Some(95_000)),
+                chapter("Setting up the build", 95_000, Some(3_723_000)),
+                chapter("Q&A 🎤", 3_723_000, Some(4_000_000)),
+            ]
+        );
+    }
+
+    #[test]
+    fn description_chapters_are_parsed_without_structured_ones() {
+        assert_eq!(
+            extractor().extract_chapters(&HashMap::new(), DESCRIPTION, 4000),
+            [
+                chapter("Intro", 0, Some(95_000)),
+                chapter("Setting up the project", 95_000, Some(727_000)),
+                chapter("Parsing requests", 727_000, Some(3_723_000)),
+                chapter("Q&A", 3_723_000, Some(4_000_000)),
+            ]
+        );
+    }
+
+    #[test]
+    fn last_chapter_has_no_end_without_a_duration() {
+        let chapters = extractor().extract_chapters(&HashMap::new(), DESCRIPTION, 0);
+
+        assert_eq!(chapters.last().unwrap().end_ms, None);
+    }
+
+    #[test]
+    fn description_chapters_must_start_at_zero() {
+        let description = DESCRIPTION.replace("0:00 Intro", "0:05 Intro");
+
+        assert!(
+            extractor()
+                .extract_description_chapters(&description)
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn too_few_description_chapters_are_ignored() {
+        assert!(
+            extractor()
+                .extract_description_chapters("0:00 Intro\n1:00 Outro")
+                .is_empty()
+        );
+    }
+}
@@ -0,0 +1,80 @@
+use std::time::Duration;
+
+use anyhow::{Result, anyhow};
+use serde_json::Value;
+use url::Url;
+
+use crate::{
+    extractor::{
+        browse::ExtractorBrowseHandle,
+        download::ExtractorDownloadHandle,
+        extract::{InfoExtractor, YtExtractor},
+        json::ExtractorJsonHandle,
+    },
+    yt_interface::{ClipId, ClipInfo, VideoId, YtClient},
+};
+
+pub trait ExtractorClipHandle {
+    /// Get the video a clip is cut from and the part of it the clip is from the clip's page.
+    async fn resolve_clip(&self, clip_id: &ClipId) -> Result<ClipInfo>;
+    /// Find the start and end of the clip in milliseconds, from its `clipConfig` or the `loopCommand`
+    /// the player loops it with.
+    fn extract_clip_range(&self, data: &Value) -> Option<(u64, u64)>;
+}
+
+impl ExtractorClipHandle for YtExtractor {
+    async fn resolve_clip(&self, clip_id: &ClipId) -> Result<ClipInfo> {
+        let webpage = self
+            .download_initial_webpage(Url::parse(&clip_id.get_url())?, &YtClient::Web, None)
+            .await?;
+        let data = Value::Object(
+            self.extract_yt_initial_data(&webpage)?
+                .into_iter()
+                .collect(),
+        );
+
+        let video_id = data
+            .get("currentVideoEndpoint")
+            .and_then(|ep| ep.get("watchEndpoint"))
+            .and_then(|we| we.get("videoId"))
+            .and_then(|id| id.as_str())
+            .ok_or_else(|| anyhow!("Failed to find the video of clip {}.", clip_id))?;
+
+        let (start_ms, end_ms) = self
+            .extract_clip_range(&data)
+            .ok_or_else(|| anyhow!("Failed to find the time range of clip {}.", clip_id))?;
+
+        let title = self
+            .find_renderers(&data, "clipAttributionRenderer")
+            .first()
+            .and_then(|attribution| self.get_text(attribution, Some(vec![vec!["title"]]), None))
+            .unwrap_or_default();
+
+        Ok(ClipInfo {
+            video_id: VideoId::new(video_id)?,
+            start: Duration::from_millis(start_ms),
+            end: Duration::from_millis(end_ms),
+            title,
+        })
+    }
+
+    fn extract_clip_range(&self, data: &Value) -> Option<(u64, u64)> {
+        // The milliseconds are strings in `clipConfig` and numbers in `loopCommand`.
+        let get_ms = |value: &Value, key: &str| {
+            value.get(key).and_then(|ms| {
+                ms.as_u64()
+                    .or_else(|| ms.as_str().and_then(|ms| ms.parse().ok()))
+            })
+        };
+
+        self.find_renderers(data, "clipConfig")
+            .into_iter()
+            .chain(self.find_renderers(data, "loopCommand"))
+            .find_map(|range| {
+                let start_ms = get_ms(range, "startTimeMs")?;
+                let end_ms = get_ms(range, "endTimeMs")?;
+
+                (end_ms > start_ms).then_some((start_ms, end_ms))
+            })
+    }
+}
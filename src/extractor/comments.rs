@@ -0,0 +1,415 @@
+use std::collections::HashMap;
+
+use anyhow::{Result, bail};
+use serde_json::Value;
+
+use crate::{
+    error::CommentsDisabled,
+    extractor::{
+        api::ExtractorApiHandle, browse::ExtractorBrowseHandle, extract::YtExtractor,
+        json::ExtractorJsonHandle,
+    },
+    utils::parse_count,
+    yt_interface::{
+        Continuation, VideoId, YtComment, YtCommentPage, YtCommentSort, YtEndpoint, YtThumbnail,
+    },
+};
+
+pub trait ExtractorCommentsHandle {
+    async fn extract_comments(
+        &self,
+        video_id: &VideoId,
+        sort: YtCommentSort,
+    ) -> Result<YtCommentPage>;
+    /// Get the next page of comments or the replies of a comment.
+    async fn extract_comments_continuation(&self, continuation: &str) -> Result<YtCommentPage>;
+    /// Get the continuation to the comments from the `next` response of a video, missing if they're disabled.
+    fn extract_comments_token(&self, data: &Value) -> Option<String>;
+    /// Get the continuation to reload the first page of comments in the order of `sort`.
+    fn extract_comment_sort_token(&self, data: &Value, sort: YtCommentSort) -> Option<String>;
+    /// Parse the comments of a page in their order, along with the continuation of the page.
+    fn extract_comment_page(&self, data: &Value) -> YtCommentPage;
+    /// Parse a comment of the older layout, where everything is in its `commentRenderer`.
+    fn extract_comment_renderer(&self, renderer: &Value) -> Option<YtComment>;
+    /// Parse a comment of the newer layout, whose `commentViewModel` only holds keys to the entities that have its data.
+    fn extract_comment_view_model(
+        &self,
+        view_model: &Value,
+        entities: &HashMap<&str, &Value>,
+    ) -> Option<YtComment>;
+    /// Join the runs of a comment's text, putting the shortcuts of custom emojis in place of their images.
+    fn extract_comment_text(&self, content: &Value) -> String;
+}
+
+impl ExtractorCommentsHandle for YtExtractor {
+    async fn extract_comments(
+        &self,
+        video_id: &VideoId,
+        sort: YtCommentSort,
+    ) -> Result<YtCommentPage> {
+        let mut query = HashMap::new();
+        query.insert("videoId".into(), video_id.as_str().into());
+
+        let response = self
+            .call_api(YtEndpoint::Next, query, None, None, None, None)
+            .await?;
+        let data = Value::Object(response.into_iter().collect());
+
+        let Some(token) = self.extract_comments_token(&data) else {
+            return Err(CommentsDisabled {
+                video_id: video_id.as_str().to_string(),
+            }
+            .into());
+        };
+
+        let data = self.browse_continuation(YtEndpoint::Next, &token).await?;
+
+        if sort == YtCommentSort::Top {
+            return Ok(self.extract_comment_page(&data));
+        }
+
+        let Some(token) = self.extract_comment_sort_token(&data, sort) else {
+            bail!(
+                "Failed to sort the comments of video {} by {:?}, YouTube didn't return the sorting options.",
+                video_id,
+                sort
+            )
+        };
+
+        self.extract_comments_continuation(&token).await
+    }
+
+    async fn extract_comments_continuation(&self, continuation: &str) -> Result<YtCommentPage> {
+        let data = self
+            .browse_continuation(YtEndpoint::Next, continuation)
+            .await?;
+
+        Ok(self.extract_comment_page(&data))
+    }
+
+    fn extract_comments_token(&self, data: &Value) -> Option<String> {
+        // The comments are loaded separately, the watch page only has the continuation to them.
+        self.find_renderers(data, "itemSectionRenderer")
+            .into_iter()
+            .find(|section| {
+                section.get("sectionIdentifier").and_then(|s| s.as_str())
+                    == Some("comment-item-section")
+            })
+            .and_then(|section| self.get_continuation_token(section))
+    }
+
+    fn extract_comment_sort_token(&self, data: &Value, sort: YtCommentSort) -> Option<String> {
+        // Top comments come first, the sort menu has the continuations to reload them in the other orders.
+        self.find_renderers(data, "sortFilterSubMenuRenderer")
+            .into_iter()
+            .find_map(|menu| menu.get("subMenuItems")?.get(sort as usize))
+            .and_then(|item| self.find_key(item, "token"))
+    }
+
+    fn extract_comment_page(&self, data: &Value) -> YtCommentPage {
+        let entities = self
+            .find_renderers(data, "mutations")
+            .into_iter()
+            .filter_map(|mutations| mutations.as_array())
+            .flatten()
+            .filter_map(|mutation| {
+                Some((
+                    mutation.get("entityKey")?.as_str()?,
+                    mutation.get("payload")?,
+                ))
+            })
+            .collect::<HashMap<_, _>>();
+
+        let items = self
+            .find_renderers(data, "continuationItems")
+            .into_iter()
+            .filter_map(|items| items.as_array())
+            .flatten()
+            .collect::<Vec<_>>();
+
+        let mut comments = Vec::new();
+        let mut continuation = None;
+
+        for item in items {
+            let thread = item.get("commentThreadRenderer");
+            let mut comment = match thread.unwrap_or(item) {
+                thread_or_item if thread_or_item.get("commentViewModel").is_some() => {
+                    let view_model = thread_or_item.get("commentViewModel").unwrap_or_default();
+                    // Threads wrap the view model of their comment in another one.
+                    let view_model = view_model.get("commentViewModel").unwrap_or(view_model);
+
+                    self.extract_comment_view_model(view_model, &entities)
+                }
+                thread_or_item => thread_or_item
+                    .get("comment")
+                    .unwrap_or(thread_or_item)
+                    .get("commentRenderer")
+                    .and_then(|renderer| self.extract_comment_renderer(renderer)),
+            };
+
+            if let Some(comment) = &mut comment {
+                comment.replies_continuation = thread
+                    .and_then(|thread| thread.get("replies"))
+                    .and_then(|replies| self.get_continuation_token(replies))
+                    .map(Continuation::new);
+            }
+
+            comments.extend(comment);
+
+            if let Some(renderer) = item.get("continuationItemRenderer") {
+                continuation = self.find_key(renderer, "token").map(Continuation::new);
+            }
+        }
+
+        YtCommentPage {
+            comments,
+            continuation,
+        }
+    }
+
+    fn extract_comment_renderer(&self, renderer: &Value) -> Option<YtComment> {
+        let comment_id = renderer.get("commentId")?.as_str()?.to_string();
+
+        Some(YtComment {
+            comment_id,
+            author_name: self
+                .get_text(renderer, Some(vec![vec!["authorText"]]), None)
+                .unwrap_or_default(),
+            author_channel_id: renderer
+                .get("authorEndpoint")
+                .and_then(|e| e.get("browseEndpoint"))
+                .and_then(|e| e.get("browseId"))
+                .and_then(|id| id.as_str())
+                .map(|id| id.to_string()),
+            author_avatar: self
+                .extract_thumbnails(renderer.get("authorThumbnail").unwrap_or_default()),
+            text: self.extract_comment_text(renderer.get("contentText").unwrap_or_default()),
+            like_count: self
+                .get_text(renderer, Some(vec![vec!["voteCount"]]), None)
+                .and_then(|likes| parse_count(&likes))
+                .or(Some(0)),
+            published_text: self.get_text(renderer, Some(vec![vec!["publishedTimeText"]]), None),
+            is_pinned: renderer.get("pinnedCommentBadge").is_some(),
+            is_hearted: self
+                .find_renderers(renderer, "creatorHeartRenderer")
+                .into_iter()
+                .any(|heart| heart.get("isHearted").and_then(|h| h.as_bool()) == Some(true)),
+            reply_count: renderer
+                .get("replyCount")
+                .and_then(|r| r.as_u64())
+                .unwrap_or_default(),
+            replies_continuation: None,
+        })
+    }
+
+    fn extract_comment_view_model(
+        &self,
+        view_model: &Value,
+        entities: &HashMap<&str, &Value>,
+    ) -> Option<YtComment> {
+        let entity = |key: &str| {
+            view_model
+                .get(key)
+                .and_then(|k| k.as_str())
+                .and_then(|k| entities.get(k))
+        };
+
+        let comment = entity("commentKey")?.get("commentEntityPayload")?;
+        let properties = comment.get("properties")?;
+        let author = comment.get("author").unwrap_or_default();
+        let toolbar = comment.get("toolbar").unwrap_or_default();
+        let toolbar_state = entity("toolbarStateKey")
+            .and_then(|state| state.get("engagementToolbarStateEntityPayload"))
+            .unwrap_or_default();
+
+        let text = |value: &Value, key: &str| {
+            value
+                .get(key)
+                .and_then(|v| v.as_str())
+                .map(|v| v.to_string())
+        };
+
+        Some(YtComment {
+            comment_id: text(properties, "commentId")?,
+            author_name: text(author, "displayName").unwrap_or_default(),
+            author_channel_id: text(author, "channelId"),
+            author_avatar: text(author, "avatarThumbnailUrl")
+                .map(|url| {
+                    vec![YtThumbnail {
+                        url,
+                        height: None,
+                        width: None,
+                    }]
+                })
+                .unwrap_or_default(),
+            text: properties
+                .get("content")
+                .and_then(|c| c.get("content"))
+                .and_then(|c| c.as_str())
+                .unwrap_or_default()
+                .to_string(),
+            like_count: text(toolbar, "likeCountNotliked")
+                .map(|likes| parse_count(&likes).unwrap_or_default()),
+            published_text: text(properties, "publishedTime"),
+            is_pinned: view_model.get("pinnedText").is_some(),
+            is_hearted: text(toolbar_state, "heartState").as_deref()
+                == Some("TOOLBAR_HEART_STATE_HEARTED"),
+            reply_count: text(toolbar, "replyCount")
+                .and_then(|replies| parse_count(&replies))
+                .unwrap_or_default(),
+            replies_continuation: None,
+        })
+    }
+
+    fn extract_comment_text(&self, content: &Value) -> String {
+        let Some(runs) = content.get("runs").and_then(|r| r.as_array()) else {
+            return self.get_text(content, None, None).unwrap_or_default();
+        };
+
+        runs.iter()
+            .filter_map(|run| {
+                run.get("text").and_then(|t| t.as_str()).or_else(|| {
+                    let emoji = run.get("emoji")?;
+
+                    emoji
+                        .get("shortcuts")
+                        .and_then(|s| s.get(0))
+                        .or_else(|| emoji.get("emojiId"))?
+                        .as_str()
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use crate::{test_server::test_extractor, tydle::TydleOptions};
+
+    fn extractor() -> YtExtractor {
+        test_extractor(TydleOptions::default(), reqwest::Client::new())
+    }
+
+    fn fixture(json: &str) -> Value {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn comments_token_is_taken_from_the_comment_section() {
+        let extractor = extractor();
+
+        assert_eq!(
+            extractor
+                .extract_comments_token(&fixture(include_str!(
+                    "../../tests/fixtures/next_comments.json"
+                )))
+                .as_deref(),
+            Some("Eg0SC2RRdzR3OVdnWGNRGAYyJSIRIgtkUXc0dzlXZ1hjUTAAeAJCEGNvbW1lbnRzLXNlY3Rpb24%3D")
+        );
+        // The continuation of the related videos isn't mistaken for the comments.
+        assert_eq!(
+            extractor.extract_comments_token(&fixture(include_str!(
+                "../../tests/fixtures/next_comments_disabled.json"
+            ))),
+            None
+        );
+    }
+
+    #[test]
+    fn sort_tokens_are_taken_from_the_sort_menu() {
+        let extractor = extractor();
+        let data = fixture(include_str!(
+            "../../tests/fixtures/comments_view_model.json"
+        ));
+
+        assert_eq!(
+            extractor
+                .extract_comment_sort_token(&data, YtCommentSort::Top)
+                .as_deref(),
+            Some("Eg0SC2RRdzR3OVdnWGNRGAYyJSIRIgtkUXc0dzlXZ1hjUTAAeAJCEGNvbW1lbnRzLXNlY3Rpb24%3D")
+        );
+        assert_eq!(
+            extractor
+                .extract_comment_sort_token(&data, YtCommentSort::Newest)
+                .as_deref(),
+            Some("Eg0SC2RRdzR3OVdnWGNRGAYyJSIRIgtkUXc0dzlXZ1hjUTABeAJCEGNvbW1lbnRzLXNlY3Rpb24%3D")
+        );
+    }
+
+    #[test]
+    fn view_model_comments_are_parsed_from_their_entities() {
+        let page = extractor().extract_comment_page(&fixture(include_str!(
+            "../../tests/fixtures/comments_view_model.json"
+        )));
+
+        assert_eq!(page.comments.len(), 2);
+
+        let pinned = &page.comments[0];
+        assert_eq!(pinned.comment_id, "UgzGpP_2TlwqV9GVpj54AaABAg");
+        assert_eq!(pinned.author_name, "@RickAstleyYT");
+        assert_eq!(
+            pinned.author_channel_id.as_deref(),
+            Some("UCuAXFkgsw1L7xaCfnd5JJOw")
+        );
+        assert_eq!(pinned.author_avatar.len(), 1);
+        assert_eq!(
+            pinned.text,
+            "1 billion views for Never Gonna Give You Up! 🎉 Thank you all"
+        );
+        assert_eq!(pinned.like_count, Some(1_100_000));
+        assert_eq!(pinned.published_text.as_deref(), Some("4 years ago"));
+        assert!(pinned.is_pinned);
+        assert!(pinned.is_hearted);
+        assert_eq!(pinned.reply_count, 2900);
+        assert_eq!(
+            pinned.replies_continuation.as_ref().unwrap().token(),
+            "Eg0SC2RRdzR3OVdnWGNRGAYygwEaUBIaVWd6R3BQXzJUbHdxVjlHVnBqNTRBYUFCQWc"
+        );
+
+        let reply_less = &page.comments[1];
+        assert_eq!(reply_less.text, "Who else got rickrolled here 😂");
+        assert_eq!(reply_less.like_count, Some(0));
+        assert!(!reply_less.is_pinned);
+        assert!(!reply_less.is_hearted);
+        assert_eq!(reply_less.reply_count, 0);
+        assert!(reply_less.replies_continuation.is_none());
+
+        assert_eq!(
+            page.continuation.unwrap().token(),
+            "Eg0SC2RRdzR3OVdnWGNRGAYy-gEKwwFnZXRfcmFua2VkX3N0cmVhbXM"
+        );
+    }
+
+    #[test]
+    fn renderer_replies_keep_their_emojis() {
+        let page = extractor().extract_comment_page(&fixture(include_str!(
+            "../../tests/fixtures/comments_replies_renderer.json"
+        )));
+
+        assert_eq!(page.comments.len(), 2);
+
+        let reply = &page.comments[0];
+        assert_eq!(reply.author_name, "@musicfan1987");
+        assert_eq!(
+            reply.author_channel_id.as_deref(),
+            Some("UCbCmjCuTUZos6Inko4u57UQ")
+        );
+        assert_eq!(reply.author_avatar.len(), 2);
+        assert_eq!(reply.text, "Congrats Rick :red_heart: legend");
+        assert_eq!(reply.like_count, Some(1200));
+        assert_eq!(reply.published_text.as_deref(), Some("3 years ago"));
+        assert!(reply.is_hearted);
+        assert!(!reply.is_pinned);
+
+        let unliked = &page.comments[1];
+        assert_eq!(unliked.text, "first reply page done");
+        assert_eq!(unliked.like_count, Some(0));
+        assert!(!unliked.is_hearted);
+
+        assert_eq!(
+            page.continuation.unwrap().token(),
+            "Eg0SC2RRdzR3OVdnWGNRGAYygwEaUBIaVWd6R3BQXzJUbHdxVjlHVnBqNTRBYUFCQWciAggAKhhVQ3VBWEZrZ3N3"
+        );
+    }
+}
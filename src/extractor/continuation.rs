@@ -0,0 +1,54 @@
+use std::any::Any;
+
+use anyhow::{Result, anyhow};
+
+use crate::{
+    extractor::{
+        channel::ExtractorChannelHandle, comments::ExtractorCommentsHandle, extract::YtExtractor,
+        mix::ExtractorMixHandle, related::ExtractorRelatedHandle, search::ExtractorSearchHandle,
+    },
+    yt_interface::{
+        Continuation, Page, YtChannelVideo, YtComment, YtPaginated, YtPlaylistEntry,
+        YtRelatedVideo, YtSearchResult,
+    },
+};
+
+pub trait ExtractorContinuationHandle {
+    /// Redeem a continuation at the endpoint of its listing.
+    async fn extract_page<T: YtPaginated>(&self, continuation: &Continuation<T>)
+    -> Result<Page<T>>;
+}
+
+impl ExtractorContinuationHandle for YtExtractor {
+    async fn extract_page<T: YtPaginated>(
+        &self,
+        continuation: &Continuation<T>,
+    ) -> Result<Page<T>> {
+        let token = continuation.token();
+
+        let page: Box<dyn Any> = match T::KIND {
+            YtChannelVideo::KIND => Box::new(Page::from(
+                self.extract_channel_videos_continuation(token).await?,
+            )),
+            YtSearchResult::KIND => Box::new(Page::from(self.search_continuation(token).await?)),
+            YtRelatedVideo::KIND => {
+                Box::new(Page::from(self.extract_related_continuation(token).await?))
+            }
+            YtComment::KIND => {
+                Box::new(Page::from(self.extract_comments_continuation(token).await?))
+            }
+            YtPlaylistEntry::KIND => {
+                Box::new(Page::from(self.extract_mix_continuation(token).await?))
+            }
+            kind => return Err(anyhow!("No endpoint redeems {} continuations.", kind)),
+        };
+
+        // Every kind belongs to a single item type, so the page is always one of `T`.
+        page.downcast::<Page<T>>().map(|page| *page).map_err(|_| {
+            anyhow!(
+                "The {} continuation returned a page of other items.",
+                T::KIND
+            )
+        })
+    }
+}
@@ -1,17 +1,36 @@
-use std::{
-    collections::HashMap,
-    time::{SystemTime, UNIX_EPOCH},
+use std::{collections::HashMap, time::SystemTime};
+
+use anyhow::{Result, anyhow};
+use reqwest::{
+    Response,
+    header::{
+        AUTHORIZATION, CONTENT_LENGTH, CONTENT_TYPE, COOKIE, HeaderMap, HeaderValue, LOCATION,
+    },
 };
 
-use anyhow::Result;
-use sha1::{Digest, Sha1};
-
 use crate::{
-    cookies::{CookieStore, Cookies},
-    extractor::extract::YtExtractor,
+    cookies::Cookies, extractor::extract::YtExtractor, utils::sid_authorization,
     yt_interface::YT_URL,
 };
 
+/// Cookies that identify a logged-in YouTube account.
+const AUTH_COOKIE_NAMES: [&str; 11] = [
+    "LOGIN_INFO",
+    "SID",
+    "HSID",
+    "SSID",
+    "APISID",
+    "SAPISID",
+    "__Secure-1PSID",
+    "__Secure-3PSID",
+    "__Secure-1PAPISID",
+    "__Secure-3PAPISID",
+    "__Secure-1PSIDTS",
+];
+
+/// Redirects followed before giving up, the same limit as reqwest's default policy.
+const MAX_REDIRECTS: usize = 10;
+
 #[derive(Debug)]
 pub struct SidCookies {
     pub yt_sapisid: Option<String>,
@@ -35,6 +54,13 @@ impl SidCookies {
 pub trait ExtractorCookieHandle {
     fn get_cookies(&self, url: &str) -> Result<Cookies>;
     fn get_youtube_cookies(&self) -> Result<Cookies>;
+    /// Build the `Cookie` header value to send along with a request to `url`.
+    /// Account cookies are left out unless `TydleOptions::authenticate` is enabled.
+    fn get_cookie_header(&self, url: &str) -> Result<Option<String>>;
+    /// Store the `Set-Cookie` headers of `response` against its URL and follow it while it's a redirect,
+    /// sending `headers` and the cookies of each hop along and storing the ones every hop sets.
+    /// Redirects are followed with a `GET`, which is all YouTube redirects these requests with.
+    async fn follow_redirects(&self, response: Response, headers: HeaderMap) -> Result<Response>;
     /// Get SAPISID, 1PSAPISID, 3PSAPISID cookie values.
     fn get_sid_cookies(&self) -> Result<SidCookies>;
     fn make_sid_authorization(
@@ -54,7 +80,7 @@ pub trait ExtractorCookieHandle {
 
 impl ExtractorCookieHandle for YtExtractor {
     fn get_cookies(&self, url: &str) -> Result<Cookies> {
-        let cookies = self.cookie_jar.get_all(url)?.unwrap_or_default();
+        let cookies = self.cookie_store.get_all(url)?.unwrap_or_default();
         Ok(cookies)
     }
 
@@ -64,6 +90,75 @@ impl ExtractorCookieHandle for YtExtractor {
         Ok(c)
     }
 
+    fn get_cookie_header(&self, url: &str) -> Result<Option<String>> {
+        let Some(cookie_header) = self.cookie_store.cookie_header(url)? else {
+            return Ok(None);
+        };
+
+        if self.tydle_options.authenticate {
+            return Ok(Some(cookie_header));
+        }
+
+        let sent_cookies: Vec<&str> = cookie_header
+            .split("; ")
+            .filter(|pair| {
+                let name = pair.split_once('=').map_or(*pair, |(name, _)| name);
+                !AUTH_COOKIE_NAMES.contains(&name)
+            })
+            .collect();
+
+        if sent_cookies.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some(sent_cookies.join("; ")))
+    }
+
+    async fn follow_redirects(
+        &self,
+        mut response: Response,
+        mut headers: HeaderMap,
+    ) -> Result<Response> {
+        headers.remove(CONTENT_TYPE);
+        headers.remove(CONTENT_LENGTH);
+
+        for _ in 0..=MAX_REDIRECTS {
+            self.cookie_store
+                .store_response_cookies(response.url().as_str(), response.headers())?;
+
+            let location = response
+                .headers()
+                .get(LOCATION)
+                .and_then(|location| location.to_str().ok());
+            let Some(location) = location.filter(|_| response.status().is_redirection()) else {
+                return Ok(response);
+            };
+            let url = response.url().join(location)?;
+
+            // Like reqwest, credentials of one host aren't handed to another.
+            if url.host_str() != response.url().host_str() {
+                headers.remove(AUTHORIZATION);
+            }
+            headers.remove(COOKIE);
+            if let Some(cookie_header) = self.get_cookie_header(url.as_str())? {
+                headers.insert(COOKIE, HeaderValue::from_str(&cookie_header)?);
+            }
+
+            response = self
+                .cookie_client
+                .get(url)
+                .headers(headers.clone())
+                .send()
+                .await?;
+        }
+
+        Err(anyhow!(
+            "Stopped after {} redirects at {}.",
+            MAX_REDIRECTS,
+            response.url()
+        ))
+    }
+
     fn get_sid_cookies(&self) -> Result<SidCookies> {
         let yt_cookies = self.get_youtube_cookies()?;
         let yt_sapisid = yt_cookies.get("SAPISID").cloned();
@@ -85,42 +180,18 @@ impl ExtractorCookieHandle for YtExtractor {
         origin: String,
         additional_parts: HashMap<&str, String>,
     ) -> Result<String> {
-        let now = SystemTime::now();
-        let epoch_duration = now.duration_since(UNIX_EPOCH)?;
-        let time_stamp = epoch_duration.as_secs_f64().round().to_string();
-
-        let mut hash_parts: Vec<String> = Vec::new();
-
-        if !additional_parts.is_empty() {
-            let joined = additional_parts
-                .values()
-                .cloned()
-                .collect::<Vec<_>>()
-                .join(":");
-            hash_parts.push(joined);
-        }
-
-        hash_parts.extend_from_slice(&[time_stamp.clone(), sid.to_string(), origin.to_string()]);
-        let joined = hash_parts.join(" ");
-
-        let mut hasher = Sha1::new();
-        hasher.update(joined.as_bytes());
-        let sid_hash = format!("{:x}", hasher.finalize());
-
-        let mut parts: Vec<String> = vec![time_stamp, sid_hash];
-
-        if !additional_parts.is_empty() {
-            let joined = additional_parts
-                .values()
-                .cloned()
-                .collect::<Vec<_>>()
-                .join("");
-
-            parts.push(joined);
-        }
-
-        let sid_auth = format!("{} {}", scheme, parts.join("_"));
-        Ok(sid_auth)
+        let additional_parts: Vec<(&str, &str)> = additional_parts
+            .iter()
+            .map(|(key, value)| (*key, value.as_str()))
+            .collect();
+
+        Ok(sid_authorization(
+            scheme,
+            &sid,
+            &origin,
+            &additional_parts,
+            SystemTime::now(),
+        ))
     }
 
     fn get_sid_authorization_header(
@@ -0,0 +1,462 @@
+use std::collections::HashMap;
+
+use anyhow::{Result, bail};
+use fancy_regex::Regex;
+use reqwest::Url;
+
+use crate::{
+    extractor::extract::YtExtractor,
+    utils::unescape_xml,
+    yt_interface::{YtDashSegments, YtStream, YtStreamOrigin, YtStreamSource},
+};
+
+pub trait ExtractorDashHandle {
+    /// Download a DASH manifest and parse its representations into streams.
+    async fn fetch_dash_streams(&self, manifest_url: &str) -> Result<Vec<YtStream>>;
+    /// Add the streams of the DASH manifest that aren't in `streams` yet.
+    /// Failing to get them is only logged, since the DASH manifest only adds to the other formats.
+    async fn merge_dash_streams(&self, streams: &mut Vec<YtStream>, manifest_url: &str);
+    /// Parse the `Representation`s of an MPD into streams, resolving their URLs against `manifest_url`.
+    fn parse_dash_manifest(&self, mpd: &str, manifest_url: &str) -> Result<Vec<YtStream>>;
+}
+
+/// Parse an ISO 8601 duration like `PT1H2M3.5S` as used by `mediaPresentationDuration` into seconds.
+fn parse_iso_duration(duration: &str) -> Option<f64> {
+    let time = duration.strip_prefix("PT")?;
+    let mut seconds = 0.0;
+    let mut number_start = 0;
+
+    for (index, c) in time.char_indices() {
+        let multiplier = match c {
+            'H' => 3600.0,
+            'M' => 60.0,
+            'S' => 1.0,
+            _ => continue,
+        };
+
+        seconds += time[number_start..index].parse::<f64>().ok()? * multiplier;
+        number_start = index + 1;
+    }
+
+    Some(seconds)
+}
+
+/// A `Representation` that's being parsed.
+struct PendingRepresentation {
+    attributes: HashMap<String, String>,
+    base_url: Option<String>,
+    segments: Option<YtDashSegments>,
+    /// Duration and timescale of a `SegmentTemplate` without a timeline, to count its segments with.
+    template_duration: Option<(f64, f64)>,
+}
+
+impl ExtractorDashHandle for YtExtractor {
+    async fn fetch_dash_streams(&self, manifest_url: &str) -> Result<Vec<YtStream>> {
+        #[cfg(feature = "logging")]
+        log::info!("Downloading DASH manifest at {}", manifest_url);
+
+        let mpd = self
+            .http_client
+            .get(manifest_url)
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+
+        self.parse_dash_manifest(&mpd, manifest_url)
+    }
+
+    async fn merge_dash_streams(&self, streams: &mut Vec<YtStream>, manifest_url: &str) {
+        let dash_streams = match self.fetch_dash_streams(manifest_url).await {
+            Ok(dash_streams) => dash_streams,
+            Err(_e) => {
+                #[cfg(feature = "logging")]
+                log::warn!("Skipping the streams of the DASH manifest: {}", _e);
+                return;
+            }
+        };
+
+        for dash_stream in dash_streams {
+            if !streams
+                .iter()
+                .any(|stream| stream.itag.is_some() && stream.itag == dash_stream.itag)
+            {
+                streams.push(dash_stream);
+            }
+        }
+    }
+
+    fn parse_dash_manifest(&self, mpd: &str, manifest_url: &str) -> Result<Vec<YtStream>> {
+        let tag_re = Regex::new(r"<(/?)([A-Za-z][\w:.-]*)([^>]*?)(/?)>")?;
+        let attribute_re = Regex::new(r#"([\w:.-]+)="([^"]*)""#)?;
+
+        let parse_attributes = |attributes: &str| -> Result<HashMap<String, String>> {
+            attribute_re
+                .captures_iter(attributes)
+                .map(|captures| {
+                    let captures = captures?;
+                    Ok((captures[1].to_string(), unescape_xml(&captures[2])))
+                })
+                .collect()
+        };
+
+        let mut streams = Vec::new();
+        // Names of the open elements, with the `BaseURL` each of them has.
+        let mut elements: Vec<(String, Option<String>)> = Vec::new();
+        let mut adaptation_set: HashMap<String, String> = HashMap::new();
+        let mut adaptation_segments: Option<YtDashSegments> = None;
+        let mut adaptation_template_duration = None;
+        let mut representation: Option<PendingRepresentation> = None;
+        let mut presentation_duration = None;
+        let mut text_start = 0;
+        let mut has_mpd = false;
+
+        for captures in tag_re.captures_iter(mpd) {
+            let captures = captures?;
+            let whole = captures.get(0).unwrap();
+            let text = mpd[text_start..whole.start()].trim();
+            text_start = whole.end();
+
+            if !text.is_empty()
+                && let Some((name, base_url)) = elements.last_mut()
+                && name == "BaseURL"
+            {
+                *base_url = Some(unescape_xml(text));
+            }
+
+            let name = captures[2].to_string();
+            let is_closing = !captures[1].is_empty();
+            let is_self_closing = !captures[4].is_empty();
+
+            if is_closing {
+                let Some((closed, base_url)) = elements.pop() else {
+                    bail!(
+                        "Failed to parse DASH manifest because </{}> closes nothing.",
+                        name
+                    )
+                };
+
+                if closed != name {
+                    bail!(
+                        "Failed to parse DASH manifest because </{}> closes <{}>.",
+                        name,
+                        closed
+                    )
+                }
+
+                match name.as_str() {
+                    // Hand the URL over to the element it belongs to.
+                    "BaseURL" => {
+                        if let Some(base_url) = base_url {
+                            match elements.last_mut() {
+                                Some((parent, _)) if parent == "Representation" => {
+                                    if let Some(representation) = &mut representation {
+                                        representation.base_url = Some(base_url);
+                                    }
+                                }
+                                Some((_, parent_base_url)) => *parent_base_url = Some(base_url),
+                                None => {}
+                            }
+                        }
+                    }
+                    "Representation" => {
+                        if let Some(pending) = representation.take() {
+                            let base_url = resolve_base_url(&elements, &pending, manifest_url)?;
+
+                            streams.push(build_stream(
+                                pending,
+                                &adaptation_set,
+                                base_url,
+                                presentation_duration,
+                            ));
+                        }
+                    }
+                    "AdaptationSet" => {
+                        adaptation_set.clear();
+                        adaptation_segments = None;
+                        adaptation_template_duration = None;
+                    }
+                    _ => {}
+                }
+
+                continue;
+            }
+
+            let attributes = parse_attributes(&captures[3])?;
+
+            match name.as_str() {
+                "MPD" => {
+                    has_mpd = true;
+                    presentation_duration = attributes
+                        .get("mediaPresentationDuration")
+                        .and_then(|d| parse_iso_duration(d));
+                }
+                "AdaptationSet" => adaptation_set = attributes,
+                "Representation" => {
+                    representation = Some(PendingRepresentation {
+                        attributes,
+                        base_url: None,
+                        segments: adaptation_segments.clone(),
+                        template_duration: adaptation_template_duration,
+                    })
+                }
+                "SegmentTemplate" | "SegmentList" => {
+                    let segments = YtDashSegments {
+                        initialization: attributes.get("initialization").cloned(),
+                        media_template: attributes.get("media").cloned(),
+                        start_number: attributes
+                            .get("startNumber")
+                            .and_then(|n| n.parse().ok())
+                            .unwrap_or(1),
+                        ..Default::default()
+                    };
+                    let template_duration = attributes
+                        .get("duration")
+                        .and_then(|d| d.parse::<f64>().ok())
+                        .map(|duration| {
+                            let timescale = attributes
+                                .get("timescale")
+                                .and_then(|t| t.parse().ok())
+                                .unwrap_or(1.0);
+                            (duration, timescale)
+                        });
+
+                    match &mut representation {
+                        Some(representation) => {
+                            representation.segments = Some(segments);
+                            representation.template_duration = template_duration;
+                        }
+                        None => {
+                            adaptation_segments = Some(segments);
+                            adaptation_template_duration = template_duration;
+                        }
+                    }
+                }
+                "Initialization" | "SegmentURL" | "S" => {
+                    let segments = match &mut representation {
+                        Some(representation) => representation.segments.as_mut(),
+                        None => adaptation_segments.as_mut(),
+                    };
+
+                    if let Some(segments) = segments {
+                        match name.as_str() {
+                            "Initialization" => {
+                                segments.initialization = attributes.get("sourceURL").cloned()
+                            }
+                            "SegmentURL" => segments.media.extend(attributes.get("media").cloned()),
+                            // `r` repeats a segment, `-1` until the end of a live stream.
+                            _ => {
+                                let repeat = attributes
+                                    .get("r")
+                                    .and_then(|r| r.parse::<i64>().ok())
+                                    .unwrap_or_default();
+
+                                segments.segment_count = match repeat {
+                                    ..0 => None,
+                                    repeat => segments
+                                        .segment_count
+                                        .or(Some(0))
+                                        .map(|count| count + 1 + repeat as u64),
+                                };
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            }
+
+            if !is_self_closing {
+                elements.push((name, None));
+            } else if name == "Representation"
+                && let Some(pending) = representation.take()
+            {
+                let base_url = resolve_base_url(&elements, &pending, manifest_url)?;
+
+                streams.push(build_stream(
+                    pending,
+                    &adaptation_set,
+                    base_url,
+                    presentation_duration,
+                ));
+            }
+        }
+
+        if !has_mpd {
+            bail!("Failed to parse DASH manifest because it has no <MPD>.")
+        }
+
+        Ok(streams)
+    }
+}
+
+/// Join the `BaseURL`s of the elements a representation is in and its own onto the URL of the manifest.
+fn resolve_base_url(
+    elements: &[(String, Option<String>)],
+    pending: &PendingRepresentation,
+    manifest_url: &str,
+) -> Result<Url> {
+    let mut base_url = Url::parse(manifest_url)?;
+
+    for element_base_url in elements
+        .iter()
+        .filter_map(|(_, base_url)| base_url.as_ref())
+        .chain(&pending.base_url)
+    {
+        base_url = base_url.join(element_base_url)?;
+    }
+
+    Ok(base_url)
+}
+
+fn build_stream(
+    pending: PendingRepresentation,
+    adaptation_set: &HashMap<String, String>,
+    base_url: Url,
+    presentation_duration: Option<f64>,
+) -> YtStream {
+    // Attributes of the adaptation set apply to all of its representations unless they override them.
+    let attribute = |key: &str| {
+        pending
+            .attributes
+            .get(key)
+            .or_else(|| adaptation_set.get(key))
+            .cloned()
+    };
+
+    let itag = attribute("id");
+    let height: Option<u64> = attribute("height").and_then(|h| h.parse().ok());
+
+    let mut segments = pending.segments.map(|mut segments| {
+        for template in [&mut segments.media_template, &mut segments.initialization]
+            .into_iter()
+            .flatten()
+        {
+            *template = template
+                .replace("$RepresentationID$", itag.as_deref().unwrap_or_default())
+                .replace(
+                    "$Bandwidth$",
+                    pending
+                        .attributes
+                        .get("bandwidth")
+                        .map(|b| b.as_str())
+                        .unwrap_or_default(),
+                );
+        }
+
+        segments
+    });
+
+    if let Some(segments) = &mut segments
+        && segments.media_template.is_some()
+        && segments.segment_count.is_none()
+        && let (Some((duration, timescale)), Some(presentation_duration)) =
+            (pending.template_duration, presentation_duration)
+        && duration > 0.0
+    {
+        segments.segment_count =
+            Some((presentation_duration / (duration / timescale)).ceil() as u64);
+    }
+
+    let mut stream = YtStream::new(
+        attribute("audioSamplingRate").and_then(|a| a.parse().ok()),
+        None,
+        itag,
+        height.map(|height| format!("{}p", height)),
+        YtStreamSource::URL(base_url.to_string()),
+        attribute("bandwidth")
+            .and_then(|b| b.parse().ok())
+            .unwrap_or(1000.0),
+    );
+
+    stream.codecs = attribute("codecs");
+    stream.mime_type = attribute("mimeType");
+    stream.parse_mime_type(true);
+    stream.bitrate = attribute("bandwidth").and_then(|b| b.parse().ok());
+    stream.width = attribute("width").and_then(|w| w.parse().ok());
+    stream.height = height;
+    // Either whole, like `30`, or a fraction, like `30000/1001`.
+    stream.fps = attribute("frameRate").and_then(|frame_rate| match frame_rate.split_once('/') {
+        Some((frames, seconds)) => {
+            let frames: f64 = frames.parse().ok()?;
+            let seconds: f64 = seconds.parse().ok()?;
+            (seconds > 0.0).then(|| (frames / seconds).round() as u64)
+        }
+        None => frame_rate.parse().ok(),
+    });
+    stream.origin = YtStreamOrigin::Dash;
+    stream.dash_segments = segments.filter(|segments| {
+        segments.initialization.is_some()
+            || !segments.media.is_empty()
+            || segments.media_template.is_some()
+    });
+    stream.fill_from_itag_table();
+
+    stream
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use std::time::Duration;
+
+    use serde_json::Value;
+
+    use super::*;
+    use crate::{
+        extractor::extract::InfoExtractor,
+        test_server::test_extractor,
+        tydle::TydleOptions,
+        yt_interface::{YtClient, YtStreamResponse},
+    };
+
+    #[test]
+    fn dash_manifest_of_the_fixture_round_trips_through_the_parser() {
+        let extractor = test_extractor(TydleOptions::default(), reqwest::Client::new());
+        let player_response: HashMap<String, Value> =
+            serde_json::from_str(include_str!("../../tests/fixtures/player_formats.json")).unwrap();
+        // The same streams from two clients, of which the manifest has to list each once.
+        let response = YtStreamResponse::new(
+            String::new(),
+            extractor
+                .extract_formats(
+                    vec![player_response; 2],
+                    &[YtClient::Web, YtClient::Android],
+                )
+                .unwrap(),
+        );
+
+        let mpd = response
+            .to_dash_manifest(Duration::from_millis(212091))
+            .unwrap();
+        let parsed = extractor
+            .parse_dash_manifest(&mpd, "https://example.com/manifest.mpd")
+            .unwrap();
+
+        // The muxed stream has no place in a DASH manifest.
+        assert_eq!(
+            parsed
+                .iter()
+                .map(|stream| stream.itag().unwrap())
+                .collect::<Vec<_>>(),
+            ["137", "136", "248", "247", "399", "140", "251"]
+        );
+
+        for stream in &parsed {
+            let source = response
+                .streams
+                .iter()
+                .find(|source| source.itag == stream.itag)
+                .unwrap();
+            let itag = stream.itag().unwrap();
+
+            assert_eq!(stream.source, source.source, "itag {}", itag);
+            assert_eq!(stream.codecs, source.codecs, "itag {}", itag);
+            assert_eq!(stream.container, source.container, "itag {}", itag);
+            assert_eq!(stream.bitrate, source.bitrate, "itag {}", itag);
+            assert_eq!(stream.width, source.width, "itag {}", itag);
+            assert_eq!(stream.height, source.height, "itag {}", itag);
+            assert_eq!(stream.fps, source.fps, "itag {}", itag);
+            assert_eq!(stream.asr, source.asr, "itag {}", itag);
+        }
+    }
+}
@@ -6,14 +6,17 @@ use reqwest::Url;
 use serde_json::Value;
 
 use crate::{
+    error::{ConsentRequired, DecipherError},
     extractor::{
         api::ExtractorApiHandle,
+        auth::ExtractorAuthHandle,
         client::INNERTUBE_CLIENTS,
+        cookies::ExtractorCookieHandle,
         extract::{InfoExtractor, YtExtractor},
         player::ExtractorPlayerHandle,
         ytcfg::ExtractorYtCfgHandle,
     },
-    yt_interface::{PlayerIdentifier, VideoId, YtClient, YtEndpoint},
+    yt_interface::{PlayerIdentifier, VideoId, YT_CONSENT_HOST, YtClient, YtEndpoint},
 };
 
 pub trait ExtractorDownloadHandle {
@@ -24,7 +27,8 @@ pub trait ExtractorDownloadHandle {
         webpage_client: &YtClient,
         webpage_ytcfg: &HashMap<String, Value>,
     ) -> Result<HashMap<String, Value>>;
-    async fn download_player_url(&self, video_id: &VideoId) -> Result<Option<String>>;
+    /// Find the URL of the current player in the iFrame API.
+    async fn download_player_url(&self, video_id: Option<&VideoId>) -> Result<Option<String>>;
     async fn download_webpage(
         &self,
         webpage_url: &str,
@@ -35,8 +39,13 @@ pub trait ExtractorDownloadHandle {
         &self,
         webpage_url: Url,
         webpage_client: &YtClient,
-        video_id: &VideoId,
+        video_id: Option<&VideoId>,
     ) -> Result<String>;
+    /// Request the first byte of a stream, failing with `DecipherError::UrlRejected` if googlevideo refuses it.
+    async fn validate_stream_url(&self, url: &str) -> Result<()>;
+    /// Request the first byte of a stream and read how large googlevideo says the whole stream is
+    /// from the `Content-Range` of the response. Fails like `validate_stream_url`.
+    async fn fetch_stream_length(&self, url: &str) -> Result<Option<u64>>;
 }
 
 impl ExtractorDownloadHandle for YtExtractor {
@@ -73,7 +82,7 @@ impl ExtractorDownloadHandle for YtExtractor {
         Ok(initial_data.unwrap())
     }
 
-    async fn download_player_url(&self, video_id: &VideoId) -> Result<Option<String>> {
+    async fn download_player_url(&self, video_id: Option<&VideoId>) -> Result<Option<String>> {
         let formatted_url = Url::parse("https://www.youtube.com/iframe_api")?;
 
         #[cfg(feature = "logging")]
@@ -104,7 +113,7 @@ impl ExtractorDownloadHandle for YtExtractor {
         video_id: &VideoId,
     ) -> Result<String> {
         let formatted_url = Url::parse(webpage_url)?;
-        self.download_initial_webpage(formatted_url, webpage_client, video_id)
+        self.download_initial_webpage(formatted_url, webpage_client, Some(video_id))
             .await
     }
 
@@ -112,34 +121,301 @@ impl ExtractorDownloadHandle for YtExtractor {
         &self,
         webpage_url: Url,
         webpage_client: &YtClient,
-        video_id: &VideoId,
+        video_id: Option<&VideoId>,
     ) -> Result<String> {
         #[cfg(feature = "logging")]
-        log::info!("{}: Downloading webpage {}", video_id.as_str(), webpage_url);
-        let mut webpage_request = self.http_client.get(webpage_url).query(&[
-            ("bpctr", "9999999999"),
-            ("has_verified", "1"),
-            ("v", video_id.as_str()),
-        ]);
-        let innertube_client = INNERTUBE_CLIENTS.get(webpage_client).unwrap();
-
-        let client = innertube_client.innertube_context.get("client").unwrap();
-        if let Some(user_agent) = client.get("userAgent") {
-            webpage_request =
-                webpage_request.header("User-Agent", user_agent.as_str().unwrap_or_default());
+        log::info!(
+            "{}: Downloading webpage {}",
+            video_id.map(|id| id.as_str()).unwrap_or("-"),
+            webpage_url
+        );
+        let mut consent_retried = false;
+
+        loop {
+            let cookie_header = self.get_cookie_header(webpage_url.as_str())?;
+            let mut webpage_request = self
+                .cookie_client
+                .get(webpage_url.clone())
+                .query(&[("bpctr", "9999999999"), ("has_verified", "1")]);
+
+            if let Some(video_id) = video_id {
+                webpage_request = webpage_request.query(&[("v", video_id.as_str())]);
+            }
+            let innertube_client = INNERTUBE_CLIENTS.get(webpage_client).unwrap();
+
+            let client = innertube_client.innertube_context.get("client").unwrap();
+            if let Some(user_agent) = client.get("userAgent") {
+                webpage_request =
+                    webpage_request.header("User-Agent", user_agent.as_str().unwrap_or_default());
+            }
+
+            if !self.tydle_options.source_address.is_empty() {
+                webpage_request = webpage_request.header(
+                    "X-Forwarded-For",
+                    self.tydle_options.source_address.as_str(),
+                );
+            }
+
+            if let Some(cookie_header) = cookie_header {
+                webpage_request = webpage_request.header("Cookie", cookie_header);
+            }
+
+            let request = webpage_request.build()?;
+            let headers = request.headers().clone();
+            let response = self.cookie_client.execute(request).await?;
+            let response = self.follow_redirects(response, headers).await?;
+
+            // Requests from the EU without accepted consent are redirected to consent.youtube.com.
+            if response.url().host_str() == Some(YT_CONSENT_HOST) {
+                if !self.tydle_options.disable_consent_bypass && !consent_retried {
+                    #[cfg(feature = "logging")]
+                    log::warn!("Redirected to the consent page, accepting consent and retrying.");
+
+                    self.set_consent_cookies()?;
+                    consent_retried = true;
+                    continue;
+                }
+
+                return Err(ConsentRequired {
+                    url: response.url().to_string(),
+                }
+                .into());
+            }
+
+            let webpage = response.text().await.map_err(|e| Error::new(e))?;
+
+            return Ok(webpage);
         }
+    }
 
-        if !self.tydle_options.source_address.is_empty() {
-            webpage_request = webpage_request.header(
-                "X-Forwarded-For",
-                self.tydle_options.source_address.as_str(),
-            );
+    async fn validate_stream_url(&self, url: &str) -> Result<()> {
+        let response = self
+            .http_client
+            .get(url)
+            .header("Range", "bytes=0-0")
+            .send()
+            .await?;
+        let status = response.status().as_u16();
+
+        if matches!(status, 403 | 410) {
+            return Err(DecipherError::UrlRejected { status }.into());
+        }
+
+        Ok(())
+    }
+
+    async fn fetch_stream_length(&self, url: &str) -> Result<Option<u64>> {
+        let response = self
+            .http_client
+            .get(url)
+            .header("Range", "bytes=0-0")
+            .send()
+            .await?;
+        let status = response.status().as_u16();
+
+        if matches!(status, 403 | 410) {
+            return Err(DecipherError::UrlRejected { status }.into());
         }
 
-        let response = webpage_request.send().await?;
+        // Like `bytes 0-0/12345678`.
+        Ok(response
+            .error_for_status()?
+            .headers()
+            .get("Content-Range")
+            .and_then(|content_range| content_range.to_str().ok())
+            .and_then(|content_range| content_range.rsplit_once('/'))
+            .and_then(|(_, length)| length.parse().ok()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        test_server::{TestResponse, TestServer, test_extractor},
+        tydle::TydleOptions,
+    };
+
+    /// Captured redirect YouTube answers EU requests without accepted consent with.
+    const CONSENT_REDIRECT_PATH: &str = "/ml?continue=https://www.youtube.com/watch%3Fv%3DdQw4w9WgXcQ%26cbrd%3D1&gl=DE&m=0&pc=yt&cm=2&hl=de&src=1";
+
+    async fn consent_server() -> (TestServer, reqwest::Client, Url) {
+        let server = TestServer::start(|request| {
+            let host = request.headers.get("host").cloned().unwrap_or_default();
 
-        let webpage = response.text().await.map_err(|e| Error::new(e))?;
+            match host.starts_with(YT_CONSENT_HOST) {
+                true => TestResponse::ok("<html>Bevor Sie zu YouTube weitergehen</html>"),
+                false => TestResponse::redirect(&format!(
+                    "http://{}:{}{}",
+                    YT_CONSENT_HOST,
+                    host.rsplit(':').next().unwrap_or_default(),
+                    CONSENT_REDIRECT_PATH
+                ))
+                .header(
+                    "Set-Cookie",
+                    "YSC=GG5rDyzqBoI; Domain=.youtube.com; Path=/; HttpOnly",
+                ),
+            }
+        })
+        .await;
+
+        let (http_client, webpage_url) = youtube_client(&server);
+
+        (server, http_client, webpage_url)
+    }
+
+    /// Client sending `www.youtube.com` and the consent host to `server`, leaving redirects to the extractor
+    /// like `YtExtractor::cookie_client` does, and the URL of the watch page on it.
+    fn youtube_client(server: &TestServer) -> (reqwest::Client, Url) {
+        let http_client = reqwest::Client::builder()
+            .resolve("www.youtube.com", server.addr())
+            .resolve(YT_CONSENT_HOST, server.addr())
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+            .unwrap();
+        let webpage_url = Url::parse(&format!(
+            "http://www.youtube.com:{}/watch",
+            server.addr().port()
+        ))
+        .unwrap();
+
+        (http_client, webpage_url)
+    }
+
+    #[tokio::test]
+    async fn consent_redirect_is_retried_exactly_once() {
+        let (server, http_client, webpage_url) = consent_server().await;
+        let extractor = test_extractor(TydleOptions::default(), http_client);
+        let video_id = VideoId::new("dQw4w9WgXcQ").unwrap();
+
+        let error = extractor
+            .download_initial_webpage(webpage_url, &YtClient::Web, Some(&video_id))
+            .await
+            .unwrap_err();
+
+        assert!(error.downcast_ref::<ConsentRequired>().is_some());
+        assert_eq!(server.count("/watch"), 2);
+        assert_eq!(server.count("/ml"), 2);
+    }
+
+    #[tokio::test]
+    async fn consent_redirect_is_not_retried_when_bypass_is_disabled() {
+        let (server, http_client, webpage_url) = consent_server().await;
+        let options = TydleOptions {
+            disable_consent_bypass: true,
+            ..Default::default()
+        };
+        let extractor = test_extractor(options, http_client);
+
+        let error = extractor
+            .download_initial_webpage(webpage_url, &YtClient::Web, None)
+            .await
+            .unwrap_err();
+
+        assert!(error.downcast_ref::<ConsentRequired>().is_some());
+        assert_eq!(server.count("/watch"), 1);
+    }
+
+    #[tokio::test]
+    async fn cookies_set_by_redirects_are_stored_and_sent_along() {
+        let server = TestServer::start(|request| match request.path.as_str() {
+            path if path.starts_with("/watch") => TestResponse::redirect("/hop")
+                .header("Set-Cookie", "YSC=first; Domain=.youtube.com; Path=/")
+                .header("Set-Cookie", "HOP_ONLY=1; Path=/hop"),
+            "/hop" => TestResponse::redirect("/final").header(
+                "Set-Cookie",
+                "VISITOR_INFO1_LIVE=second; Domain=.youtube.com; Expires=Thu, 15-Apr-2027 10:00:00 GMT; Path=/",
+            ),
+            _ => TestResponse::ok("<html></html>"),
+        })
+        .await;
+        let (http_client, webpage_url) = youtube_client(&server);
+        let extractor = test_extractor(TydleOptions::default(), http_client);
+
+        let webpage = extractor
+            .download_initial_webpage(webpage_url, &YtClient::Web, None)
+            .await
+            .unwrap();
+
+        assert_eq!(webpage, "<html></html>");
+        let cookies = extractor
+            .get_cookies("https://www.youtube.com/hop")
+            .unwrap();
+        assert_eq!(cookies.get("YSC").map(String::as_str), Some("first"));
+        assert_eq!(cookies.get("HOP_ONLY").map(String::as_str), Some("1"));
+        assert_eq!(
+            cookies.get("VISITOR_INFO1_LIVE").map(String::as_str),
+            Some("second")
+        );
+
+        let cookie_headers: HashMap<String, String> = server
+            .requests()
+            .into_iter()
+            .map(|request| {
+                let path = request
+                    .path
+                    .split('?')
+                    .next()
+                    .unwrap_or_default()
+                    .to_string();
+                (
+                    path,
+                    request.headers.get("cookie").cloned().unwrap_or_default(),
+                )
+            })
+            .collect();
+        assert!(cookie_headers["/hop"].contains("YSC=first"));
+        assert!(cookie_headers["/hop"].contains("HOP_ONLY=1"));
+        assert!(cookie_headers["/final"].contains("VISITOR_INFO1_LIVE=second"));
+        assert!(!cookie_headers["/final"].contains("HOP_ONLY"));
+    }
+
+    #[tokio::test]
+    async fn cookies_set_by_the_consent_redirect_are_stored() {
+        let (_server, http_client, webpage_url) = consent_server().await;
+        let options = TydleOptions {
+            disable_consent_bypass: true,
+            ..Default::default()
+        };
+        let extractor = test_extractor(options, http_client);
+
+        let _ = extractor
+            .download_initial_webpage(webpage_url, &YtClient::Web, None)
+            .await;
+
+        let cookies = extractor.get_youtube_cookies().unwrap();
+        assert_eq!(cookies.get("YSC").map(String::as_str), Some("GG5rDyzqBoI"));
+    }
+
+    #[tokio::test]
+    async fn removed_cookies_are_no_longer_sent() {
+        let server = TestServer::start(|_| TestResponse::ok("<html></html>")).await;
+        let (http_client, webpage_url) = youtube_client(&server);
+        let extractor = test_extractor(TydleOptions::default(), http_client);
+        extractor
+            .cookie_store
+            .set("https://www.youtube.com", "GPS", "1")
+            .unwrap();
+
+        for _ in 0..2 {
+            extractor
+                .download_initial_webpage(webpage_url.clone(), &YtClient::Web, None)
+                .await
+                .unwrap();
+            extractor
+                .cookie_store
+                .remove("www.youtube.com", "GPS")
+                .unwrap();
+        }
 
-        Ok(webpage)
+        let cookie_headers: Vec<String> = server
+            .requests()
+            .into_iter()
+            .map(|request| request.headers.get("cookie").cloned().unwrap_or_default())
+            .collect();
+        assert_eq!(cookie_headers.len(), 2);
+        assert!(cookie_headers[0].contains("GPS=1"));
+        assert!(!cookie_headers[1].contains("GPS"));
+        assert!(cookie_headers[1].contains("PREF="));
     }
 }
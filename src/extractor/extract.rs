@@ -1,33 +1,58 @@
 use std::{
     collections::{HashMap, HashSet},
-    sync::{Arc, atomic::AtomicBool},
+    sync::{Arc, RwLock, atomic::AtomicBool},
+    time::Duration,
 };
 
 use anyhow::{Result, anyhow, bail};
 use fancy_regex::Regex;
 use serde_json::{Map, Value};
 
+#[cfg(feature = "oauth")]
+use crate::oauth::{MemoryTokenStore, OAuthSession};
 use crate::{
     TydleOptions, YT_URL,
-    cache::CacheStore,
-    cookies::CookieJar,
+    cache::{CacheBackend, SingleFlight, TypedCache},
+    cookies::CookieStore,
+    error::VideoUnavailable,
     extractor::{
-        auth::ExtractorAuthHandle, client::INNERTUBE_CLIENTS, download::ExtractorDownloadHandle,
-        json::ExtractorJsonHandle, player::ExtractorPlayerHandle, ytcfg::ExtractorYtCfgHandle,
+        auth::ExtractorAuthHandle, browse::ExtractorBrowseHandle,
+        chapters::ExtractorChaptersHandle, client::INNERTUBE_CLIENTS, dash::ExtractorDashHandle,
+        download::ExtractorDownloadHandle, heatmap::ExtractorHeatmapHandle,
+        hls::ExtractorHlsHandle, json::ExtractorJsonHandle, music::ExtractorMusicHandle,
+        player::ExtractorPlayerHandle, storyboards::ExtractorStoryboardsHandle,
+        ytcfg::ExtractorYtCfgHandle,
     },
+    tydle::{DEFAULT_CACHE_TTL, DEFAULT_UNAVAILABLE_VIDEO_TTL_SECS},
+    utils::now,
     yt_interface::{
-        VideoId, YtAgeLimit, YtChannel, YtClient, YtManifest, YtMediaType, YtStream,
-        YtStreamResponse, YtStreamSource, YtThumbnail, YtVideoInfo,
+        ChannelId, VideoId, YtAgeLimit, YtAudioTrack, YtByteRange, YtCaptionTrack, YtChannel,
+        YtChannelInfo, YtClient, YtColorInfo, YtManifest, YtMediaType, YtProjection, YtQuality,
+        YtStream, YtStreamResponse, YtStreamSource, YtThumbnail, YtVideoInfo,
     },
 };
 
 pub struct YtExtractor {
     pub passed_auth_cookies: AtomicBool,
     pub http_client: reqwest::Client,
-    pub cookie_jar: CookieJar,
-    pub player_cache: Arc<CacheStore<(String, String)>>,
-    pub code_cache: Arc<CacheStore>,
+    /// Client of the requests that carry the cookie jar. It doesn't follow redirects itself,
+    /// so `ExtractorCookieHandle::follow_redirects` sees the `Set-Cookie` headers of every hop.
+    pub cookie_client: reqwest::Client,
+    pub cookie_store: Arc<dyn CookieStore + Send + Sync>,
+    pub player_cache: Arc<dyn CacheBackend + Send + Sync>,
+    pub code_cache: Arc<dyn CacheBackend + Send + Sync>,
+    /// Downloads of player.js files currently in progress.
+    pub player_fetches: SingleFlight,
+    /// `VideoUnavailable` errors of videos that can't be played, see `TydleOptions::cache_unavailable_videos`.
+    pub unavailable_videos: TypedCache<VideoId>,
+    /// `PlayerInfo` of the videos extracted so far.
+    pub player_infos: TypedCache<VideoId>,
+    /// `visitorData` sent with every request, either `TydleOptions::visitor_data` or the first one YouTube assigned.
+    pub visitor_data: RwLock<Option<String>>,
     pub tydle_options: TydleOptions,
+    /// Token of the account signed in with `Tydle::oauth_device_flow`.
+    #[cfg(feature = "oauth")]
+    pub oauth: OAuthSession,
 }
 
 pub trait InfoExtractor {
@@ -36,6 +61,7 @@ pub trait InfoExtractor {
     fn extract_metadata(
         &self,
         player_responses: Vec<HashMap<String, Value>>,
+        initial_data: &HashMap<String, Value>,
     ) -> Result<YtVideoInfo>;
     async fn extract_video_info(&self, video_id: &VideoId) -> Result<YtVideoInfo>;
     async fn extract_streams_from_manifest(
@@ -43,39 +69,81 @@ pub trait InfoExtractor {
         manifest: &YtManifest,
     ) -> Result<YtStreamResponse>;
     async fn extract_manifest(&self, video_id: &VideoId) -> Result<YtManifest>;
+    /// Extract the streams of the player responses, `clients` being the client of each of them.
     fn extract_formats(
         &self,
         player_responses: Vec<HashMap<String, Value>>,
+        clients: &[YtClient],
     ) -> Result<Vec<YtStream>>;
+    /// Get the HLS manifest of a live stream that's currently broadcasting.
+    fn extract_hls_manifest_url(
+        &self,
+        player_responses: &[HashMap<String, Value>],
+    ) -> Option<String>;
+    fn extract_dash_manifest_url(
+        &self,
+        player_responses: &[HashMap<String, Value>],
+    ) -> Option<String>;
     async fn extract_streams(&self, video_id: &VideoId) -> Result<YtStreamResponse>;
     fn generate_checkok_params(&self) -> HashMap<String, Value>;
     fn is_premium_subscriber(&self, initial_data: &HashMap<String, Value>) -> Result<bool>;
     fn extract_ytcfg(&self, webpage_content: String) -> Result<HashMap<String, Value>>;
     fn extract_yt_initial_data(&self, webpage_content: &String) -> Result<HashMap<String, Value>>;
     fn get_clients(&self, is_premium_subscriber: bool) -> Result<Vec<YtClient>>;
+    /// Get the details of the uploader from the owner renderer of the watch page's initial data,
+    /// falling back to the profile URL of the microformat for the handle.
+    fn extract_channel_info(
+        &self,
+        channel_id: &str,
+        name: Option<String>,
+        owner_profile_url: Option<&str>,
+        initial_data: &HashMap<String, Value>,
+    ) -> Result<YtChannelInfo>;
     async fn extract(
         &self,
         webpage_url: &str,
         webpage_client: &YtClient,
         video_id: &VideoId,
-    ) -> Result<(Vec<HashMap<String, Value>>, String)>;
+    ) -> Result<YtManifest>;
 }
 
 impl YtExtractor {
     pub fn new(
-        player_cache: Arc<CacheStore<(String, String)>>,
-        code_cache: Arc<CacheStore>,
+        player_cache: Arc<dyn CacheBackend + Send + Sync>,
+        code_cache: Arc<dyn CacheBackend + Send + Sync>,
+        cookie_store: Arc<dyn CookieStore + Send + Sync>,
         tydle_options: TydleOptions,
     ) -> Result<Self> {
-        let cookie_jar = CookieJar::new_from_domain(YT_URL, tydle_options.auth_cookies.clone())?;
+        for (name, value) in &tydle_options.auth_cookies {
+            cookie_store.set(YT_URL, name, value)?;
+        }
+
+        let unavailable_video_ttl = Duration::from_secs(
+            tydle_options
+                .unavailable_video_ttl_secs
+                .unwrap_or(DEFAULT_UNAVAILABLE_VIDEO_TTL_SECS),
+        );
 
         let extractor = Self {
             passed_auth_cookies: AtomicBool::new(false),
             http_client: reqwest::Client::new(),
-            cookie_jar,
+            #[cfg(not(target_arch = "wasm32"))]
+            cookie_client: reqwest::Client::builder()
+                .redirect(reqwest::redirect::Policy::none())
+                .build()?,
+            // The browser follows redirects and keeps the cookies itself.
+            #[cfg(target_arch = "wasm32")]
+            cookie_client: reqwest::Client::new(),
+            cookie_store,
             player_cache,
             code_cache,
+            player_fetches: SingleFlight::new(),
+            unavailable_videos: TypedCache::with_ttl(unavailable_video_ttl),
+            player_infos: TypedCache::with_ttl(DEFAULT_CACHE_TTL),
+            visitor_data: RwLock::new(tydle_options.visitor_data.clone()),
             tydle_options, // x_forwarded_for_ip: None,
+            #[cfg(feature = "oauth")]
+            oauth: OAuthSession::new(Arc::new(MemoryTokenStore::default()))?,
         };
 
         extractor.initialize_pref()?;
@@ -84,9 +152,83 @@ impl YtExtractor {
 
         Ok(extractor)
     }
+
+    /// Remember that `video_id` can't be extracted if `error` is a permanent `VideoUnavailable`.
+    /// Only verdicts from YouTube itself are remembered, never network errors or rate limits.
+    fn remember_unavailable_video(&self, video_id: &VideoId, error: &anyhow::Error) -> Result<()> {
+        if let Some(unavailability) = error.downcast_ref::<VideoUnavailable>()
+            && unavailability.is_permanent()
+        {
+            self.unavailable_videos
+                .insert(video_id.clone(), unavailability.clone())?;
+        }
+
+        Ok(())
+    }
 }
 
 impl InfoExtractor for YtExtractor {
+    fn extract_channel_info(
+        &self,
+        channel_id: &str,
+        name: Option<String>,
+        owner_profile_url: Option<&str>,
+        initial_data: &HashMap<String, Value>,
+    ) -> Result<YtChannelInfo> {
+        let initial_data = Value::Object(initial_data.clone().into_iter().collect());
+        let owner = self
+            .find_renderers(&initial_data, "videoOwnerRenderer")
+            .into_iter()
+            .next()
+            .unwrap_or_default();
+
+        // Like `/@handle`, or `/channel/UC...` for channels without one.
+        let handle_of = |url: &str| {
+            url.rsplit_once('/')
+                .map(|(_, handle)| handle)
+                .filter(|handle| handle.starts_with('@'))
+                .map(|handle| handle.to_string())
+        };
+        let handle = owner
+            .get("navigationEndpoint")
+            .and_then(|ne| ne.get("browseEndpoint"))
+            .and_then(|be| be.get("canonicalBaseUrl"))
+            .and_then(|url| url.as_str())
+            .and_then(handle_of)
+            .or_else(|| owner_profile_url.and_then(handle_of));
+
+        const VERIFIED_BADGE_STYLES: [&str; 2] = [
+            "BADGE_STYLE_TYPE_VERIFIED",
+            "BADGE_STYLE_TYPE_VERIFIED_ARTIST",
+        ];
+
+        Ok(YtChannelInfo {
+            id: ChannelId::new(channel_id)?,
+            name: name
+                .or_else(|| self.get_text(owner, Some(vec![vec!["title"]]), None))
+                .unwrap_or_default(),
+            handle,
+            avatar: self.extract_thumbnails(owner.get("thumbnail").unwrap_or_default()),
+            subscriber_count_text: self.get_text(
+                owner,
+                Some(vec![vec!["subscriberCountText"]]),
+                None,
+            ),
+            is_verified: self
+                .find_renderers(
+                    owner.get("badges").unwrap_or_default(),
+                    "metadataBadgeRenderer",
+                )
+                .into_iter()
+                .any(|badge| {
+                    badge
+                        .get("style")
+                        .and_then(|s| s.as_str())
+                        .is_some_and(|style| VERIFIED_BADGE_STYLES.contains(&style))
+                }),
+        })
+    }
+
     fn generate_checkok_params(&self) -> HashMap<String, Value> {
         let mut checkout_params_map = HashMap::new();
 
@@ -156,7 +298,7 @@ impl InfoExtractor for YtExtractor {
     }
 
     fn get_clients(&self, is_premium_subscriber: bool) -> Result<Vec<YtClient>> {
-        let mut clients = if is_premium_subscriber {
+        let default_clients = if is_premium_subscriber {
             // Premium does not require POT. (except for subtitles)
             vec![
                 YtClient::Tv,
@@ -175,6 +317,14 @@ impl InfoExtractor for YtExtractor {
             ]
         };
 
+        let mut clients: Vec<YtClient> = self.tydle_options.client.into_iter().collect();
+        clients.extend(
+            self.tydle_options
+                .fallback_clients
+                .clone()
+                .unwrap_or(default_clients),
+        );
+
         if self.is_authenticated()? {
             let mut unsupported_clients = Vec::new();
 
@@ -204,10 +354,11 @@ impl InfoExtractor for YtExtractor {
     fn extract_formats(
         &self,
         player_responses: Vec<HashMap<String, Value>>,
+        clients: &[YtClient],
     ) -> Result<Vec<YtStream>> {
         let mut streams: Vec<YtStream> = vec![];
 
-        for player_response in &player_responses {
+        for (index, player_response) in player_responses.iter().enumerate() {
             let streaming_formats = player_response.get("streamingData").unwrap_or_default();
 
             if streaming_formats.is_null() {
@@ -218,31 +369,32 @@ impl InfoExtractor for YtExtractor {
 
             if let Some(streaming_data) = player_response.get("streamingData") {
                 if let Some(formats) = streaming_data.get("formats").and_then(|v| v.as_array()) {
-                    all_formats.extend(formats.clone());
+                    all_formats.extend(formats.iter().map(|fmt| (fmt.clone(), false)));
                 }
                 if let Some(adaptive_formats) = streaming_data
                     .get("adaptiveFormats")
                     .and_then(|v| v.as_array())
                 {
-                    all_formats.extend(adaptive_formats.clone());
+                    all_formats.extend(adaptive_formats.iter().map(|fmt| (fmt.clone(), true)));
                 }
             }
 
-            for fmt in all_formats {
+            for (fmt, is_adaptive) in all_formats {
                 let target_duration_sec = fmt.get("targetDurationSec");
 
-                // Skip livestream.
+                // Segments of a live stream, which are only downloadable through `hlsManifestUrl`.
                 if target_duration_sec.is_some() {
                     #[cfg(feature = "logging")]
-                    log::info!("Skipped a found livestream because livestreams are not supported.");
+                    log::info!(
+                        "Skipped a live stream format, use the HLS manifest to download it."
+                    );
                     continue;
                 }
 
-                let itag = fmt
-                    .get("itag")
-                    .unwrap_or_default()
-                    .as_str()
-                    .and_then(|s| Some(s.to_string()));
+                let itag = fmt.get("itag").and_then(|i| match i {
+                    Value::Number(itag) => Some(itag.to_string()),
+                    _ => i.as_str().map(|s| s.to_string()),
+                });
 
                 let mut quality = fmt
                     .get("quality")
@@ -273,7 +425,16 @@ impl InfoExtractor for YtExtractor {
                 }
 
                 if let Some(sc) = fmt.get("signatureCipher").unwrap_or_default().as_str() {
-                    stream_source = Some(YtStreamSource::Signature(sc.to_string()));
+                    match sc.parse() {
+                        Ok(signature_cipher) => {
+                            stream_source = Some(YtStreamSource::Signature(signature_cipher))
+                        }
+                        Err(_e) => {
+                            #[cfg(feature = "logging")]
+                            log::warn!("Skipping format {:?}: {}", itag, _e);
+                            continue;
+                        }
+                    }
                 }
 
                 let Some(src) = stream_source else {
@@ -286,8 +447,10 @@ impl InfoExtractor for YtExtractor {
                     .and_then(|v| v.as_f64())
                     .unwrap_or(1000 as f64);
 
-                let yt_stream = YtStream::new(
-                    fmt.get("audioSampleRate").and_then(|v| v.as_u64()),
+                let mut yt_stream = YtStream::new(
+                    // Sent as a string, like `"44100"`.
+                    fmt.get("audioSampleRate")
+                        .and_then(|v| v.as_u64().or_else(|| v.as_str()?.parse().ok())),
                     fmt.get("contentLength")
                         .and_then(|v| v.as_str().and_then(|s| s.parse().ok())),
                     itag,
@@ -296,6 +459,99 @@ impl InfoExtractor for YtExtractor {
                     tbr,
                 );
 
+                // Like `video/mp4; codecs="avc1.4d401f"`.
+                let mime_type = fmt.get("mimeType").and_then(|m| m.as_str());
+                yt_stream.codecs = mime_type
+                    .and_then(|m| m.split_once("codecs=\""))
+                    .map(|(_, codecs)| codecs.trim_end_matches('"').to_string());
+                yt_stream.mime_type = mime_type.map(|m| m.to_string());
+                yt_stream.parse_mime_type(is_adaptive);
+                yt_stream.bitrate = fmt.get("bitrate").and_then(|b| b.as_u64());
+                yt_stream.average_bitrate = fmt.get("averageBitrate").and_then(|b| b.as_u64());
+                yt_stream.width = fmt.get("width").and_then(|w| w.as_u64());
+                yt_stream.height = fmt.get("height").and_then(|h| h.as_u64());
+                yt_stream.fps = fmt.get("fps").and_then(|f| f.as_u64());
+                yt_stream.quality_label = fmt
+                    .get("qualityLabel")
+                    .and_then(|q| q.as_str())
+                    .map(|q| q.to_string());
+                yt_stream.parsed_quality = yt_stream
+                    .quality_label
+                    .as_deref()
+                    .or(yt_stream.quality.as_deref())
+                    .map(YtQuality::from);
+                yt_stream.audio_quality = fmt
+                    .get("audioQuality")
+                    .and_then(|q| q.as_str())
+                    .map(|q| q.to_lowercase());
+                yt_stream.audio_channels = fmt.get("audioChannels").and_then(|c| c.as_u64());
+                yt_stream.audio_track = fmt.get("audioTrack").and_then(|track| {
+                    Some(YtAudioTrack::new(
+                        track.get("id")?.as_str()?.to_string(),
+                        track
+                            .get("displayName")
+                            .and_then(|name| name.as_str())
+                            .unwrap_or_default()
+                            .to_string(),
+                        track
+                            .get("audioIsDefault")
+                            .and_then(|is_default| is_default.as_bool())
+                            .unwrap_or_default(),
+                    ))
+                });
+                yt_stream.is_drc |= fmt
+                    .get("isDrc")
+                    .and_then(|d| d.as_bool())
+                    .unwrap_or_default();
+                yt_stream.color_info = fmt.get("colorInfo").map(|color_info| {
+                    let get = |key: &str| {
+                        color_info
+                            .get(key)
+                            .and_then(|v| v.as_str())
+                            .map(|v| v.to_string())
+                    };
+
+                    YtColorInfo {
+                        primaries: get("primaries"),
+                        transfer_characteristics: get("transferCharacteristics"),
+                        matrix_coefficients: get("matrixCoefficients"),
+                    }
+                });
+                // Like `2160p60 HDR` or `1080p Premium`.
+                let quality_label = yt_stream.quality_label.as_deref().unwrap_or_default();
+                yt_stream.is_hdr = yt_stream
+                    .color_info
+                    .as_ref()
+                    .is_some_and(|color_info| color_info.is_hdr())
+                    || quality_label.ends_with("HDR");
+                yt_stream.is_premium_bitrate = quality_label.contains("Premium");
+                // Sometimes sent as a whole number, which `as_f64` reads all the same.
+                yt_stream.loudness_db = fmt
+                    .get("loudnessDb")
+                    .and_then(|l| l.as_f64())
+                    .map(|l| l as f32);
+                yt_stream.projection = fmt
+                    .get("projectionType")
+                    .and_then(|p| p.as_str())
+                    .map(YtProjection::parse)
+                    .unwrap_or_default();
+                // Like `{ "start": "0", "end": "740" }`.
+                let byte_range = |key: &str| {
+                    let range = fmt.get(key)?;
+                    let parse = |key: &str| range.get(key)?.as_str()?.parse().ok();
+
+                    Some(YtByteRange {
+                        start: parse("start")?,
+                        end: parse("end")?,
+                    })
+                };
+                yt_stream.init_range = byte_range("initRange");
+                yt_stream.index_range = byte_range("indexRange");
+                yt_stream.client = clients.get(index).copied();
+                yt_stream.is_otf =
+                    fmt.get("type").and_then(|t| t.as_str()) == Some("FORMAT_STREAM_TYPE_OTF");
+                yt_stream.fill_from_itag_table();
+
                 streams.push(yt_stream);
             }
         }
@@ -303,20 +559,75 @@ impl InfoExtractor for YtExtractor {
         Ok(streams)
     }
 
+    fn extract_hls_manifest_url(
+        &self,
+        player_responses: &[HashMap<String, Value>],
+    ) -> Option<String> {
+        player_responses.iter().find_map(|player_response| {
+            player_response
+                .get("streamingData")?
+                .get("hlsManifestUrl")?
+                .as_str()
+                .map(|url| url.to_string())
+        })
+    }
+
+    fn extract_dash_manifest_url(
+        &self,
+        player_responses: &[HashMap<String, Value>],
+    ) -> Option<String> {
+        player_responses.iter().find_map(|player_response| {
+            player_response
+                .get("streamingData")?
+                .get("dashManifestUrl")?
+                .as_str()
+                .map(|url| url.to_string())
+        })
+    }
+
     fn extract_metadata(
         &self,
         player_responses: Vec<HashMap<String, Value>>,
+        initial_data: &HashMap<String, Value>,
     ) -> Result<YtVideoInfo> {
         let mut extracted_title: Option<String> = None;
         let mut extracted_length_seconds: Option<u64> = None;
         let mut extracted_channel_id: Option<String> = None;
         let mut extracted_channel_name: Option<String> = None;
+        let mut extracted_owner_profile_url: Option<String> = None;
         let mut extracted_keywords: Option<Vec<String>> = None;
         let mut extracted_media_type: Option<YtMediaType> = None;
         let mut extracted_view_count: Option<u64> = None;
         let mut extracted_thumbnails: Vec<YtThumbnail> = vec![];
         let mut extracted_description: Option<String> = None;
         let mut extracted_age_limit: Option<YtAgeLimit> = None;
+        let mut extracted_captions: Vec<YtCaptionTrack> = vec![];
+        let mut extracted_is_live = false;
+        let mut extracted_storyboard_spec: Option<String> = None;
+        let is_age_restricted = player_responses.iter().any(|pr| self.is_age_gated(pr));
+        let is_upcoming = player_responses.iter().any(|pr| self.is_upcoming(pr));
+        let scheduled_start_time = player_responses
+            .iter()
+            .find_map(|pr| self.get_scheduled_start_time(pr));
+        let loudness_db = player_responses.iter().find_map(|pr| {
+            pr.get("playerConfig")?
+                .get("audioConfig")?
+                .get("loudnessDb")?
+                .as_f64()
+        });
+        let projection = player_responses
+            .iter()
+            .filter_map(|pr| pr.get("streamingData"))
+            .flat_map(|streaming_data| {
+                ["formats", "adaptiveFormats"]
+                    .into_iter()
+                    .filter_map(move |key| streaming_data.get(key)?.as_array())
+                    .flatten()
+            })
+            .filter_map(|fmt| fmt.get("projectionType")?.as_str())
+            .map(YtProjection::parse)
+            .find(|projection| *projection != YtProjection::Rectangular)
+            .unwrap_or_default();
 
         for player_response in player_responses {
             let Some(vd_value) = player_response.get("videoDetails") else {
@@ -387,6 +698,13 @@ impl InfoExtractor for YtExtractor {
                     .clone();
             }
 
+            if extracted_owner_profile_url.is_none() {
+                extracted_owner_profile_url = microformats
+                    .get("ownerProfileUrl")
+                    .and_then(|url| url.as_str())
+                    .map(|url| url.to_string());
+            }
+
             if extracted_media_type.is_none() {
                 extracted_media_type = Some(
                     if video_details
@@ -448,6 +766,50 @@ impl InfoExtractor for YtExtractor {
                     },
                 )
             }
+
+            extracted_is_live |= video_details
+                .get("isLive")
+                .and_then(|il| il.as_bool())
+                .unwrap_or_default();
+
+            // Live streams have a `playerLiveStoryboardSpecRenderer` instead, whose sheets change as they go.
+            if extracted_storyboard_spec.is_none() {
+                extracted_storyboard_spec = player_response
+                    .get("storyboards")
+                    .and_then(|s| s.get("playerStoryboardSpecRenderer"))
+                    .and_then(|s| s.get("spec"))
+                    .and_then(|s| s.as_str())
+                    .map(|s| s.to_string());
+            }
+
+            if extracted_captions.is_empty() {
+                extracted_captions = player_response
+                    .get("captions")
+                    .and_then(|c| c.get("playerCaptionsTracklistRenderer"))
+                    .and_then(|c| c.get("captionTracks"))
+                    .and_then(|c| c.as_array())
+                    .map(|tracks| {
+                        tracks
+                            .iter()
+                            .filter_map(|track| {
+                                Some(YtCaptionTrack {
+                                    language_code: track.get("languageCode")?.as_str()?.to_string(),
+                                    name: self
+                                        .get_text(track, Some(vec![vec!["name"]]), None)
+                                        .unwrap_or_default(),
+                                    is_auto_generated: track.get("kind").and_then(|k| k.as_str())
+                                        == Some("asr"),
+                                    is_translatable: track
+                                        .get("isTranslatable")
+                                        .and_then(|t| t.as_bool())
+                                        .unwrap_or_default(),
+                                    base_url: track.get("baseUrl")?.as_str()?.to_string(),
+                                })
+                            })
+                            .collect()
+                    })
+                    .unwrap_or_default();
+            }
         }
 
         if let (
@@ -463,16 +825,39 @@ impl InfoExtractor for YtExtractor {
             extracted_view_count,
             extracted_channel_id,
         ) {
+            let chapters = self.extract_chapters(initial_data, &description, length_seconds);
+            let music = self.extract_music(initial_data, &description);
+            let storyboards = extracted_storyboard_spec
+                .map(|spec| self.extract_storyboards(&spec, length_seconds))
+                .unwrap_or_default();
+
             return Ok(YtVideoInfo {
                 title,
                 description,
                 duration: length_seconds,
                 view_count,
+                is_live: extracted_is_live,
+                is_upcoming,
+                scheduled_start_time,
+                is_age_restricted,
+                channel_info: self.extract_channel_info(
+                    &channel_id,
+                    extracted_channel_name.clone(),
+                    extracted_owner_profile_url.as_deref(),
+                    initial_data,
+                )?,
                 channel: YtChannel::new(channel_id, extracted_channel_name)?,
                 keywords: extracted_keywords.unwrap_or_default(),
                 thumbnails: extracted_thumbnails,
                 age_limit: extracted_age_limit.unwrap_or_default(),
                 media_type: extracted_media_type.unwrap_or_default(),
+                captions: extracted_captions,
+                chapters,
+                storyboards,
+                heatmap: self.extract_heatmap(initial_data),
+                music,
+                projection,
+                loudness_db: loudness_db.map(|loudness_db| loudness_db as f32),
             });
         }
 
@@ -486,7 +871,7 @@ impl InfoExtractor for YtExtractor {
         webpage_url: &str,
         webpage_client: &YtClient,
         video_id: &VideoId,
-    ) -> Result<(Vec<HashMap<String, Value>>, String)> {
+    ) -> Result<YtManifest> {
         let webpage = self
             .download_webpage(webpage_url, webpage_client, video_id)
             .await?;
@@ -504,11 +889,16 @@ impl InfoExtractor for YtExtractor {
 
         let is_premium_subscriber = self.is_premium_subscriber(&initial_data)?;
         let clients = self.get_clients(is_premium_subscriber)?;
-        let player_responses = self
+        let (extracted_manifest, clients, player_url) = self
             .extract_player_responses(&clients, video_id, &webpage, webpage_client, &webpage_ytcfg)
             .await?;
 
-        Ok(player_responses)
+        Ok(YtManifest {
+            extracted_manifest,
+            player_url,
+            initial_data,
+            clients,
+        })
     }
 
     fn http_scheme(&self) -> &str {
@@ -519,39 +909,243 @@ impl InfoExtractor for YtExtractor {
     }
 
     async fn extract_manifest(&self, video_id: &VideoId) -> Result<YtManifest> {
+        let cache_unavailable = self.tydle_options.cache_unavailable_videos;
+
+        if cache_unavailable
+            && let Some(unavailability) =
+                self.unavailable_videos.get::<VideoUnavailable>(video_id)?
+        {
+            return Err(VideoUnavailable::clone(&unavailability).into());
+        }
+
         let webpage_url = format!("{}://www.youtube.com/watch", self.http_scheme());
-        let (initial_extracted_data, player_url) =
-            self.extract(&webpage_url, &YtClient::Web, video_id).await?;
+        let manifest = match self.extract(&webpage_url, &YtClient::Web, video_id).await {
+            Ok(extracted) => extracted,
+            Err(e) => {
+                if cache_unavailable {
+                    self.remember_unavailable_video(video_id, &e)?;
+                }
 
-        Ok(YtManifest::new(initial_extracted_data, player_url))
+                return Err(e);
+            }
+        };
+
+        if !manifest.player_url.is_empty() {
+            self.record_player_info(video_id, manifest.player_url.clone())?;
+        }
+
+        Ok(manifest)
     }
 
     async fn extract_streams(&self, video_id: &VideoId) -> Result<YtStreamResponse> {
         let yt_manifest = self.extract_manifest(video_id).await?;
 
-        let formats = self.extract_formats(yt_manifest.extracted_manifest)?;
-        let stream_response = YtStreamResponse::new(yt_manifest.player_url, formats);
-
-        Ok(stream_response)
+        self.extract_streams_from_manifest(&yt_manifest).await
     }
 
     async fn extract_streams_from_manifest(
         &self,
         manifest: &YtManifest,
     ) -> Result<YtStreamResponse> {
-        let formats = self.extract_formats(manifest.extracted_manifest.clone())?;
-        Ok(YtStreamResponse::new(manifest.player_url.clone(), formats))
+        if let Some(upcoming) = manifest
+            .extracted_manifest
+            .iter()
+            .find(|pr| self.is_upcoming(pr))
+        {
+            return Err(VideoUnavailable::Upcoming {
+                reason: self
+                    .get_playability_reason(upcoming)
+                    .unwrap_or_else(|| "This live event will begin soon.".into()),
+                scheduled_start_time: self.get_scheduled_start_time(upcoming),
+            }
+            .into());
+        }
+
+        let mut formats =
+            self.extract_formats(manifest.extracted_manifest.clone(), &manifest.clients)?;
+        let dash_manifest_url = self.extract_dash_manifest_url(&manifest.extracted_manifest);
+
+        if let Some(dash_manifest_url) = &dash_manifest_url {
+            self.merge_dash_streams(&mut formats, dash_manifest_url)
+                .await;
+        }
+
+        let hls_manifest_url = self.extract_hls_manifest_url(&manifest.extracted_manifest);
+
+        // Live streams, and videos only some clients play, have nothing but the HLS manifest.
+        if formats.is_empty()
+            && let Some(hls_manifest_url) = &hls_manifest_url
+        {
+            match self.fetch_hls_streams(hls_manifest_url).await {
+                Ok(hls_streams) => formats = hls_streams,
+                Err(_e) => {
+                    #[cfg(feature = "logging")]
+                    log::warn!("Skipping the streams of the HLS manifest: {}", _e);
+                }
+            }
+        }
+
+        let mut stream_response = YtStreamResponse::new(manifest.player_url.clone(), formats);
+        stream_response.hls_manifest_url = hls_manifest_url;
+        stream_response.dash_manifest_url = dash_manifest_url;
+        // Relative to when the player responses were requested, which was moments ago.
+        stream_response.expires_at = manifest
+            .extracted_manifest
+            .iter()
+            .filter_map(|player_response| {
+                player_response
+                    .get("streamingData")?
+                    .get("expiresInSeconds")?
+                    .as_str()?
+                    .parse()
+                    .ok()
+            })
+            .min()
+            .and_then(|expires_in| now().checked_add(Duration::from_secs(expires_in)));
+
+        Ok(stream_response)
     }
 
     async fn extract_video_info(&self, video_id: &VideoId) -> Result<YtVideoInfo> {
         let yt_manifest = self.extract_manifest(video_id).await?;
 
-        let yt_video_info = self.extract_metadata(yt_manifest.extracted_manifest)?;
+        let yt_video_info =
+            self.extract_metadata(yt_manifest.extracted_manifest, &yt_manifest.initial_data)?;
         Ok(yt_video_info)
     }
 
     async fn extract_video_info_from_manifest(&self, manifest: &YtManifest) -> Result<YtVideoInfo> {
-        let yt_video_info = self.extract_metadata(manifest.extracted_manifest.clone())?;
+        let yt_video_info =
+            self.extract_metadata(manifest.extracted_manifest.clone(), &manifest.initial_data)?;
         Ok(yt_video_info)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{TestResponse, TestServer, test_extractor};
+
+    const WATCH_URL: &str = "http://www.youtube.com/watch";
+
+    /// Server whose watch page breaks off before its body, like a dropped connection.
+    async fn dropping_server() -> TestServer {
+        TestServer::start(|_| TestResponse::ok("<html></html>").truncate_after(0)).await
+    }
+
+    /// Extractor remembering unavailable videos for a second that requests the watch page through `server`.
+    fn negative_caching_extractor(server: &TestServer) -> YtExtractor {
+        let options = TydleOptions {
+            prefer_insecure: true,
+            cache_unavailable_videos: true,
+            unavailable_video_ttl_secs: Some(1),
+            ..Default::default()
+        };
+        let http_client = reqwest::Client::builder()
+            .proxy(reqwest::Proxy::http(server.url("")).unwrap())
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+            .unwrap();
+
+        test_extractor(options, http_client)
+    }
+
+    #[tokio::test]
+    async fn permanent_unavailability_is_cached_for_its_ttl() {
+        let server = dropping_server().await;
+        let extractor = negative_caching_extractor(&server);
+        let video_id = VideoId::new("dQw4w9WgXcQ").unwrap();
+        let private = VideoUnavailable::Private {
+            reason: "This video is private.".into(),
+        };
+
+        extractor
+            .remember_unavailable_video(&video_id, &anyhow::Error::new(private.clone()))
+            .unwrap();
+        let error = extractor.extract_manifest(&video_id).await.unwrap_err();
+
+        assert_eq!(error.downcast_ref::<VideoUnavailable>(), Some(&private));
+        assert_eq!(server.count(WATCH_URL), 0);
+
+        tokio::time::sleep(Duration::from_millis(1100)).await;
+        let error = extractor.extract_manifest(&video_id).await.unwrap_err();
+
+        assert!(error.downcast_ref::<VideoUnavailable>().is_none());
+        assert_eq!(server.count(WATCH_URL), 1);
+    }
+
+    #[tokio::test]
+    async fn transient_failures_are_not_cached() {
+        let server = dropping_server().await;
+        let extractor = negative_caching_extractor(&server);
+        let video_id = VideoId::new("dQw4w9WgXcQ").unwrap();
+
+        for _ in 0..2 {
+            let error = extractor.extract_manifest(&video_id).await.unwrap_err();
+            assert!(error.downcast_ref::<VideoUnavailable>().is_none());
+        }
+        assert_eq!(server.count(WATCH_URL), 2);
+
+        for unavailability in [
+            VideoUnavailable::BotCheck {
+                reason: "Sign in to confirm you’re not a bot".into(),
+            },
+            VideoUnavailable::GeoBlocked {
+                reason: "The uploader has not made this video available in your country".into(),
+                allowed_countries: vec!["DE".into()],
+            },
+        ] {
+            extractor
+                .remember_unavailable_video(&video_id, &anyhow::Error::new(unavailability))
+                .unwrap();
+        }
+        assert!(extractor.unavailable_videos.is_empty().unwrap());
+    }
+
+    fn video_info_of(player_response: &str) -> YtVideoInfo {
+        test_extractor(TydleOptions::default(), reqwest::Client::new())
+            .extract_metadata(
+                vec![serde_json::from_str(player_response).unwrap()],
+                &HashMap::new(),
+            )
+            .unwrap()
+    }
+
+    #[test]
+    fn quiet_video_is_only_boosted_when_allowed() {
+        // Sent as the whole number `-9`.
+        let info = video_info_of(include_str!("../../tests/fixtures/player_quiet.json"));
+
+        assert_eq!(info.loudness_db, Some(-9.0));
+        assert_eq!(info.normalized_gain(false), Some(1.0));
+
+        let boost = info.normalized_gain(true).unwrap();
+        assert!((boost - 2.8184).abs() < 1e-3, "{}", boost);
+    }
+
+    #[test]
+    fn loud_video_is_turned_down() {
+        let info = video_info_of(include_str!("../../tests/fixtures/player_loud.json"));
+
+        assert_eq!(info.loudness_db, Some(6.27));
+
+        let gain = info.normalized_gain(false).unwrap();
+        assert!((gain - 0.4858).abs() < 1e-3, "{}", gain);
+        // Boosting doesn't change anything for a video above the target.
+        assert_eq!(info.normalized_gain(true), Some(gain));
+    }
+
+    #[test]
+    fn video_without_loudness_has_no_gain() {
+        let mut player_response: Value =
+            serde_json::from_str(include_str!("../../tests/fixtures/player_quiet.json")).unwrap();
+        player_response
+            .as_object_mut()
+            .unwrap()
+            .remove("playerConfig");
+        let info = video_info_of(&player_response.to_string());
+
+        assert_eq!(info.loudness_db, None);
+        assert_eq!(info.normalized_gain(true), None);
+    }
+}
@@ -0,0 +1,152 @@
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use crate::{
+    extractor::{browse::ExtractorBrowseHandle, extract::YtExtractor},
+    yt_interface::YtHeatMarker,
+};
+
+pub trait ExtractorHeatmapHandle {
+    /// Get the "most replayed" markers of a video in their order, empty for videos without enough views to have them.
+    fn extract_heatmap(&self, initial_data: &HashMap<String, Value>) -> Vec<YtHeatMarker>;
+    /// Parse the markers of a `macroMarkersListEntity` in the entities of the framework updates.
+    fn extract_entity_heatmap(&self, initial_data: &Value) -> Vec<YtHeatMarker>;
+    /// Parse the `heatMarkerRenderer`s of the player bar, where the markers were before the framework updates.
+    fn extract_player_bar_heatmap(&self, initial_data: &Value) -> Vec<YtHeatMarker>;
+}
+
+impl ExtractorHeatmapHandle for YtExtractor {
+    fn extract_heatmap(&self, initial_data: &HashMap<String, Value>) -> Vec<YtHeatMarker> {
+        let initial_data = Value::Object(initial_data.clone().into_iter().collect());
+
+        let mut heatmap = self.extract_entity_heatmap(&initial_data);
+
+        if heatmap.is_empty() {
+            heatmap = self.extract_player_bar_heatmap(&initial_data);
+        }
+
+        // The scores are normalized already, but don't rely on it.
+        let max_intensity = heatmap
+            .iter()
+            .map(|marker| marker.intensity)
+            .fold(0.0, f32::max);
+
+        if max_intensity > 1.0 {
+            for marker in &mut heatmap {
+                marker.intensity /= max_intensity;
+            }
+        }
+
+        heatmap
+    }
+
+    fn extract_entity_heatmap(&self, initial_data: &Value) -> Vec<YtHeatMarker> {
+        // Numbers in entities are strings.
+        let millis = |value: &Value| match value {
+            Value::String(millis) => millis.parse::<u64>().ok(),
+            _ => value.as_u64(),
+        };
+
+        self.find_renderers(initial_data, "macroMarkersListEntity")
+            .into_iter()
+            .filter_map(|entity| entity.get("markersList"))
+            .filter(|markers_list| {
+                markers_list.get("markerType").and_then(|t| t.as_str())
+                    == Some("MARKER_TYPE_HEATMAP")
+            })
+            .filter_map(|markers_list| markers_list.get("markers")?.as_array())
+            .flatten()
+            .filter_map(|marker| {
+                Some(YtHeatMarker {
+                    start_ms: millis(marker.get("startMillis")?)?,
+                    duration_ms: millis(marker.get("durationMillis")?)?,
+                    intensity: marker.get("intensityScoreNormalized")?.as_f64()? as f32,
+                })
+            })
+            .collect()
+    }
+
+    fn extract_player_bar_heatmap(&self, initial_data: &Value) -> Vec<YtHeatMarker> {
+        let Some(player_overlays) = initial_data.get("playerOverlays") else {
+            return vec![];
+        };
+
+        self.find_renderers(player_overlays, "heatMarkerRenderer")
+            .into_iter()
+            .filter_map(|marker| {
+                Some(YtHeatMarker {
+                    start_ms: marker.get("timeRangeStartMillis")?.as_u64()?,
+                    duration_ms: marker.get("markerDurationMillis")?.as_u64()?,
+                    intensity: marker.get("heatMarkerIntensityScoreNormalized")?.as_f64()? as f32,
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use crate::{test_server::test_extractor, tydle::TydleOptions};
+
+    fn heatmap(json: &str) -> Vec<YtHeatMarker> {
+        test_extractor(TydleOptions::default(), reqwest::Client::new())
+            .extract_heatmap(&serde_json::from_str(json).unwrap())
+    }
+
+    fn marker(start_ms: u64, duration_ms: u64, intensity: f32) -> YtHeatMarker {
+        YtHeatMarker {
+            start_ms,
+            duration_ms,
+            intensity,
+        }
+    }
+
+    #[test]
+    fn heatmap_is_parsed_from_the_framework_updates() {
+        assert_eq!(
+            heatmap(include_str!(
+                "../../tests/fixtures/next_heatmap_entities.json"
+            )),
+            [
+                marker(0, 2130, 1.0),
+                marker(2130, 2130, 0.5128),
+                marker(4260, 2130, 0.25)
+            ]
+        );
+    }
+
+    #[test]
+    fn heatmap_is_parsed_from_the_player_bar() {
+        assert_eq!(
+            heatmap(include_str!(
+                "../../tests/fixtures/next_heatmap_player_bar.json"
+            )),
+            [marker(0, 2130, 1.0), marker(2130, 2130, 0.75)]
+        );
+    }
+
+    #[test]
+    fn intensities_above_one_are_normalized() {
+        let json = include_str!("../../tests/fixtures/next_heatmap_player_bar.json")
+            .replace(
+                r#""heatMarkerIntensityScoreNormalized": 1 "#,
+                r#""heatMarkerIntensityScoreNormalized": 4 "#,
+            )
+            .replace(
+                r#""heatMarkerIntensityScoreNormalized": 0.75 "#,
+                r#""heatMarkerIntensityScoreNormalized": 3 "#,
+            );
+
+        assert_eq!(
+            heatmap(&json),
+            [marker(0, 2130, 1.0), marker(2130, 2130, 0.75)]
+        );
+    }
+
+    #[test]
+    fn videos_without_a_heatmap_have_no_markers() {
+        assert!(heatmap(include_str!("../../tests/fixtures/next_chapters.json")).is_empty());
+    }
+}
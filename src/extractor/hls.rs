@@ -0,0 +1,417 @@
+use std::collections::{HashMap, HashSet};
+
+use anyhow::{Result, bail};
+use reqwest::Url;
+
+use crate::{
+    extractor::extract::YtExtractor,
+    yt_interface::{
+        YtAudioTrack, YtHlsVariant, YtStream, YtStreamKind, YtStreamOrigin, YtStreamSource,
+    },
+};
+
+pub trait ExtractorHlsHandle {
+    async fn fetch_hls_master_playlist(&self, manifest_url: &str) -> Result<String>;
+    /// Download the master playlist of a live stream and parse its variants.
+    async fn fetch_hls_variants(&self, manifest_url: &str) -> Result<Vec<YtHlsVariant>>;
+    /// Download the master playlist of a video and turn it into streams, see `parse_hls_streams`.
+    async fn fetch_hls_streams(&self, manifest_url: &str) -> Result<Vec<YtStream>>;
+    /// Parse the `#EXT-X-STREAM-INF` variants of a master playlist, resolving their URLs against `manifest_url`.
+    fn parse_hls_master_playlist(
+        &self,
+        playlist: &str,
+        manifest_url: &str,
+    ) -> Result<Vec<YtHlsVariant>>;
+    /// Turn the variants of a master playlist and its `#EXT-X-MEDIA` audio renditions into streams
+    /// from `YtStreamOrigin::Hls`. Variants that play with an audio rendition are video-only.
+    fn parse_hls_streams(&self, playlist: &str, manifest_url: &str) -> Result<Vec<YtStream>>;
+}
+
+/// YouTube puts the itag in the path of the playlists, like `.../itag/96/...`.
+fn itag_from_url(url: &str) -> Option<String> {
+    url.split('/')
+        .skip_while(|segment| *segment != "itag")
+        .nth(1)
+        .map(|itag| itag.to_string())
+}
+
+/// Whether a codec of `CODECS`, like `mp4a.40.2`, is an audio codec.
+fn is_audio_codec(codec: &str) -> bool {
+    let family = codec.trim().split('.').next().unwrap_or_default();
+
+    matches!(
+        family,
+        "mp4a" | "opus" | "vorbis" | "flac" | "ac-3" | "ec-3"
+    )
+}
+
+/// Split the attribute list of a tag into its keys and values, keeping the commas within quoted values like `CODECS`.
+fn parse_attribute_list(attributes: &str) -> Vec<(&str, &str)> {
+    let mut pairs = Vec::new();
+    let mut rest = attributes;
+
+    while let Some((key, value_start)) = rest.split_once('=') {
+        let (value, next) = match value_start.strip_prefix('"') {
+            Some(quoted) => {
+                let end = quoted.find('"').unwrap_or(quoted.len());
+                let after = quoted.get(end + 1..).unwrap_or_default();
+                (&quoted[..end], after.strip_prefix(',').unwrap_or(after))
+            }
+            None => value_start.split_once(',').unwrap_or((value_start, "")),
+        };
+
+        pairs.push((key.trim(), value));
+        rest = next;
+    }
+
+    pairs
+}
+
+impl ExtractorHlsHandle for YtExtractor {
+    async fn fetch_hls_master_playlist(&self, manifest_url: &str) -> Result<String> {
+        #[cfg(feature = "logging")]
+        log::info!("Downloading HLS manifest at {}", manifest_url);
+
+        Ok(self
+            .http_client
+            .get(manifest_url)
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?)
+    }
+
+    async fn fetch_hls_variants(&self, manifest_url: &str) -> Result<Vec<YtHlsVariant>> {
+        let playlist = self.fetch_hls_master_playlist(manifest_url).await?;
+
+        self.parse_hls_master_playlist(&playlist, manifest_url)
+    }
+
+    async fn fetch_hls_streams(&self, manifest_url: &str) -> Result<Vec<YtStream>> {
+        let playlist = self.fetch_hls_master_playlist(manifest_url).await?;
+
+        self.parse_hls_streams(&playlist, manifest_url)
+    }
+
+    fn parse_hls_master_playlist(
+        &self,
+        playlist: &str,
+        manifest_url: &str,
+    ) -> Result<Vec<YtHlsVariant>> {
+        if !playlist.trim_start().starts_with("#EXTM3U") {
+            bail!("Failed to parse HLS manifest because it isn't an M3U8 playlist.")
+        }
+
+        let base_url = Url::parse(manifest_url)?;
+        let mut variants = Vec::new();
+        let mut lines = playlist.lines().map(|line| line.trim());
+
+        while let Some(line) = lines.next() {
+            let Some(attributes) = line.strip_prefix("#EXT-X-STREAM-INF:") else {
+                continue;
+            };
+
+            // The URI of a variant is the first line after its tag that isn't a comment.
+            let Some(uri) = lines.find(|line| !line.is_empty() && !line.starts_with('#')) else {
+                break;
+            };
+
+            let mut variant = YtHlsVariant {
+                url: base_url.join(uri)?.to_string(),
+                itag: None,
+                bandwidth: 0,
+                width: None,
+                height: None,
+                codecs: None,
+                frame_rate: None,
+                audio_group: None,
+            };
+
+            for (key, value) in parse_attribute_list(attributes) {
+                match key {
+                    "BANDWIDTH" => variant.bandwidth = value.parse().unwrap_or_default(),
+                    "RESOLUTION" => {
+                        if let Some((width, height)) = value.split_once('x') {
+                            variant.width = width.parse().ok();
+                            variant.height = height.parse().ok();
+                        }
+                    }
+                    "CODECS" => variant.codecs = Some(value.to_string()),
+                    "FRAME-RATE" => variant.frame_rate = value.parse().ok(),
+                    "AUDIO" => variant.audio_group = Some(value.to_string()),
+                    _ => {}
+                }
+            }
+
+            variant.itag = itag_from_url(&variant.url);
+
+            variants.push(variant);
+        }
+
+        Ok(variants)
+    }
+
+    fn parse_hls_streams(&self, playlist: &str, manifest_url: &str) -> Result<Vec<YtStream>> {
+        let variants = self.parse_hls_master_playlist(playlist, manifest_url)?;
+        let base_url = Url::parse(manifest_url)?;
+        let mut audio_streams: Vec<YtStream> = Vec::new();
+        // Groups whose audio is in playlists of its own rather than muxed into the variants.
+        let mut audio_groups = HashSet::new();
+
+        for line in playlist.lines().map(|line| line.trim()) {
+            let Some(attributes) = line.strip_prefix("#EXT-X-MEDIA:") else {
+                continue;
+            };
+
+            let attributes: HashMap<&str, &str> =
+                parse_attribute_list(attributes).into_iter().collect();
+            let (Some(&"AUDIO"), Some(uri)) = (attributes.get("TYPE"), attributes.get("URI"))
+            else {
+                continue;
+            };
+
+            let url = base_url.join(uri)?.to_string();
+            let group = attributes.get("GROUP-ID").copied();
+            audio_groups.extend(group);
+
+            // A rendition can be in more than one group.
+            if audio_streams
+                .iter()
+                .any(|stream| matches!(&stream.source, YtStreamSource::URL(u) if *u == url))
+            {
+                continue;
+            }
+
+            let mut stream = YtStream::new(
+                None,
+                None,
+                itag_from_url(&url),
+                None,
+                YtStreamSource::URL(url),
+                1000.0,
+            );
+            // Only the variants list the codecs, the audio one next to the video one.
+            stream.codecs = variants
+                .iter()
+                .filter(|variant| variant.audio_group.as_deref() == group)
+                .find_map(|variant| {
+                    variant
+                        .codecs
+                        .as_deref()?
+                        .split(',')
+                        .find(|codec| is_audio_codec(codec))
+                })
+                .map(|codec| codec.trim().to_string());
+            stream.audio_codec = stream.codecs.clone();
+            stream.kind = YtStreamKind::AdaptiveAudio;
+            stream.audio_track = attributes.get("YT-EXT-AUDIO-CONTENT-ID").map(|id| {
+                YtAudioTrack::new(
+                    id.to_string(),
+                    attributes
+                        .get("NAME")
+                        .copied()
+                        .unwrap_or_default()
+                        .to_string(),
+                    attributes.get("DEFAULT") == Some(&"YES"),
+                )
+            });
+            stream.origin = YtStreamOrigin::Hls;
+            stream.fill_from_itag_table();
+
+            audio_streams.push(stream);
+        }
+
+        let mut streams: Vec<YtStream> = variants
+            .into_iter()
+            .map(|variant| {
+                let mut stream = YtStream::new(
+                    None,
+                    None,
+                    variant.itag,
+                    None,
+                    YtStreamSource::URL(variant.url),
+                    variant.bandwidth as f64,
+                );
+                stream.bitrate = Some(variant.bandwidth);
+                stream.codecs = variant.codecs;
+                stream.parse_mime_type(false);
+                // Unlike formats, HLS doesn't fix the order of `CODECS`, and live streams list the audio first.
+                if stream.video_codec.as_deref().is_some_and(is_audio_codec) {
+                    std::mem::swap(&mut stream.video_codec, &mut stream.audio_codec);
+                }
+
+                // `CODECS` lists the audio codec even when the audio is in a rendition of its own.
+                if variant
+                    .audio_group
+                    .is_some_and(|group| audio_groups.contains(group.as_str()))
+                {
+                    stream.kind = YtStreamKind::AdaptiveVideo;
+                    stream.audio_codec = None;
+                }
+
+                stream.width = variant.width;
+                stream.height = variant.height;
+                stream.fps = variant
+                    .frame_rate
+                    .map(|frame_rate| frame_rate.round() as u64);
+                stream.origin = YtStreamOrigin::Hls;
+                stream.fill_from_itag_table();
+
+                stream
+            })
+            .collect();
+
+        streams.extend(audio_streams);
+        Ok(streams)
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use crate::{
+        test_server::{TestResponse, TestServer, test_extractor},
+        tydle::TydleOptions,
+    };
+
+    const LIVE_MASTER: &str = include_str!("../../tests/fixtures/hls_live_master.m3u8");
+    const IOS_VOD_MASTER: &str = include_str!("../../tests/fixtures/hls_ios_vod_master.m3u8");
+    const MANIFEST_URL: &str = "https://manifest.googlevideo.com/api/manifest/hls_variant/id/jfKfPfyJRdk.2/file/index.m3u8";
+
+    fn parse_streams(playlist: &str) -> Vec<YtStream> {
+        test_extractor(TydleOptions::default(), reqwest::Client::new())
+            .parse_hls_streams(playlist, MANIFEST_URL)
+            .unwrap()
+    }
+
+    fn url_of(stream: &YtStream) -> &str {
+        match &stream.source {
+            YtStreamSource::URL(url) => url,
+            YtStreamSource::Signature(signature_cipher) => &signature_cipher.url,
+        }
+    }
+
+    #[test]
+    fn variants_of_a_live_stream_are_muxed_streams() {
+        let streams = parse_streams(LIVE_MASTER);
+
+        assert_eq!(
+            streams
+                .iter()
+                .map(|stream| stream.itag().unwrap())
+                .collect::<Vec<_>>(),
+            ["91", "92", "93", "94", "95", "96", "300", "301"]
+        );
+        assert!(streams.iter().all(|stream| {
+            stream.kind == YtStreamKind::Muxed && stream.origin == YtStreamOrigin::Hls
+        }));
+
+        let hd = &streams[7];
+        assert_eq!(hd.bitrate, Some(6740033));
+        assert_eq!((hd.width, hd.height), (Some(1920), Some(1080)));
+        // `59.940` frames a second.
+        assert_eq!(hd.fps, Some(60));
+        assert!(url_of(hd).contains("/itag/301/"));
+        assert!(url_of(hd).ends_with("/playlist/index.m3u8"));
+        // Live streams list the audio codec first.
+        assert_eq!(hd.codecs.as_deref(), Some("mp4a.40.2,avc1.64002a"));
+        assert_eq!(hd.video_codec.as_deref(), Some("avc1.64002a"));
+        assert_eq!(hd.audio_codec.as_deref(), Some("mp4a.40.2"));
+    }
+
+    #[test]
+    fn variants_of_an_ios_video_play_with_audio_renditions() {
+        let streams = parse_streams(IOS_VOD_MASTER);
+
+        let (audio, video): (Vec<_>, Vec<_>) = streams
+            .iter()
+            .partition(|stream| stream.kind == YtStreamKind::AdaptiveAudio);
+
+        assert_eq!(
+            video
+                .iter()
+                .map(|stream| stream.itag().unwrap())
+                .collect::<Vec<_>>(),
+            ["269", "229", "230", "231", "232", "270"]
+        );
+        assert!(video.iter().all(|stream| {
+            stream.kind == YtStreamKind::AdaptiveVideo
+                && stream.audio_codec.is_none()
+                && stream.fps == Some(25)
+        }));
+
+        let full_hd = video[5];
+        assert_eq!(full_hd.video_codec.as_deref(), Some("avc1.640028"));
+        assert_eq!((full_hd.width, full_hd.height), (Some(1920), Some(1080)));
+        assert_eq!(full_hd.bitrate, Some(4382563));
+
+        // Each group has the original English and a Spanish dub.
+        assert_eq!(
+            audio
+                .iter()
+                .map(|stream| {
+                    let track = stream.audio_track.as_ref().unwrap();
+                    (
+                        stream.itag().unwrap(),
+                        track.language.as_deref().unwrap(),
+                        track.is_default,
+                    )
+                })
+                .collect::<Vec<_>>(),
+            [
+                ("233", "en", true),
+                ("233", "es", false),
+                ("234", "en", true),
+                ("234", "es", false),
+            ]
+        );
+        // The codec of a rendition comes from the variants of its group.
+        assert_eq!(audio[0].audio_codec.as_deref(), Some("mp4a.40.5"));
+        assert_eq!(audio[2].audio_codec.as_deref(), Some("mp4a.40.2"));
+        assert!(
+            audio
+                .iter()
+                .all(|stream| stream.origin == YtStreamOrigin::Hls)
+        );
+        assert!(url_of(audio[1]).contains("/xtags/acont%3Ddubbed%3Alang%3Des/"));
+    }
+
+    #[test]
+    fn relative_variant_urls_resolve_against_the_manifest() {
+        let playlist = "#EXTM3U\n#EXT-X-STREAM-INF:BANDWIDTH=290288,CODECS=\"mp4a.40.5,avc1.42c00b\",RESOLUTION=256x144\nitag/91/playlist/index.m3u8\n";
+
+        let variants = test_extractor(TydleOptions::default(), reqwest::Client::new())
+            .parse_hls_master_playlist(playlist, MANIFEST_URL)
+            .unwrap();
+
+        assert_eq!(
+            variants[0].url,
+            "https://manifest.googlevideo.com/api/manifest/hls_variant/id/jfKfPfyJRdk.2/file/itag/91/playlist/index.m3u8"
+        );
+        assert_eq!(variants[0].itag.as_deref(), Some("91"));
+    }
+
+    #[test]
+    fn a_playlist_that_isnt_m3u8_is_an_error() {
+        let error = test_extractor(TydleOptions::default(), reqwest::Client::new())
+            .parse_hls_streams("<html></html>", MANIFEST_URL)
+            .unwrap_err();
+
+        assert!(error.to_string().contains("isn't an M3U8 playlist"));
+    }
+
+    #[tokio::test]
+    async fn master_playlist_is_downloaded_and_parsed() {
+        let server = TestServer::start(|_| TestResponse::ok(IOS_VOD_MASTER)).await;
+        let extractor = test_extractor(TydleOptions::default(), reqwest::Client::new());
+
+        let streams = extractor
+            .fetch_hls_streams(&server.url("/manifest.m3u8"))
+            .await
+            .unwrap();
+
+        assert_eq!(streams.len(), 10);
+        assert_eq!(server.count("/manifest.m3u8"), 1);
+    }
+}
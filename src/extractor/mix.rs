@@ -0,0 +1,143 @@
+use std::collections::HashMap;
+
+use anyhow::{Result, anyhow, bail};
+use serde_json::{Value, json};
+
+use crate::{
+    error::ContinuationExpired,
+    extractor::{
+        api::ExtractorApiHandle, browse::ExtractorBrowseHandle, extract::YtExtractor,
+        json::ExtractorJsonHandle,
+    },
+    utils::parse_duration,
+    yt_interface::{Continuation, PlaylistId, VideoId, YtEndpoint, YtMix, YtPlaylistEntry},
+};
+
+/// Params of the watch endpoints of mixes, used when YouTube leaves them out.
+const MIX_PARAMS: &str = "OAE%3D";
+
+pub trait ExtractorMixHandle {
+    async fn extract_mix(&self, video_id: &VideoId) -> Result<YtMix>;
+    async fn extract_mix_continuation(&self, continuation: &str) -> Result<YtMix>;
+    /// Parse the playlist panel next to the player, skipping the entries up to and including `last_video_id`
+    /// which were on the previous page already.
+    fn extract_mix_page(&self, data: &Value, last_video_id: Option<&str>) -> Result<YtMix>;
+}
+
+impl ExtractorMixHandle for YtExtractor {
+    async fn extract_mix(&self, video_id: &VideoId) -> Result<YtMix> {
+        let mut query = HashMap::new();
+        query.insert("videoId".into(), video_id.as_str().into());
+        query.insert("playlistId".into(), format!("RD{}", video_id).into());
+
+        let response = self
+            .call_api(YtEndpoint::Next, query, None, None, None, None)
+            .await?;
+
+        self.extract_mix_page(&Value::Object(response.into_iter().collect()), None)
+    }
+
+    async fn extract_mix_continuation(&self, continuation: &str) -> Result<YtMix> {
+        // Mixes have no continuation tokens, the next entries are the ones YouTube lists when watching the last one.
+        let query: HashMap<String, Value> =
+            serde_json::from_str(continuation).map_err(|_| ContinuationExpired {
+                token: continuation.to_string(),
+            })?;
+        let last_video_id = query
+            .get("videoId")
+            .and_then(|v| v.as_str())
+            .map(|v| v.to_string());
+
+        let response = self
+            .call_api(YtEndpoint::Next, query, None, None, None, None)
+            .await?;
+
+        self.extract_mix_page(
+            &Value::Object(response.into_iter().collect()),
+            last_video_id.as_deref(),
+        )
+    }
+
+    fn extract_mix_page(&self, data: &Value, last_video_id: Option<&str>) -> Result<YtMix> {
+        let Some(playlist) = data
+            .get("contents")
+            .and_then(|c| c.get("twoColumnWatchNextResults"))
+            .and_then(|c| c.get("playlist"))
+            .and_then(|p| p.get("playlist"))
+        else {
+            bail!("Failed to extract mix because YouTube didn't return its playlist.")
+        };
+
+        let id = playlist
+            .get("playlistId")
+            .and_then(|id| id.as_str())
+            .ok_or_else(|| anyhow!("Failed to extract mix because YouTube didn't return its ID."))
+            .and_then(PlaylistId::new)?;
+
+        let renderers = self.find_renderers(playlist, "playlistPanelVideoRenderer");
+
+        // The panel starts a few entries before the one being watched.
+        let start = last_video_id
+            .and_then(|last_video_id| {
+                renderers.iter().position(|renderer| {
+                    renderer.get("videoId").and_then(|v| v.as_str()) == Some(last_video_id)
+                })
+            })
+            .map(|position| position + 1)
+            .unwrap_or_default();
+        let renderers = &renderers[start.min(renderers.len())..];
+
+        let entries = renderers
+            .iter()
+            .filter_map(|renderer| {
+                let video_id = renderer
+                    .get("videoId")
+                    .and_then(|v| v.as_str())
+                    .and_then(|v| VideoId::new(v).ok())?;
+                let duration = self
+                    .get_text(renderer, Some(vec![vec!["lengthText"]]), None)
+                    .and_then(|l| parse_duration(&l));
+
+                Some(YtPlaylistEntry {
+                    video_id,
+                    title: self
+                        .get_text(renderer, Some(vec![vec!["title"]]), None)
+                        .unwrap_or_default(),
+                    duration,
+                    thumbnails: self
+                        .extract_thumbnails(renderer.get("thumbnail").unwrap_or_default()),
+                    is_available: true,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let continuation = renderers.last().and_then(|renderer| {
+            let watch_endpoint = renderer
+                .get("navigationEndpoint")
+                .and_then(|ne| ne.get("watchEndpoint"))
+                .unwrap_or_default();
+
+            Some(Continuation::new(
+                json!({
+                    "videoId": renderer.get("videoId")?.as_str()?,
+                    "playlistId": id.as_str(),
+                    "index": watch_endpoint.get("index").and_then(|i| i.as_u64()),
+                    "params": watch_endpoint
+                        .get("params")
+                        .and_then(|p| p.as_str())
+                        .unwrap_or(MIX_PARAMS),
+                })
+                .to_string(),
+            ))
+        });
+
+        Ok(YtMix {
+            title: self
+                .get_text(playlist, Some(vec![vec!["title"]]), None)
+                .unwrap_or_default(),
+            id,
+            entries,
+            continuation,
+        })
+    }
+}
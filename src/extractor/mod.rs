@@ -1,11 +1,31 @@
 mod api;
 mod auth;
+mod browse;
+mod chapters;
 mod client;
-mod cookies;
-mod download;
+mod dash;
+mod heatmap;
 mod json;
-mod player;
+mod music;
+mod storyboards;
 mod token_policy;
 mod ytcfg;
 
+pub mod availability;
+pub mod captions;
+pub mod channel;
+pub mod clip;
+pub mod comments;
+pub mod continuation;
+pub mod cookies;
+pub mod download;
 pub mod extract;
+pub mod hls;
+pub mod mix;
+pub mod oembed;
+pub mod otf;
+pub mod player;
+pub mod playlist;
+pub mod related;
+pub mod search;
+pub mod trending;
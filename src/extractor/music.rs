@@ -0,0 +1,244 @@
+use std::collections::HashMap;
+
+use fancy_regex::Regex;
+use serde_json::Value;
+
+use crate::{
+    extractor::{browse::ExtractorBrowseHandle, extract::YtExtractor, json::ExtractorJsonHandle},
+    yt_interface::YtMusicTrack,
+};
+
+pub trait ExtractorMusicHandle {
+    /// Get the songs used in a video, preferring the description of auto-generated "Art Track" uploads
+    /// over the "Music" section of the watch page, which is missing for videos without any.
+    fn extract_music(
+        &self,
+        initial_data: &HashMap<String, Value>,
+        description: &str,
+    ) -> Vec<YtMusicTrack>;
+    /// Parse the description YouTube generates for "Art Track" uploads, like
+    /// `Provided to YouTube by <label>\n\n<song> · <artist>\n\n<album>\n\n℗ <year> ...\n\nAuto-generated by YouTube.`
+    fn extract_description_music(&self, description: &str) -> Option<YtMusicTrack>;
+    /// Parse the carousel of the "Music" section in the structured description of the watch page.
+    fn extract_music_section(&self, initial_data: &Value) -> Vec<YtMusicTrack>;
+}
+
+impl ExtractorMusicHandle for YtExtractor {
+    fn extract_music(
+        &self,
+        initial_data: &HashMap<String, Value>,
+        description: &str,
+    ) -> Vec<YtMusicTrack> {
+        if let Some(track) = self.extract_description_music(description) {
+            return vec![track];
+        }
+
+        let initial_data = Value::Object(initial_data.clone().into_iter().collect());
+
+        self.extract_music_section(&initial_data)
+    }
+
+    fn extract_description_music(&self, description: &str) -> Option<YtMusicTrack> {
+        let art_track_re = Regex::new(
+            r"(?s)^Provided to YouTube by [^\n]+\n+(?P<song>[^·\n]+?)\s*·\s*(?P<artists>[^\n]+)\n+(?P<album>[^\n]+)(?:.+?℗\s*(?P<year>\d{4})(?!\d))?(?:.+?Released on\s*:\s*(?P<release_year>\d{4})-\d{2}-\d{2})?.*\nAuto-generated by YouTube\.\s*$",
+        )
+        .ok()?;
+        let captures = art_track_re.captures(description.trim()).ok()??;
+
+        Some(YtMusicTrack {
+            song: captures["song"].trim().to_string(),
+            artists: captures["artists"]
+                .split('·')
+                .map(|artist| artist.trim().to_string())
+                .filter(|artist| !artist.is_empty())
+                .collect(),
+            album: Some(captures["album"].trim().to_string()),
+            // The release date is of this release, the ℗ year the one of the recording.
+            release_year: captures
+                .name("release_year")
+                .or_else(|| captures.name("year"))
+                .and_then(|year| year.as_str().parse().ok()),
+            licenses: None,
+        })
+    }
+
+    fn extract_music_section(&self, initial_data: &Value) -> Vec<YtMusicTrack> {
+        let Some(engagement_panels) = initial_data.get("engagementPanels") else {
+            return vec![];
+        };
+
+        let sections =
+            self.find_renderers(engagement_panels, "videoDescriptionMusicSectionRenderer");
+
+        // Older layout, a lockup per song with a row per attribute.
+        let lockup_tracks = sections
+            .iter()
+            .flat_map(|section| self.find_renderers(section, "carouselLockupRenderer"))
+            .filter_map(|lockup| {
+                let mut song = None;
+                let mut artists = Vec::new();
+                let mut album = None;
+                let mut licenses = None;
+
+                for row in self.find_renderers(lockup, "infoRowRenderer") {
+                    let Some(value) = self.get_text(
+                        row,
+                        Some(vec![vec!["defaultMetadata"], vec!["expandedMetadata"]]),
+                        None,
+                    ) else {
+                        continue;
+                    };
+
+                    match self
+                        .get_text(row, Some(vec![vec!["title"]]), None)
+                        .unwrap_or_default()
+                        .to_uppercase()
+                        .as_str()
+                    {
+                        "SONG" => song = Some(value),
+                        "ARTIST" | "ARTISTS" => {
+                            artists = value.split(',').map(|a| a.trim().to_string()).collect()
+                        }
+                        "ALBUM" => album = Some(value),
+                        "LICENSES" => licenses = Some(value),
+                        _ => {}
+                    }
+                }
+
+                // The song of the lockup's video when the rows don't name it.
+                let song = song.or_else(|| {
+                    self.find_renderers(lockup, "videoLockupRenderer")
+                        .first()
+                        .and_then(|video| self.get_text(video, Some(vec![vec!["title"]]), None))
+                })?;
+
+                Some(YtMusicTrack {
+                    song,
+                    artists,
+                    album,
+                    release_year: None,
+                    licenses,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        if !lockup_tracks.is_empty() {
+            return lockup_tracks;
+        }
+
+        // Newer layout, a card per song with the artist and album as its subtitles.
+        sections
+            .iter()
+            .flat_map(|section| self.find_renderers(section, "videoAttributeViewModel"))
+            .filter_map(|card| {
+                let text = |key: &str| {
+                    card.get(key).and_then(|value| match value {
+                        Value::String(text) => Some(text.to_string()),
+                        _ => value
+                            .get("content")
+                            .and_then(|c| c.as_str())
+                            .map(|c| c.to_string()),
+                    })
+                };
+
+                Some(YtMusicTrack {
+                    song: text("title")?,
+                    artists: text("subtitle")
+                        .map(|artists| {
+                            artists
+                                .split(['·', ','])
+                                .map(|artist| artist.trim().to_string())
+                                .filter(|artist| !artist.is_empty())
+                                .collect()
+                        })
+                        .unwrap_or_default(),
+                    album: text("secondarySubtitle"),
+                    release_year: None,
+                    licenses: None,
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use crate::{test_server::test_extractor, tydle::TydleOptions};
+
+    fn music(initial_data: &str, description: &str) -> Vec<YtMusicTrack> {
+        test_extractor(TydleOptions::default(), reqwest::Client::new())
+            .extract_music(&serde_json::from_str(initial_data).unwrap(), description)
+    }
+
+    #[test]
+    fn art_track_description_is_parsed() {
+        assert_eq!(
+            music(
+                "{}",
+                include_str!("../../tests/fixtures/description_art_track.txt")
+            ),
+            [YtMusicTrack {
+                song: "Never Gonna Give You Up (2022 Remaster)".into(),
+                artists: vec!["Rick Astley".into(), "Stock Aitken Waterman".into()],
+                album: Some("Whenever You Need Somebody (2022 Remaster)".into()),
+                release_year: Some(2022),
+                licenses: None,
+            }]
+        );
+    }
+
+    #[test]
+    fn music_section_lockups_are_parsed_per_track() {
+        assert_eq!(
+            music(
+                include_str!("../../tests/fixtures/next_music_lockups.json"),
+                "Taken from A Night At The Opera"
+            ),
+            [
+                YtMusicTrack {
+                    song: "Bohemian Rhapsody".into(),
+                    artists: vec!["Queen".into()],
+                    album: Some("A Night At The Opera (2011 Remaster)".into()),
+                    release_year: None,
+                    licenses: Some("UMG (on behalf of EMI Catalogue); LatinAutorPerf, UMPG Publishing, and 12 Music Rights Societies".into()),
+                },
+                YtMusicTrack {
+                    song: "Under Pressure".into(),
+                    artists: vec!["Queen".into(), "David Bowie".into()],
+                    album: Some("Hot Space".into()),
+                    release_year: None,
+                    licenses: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn music_section_cards_are_parsed() {
+        assert_eq!(
+            music(
+                include_str!("../../tests/fixtures/next_music_cards.json"),
+                ""
+            ),
+            [YtMusicTrack {
+                song: "Never Gonna Give You Up".into(),
+                artists: vec!["Rick Astley".into()],
+                album: Some("Whenever You Need Somebody".into()),
+                release_year: None,
+                licenses: None,
+            }]
+        );
+    }
+
+    #[test]
+    fn videos_without_music_have_no_tracks() {
+        assert!(
+            music(
+                include_str!("../../tests/fixtures/next_chapters.json"),
+                include_str!("../../tests/fixtures/description_chapters.txt")
+            )
+            .is_empty()
+        );
+    }
+}
@@ -0,0 +1,72 @@
+use anyhow::Result;
+use reqwest::StatusCode;
+use serde::Deserialize;
+
+use crate::{
+    error::OEmbedError,
+    extractor::extract::{InfoExtractor, YtExtractor},
+    yt_interface::{OEmbedInfo, VideoId},
+};
+
+/// The JSON oEmbed responds with, only the fields that say something about the video.
+#[derive(Deserialize)]
+struct OEmbedResponse {
+    title: String,
+    author_name: String,
+    author_url: String,
+    thumbnail_url: String,
+    thumbnail_width: u64,
+    thumbnail_height: u64,
+    html: String,
+}
+
+pub trait ExtractorOEmbedHandle {
+    /// Get the basic metadata of a video from the oEmbed endpoint, without the player or any cache.
+    async fn extract_oembed(&self, video_id: &VideoId) -> Result<OEmbedInfo>;
+}
+
+impl ExtractorOEmbedHandle for YtExtractor {
+    async fn extract_oembed(&self, video_id: &VideoId) -> Result<OEmbedInfo> {
+        let scheme = self.http_scheme();
+        let watch_url = format!("{}://www.youtube.com/watch?v={}", scheme, video_id);
+
+        let mut request_builder = self
+            .http_client
+            .get(format!("{}://www.youtube.com/oembed", scheme))
+            .query(&[("url", watch_url.as_str()), ("format", "json")]);
+
+        if !self.tydle_options.source_address.is_empty() {
+            request_builder = request_builder.header(
+                "X-Forwarded-For",
+                self.tydle_options.source_address.as_str(),
+            );
+        }
+
+        let response = request_builder.send().await?;
+
+        match response.status() {
+            StatusCode::NOT_FOUND => Err(OEmbedError::NotFound {
+                video_id: video_id.to_string(),
+            }
+            .into()),
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => Err(OEmbedError::NotEmbeddable {
+                video_id: video_id.to_string(),
+            }
+            .into()),
+            _ => {
+                let oembed: OEmbedResponse = response.error_for_status()?.json().await?;
+
+                Ok(OEmbedInfo {
+                    video_id: video_id.clone(),
+                    title: oembed.title,
+                    author_name: oembed.author_name,
+                    author_url: oembed.author_url,
+                    thumbnail_url: oembed.thumbnail_url,
+                    thumbnail_width: oembed.thumbnail_width,
+                    thumbnail_height: oembed.thumbnail_height,
+                    html: oembed.html,
+                })
+            }
+        }
+    }
+}
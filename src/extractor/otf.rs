@@ -0,0 +1,148 @@
+use anyhow::{Context, Result};
+
+use crate::{extractor::extract::YtExtractor, yt_interface::StreamUrl};
+
+pub trait ExtractorOtfHandle {
+    /// Download the first segment of an OTF stream and list the URLs of all of its segments in their order.
+    async fn fetch_otf_segments(&self, stream_url: &str) -> Result<Vec<String>>;
+    /// Find the `Segment-Count: <n>` header YouTube puts in the first segment of an OTF stream.
+    fn parse_otf_segment_count(&self, first_segment: &[u8]) -> Option<u64>;
+}
+
+impl ExtractorOtfHandle for YtExtractor {
+    async fn fetch_otf_segments(&self, stream_url: &str) -> Result<Vec<String>> {
+        let stream_url: StreamUrl = stream_url.parse()?;
+        let first_segment_url = stream_url.clone().with_segment(0).to_string();
+
+        #[cfg(feature = "logging")]
+        log::info!(
+            "Downloading the first segment of OTF stream {}",
+            first_segment_url
+        );
+
+        let first_segment = async {
+            self.http_client
+                .get(&first_segment_url)
+                .send()
+                .await?
+                .error_for_status()?
+                .bytes()
+                .await
+        }
+        .await
+        .context("Failed to download segment 0 of the OTF stream.")?;
+
+        let segment_count = self
+            .parse_otf_segment_count(&first_segment)
+            .context("Segment 0 of the OTF stream has no `Segment-Count`.")?;
+
+        let mut segment_urls = vec![first_segment_url];
+        segment_urls.extend(
+            (1..=segment_count).map(|segment| stream_url.clone().with_segment(segment).to_string()),
+        );
+
+        Ok(segment_urls)
+    }
+
+    fn parse_otf_segment_count(&self, first_segment: &[u8]) -> Option<u64> {
+        const HEADER: &[u8] = b"Segment-Count: ";
+
+        // The headers are plain text among the binary media data.
+        let start = first_segment
+            .windows(HEADER.len())
+            .position(|window| window == HEADER)?
+            + HEADER.len();
+        let digits: String = first_segment[start..]
+            .iter()
+            .take_while(|byte| byte.is_ascii_digit())
+            .map(|byte| *byte as char)
+            .collect();
+
+        digits.parse().ok()
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use crate::{
+        test_server::{TestResponse, TestServer, test_extractor},
+        tydle::TydleOptions,
+    };
+
+    /// Segment 0 of an OTF stream of 43 more segments, with its headers in a `uuid` box.
+    const FIRST_SEGMENT: &[u8] = include_bytes!("../../tests/fixtures/otf_segment_0.bin");
+
+    fn extractor() -> YtExtractor {
+        test_extractor(TydleOptions::default(), reqwest::Client::new())
+    }
+
+    #[test]
+    fn segment_count_is_read_from_the_first_segment() {
+        assert_eq!(extractor().parse_otf_segment_count(FIRST_SEGMENT), Some(43));
+    }
+
+    #[test]
+    fn segment_count_is_missing_without_the_header() {
+        let extractor = extractor();
+        let without_count: Vec<u8> = FIRST_SEGMENT
+            .windows(b"Segment-Count".len())
+            .position(|window| window == b"Segment-Count")
+            .map(|start| [&FIRST_SEGMENT[..start], &FIRST_SEGMENT[start + 1..]].concat())
+            .unwrap();
+
+        assert_eq!(extractor.parse_otf_segment_count(&without_count), None);
+        assert_eq!(
+            extractor.parse_otf_segment_count(b"Segment-Count: \r\n"),
+            None
+        );
+        assert_eq!(extractor.parse_otf_segment_count(b""), None);
+    }
+
+    #[tokio::test]
+    async fn segment_urls_are_listed_in_order_after_segment_zero() {
+        let server = TestServer::start(|request| {
+            if request.path.ends_with("&sq=0") {
+                TestResponse::ok(FIRST_SEGMENT)
+            } else {
+                TestResponse::status(404)
+            }
+        })
+        .await;
+        let stream_url = server.url("/videoplayback?itag=137&otf=1&sig=AOq0QJ8w%3D%3D");
+
+        let segment_urls = extractor().fetch_otf_segments(&stream_url).await.unwrap();
+
+        assert_eq!(segment_urls.len(), 44);
+        for (sq, segment_url) in segment_urls.iter().enumerate() {
+            assert_eq!(*segment_url, format!("{}&sq={}", stream_url, sq));
+        }
+        // Only segment 0 is downloaded, the rest is left to the downloader.
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn failing_first_segment_is_named_in_the_error() {
+        let server = TestServer::start(|request| {
+            if request.path.starts_with("/gone") {
+                TestResponse::status(403)
+            } else {
+                TestResponse::ok("no headers here")
+            }
+        })
+        .await;
+        let extractor = extractor();
+
+        let error = extractor
+            .fetch_otf_segments(&server.url("/gone?itag=137"))
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("segment 0"), "{}", error);
+
+        let error = extractor
+            .fetch_otf_segments(&server.url("/videoplayback?itag=137"))
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("`Segment-Count`"), "{}", error);
+    }
+}
@@ -1,11 +1,16 @@
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
 
 use anyhow::{Result, anyhow};
 use fancy_regex::Regex;
 use serde_json::{Value, json};
+use url::Url;
 
 use crate::{
-    cache::{CacheAccess, PlayerCacheHandle},
+    cache::PlayerCacheHandle,
+    error::{ClientsFailed, DecipherError, VideoUnavailable},
     extractor::{
         api::ExtractorApiHandle,
         auth::ExtractorAuthHandle,
@@ -14,21 +19,42 @@ use crate::{
         json::ExtractorJsonHandle,
         ytcfg::ExtractorYtCfgHandle,
     },
+    utils::now,
     yt_interface::{
-        PLAYER_JS_MAIN_VARIANT, PlayerIdentifier, VideoId, YT_URL, YtClient, YtEndpoint,
+        PLAYER_JS_MAIN_VARIANT, PlayerIdentifier, PlayerInfo, VideoId, YT_URL, YtClient, YtEndpoint,
     },
 };
 
 pub trait ExtractorPlayerHandle {
     fn is_unplayable(&self, player_response: &HashMap<String, Value>) -> bool;
     fn is_age_gated(&self, player_response: &HashMap<String, Value>) -> bool;
+    /// Human readable reason YouTube gave for not playing the video.
+    fn get_playability_reason(&self, player_response: &HashMap<String, Value>) -> Option<String>;
+    /// Whether the video is a premiere or live stream that hasn't started yet.
+    fn is_upcoming(&self, player_response: &HashMap<String, Value>) -> bool;
+    /// When an upcoming premiere or live stream is scheduled to start, in seconds since the unix epoch.
+    fn get_scheduled_start_time(&self, player_response: &HashMap<String, Value>) -> Option<u64>;
+    /// Why a client's player response has no streams, if it has none.
+    fn get_client_failure(&self, player_response: &HashMap<String, Value>) -> Option<String>;
+    /// Why the video can never be played, if the player response says so.
+    fn get_unavailability(
+        &self,
+        player_response: &HashMap<String, Value>,
+    ) -> Option<VideoUnavailable>;
     fn generate_player_context(&self, sts: Option<i64>) -> HashMap<String, Value>;
-    async fn load_player(&self, video_id: &VideoId, player_url: String) -> Result<String>;
+    async fn load_player(&self, video_id: Option<&VideoId>, player_url: String) -> Result<String>;
+    /// Download the player at `player_url` and cache it along with its signature timestamp.
+    async fn warm_up_player(&self, player_url: String) -> Result<()>;
+    /// Remember `player_url` as the player of `video_id`.
+    fn record_player_info(&self, video_id: &VideoId, player_url: String)
+    -> Result<Arc<PlayerInfo>>;
+    /// Get the player YouTube serves for `video_id`, looking it up on the watch page unless it's cached.
+    async fn get_player_info(&self, video_id: &VideoId) -> Result<Arc<PlayerInfo>>;
     /// Extract `signatureTimestamp` (sts)
     /// Required to tell API what sig/player version is in use.
     async fn extract_signature_timestamp(
         &self,
-        video_id: &VideoId,
+        video_id: Option<&VideoId>,
         player_url: String,
         ytcfg: &HashMap<String, Value>,
     ) -> Result<Option<i64>>;
@@ -57,7 +83,7 @@ pub trait ExtractorPlayerHandle {
         webpage: &String,
         webpage_client: &YtClient,
         webpage_ytcfg: &HashMap<String, Value>,
-    ) -> Result<(Vec<HashMap<String, Value>>, String)>;
+    ) -> Result<(Vec<HashMap<String, Value>>, Vec<YtClient>, String)>;
 }
 
 impl ExtractorPlayerHandle for YtExtractor {
@@ -125,19 +151,17 @@ impl ExtractorPlayerHandle for YtExtractor {
             return true;
         }
 
-        let reasons_array: Vec<Value> = player_response
+        let Some(playability_status) = player_response
             .get("playabilityStatus")
             .and_then(|ps| ps.as_object())
-            .and_then(|o| o.get("status"))
-            .and_then(|s| s.as_object())
-            .and_then(|s| s.get("reason"))
-            .and_then(|r| r.as_array())
-            .cloned()
-            .unwrap_or_else(|| vec![]);
-
-        let reasons: Vec<&str> = reasons_array
+        else {
+            return false;
+        };
+
+        let reasons: Vec<String> = ["status", "reason"]
             .iter()
-            .map(|x| x.as_str().unwrap_or_default())
+            .filter_map(|key| playability_status.get(*key).and_then(|r| r.as_str()))
+            .map(|r| r.to_lowercase())
             .collect();
 
         const AGE_GATE_REASONS: [&str; 5] = [
@@ -159,6 +183,167 @@ impl ExtractorPlayerHandle for YtExtractor {
         false
     }
 
+    fn get_playability_reason(&self, player_response: &HashMap<String, Value>) -> Option<String> {
+        player_response
+            .get("playabilityStatus")
+            .and_then(|ps| ps.get("reason"))
+            .and_then(|r| r.as_str())
+            .map(|r| r.to_string())
+    }
+
+    fn is_upcoming(&self, player_response: &HashMap<String, Value>) -> bool {
+        player_response
+            .get("playabilityStatus")
+            .and_then(|ps| ps.get("status"))
+            .and_then(|s| s.as_str())
+            == Some("LIVE_STREAM_OFFLINE")
+    }
+
+    fn get_scheduled_start_time(&self, player_response: &HashMap<String, Value>) -> Option<u64> {
+        // An epoch timestamp as a string, like "1767225600".
+        player_response
+            .get("playabilityStatus")
+            .and_then(|ps| ps.get("liveStreamability"))
+            .and_then(|ls| ls.get("liveStreamabilityRenderer"))
+            .and_then(|lsr| lsr.get("offlineSlate"))
+            .and_then(|os| os.get("liveStreamOfflineSlateRenderer"))
+            .and_then(|lsosr| lsosr.get("scheduledStartTime"))
+            .and_then(|sst| match sst {
+                Value::String(sst) => sst.parse().ok(),
+                _ => sst.as_u64(),
+            })
+    }
+
+    fn get_client_failure(&self, player_response: &HashMap<String, Value>) -> Option<String> {
+        let status = player_response
+            .get("playabilityStatus")
+            .and_then(|ps| ps.get("status"))
+            .and_then(|s| s.as_str())
+            .unwrap_or("OK");
+
+        if status != "OK" {
+            return Some(format!(
+                "{} ({})",
+                status,
+                self.get_playability_reason(player_response)
+                    .unwrap_or_else(|| "no reason given".into())
+            ));
+        }
+
+        match player_response.get("streamingData") {
+            Some(streaming_data) if !streaming_data.is_null() => None,
+            _ => Some("Returned no streaming data.".into()),
+        }
+    }
+
+    fn get_unavailability(
+        &self,
+        player_response: &HashMap<String, Value>,
+    ) -> Option<VideoUnavailable> {
+        let playability_status = player_response.get("playabilityStatus")?;
+        let status = playability_status.get("status").and_then(|s| s.as_str())?;
+
+        // Upcoming live streams can't be played yet either, but aren't unavailable.
+        if status == "OK" || self.is_upcoming(player_response) || self.is_age_gated(player_response)
+        {
+            return None;
+        }
+
+        let mut reason = self.get_playability_reason(player_response)?;
+
+        // Like "Video unavailable", with the actual reason in the subreason.
+        if let Some(subreason) = playability_status
+            .get("errorScreen")
+            .and_then(|es| es.get("playerErrorMessageRenderer"))
+            .and_then(|renderer| self.get_text(renderer, Some(vec![vec!["subreason"]]), None))
+            .filter(|subreason| !subreason.is_empty() && *subreason != reason)
+        {
+            reason = format!("{}: {}", reason, subreason);
+        }
+
+        let lowercase_reason = reason.to_lowercase();
+
+        // Like "Sign in to confirm you're not a bot", with either apostrophe.
+        if lowercase_reason.contains("not a bot") {
+            return Some(VideoUnavailable::BotCheck { reason });
+        }
+
+        if lowercase_reason.contains("copyright") {
+            let claimant = Regex::new(r"content from (.+?),? who has blocked it")
+                .ok()
+                .and_then(|re| re.captures(&reason).ok().flatten())
+                .and_then(|caps| caps.get(1))
+                .map(|claimant| claimant.as_str().to_string());
+
+            return Some(VideoUnavailable::Copyright { reason, claimant });
+        }
+
+        if lowercase_reason.contains("in your country") {
+            let allowed_countries = player_response
+                .get("microformat")
+                .and_then(|mf| mf.get("playerMicroformatRenderer"))
+                .and_then(|pmr| pmr.get("availableCountries"))
+                .and_then(|countries| countries.as_array())
+                .map(|countries| {
+                    countries
+                        .iter()
+                        .filter_map(|country| country.as_str())
+                        .map(|country| country.to_string())
+                        .collect()
+                })
+                .unwrap_or_default();
+
+            return Some(VideoUnavailable::GeoBlocked {
+                reason,
+                allowed_countries,
+            });
+        }
+
+        const MEMBERS_ONLY_REASONS: [&str; 3] = [
+            "members-only",
+            "join this channel",
+            "available to this channel's members",
+        ];
+
+        if MEMBERS_ONLY_REASONS
+            .iter()
+            .any(|expected| lowercase_reason.contains(expected))
+        {
+            return Some(VideoUnavailable::MembersOnly { reason });
+        }
+
+        if lowercase_reason.contains("account associated with this video has been terminated") {
+            return Some(VideoUnavailable::AccountTerminated { reason });
+        }
+
+        if lowercase_reason.contains("private video")
+            || lowercase_reason.contains("video is private")
+        {
+            return Some(VideoUnavailable::Private { reason });
+        }
+
+        const DELETED_REASONS: [&str; 4] = [
+            "removed by the uploader",
+            "no longer available",
+            "video unavailable",
+            "video is unavailable",
+        ];
+
+        // Geo-restricted videos are reported as "UNPLAYABLE", a deleted video is an "ERROR".
+        if status == "ERROR"
+            && DELETED_REASONS
+                .iter()
+                .any(|expected| lowercase_reason.contains(expected))
+        {
+            return Some(VideoUnavailable::Deleted { reason });
+        }
+
+        Some(VideoUnavailable::Unknown {
+            status: status.to_string(),
+            reason,
+        })
+    }
+
     fn is_unplayable(&self, player_response: &HashMap<String, Value>) -> bool {
         if let Some(status) = player_response
             .get("playabilityStatus")
@@ -171,27 +356,95 @@ impl ExtractorPlayerHandle for YtExtractor {
         false
     }
 
-    async fn load_player(&self, video_id: &VideoId, player_url: String) -> Result<String> {
+    async fn load_player(&self, video_id: Option<&VideoId>, player_url: String) -> Result<String> {
         let player_js_key = self.player_cache.player_js_cache_key(&player_url)?;
 
-        if self.code_cache.contains(&player_js_key)? {
-            return Ok(self.code_cache.get(&player_js_key)?.unwrap().clone());
+        if let Some(code) = self.code_cache.get(&player_js_key)? {
+            return Ok(code);
         }
 
-        let code = self
-            .download_webpage(&player_url, &YtClient::Web, video_id)
+        self.player_fetches
+            .run(&player_js_key, || async {
+                // Another fetch might have finished between the lookup above and this one starting.
+                if let Some(code) = self.code_cache.get(&player_js_key)? {
+                    return Ok(code);
+                }
+
+                let code = self
+                    .download_initial_webpage(Url::parse(&player_url)?, &YtClient::Web, video_id)
+                    .await
+                    .map_err(|e| {
+                        let status = e
+                            .downcast_ref::<reqwest::Error>()
+                            .and_then(|e| e.status())
+                            .map(|status| status.as_u16());
+
+                        e.context(DecipherError::PlayerFetchFailed {
+                            url: player_url.clone(),
+                            status,
+                        })
+                    })?;
+
+                if !code.is_empty() {
+                    self.code_cache.insert(&player_js_key, code.clone())?;
+                }
+
+                Ok(code)
+            })
+            .await
+    }
+
+    async fn warm_up_player(&self, player_url: String) -> Result<()> {
+        self.extract_signature_timestamp(None, player_url, &HashMap::new())
             .await?;
 
-        if !code.is_empty() {
-            self.code_cache.add(player_js_key, code.clone())?;
+        Ok(())
+    }
+
+    fn record_player_info(
+        &self,
+        video_id: &VideoId,
+        player_url: String,
+    ) -> Result<Arc<PlayerInfo>> {
+        let player_info = PlayerInfo {
+            version: self.player_cache.extract_player_info(&player_url)?,
+            url: player_url,
+            fetched_at: now(),
+        };
+
+        self.player_infos.insert(video_id.clone(), player_info)
+    }
+
+    async fn get_player_info(&self, video_id: &VideoId) -> Result<Arc<PlayerInfo>> {
+        if let Some(player_info) = self.player_infos.get::<PlayerInfo>(video_id)? {
+            return Ok(player_info);
         }
 
-        Ok(code)
+        let webpage_url = format!("{}://www.youtube.com/watch", self.http_scheme());
+        let webpage = self
+            .download_webpage(&webpage_url, &YtClient::Web, video_id)
+            .await?;
+        let webpage_ytcfg = self.extract_ytcfg(webpage)?;
+
+        let player_url = match self.get_player_url(&[&webpage_ytcfg]) {
+            Ok(player_url) if !player_url.is_empty() => player_url,
+            _ => self
+                .download_player_url(Some(video_id))
+                .await?
+                .ok_or_else(|| {
+                    anyhow!(
+                        "Failed to find the URL of the player for {}.",
+                        video_id.as_str()
+                    )
+                })?,
+        };
+
+        self.record_player_info(video_id, player_url)
     }
 
     async fn extract_signature_timestamp(
         &self,
-        video_id: &VideoId,
+        video_id: Option<&VideoId>,
         player_url: String,
         ytcfg: &HashMap<String, Value>,
     ) -> Result<Option<i64>> {
@@ -206,7 +459,7 @@ impl ExtractorPlayerHandle for YtExtractor {
             return Ok(Some(sts.parse::<i64>()?));
         }
 
-        let code = self.load_player(video_id, player_url).await?;
+        let code = self.load_player(video_id, player_url.clone()).await?;
 
         let re = Regex::new(r"(?:signatureTimestamp|sts)\s*:\s*(?P<sts>[0-9]{5})")?;
         let code_caps = re.captures(&code)?;
@@ -221,6 +474,9 @@ impl ExtractorPlayerHandle for YtExtractor {
             return Ok(None);
         };
 
+        self.player_cache
+            .store_player_data_to_cache("sts", player_url, sts.to_string())?;
+
         Ok(Some(sts))
     }
 
@@ -277,7 +533,7 @@ impl ExtractorPlayerHandle for YtExtractor {
 
         let sts = self
             .extract_signature_timestamp(
-                video_id,
+                Some(video_id),
                 player_url.clone().unwrap_or_default(),
                 player_ytcfg,
             )
@@ -296,6 +552,13 @@ impl ExtractorPlayerHandle for YtExtractor {
 
         yt_query.extend(player_context);
 
+        if let Some(po_token) = &self.tydle_options.po_token {
+            yt_query.insert(
+                "serviceIntegrityDimensions".into(),
+                json!({ "poToken": po_token }),
+            );
+        }
+
         let player_response = self
             .call_api(
                 YtEndpoint::Player,
@@ -317,9 +580,10 @@ impl ExtractorPlayerHandle for YtExtractor {
         webpage: &String,
         webpage_client: &YtClient,
         webpage_ytcfg: &HashMap<String, Value>,
-    ) -> Result<(Vec<HashMap<String, Value>>, String)> {
+    ) -> Result<(Vec<HashMap<String, Value>>, Vec<YtClient>, String)> {
         let initial_pr = self.search_json(r"ytInitialPlayerResponse\s*=", &webpage, None, None)?;
         let mut prs: Vec<HashMap<String, Value>> = vec![];
+        let mut pr_clients: Vec<YtClient> = vec![];
 
         let mut init_pr_copy = initial_pr.clone();
         init_pr_copy.insert("streamingData".into(), Value::Null);
@@ -333,6 +597,7 @@ impl ExtractorPlayerHandle for YtExtractor {
             // Android player_response does not have microFormats which are needed for extraction of some data.
             // So we return the initial_pr with formats stripped out even if not requested by the user.
             prs.push(init_pr_copy);
+            pr_clients.push(*webpage_client);
         }
 
         let mut actual_clients = clients.clone();
@@ -340,11 +605,19 @@ impl ExtractorPlayerHandle for YtExtractor {
 
         let mut tried_iframe_fallback = false;
         let mut player_url: Option<String> = None;
-        let mut visitor_data: Option<String> = None;
+        let mut visitor_data = self.tydle_options.visitor_data.clone();
         let mut data_sync_id: Option<String> = None;
+        let mut tried_clients: HashSet<YtClient> = HashSet::new();
+        let mut age_gate_reason: Option<String> = None;
+        let mut unavailability: Option<VideoUnavailable> = None;
+        let mut client_failures: Vec<(YtClient, String)> = Vec::new();
+
+        while let Some(popped_client) = actual_clients.pop() {
+            // Fallback clients are pushed again every time an age-gated response is received.
+            if !tried_clients.insert(popped_client) {
+                continue;
+            }
 
-        while !actual_clients.is_empty() {
-            let popped_client = actual_clients.pop().unwrap();
             let client = popped_client.as_str();
             let variant = popped_client.get_variant();
 
@@ -368,13 +641,14 @@ impl ExtractorPlayerHandle for YtExtractor {
                 .require_js_player;
 
             if player_url.is_none() && !tried_iframe_fallback && require_js_player {
-                player_url = self.download_player_url(video_id).await?;
+                player_url = self.download_player_url(Some(video_id)).await?;
                 tried_iframe_fallback = true;
             }
 
             if visitor_data.is_none() {
-                visitor_data =
-                    self.select_visitor_data(&[webpage_ytcfg, &initial_pr, player_ytcfg]);
+                visitor_data = self
+                    .select_visitor_data(&[webpage_ytcfg, &initial_pr, player_ytcfg])
+                    .or_else(|| self.get_session_visitor_data());
             }
 
             if data_sync_id.is_none() {
@@ -415,7 +689,7 @@ impl ExtractorPlayerHandle for YtExtractor {
                 .collect(),
             );
 
-            let player_response = self
+            let player_response = match self
                 .extract_player_response(
                     &popped_client,
                     video_id,
@@ -430,7 +704,31 @@ impl ExtractorPlayerHandle for YtExtractor {
                     &visitor_data,
                     &data_sync_id,
                 )
-                .await?;
+                .await
+            {
+                Ok(player_response) => player_response,
+                Err(e) => {
+                    #[cfg(feature = "logging")]
+                    log::warn!(
+                        "Skipped {}. Requesting the player response failed: {}",
+                        client,
+                        e
+                    );
+
+                    client_failures.push((popped_client, e.to_string()));
+                    continue;
+                }
+            };
+
+            // Responses for unavailable videos come without `videoDetails`, so check before discarding them as invalid.
+            // Keep the first reason that's more specific than an unknown one.
+            if unavailability
+                .as_ref()
+                .is_none_or(|u| matches!(u, VideoUnavailable::Unknown { .. }))
+                && let Some(new_unavailability) = self.get_unavailability(&player_response)
+            {
+                unavailability = Some(new_unavailability);
+            }
 
             if let Some(_invalid_pr_id) = self.invalid_player_response(&player_response, video_id) {
                 #[cfg(feature = "logging")]
@@ -440,11 +738,30 @@ impl ExtractorPlayerHandle for YtExtractor {
                     video_id.as_str(),
                     _invalid_pr_id
                 );
+                client_failures.push((
+                    popped_client,
+                    "Returned the player response of another video.".into(),
+                ));
                 continue;
             }
 
             if !player_response.is_empty() {
                 prs.push(player_response.clone());
+                pr_clients.push(popped_client);
+            }
+
+            match self.get_client_failure(&player_response) {
+                Some(failure) => client_failures.push((popped_client, failure)),
+                // A preferred client that works is all that's needed, the others are only fallbacks for it.
+                None if self.tydle_options.client.is_some() => break,
+                None => {}
+            }
+
+            if self.is_age_gated(&player_response) && age_gate_reason.is_none() {
+                age_gate_reason = Some(
+                    self.get_playability_reason(&player_response)
+                        .unwrap_or_else(|| "Sign in to confirm your age.".into()),
+                );
             }
 
             // web_embedded can work around age-gate and age-verification for some embeddable videos.
@@ -452,13 +769,18 @@ impl ExtractorPlayerHandle for YtExtractor {
                 actual_clients.push(YtClient::WebEmbedded);
             }
 
-            // Unauthenticated users will only get web_embedded client formats if age-gated.
+            // Unauthenticated users will only get embedded client formats if age-gated.
             if self.is_age_gated(&player_response) && !self.is_authenticated()? {
                 #[cfg(feature = "logging")]
                 log::warn!(
                     "Skipping client \"{}\" since the video is age-restricted and unavailable without authentication.",
                     client
                 );
+
+                // The TV embedded player still serves many age-restricted videos without an account.
+                if !self.tydle_options.disable_age_gate_bypass {
+                    actual_clients.push(YtClient::TvEmbedded);
+                }
                 continue;
             }
 
@@ -479,10 +801,124 @@ impl ExtractorPlayerHandle for YtExtractor {
             }
         }
 
+        let has_streaming_data = prs
+            .iter()
+            .any(|pr| pr.get("streamingData").is_some_and(|sd| !sd.is_null()));
+
+        if let (false, Some(reason)) = (has_streaming_data, age_gate_reason)
+            && !self.is_authenticated()?
+        {
+            return Err(VideoUnavailable::AgeRestricted { reason }.into());
+        }
+
+        if let (false, Some(unavailability)) = (has_streaming_data, unavailability) {
+            // The failures of every client say more than the unknown reason of one of them.
+            if !matches!(unavailability, VideoUnavailable::Unknown { .. })
+                || client_failures.len() <= 1
+            {
+                return Err(unavailability.into());
+            }
+        }
+
+        // Upcoming live streams have no streams yet, but their details are still extractable.
+        let is_upcoming = prs.iter().any(|pr| self.is_upcoming(pr));
+
+        if !has_streaming_data && !is_upcoming && !client_failures.is_empty() {
+            return Err(ClientsFailed {
+                failures: client_failures,
+            }
+            .into());
+        }
+
         if prs.is_empty() {
             return Err(anyhow!("Failed to extract any player response."));
         }
 
-        Ok((prs, player_url.unwrap_or_default()))
+        Ok((prs, pr_clients, player_url.unwrap_or_default()))
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use crate::{test_server::test_extractor, tydle::TydleOptions};
+
+    fn unavailability(status: &str) -> Option<VideoUnavailable> {
+        let mut fixtures: HashMap<String, HashMap<String, Value>> = serde_json::from_str(
+            include_str!("../../tests/fixtures/playability_statuses.json"),
+        )
+        .unwrap();
+        let player_response = fixtures.remove(status).unwrap();
+
+        test_extractor(TydleOptions::default(), reqwest::Client::new())
+            .get_unavailability(&player_response)
+    }
+
+    #[test]
+    fn playability_statuses_map_to_their_variant() {
+        let cases = [
+            (
+                "private",
+                VideoUnavailable::Private {
+                    reason: "Private video".into(),
+                },
+            ),
+            (
+                "deleted",
+                VideoUnavailable::Deleted {
+                    reason: "Video unavailable: This video has been removed by the uploader".into(),
+                },
+            ),
+            (
+                "account_terminated",
+                VideoUnavailable::AccountTerminated {
+                    reason: "This video is no longer available because the YouTube account associated with this video has been terminated.".into(),
+                },
+            ),
+            (
+                "geo_blocked",
+                VideoUnavailable::GeoBlocked {
+                    reason: "Video unavailable: The uploader has not made this video available in your country".into(),
+                    allowed_countries: vec!["DE".into(), "AT".into(), "CH".into()],
+                },
+            ),
+            (
+                "members_only",
+                VideoUnavailable::MembersOnly {
+                    reason: "Join this channel to get access to members-only content like this video, and other exclusive perks.".into(),
+                },
+            ),
+            (
+                "copyright",
+                VideoUnavailable::Copyright {
+                    reason: "Video unavailable: This video contains content from SME, who has blocked it on copyright grounds.".into(),
+                    claimant: Some("SME".into()),
+                },
+            ),
+            (
+                "bot_check",
+                VideoUnavailable::BotCheck {
+                    reason: "Sign in to confirm you’re not a bot: This helps protect our community. Learn more".into(),
+                },
+            ),
+            (
+                "unknown",
+                VideoUnavailable::Unknown {
+                    status: "UNPLAYABLE".into(),
+                    reason: "Playback on other websites has been disabled by the video owner.".into(),
+                },
+            ),
+        ];
+
+        for (status, expected) in cases {
+            assert_eq!(unavailability(status), Some(expected), "{}", status);
+        }
+    }
+
+    #[test]
+    fn playable_and_age_gated_videos_are_not_unavailable() {
+        // Age-gated videos can still be extracted with cookies, see `extract_player_responses`.
+        assert_eq!(unavailability("age_restricted"), None);
+        assert_eq!(unavailability("playable"), None);
     }
 }
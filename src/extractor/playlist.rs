@@ -0,0 +1,204 @@
+use anyhow::{Result, bail};
+use serde_json::Value;
+
+use crate::{
+    extractor::{browse::ExtractorBrowseHandle, extract::YtExtractor, json::ExtractorJsonHandle},
+    utils::{parse_count, parse_duration},
+    yt_interface::{
+        PlaylistId, VideoId, YtEndpoint, YtPlaylist, YtPlaylistEntry, YtPlaylistVisibility,
+    },
+};
+
+/// Makes YouTube list the deleted and private videos of a playlist instead of hiding them.
+const SHOW_UNAVAILABLE_VIDEOS_PARAMS: &str = "wgYCCAA%3D";
+
+pub trait ExtractorPlaylistHandle {
+    async fn extract_playlist(&self, playlist_id: &PlaylistId) -> Result<YtPlaylist>;
+    /// Parse the videos of a page of a playlist.
+    fn extract_playlist_entries(&self, data: &Value) -> Vec<YtPlaylistEntry>;
+    fn extract_playlist_visibility(&self, data: &Value) -> YtPlaylistVisibility;
+}
+
+impl ExtractorPlaylistHandle for YtExtractor {
+    async fn extract_playlist(&self, playlist_id: &PlaylistId) -> Result<YtPlaylist> {
+        if playlist_id.as_str().starts_with("RD") {
+            bail!(
+                "{} is a mix, which YouTube generates for every request. Use `get_mix` with the video it's generated from instead.",
+                playlist_id
+            )
+        }
+
+        let data = self
+            .browse(
+                &format!("VL{}", playlist_id.as_str()),
+                Some(SHOW_UNAVAILABLE_VIDEOS_PARAMS),
+            )
+            .await?;
+
+        // Missing and private playlists come back with an alert instead of contents.
+        if let Some(alert) = self
+            .find_renderers(&data, "alertRenderer")
+            .into_iter()
+            .find(|alert| alert.get("type").and_then(|t| t.as_str()) == Some("ERROR"))
+        {
+            bail!(
+                "Failed to extract playlist {}: {}",
+                playlist_id,
+                self.get_text(alert, Some(vec![vec!["text"]]), None)
+                    .unwrap_or_default()
+            )
+        }
+
+        let metadata = data
+            .get("metadata")
+            .and_then(|m| m.get("playlistMetadataRenderer"))
+            .unwrap_or_default();
+        let header = self
+            .find_renderers(&data, "playlistHeaderRenderer")
+            .into_iter()
+            .next()
+            .unwrap_or_default();
+        let sidebar = self
+            .find_renderers(&data, "playlistSidebarPrimaryInfoRenderer")
+            .into_iter()
+            .next()
+            .unwrap_or_default();
+
+        let Some(title) = self
+            .get_text(metadata, Some(vec![vec!["title"]]), None)
+            .or_else(|| self.get_text(header, Some(vec![vec!["title"]]), None))
+            .or_else(|| self.get_text(sidebar, Some(vec![vec!["title"]]), None))
+        else {
+            bail!(
+                "Failed to extract playlist {} because YouTube didn't return its title.",
+                playlist_id
+            )
+        };
+
+        let author = self
+            .get_text(header, Some(vec![vec!["ownerText"]]), None)
+            .or_else(|| {
+                self.find_renderers(&data, "videoOwnerRenderer")
+                    .into_iter()
+                    .find_map(|owner| self.get_text(owner, Some(vec![vec!["title"]]), None))
+            });
+
+        let description = metadata
+            .get("description")
+            .and_then(|d| d.as_str())
+            .map(|d| d.to_string())
+            .or_else(|| self.get_text(header, Some(vec![vec!["descriptionText"]]), None))
+            .filter(|d| !d.is_empty());
+
+        let video_count = self
+            .get_text(header, Some(vec![vec!["numVideosText"]]), None)
+            .or_else(|| {
+                sidebar
+                    .get("stats")
+                    .and_then(|s| s.get(0))
+                    .and_then(|s| self.get_text(s, None, None))
+            })
+            .and_then(|count| parse_count(&count));
+
+        let mut entries = self.extract_playlist_entries(&data);
+        let mut continuation = self.get_continuation_token(&data);
+        let mut pages = 1;
+
+        while let Some(token) = continuation {
+            if self
+                .tydle_options
+                .max_playlist_pages
+                .is_some_and(|max_pages| pages >= max_pages)
+            {
+                #[cfg(feature = "logging")]
+                log::info!(
+                    "{}: Stopping after {} pages because of `max_playlist_pages`.",
+                    playlist_id,
+                    pages
+                );
+                break;
+            }
+
+            #[cfg(feature = "logging")]
+            log::info!("{}: Downloading page {}", playlist_id, pages + 1);
+
+            let page = self.browse_continuation(YtEndpoint::Browse, &token).await?;
+            entries.extend(self.extract_playlist_entries(&page));
+
+            // Guards against YouTube handing out the same page again, which would never end.
+            continuation = self
+                .get_continuation_token(&page)
+                .filter(|next_token| *next_token != token);
+            pages += 1;
+        }
+
+        Ok(YtPlaylist {
+            id: playlist_id.clone(),
+            title,
+            author,
+            description,
+            visibility: self.extract_playlist_visibility(&data),
+            video_count,
+            entries,
+        })
+    }
+
+    fn extract_playlist_entries(&self, data: &Value) -> Vec<YtPlaylistEntry> {
+        self.find_renderers(data, "playlistVideoRenderer")
+            .into_iter()
+            .filter_map(|renderer| {
+                let video_id = renderer
+                    .get("videoId")
+                    .and_then(|v| v.as_str())
+                    .and_then(|v| VideoId::new(v).ok())?;
+                let duration = renderer
+                    .get("lengthSeconds")
+                    .and_then(|l| l.as_str())
+                    .and_then(|l| l.parse().ok())
+                    .or_else(|| {
+                        self.get_text(renderer, Some(vec![vec!["lengthText"]]), None)
+                            .and_then(|l| parse_duration(&l))
+                    });
+
+                Some(YtPlaylistEntry {
+                    video_id,
+                    title: self
+                        .get_text(renderer, Some(vec![vec!["title"]]), None)
+                        .unwrap_or_default(),
+                    duration,
+                    thumbnails: self
+                        .extract_thumbnails(renderer.get("thumbnail").unwrap_or_default()),
+                    is_available: renderer
+                        .get("isPlayable")
+                        .and_then(|p| p.as_bool())
+                        .unwrap_or(duration.is_some()),
+                })
+            })
+            .collect()
+    }
+
+    fn extract_playlist_visibility(&self, data: &Value) -> YtPlaylistVisibility {
+        let privacy = self
+            .find_renderers(data, "playlistHeaderRenderer")
+            .into_iter()
+            .find_map(|header| header.get("privacy").and_then(|p| p.as_str()));
+
+        match privacy {
+            Some("UNLISTED") => return YtPlaylistVisibility::Unlisted,
+            Some("PRIVATE") => return YtPlaylistVisibility::Private,
+            Some(_) => return YtPlaylistVisibility::Public,
+            None => {}
+        }
+
+        // Newer layouts only show the visibility as a badge.
+        for badge in self.find_renderers(data, "metadataBadgeRenderer") {
+            match badge.get("label").and_then(|l| l.as_str()) {
+                Some("Unlisted") => return YtPlaylistVisibility::Unlisted,
+                Some("Private") => return YtPlaylistVisibility::Private,
+                _ => {}
+            }
+        }
+
+        YtPlaylistVisibility::Public
+    }
+}
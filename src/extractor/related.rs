@@ -0,0 +1,159 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use serde_json::Value;
+
+use crate::{
+    extractor::{
+        api::ExtractorApiHandle, browse::ExtractorBrowseHandle, extract::YtExtractor,
+        json::ExtractorJsonHandle,
+    },
+    utils::parse_duration,
+    yt_interface::{Continuation, VideoId, YtEndpoint, YtRelatedVideo, YtRelatedVideos},
+};
+
+pub trait ExtractorRelatedHandle {
+    async fn extract_related(&self, video_id: &VideoId) -> Result<YtRelatedVideos>;
+    async fn extract_related_continuation(&self, continuation: &str) -> Result<YtRelatedVideos>;
+    /// Parse the videos of the sidebar in their order, skipping the playlists, mixes and ads between them.
+    fn extract_related_videos(&self, results: &Value) -> Vec<YtRelatedVideo>;
+    fn extract_related_lockup(&self, lockup: &Value) -> Option<YtRelatedVideo>;
+}
+
+impl ExtractorRelatedHandle for YtExtractor {
+    async fn extract_related(&self, video_id: &VideoId) -> Result<YtRelatedVideos> {
+        let mut query = HashMap::new();
+        query.insert("videoId".into(), video_id.as_str().into());
+
+        let response = self
+            .call_api(YtEndpoint::Next, query, None, None, None, None)
+            .await?;
+        let data = Value::Object(response.into_iter().collect());
+
+        // The comments have a continuation of their own, only the sidebar's leads to more videos.
+        let results = data
+            .get("contents")
+            .and_then(|c| c.get("twoColumnWatchNextResults"))
+            .and_then(|c| c.get("secondaryResults"))
+            .unwrap_or_default();
+
+        Ok(YtRelatedVideos {
+            videos: self.extract_related_videos(results),
+            continuation: self.get_continuation_token(results).map(Continuation::new),
+        })
+    }
+
+    async fn extract_related_continuation(&self, continuation: &str) -> Result<YtRelatedVideos> {
+        let data = self
+            .browse_continuation(YtEndpoint::Next, continuation)
+            .await?;
+
+        Ok(YtRelatedVideos {
+            videos: self.extract_related_videos(&data),
+            continuation: self.get_continuation_token(&data).map(Continuation::new),
+        })
+    }
+
+    fn extract_related_videos(&self, results: &Value) -> Vec<YtRelatedVideo> {
+        let compact_videos = self.find_renderers(results, "compactVideoRenderer");
+
+        // Newer layouts show every item of the sidebar as a lockup instead.
+        if compact_videos.is_empty() {
+            return self
+                .find_renderers(results, "lockupViewModel")
+                .into_iter()
+                .filter_map(|lockup| self.extract_related_lockup(lockup))
+                .collect();
+        }
+
+        compact_videos
+            .into_iter()
+            .filter_map(|renderer| {
+                let video_id = renderer
+                    .get("videoId")
+                    .and_then(|v| v.as_str())
+                    .and_then(|v| VideoId::new(v).ok())?;
+
+                Some(YtRelatedVideo {
+                    video_id,
+                    title: self
+                        .get_text(renderer, Some(vec![vec!["title"]]), None)
+                        .unwrap_or_default(),
+                    channel_name: self.get_text(
+                        renderer,
+                        Some(vec![vec!["longBylineText"], vec!["shortBylineText"]]),
+                        None,
+                    ),
+                    duration: self
+                        .get_text(renderer, Some(vec![vec!["lengthText"]]), None)
+                        .and_then(|length| parse_duration(&length)),
+                    view_count_text: self.get_text(
+                        renderer,
+                        Some(vec![vec!["viewCountText"], vec!["shortViewCountText"]]),
+                        None,
+                    ),
+                    thumbnails: self
+                        .extract_thumbnails(renderer.get("thumbnail").unwrap_or_default()),
+                })
+            })
+            .collect()
+    }
+
+    fn extract_related_lockup(&self, lockup: &Value) -> Option<YtRelatedVideo> {
+        if lockup.get("contentType").and_then(|t| t.as_str()) != Some("LOCKUP_CONTENT_TYPE_VIDEO") {
+            return None;
+        }
+
+        let video_id = lockup
+            .get("contentId")
+            .and_then(|v| v.as_str())
+            .and_then(|v| VideoId::new(v).ok())?;
+
+        let title = self
+            .find_renderers(lockup, "lockupMetadataViewModel")
+            .into_iter()
+            .find_map(|metadata| metadata.get("title")?.get("content")?.as_str())
+            .unwrap_or_default()
+            .to_string();
+
+        // The first row has the channel, the second one the views and the upload date.
+        let rows = self
+            .find_renderers(lockup, "metadataRows")
+            .into_iter()
+            .filter_map(|rows| rows.as_array())
+            .flatten()
+            .map(|row| {
+                row.get("metadataParts")
+                    .and_then(|parts| parts.as_array())
+                    .map(|parts| {
+                        parts
+                            .iter()
+                            .filter_map(|part| part.get("text")?.get("content")?.as_str())
+                            .collect::<Vec<_>>()
+                    })
+                    .unwrap_or_default()
+            })
+            .collect::<Vec<_>>();
+
+        let duration = self
+            .find_renderers(lockup, "thumbnailBadgeViewModel")
+            .into_iter()
+            .find_map(|badge| badge.get("text")?.as_str())
+            .and_then(parse_duration);
+
+        Some(YtRelatedVideo {
+            video_id,
+            title,
+            channel_name: rows
+                .first()
+                .and_then(|row| row.first())
+                .map(|name| name.to_string()),
+            duration,
+            view_count_text: rows
+                .get(1)
+                .and_then(|row| row.first())
+                .map(|views| views.to_string()),
+            thumbnails: self.extract_lockup_thumbnails(lockup),
+        })
+    }
+}
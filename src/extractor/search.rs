@@ -0,0 +1,267 @@
+use std::collections::HashMap;
+
+use anyhow::{Result, bail};
+use serde_json::Value;
+
+use crate::{
+    extractor::{
+        api::ExtractorApiHandle, browse::ExtractorBrowseHandle, extract::YtExtractor,
+        json::ExtractorJsonHandle,
+    },
+    utils::{parse_count, parse_duration},
+    yt_interface::{
+        ChannelId, Continuation, PREFERRED_LOCALE, PlaylistId, VideoId, YtEndpoint,
+        YtSearchChannel, YtSearchFilters, YtSearchPlaylist, YtSearchResponse, YtSearchResult,
+        YtSearchVideo,
+    },
+};
+
+const SUGGEST_QUERIES_URL: &str = "https://suggestqueries-clients6.youtube.com/complete/search";
+
+pub trait ExtractorSearchHandle {
+    async fn search(
+        &self,
+        query: &str,
+        filters: Option<&YtSearchFilters>,
+    ) -> Result<YtSearchResponse>;
+    async fn search_continuation(&self, continuation: &str) -> Result<YtSearchResponse>;
+    /// Get the autocomplete suggestions for `query` from the suggestqueries endpoint, without any innertube call.
+    async fn search_suggestions(&self, query: &str) -> Result<Vec<String>>;
+    /// Parse the suggestions out of the `window.google.ac.h(...)` wrapper or the plain JSON the endpoint returns.
+    fn parse_search_suggestions(&self, body: &str) -> Result<Vec<String>>;
+    /// Parse the results of a page of a search in their order.
+    fn extract_search_results(&self, data: &Value) -> Vec<YtSearchResult>;
+    fn extract_search_video(&self, renderer: &Value) -> Option<YtSearchVideo>;
+    fn extract_search_channel(&self, renderer: &Value) -> Option<YtSearchChannel>;
+    fn extract_search_playlist(&self, renderer: &Value) -> Option<YtSearchPlaylist>;
+    /// Parse the newer `lockupViewModel` layout, which YouTube has moved playlist results to.
+    fn extract_search_lockup(&self, lockup: &Value) -> Option<YtSearchPlaylist>;
+}
+
+impl ExtractorSearchHandle for YtExtractor {
+    async fn search(
+        &self,
+        query: &str,
+        filters: Option<&YtSearchFilters>,
+    ) -> Result<YtSearchResponse> {
+        let mut search_query = HashMap::new();
+        search_query.insert("query".into(), query.into());
+
+        if let Some(sp) = filters
+            .map(|filters| filters.to_sp())
+            .filter(|sp| !sp.is_empty())
+        {
+            search_query.insert("params".into(), sp.into());
+        }
+
+        let response = self
+            .call_api(YtEndpoint::Search, search_query, None, None, None, None)
+            .await?;
+        let data = Value::Object(response.into_iter().collect());
+
+        Ok(YtSearchResponse {
+            results: self.extract_search_results(&data),
+            continuation: self.get_continuation_token(&data).map(Continuation::new),
+            estimated_results: data
+                .get("estimatedResults")
+                .and_then(|e| e.as_str())
+                .and_then(|e| e.parse().ok()),
+        })
+    }
+
+    async fn search_continuation(&self, continuation: &str) -> Result<YtSearchResponse> {
+        let data = self
+            .browse_continuation(YtEndpoint::Search, continuation)
+            .await?;
+
+        Ok(YtSearchResponse {
+            results: self.extract_search_results(&data),
+            continuation: self.get_continuation_token(&data).map(Continuation::new),
+            estimated_results: None,
+        })
+    }
+
+    async fn search_suggestions(&self, query: &str) -> Result<Vec<String>> {
+        if query.trim().is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut params = vec![
+            ("client", "youtube"),
+            ("ds", "yt"),
+            ("q", query),
+            (
+                "hl",
+                self.tydle_options.hl.as_deref().unwrap_or(PREFERRED_LOCALE),
+            ),
+        ];
+
+        if let Some(gl) = &self.tydle_options.gl {
+            params.push(("gl", gl));
+        }
+
+        let body = self
+            .http_client
+            .get(SUGGEST_QUERIES_URL)
+            .query(&params)
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+
+        self.parse_search_suggestions(&body)
+    }
+
+    fn parse_search_suggestions(&self, body: &str) -> Result<Vec<String>> {
+        // Like `window.google.ac.h(["query",[["suggestion",0,[512]],...],{...}])`, or the array alone.
+        let (Some(start), Some(end)) = (body.find('['), body.rfind(']')) else {
+            bail!("Failed to parse search suggestions: {}", body)
+        };
+        let data: Value = serde_json::from_str(&body[start..=end])?;
+
+        Ok(data
+            .get(1)
+            .and_then(|suggestions| suggestions.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|suggestion| match suggestion {
+                Value::String(suggestion) => Some(suggestion.clone()),
+                Value::Array(parts) => parts.first()?.as_str().map(|s| s.to_string()),
+                _ => None,
+            })
+            .collect())
+    }
+
+    fn extract_search_results(&self, data: &Value) -> Vec<YtSearchResult> {
+        // Only the direct items of a section are results, shelves like "People also watched"
+        // nest videos of their own and ads come in their own renderers, both are skipped.
+        self.find_renderers(data, "itemSectionRenderer")
+            .into_iter()
+            .filter_map(|section| section.get("contents").and_then(|c| c.as_array()))
+            .flatten()
+            .filter_map(|item| {
+                if let Some(renderer) = item.get("videoRenderer") {
+                    self.extract_search_video(renderer)
+                        .map(YtSearchResult::Video)
+                } else if let Some(renderer) = item.get("channelRenderer") {
+                    self.extract_search_channel(renderer)
+                        .map(YtSearchResult::Channel)
+                } else if let Some(renderer) = item.get("playlistRenderer") {
+                    self.extract_search_playlist(renderer)
+                        .map(YtSearchResult::Playlist)
+                } else if let Some(lockup) = item.get("lockupViewModel") {
+                    self.extract_search_lockup(lockup)
+                        .map(YtSearchResult::Playlist)
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
+    fn extract_search_video(&self, renderer: &Value) -> Option<YtSearchVideo> {
+        let video_id = renderer
+            .get("videoId")
+            .and_then(|v| v.as_str())
+            .and_then(|v| VideoId::new(v).ok())?;
+
+        Some(YtSearchVideo {
+            video_id,
+            title: self
+                .get_text(renderer, Some(vec![vec!["title"]]), None)
+                .unwrap_or_default(),
+            channel_name: self.get_text(
+                renderer,
+                Some(vec![vec!["ownerText"], vec!["longBylineText"]]),
+                None,
+            ),
+            duration: self
+                .get_text(renderer, Some(vec![vec!["lengthText"]]), None)
+                .and_then(|length| parse_duration(&length)),
+            view_count: self
+                .get_text(renderer, Some(vec![vec!["viewCountText"]]), None)
+                .and_then(|views| parse_count(&views)),
+            thumbnails: self.extract_thumbnails(renderer.get("thumbnail").unwrap_or_default()),
+        })
+    }
+
+    fn extract_search_channel(&self, renderer: &Value) -> Option<YtSearchChannel> {
+        let channel_id = renderer
+            .get("channelId")
+            .and_then(|c| c.as_str())
+            .and_then(|c| ChannelId::new(c).ok())?;
+
+        // Channels with a handle show it where the subscriber count used to be,
+        // which moved to the field of the video count.
+        let subscriber_text = [vec!["subscriberCountText"], vec!["videoCountText"]]
+            .into_iter()
+            .filter_map(|path| self.get_text(renderer, Some(vec![path]), None))
+            .find(|text| text.contains("subscriber"));
+
+        Some(YtSearchChannel {
+            channel_id,
+            title: self
+                .get_text(renderer, Some(vec![vec!["title"]]), None)
+                .unwrap_or_default(),
+            subscriber_text,
+            thumbnails: self.extract_thumbnails(renderer.get("thumbnail").unwrap_or_default()),
+        })
+    }
+
+    fn extract_search_playlist(&self, renderer: &Value) -> Option<YtSearchPlaylist> {
+        let playlist_id = renderer
+            .get("playlistId")
+            .and_then(|p| p.as_str())
+            .and_then(|p| PlaylistId::new(p).ok())?;
+
+        Some(YtSearchPlaylist {
+            playlist_id,
+            title: self
+                .get_text(renderer, Some(vec![vec!["title"]]), None)
+                .unwrap_or_default(),
+            video_count: renderer
+                .get("videoCount")
+                .and_then(|c| c.as_str())
+                .and_then(parse_count),
+            thumbnails: renderer
+                .get("thumbnails")
+                .and_then(|t| t.get(0))
+                .map(|t| self.extract_thumbnails(t))
+                .unwrap_or_default(),
+        })
+    }
+
+    fn extract_search_lockup(&self, lockup: &Value) -> Option<YtSearchPlaylist> {
+        if lockup.get("contentType").and_then(|t| t.as_str())
+            != Some("LOCKUP_CONTENT_TYPE_PLAYLIST")
+        {
+            return None;
+        }
+
+        let playlist_id = lockup
+            .get("contentId")
+            .and_then(|p| p.as_str())
+            .and_then(|p| PlaylistId::new(p).ok())?;
+
+        let title = self
+            .find_renderers(lockup, "lockupMetadataViewModel")
+            .into_iter()
+            .find_map(|metadata| metadata.get("title")?.get("content")?.as_str())
+            .unwrap_or_default()
+            .to_string();
+
+        let video_count = self
+            .find_renderers(lockup, "thumbnailBadgeViewModel")
+            .into_iter()
+            .find_map(|badge| badge.get("text")?.as_str())
+            .and_then(parse_count);
+
+        Some(YtSearchPlaylist {
+            playlist_id,
+            title,
+            video_count,
+            thumbnails: self.extract_lockup_thumbnails(lockup),
+        })
+    }
+}
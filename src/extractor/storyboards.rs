@@ -0,0 +1,55 @@
+use crate::{extractor::extract::YtExtractor, yt_interface::YtStoryboardLevel};
+
+pub trait ExtractorStoryboardsHandle {
+    /// Parse the `spec` of a `playerStoryboardSpecRenderer` into its levels, from the smallest thumbnails to the largest.
+    ///
+    /// The spec is the URL template followed by one `#` separated group per level, all separated by `|`:
+    /// `https://i.ytimg.com/sb/<id>/storyboard3_L$L/$N.jpg?sqp=...|48#27#100#10#10#0#default#rs$...|...`,
+    /// each group being the tile width, tile height, thumbnail count, columns, rows, interval, name and signature.
+    fn extract_storyboards(&self, spec: &str, duration: u64) -> Vec<YtStoryboardLevel>;
+}
+
+impl ExtractorStoryboardsHandle for YtExtractor {
+    fn extract_storyboards(&self, spec: &str, duration: u64) -> Vec<YtStoryboardLevel> {
+        let mut parts = spec.split('|');
+        let Some(base_url) = parts.next().filter(|url| !url.is_empty()) else {
+            return Vec::new();
+        };
+
+        parts
+            .enumerate()
+            .filter_map(|(level, args)| {
+                let args = args.split('#').collect::<Vec<_>>();
+                let [width, height, count, columns, rows, interval, name, sigh] = args[..] else {
+                    return None;
+                };
+
+                let thumbnail_count: u64 = count.parse().ok().filter(|c| *c > 0)?;
+                let interval_ms = match interval.parse::<u64>().ok().filter(|i| *i > 0) {
+                    Some(interval_ms) => interval_ms,
+                    // The first level has no interval, its thumbnails are spread over the whole video.
+                    None => duration * 1000 / thumbnail_count,
+                };
+
+                let separator = if base_url.contains('?') { '&' } else { '?' };
+
+                Some(YtStoryboardLevel {
+                    url_template: format!(
+                        "{}{}sigh={}",
+                        base_url
+                            .replace("$L", &level.to_string())
+                            .replace("$N", name),
+                        separator,
+                        sigh
+                    ),
+                    width: width.parse().ok()?,
+                    height: height.parse().ok()?,
+                    columns: columns.parse().ok().filter(|c| *c > 0)?,
+                    rows: rows.parse().ok().filter(|r| *r > 0)?,
+                    thumbnail_count,
+                    interval_ms,
+                })
+            })
+            .collect()
+    }
+}
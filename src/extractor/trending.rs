@@ -0,0 +1,267 @@
+use anyhow::Result;
+use serde_json::Value;
+
+use crate::{
+    extractor::{browse::ExtractorBrowseHandle, extract::YtExtractor, json::ExtractorJsonHandle},
+    utils::parse_duration,
+    yt_interface::{VideoId, YtTrending, YtTrendingCategory, YtTrendingSection, YtTrendingVideo},
+};
+
+const TRENDING_BROWSE_ID: &str = "FEtrending";
+
+pub trait ExtractorTrendingHandle {
+    /// Get the trending videos of a tab, for the country of `TydleOptions::gl` or the one YouTube guesses.
+    async fn extract_trending(&self, category: YtTrendingCategory) -> Result<YtTrending>;
+    /// Get the browse params of a tab from the tabs of the trending page, which differ between countries.
+    fn get_trending_tab_params(&self, data: &Value, category: YtTrendingCategory)
+    -> Option<String>;
+    /// Parse the shelves of a tab in their order, along with the videos of each.
+    fn extract_trending_sections(&self, data: &Value) -> Vec<YtTrendingSection>;
+    fn extract_trending_video(&self, renderer: &Value) -> Option<YtTrendingVideo>;
+}
+
+impl ExtractorTrendingHandle for YtExtractor {
+    async fn extract_trending(&self, category: YtTrendingCategory) -> Result<YtTrending> {
+        let data = self.browse(TRENDING_BROWSE_ID, None).await?;
+        let default_tab = YtTrending {
+            category: YtTrendingCategory::Now,
+            sections: self.extract_trending_sections(&data),
+        };
+
+        if category == YtTrendingCategory::Now {
+            return Ok(default_tab);
+        }
+
+        let Some(params) = self.get_trending_tab_params(&data, category) else {
+            #[cfg(feature = "logging")]
+            log::warn!(
+                "The trending page has no {:?} tab in this country, returning the default one.",
+                category
+            );
+
+            return Ok(default_tab);
+        };
+
+        let tab_data = self.browse(TRENDING_BROWSE_ID, Some(&params)).await?;
+        let sections = self.extract_trending_sections(&tab_data);
+
+        // YouTube answers params it doesn't know for a country with an empty tab.
+        if sections.iter().all(|section| section.videos.is_empty()) {
+            return Ok(default_tab);
+        }
+
+        Ok(YtTrending { category, sections })
+    }
+
+    fn get_trending_tab_params(
+        &self,
+        data: &Value,
+        category: YtTrendingCategory,
+    ) -> Option<String> {
+        // The tabs are always in the same order, but their titles are localized.
+        self.find_renderers(data, "tabRenderer")
+            .get(category as usize)?
+            .get("endpoint")?
+            .get("browseEndpoint")?
+            .get("params")?
+            .as_str()
+            .map(|params| params.to_string())
+    }
+
+    fn extract_trending_sections(&self, data: &Value) -> Vec<YtTrendingSection> {
+        let tab = self
+            .find_renderers(data, "tabRenderer")
+            .into_iter()
+            .find(|tab| tab.get("selected").and_then(|s| s.as_bool()) == Some(true))
+            .unwrap_or(data);
+
+        self.find_renderers(tab, "itemSectionRenderer")
+            .into_iter()
+            .filter_map(|section| {
+                let videos = self
+                    .find_renderers(section, "videoRenderer")
+                    .into_iter()
+                    .filter_map(|renderer| self.extract_trending_video(renderer))
+                    .collect::<Vec<_>>();
+
+                if videos.is_empty() {
+                    return None;
+                }
+
+                Some(YtTrendingSection {
+                    title: self
+                        .find_renderers(section, "shelfRenderer")
+                        .first()
+                        .and_then(|shelf| self.get_text(shelf, Some(vec![vec!["title"]]), None)),
+                    videos,
+                })
+            })
+            .collect()
+    }
+
+    fn extract_trending_video(&self, renderer: &Value) -> Option<YtTrendingVideo> {
+        let video_id = renderer
+            .get("videoId")
+            .and_then(|v| v.as_str())
+            .and_then(|v| VideoId::new(v).ok())?;
+
+        Some(YtTrendingVideo {
+            video_id,
+            title: self
+                .get_text(renderer, Some(vec![vec!["title"]]), None)
+                .unwrap_or_default(),
+            channel_name: self.get_text(
+                renderer,
+                Some(vec![vec!["ownerText"], vec!["longBylineText"]]),
+                None,
+            ),
+            channel_id: self
+                .find_renderers(
+                    renderer.get("ownerText").unwrap_or_default(),
+                    "browseEndpoint",
+                )
+                .first()
+                .and_then(|endpoint| endpoint.get("browseId"))
+                .and_then(|id| id.as_str())
+                .map(|id| id.to_string()),
+            view_count_text: self.get_text(renderer, Some(vec![vec!["viewCountText"]]), None),
+            published_text: self.get_text(renderer, Some(vec![vec!["publishedTimeText"]]), None),
+            duration: self
+                .get_text(renderer, Some(vec![vec!["lengthText"]]), None)
+                .and_then(|l| parse_duration(&l)),
+            thumbnails: self.extract_thumbnails(renderer.get("thumbnail").unwrap_or_default()),
+        })
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use crate::{
+        extractor::ytcfg::ExtractorYtCfgHandle, test_server::test_extractor, tydle::TydleOptions,
+    };
+
+    fn extractor() -> YtExtractor {
+        test_extractor(TydleOptions::default(), reqwest::Client::new())
+    }
+
+    fn fixture(json: &str) -> Value {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn now_tab_sections_are_parsed_in_order() {
+        let sections = extractor().extract_trending_sections(&fixture(include_str!(
+            "../../tests/fixtures/trending_now.json"
+        )));
+
+        assert_eq!(sections.len(), 2);
+        assert_eq!(sections[0].title, None);
+        assert_eq!(sections[0].videos.len(), 2);
+        assert_eq!(sections[1].title.as_deref(), Some("Recently trending"));
+
+        let video = &sections[0].videos[0];
+        assert_eq!(video.video_id.as_str(), "Z9g6dY1xGXo");
+        assert_eq!(video.title, "Official Trailer | Season 2");
+        assert_eq!(video.channel_name.as_deref(), Some("Netflix"));
+        assert_eq!(
+            video.channel_id.as_deref(),
+            Some("UCWOA1ZGywLbqmigxE4Qlvuw")
+        );
+        assert_eq!(video.view_count_text.as_deref(), Some("4,512,337 views"));
+        assert_eq!(video.published_text.as_deref(), Some("1 day ago"));
+        assert_eq!(video.duration, Some(151));
+        assert_eq!(video.thumbnails.len(), 2);
+
+        // Live streams have neither a duration nor an upload time.
+        let live = &sections[1].videos[0];
+        assert_eq!(live.duration, None);
+        assert_eq!(live.published_text, None);
+    }
+
+    #[test]
+    fn every_tab_fixture_has_its_videos() {
+        let cases = [
+            (
+                include_str!("../../tests/fixtures/trending_music.json"),
+                None,
+                "fHI8X4OXluQ",
+                Some(235),
+            ),
+            (
+                include_str!("../../tests/fixtures/trending_gaming.json"),
+                Some("Trending in Gaming"),
+                "8X2kIfS6fb8",
+                Some(3723),
+            ),
+            (
+                include_str!("../../tests/fixtures/trending_movies.json"),
+                Some("Trending trailers"),
+                "d9MyW72ELq0",
+                Some(136),
+            ),
+        ];
+
+        for (json, title, video_id, duration) in cases {
+            let sections = extractor().extract_trending_sections(&fixture(json));
+
+            assert_eq!(sections.len(), 1);
+            assert_eq!(sections[0].title.as_deref(), title);
+            assert_eq!(sections[0].videos[0].video_id.as_str(), video_id);
+            assert_eq!(sections[0].videos[0].duration, duration);
+        }
+    }
+
+    #[test]
+    fn tab_params_are_taken_from_the_tabs() {
+        let extractor = extractor();
+        let data = fixture(include_str!("../../tests/fixtures/trending_now.json"));
+
+        assert_eq!(
+            extractor
+                .get_trending_tab_params(&data, YtTrendingCategory::Music)
+                .as_deref(),
+            Some("4gINGgt5dG1hX2NoYXJ0cw%3D%3D")
+        );
+        assert_eq!(
+            extractor
+                .get_trending_tab_params(&data, YtTrendingCategory::Gaming)
+                .as_deref(),
+            Some("4gIcGhpnYW1pbmdfY29ycHVzX21vc3RfcG9wdWxhcg%3D%3D")
+        );
+        assert_eq!(
+            extractor
+                .get_trending_tab_params(&data, YtTrendingCategory::Movies)
+                .as_deref(),
+            Some("4gIKGgh0cmFpbGVycw%3D%3D")
+        );
+    }
+
+    #[test]
+    fn missing_tabs_have_no_params() {
+        let mut data = fixture(include_str!("../../tests/fixtures/trending_now.json"));
+        // Like countries whose trending page only has the Now and Music tabs.
+        data["contents"]["twoColumnBrowseResultsRenderer"]["tabs"]
+            .as_array_mut()
+            .unwrap()
+            .truncate(2);
+
+        assert_eq!(
+            extractor().get_trending_tab_params(&data, YtTrendingCategory::Gaming),
+            None
+        );
+    }
+
+    #[test]
+    fn gl_is_sent_in_the_client_context() {
+        let options = TydleOptions {
+            gl: Some("DE".into()),
+            ..Default::default()
+        };
+        let context = test_extractor(options, reqwest::Client::new())
+            .select_context(None, None)
+            .unwrap();
+
+        assert_eq!(context.get("gl"), Some(&Value::String("DE".into())));
+    }
+}
@@ -21,6 +21,10 @@ pub trait ExtractorYtCfgHandle {
         default_client: Option<&YtClient>,
     ) -> Result<HashMap<String, Value>>;
     fn select_visitor_data(&self, ytcfgs: &[&HashMap<String, Value>]) -> Option<String>;
+    /// Get the `visitorData` of the instance, see `YtExtractor::visitor_data`.
+    fn get_session_visitor_data(&self) -> Option<String>;
+    /// Keep the `visitorData` YouTube assigned in the `responseContext` of `response`, unless there already is one.
+    fn remember_visitor_data(&self, response: &HashMap<String, Value>);
     fn select_default_ytcfg(&self, default_client: Option<&YtClient>) -> Result<InnerTubeClient>;
 }
 
@@ -76,10 +80,27 @@ impl ExtractorYtCfgHandle for YtExtractor {
         if let Some(map) = client_context.as_object_mut() {
             map.insert(
                 "hl".to_string(),
-                Value::String(PREFERRED_LOCALE.to_string()),
+                Value::String(
+                    self.tydle_options
+                        .hl
+                        .clone()
+                        .unwrap_or_else(|| PREFERRED_LOCALE.to_string()),
+                ),
             );
+
+            if let Some(gl) = &self.tydle_options.gl {
+                map.insert("gl".to_string(), Value::String(gl.clone()));
+            }
+
             map.insert("timeZone".to_string(), Value::String("UTC".to_string()));
             map.insert("utcOffsetMinutes".to_string(), Value::Number(0.into()));
+
+            // The one of the options has to replace the ytcfg's, since a PO token may be bound to it.
+            if let Some(visitor_data) = self.get_session_visitor_data()
+                && (self.tydle_options.visitor_data.is_some() || !map.contains_key("visitorData"))
+            {
+                map.insert("visitorData".to_string(), Value::String(visitor_data));
+            }
         }
 
         if let Value::Object(map) = client_context {
@@ -116,6 +137,29 @@ impl ExtractorYtCfgHandle for YtExtractor {
         None
     }
 
+    fn get_session_visitor_data(&self) -> Option<String> {
+        self.visitor_data
+            .read()
+            .ok()
+            .and_then(|visitor_data| visitor_data.clone())
+    }
+
+    fn remember_visitor_data(&self, response: &HashMap<String, Value>) {
+        let Some(new_visitor_data) = response
+            .get("responseContext")
+            .and_then(|rc| rc.get("visitorData"))
+            .and_then(|vd| vd.as_str())
+        else {
+            return;
+        };
+
+        if let Ok(mut visitor_data) = self.visitor_data.write()
+            && visitor_data.is_none()
+        {
+            *visitor_data = Some(new_visitor_data.to_string());
+        }
+    }
+
     fn select_default_ytcfg(&self, default_client: Option<&YtClient>) -> Result<InnerTubeClient> {
         let client = default_client.unwrap_or(&DEFAULT_YT_CLIENT);
         let mut ytcfg = INNERTUBE_CLIENTS.get(client).cloned().unwrap();
@@ -0,0 +1,134 @@
+use crate::yt_interface::YtStreamKind;
+
+/// What an itag stands for, as it has been for years. YouTube doesn't change the meaning of an itag,
+/// but it leaves out some of these details from the formats it sends every now and then.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ItagInfo {
+    pub itag: u32,
+    /// Like `mp4` or `webm`, the same as `YtStream::container`.
+    pub container: &'static str,
+    pub kind: YtStreamKind,
+    /// Family of the video codec, like `avc1` or `vp9`, the exact profile varies.
+    pub video_codec: Option<&'static str>,
+    /// Family of the audio codec, like `mp4a.40.2` or `opus`.
+    pub audio_codec: Option<&'static str>,
+    pub width: Option<u64>,
+    /// Missing for itags that are used for more than one resolution.
+    pub height: Option<u64>,
+    /// Only known for the high frame rate itags.
+    pub fps: Option<u64>,
+}
+
+const fn muxed(
+    itag: u32,
+    container: &'static str,
+    video_codec: &'static str,
+    audio_codec: &'static str,
+    width: u64,
+    height: u64,
+) -> ItagInfo {
+    ItagInfo {
+        itag,
+        container,
+        kind: YtStreamKind::Muxed,
+        video_codec: Some(video_codec),
+        audio_codec: Some(audio_codec),
+        width: Some(width),
+        height: Some(height),
+        fps: None,
+    }
+}
+
+const fn video(
+    itag: u32,
+    container: &'static str,
+    video_codec: &'static str,
+    resolution: Option<(u64, u64)>,
+    fps: Option<u64>,
+) -> ItagInfo {
+    ItagInfo {
+        itag,
+        container,
+        kind: YtStreamKind::AdaptiveVideo,
+        video_codec: Some(video_codec),
+        audio_codec: None,
+        width: match resolution {
+            Some((width, _)) => Some(width),
+            None => None,
+        },
+        height: match resolution {
+            Some((_, height)) => Some(height),
+            None => None,
+        },
+        fps,
+    }
+}
+
+const fn audio(itag: u32, container: &'static str, audio_codec: &'static str) -> ItagInfo {
+    ItagInfo {
+        itag,
+        container,
+        kind: YtStreamKind::AdaptiveAudio,
+        video_codec: None,
+        audio_codec: Some(audio_codec),
+        width: None,
+        height: None,
+        fps: None,
+    }
+}
+
+const ITAGS: [ItagInfo; 42] = [
+    // Muxed.
+    muxed(17, "3gpp", "mp4v.20.3", "mp4a.40.2", 176, 144),
+    muxed(18, "mp4", "avc1", "mp4a.40.2", 640, 360),
+    muxed(22, "mp4", "avc1", "mp4a.40.2", 1280, 720),
+    // H.264 video.
+    video(160, "mp4", "avc1", Some((256, 144)), None),
+    video(133, "mp4", "avc1", Some((426, 240)), None),
+    video(134, "mp4", "avc1", Some((640, 360)), None),
+    video(135, "mp4", "avc1", Some((854, 480)), None),
+    video(136, "mp4", "avc1", Some((1280, 720)), None),
+    video(137, "mp4", "avc1", Some((1920, 1080)), None),
+    // Anything from 2160p to 4320p.
+    video(138, "mp4", "avc1", None, None),
+    video(264, "mp4", "avc1", Some((2560, 1440)), None),
+    video(266, "mp4", "avc1", Some((3840, 2160)), None),
+    video(298, "mp4", "avc1", Some((1280, 720)), Some(60)),
+    video(299, "mp4", "avc1", Some((1920, 1080)), Some(60)),
+    // VP9 video.
+    video(278, "webm", "vp9", Some((256, 144)), None),
+    video(242, "webm", "vp9", Some((426, 240)), None),
+    video(243, "webm", "vp9", Some((640, 360)), None),
+    video(244, "webm", "vp9", Some((854, 480)), None),
+    video(247, "webm", "vp9", Some((1280, 720)), None),
+    video(248, "webm", "vp9", Some((1920, 1080)), None),
+    video(271, "webm", "vp9", Some((2560, 1440)), None),
+    video(313, "webm", "vp9", Some((3840, 2160)), None),
+    video(302, "webm", "vp9", Some((1280, 720)), Some(60)),
+    video(303, "webm", "vp9", Some((1920, 1080)), Some(60)),
+    video(308, "webm", "vp9", Some((2560, 1440)), Some(60)),
+    video(315, "webm", "vp9", Some((3840, 2160)), Some(60)),
+    // AV1 video, whose frame rate follows the source.
+    video(394, "mp4", "av01", Some((256, 144)), None),
+    video(395, "mp4", "av01", Some((426, 240)), None),
+    video(396, "mp4", "av01", Some((640, 360)), None),
+    video(397, "mp4", "av01", Some((854, 480)), None),
+    video(398, "mp4", "av01", Some((1280, 720)), None),
+    video(399, "mp4", "av01", Some((1920, 1080)), None),
+    video(400, "mp4", "av01", Some((2560, 1440)), None),
+    video(401, "mp4", "av01", Some((3840, 2160)), None),
+    video(571, "mp4", "av01", Some((7680, 4320)), None),
+    // Audio.
+    audio(139, "mp4", "mp4a.40.5"),
+    audio(140, "mp4", "mp4a.40.2"),
+    audio(141, "mp4", "mp4a.40.2"),
+    audio(249, "webm", "opus"),
+    audio(250, "webm", "opus"),
+    audio(251, "webm", "opus"),
+    audio(600, "webm", "opus"),
+];
+
+/// Look up what `itag` stands for, `None` for itags that aren't in the table.
+pub fn lookup(itag: u32) -> Option<ItagInfo> {
+    ITAGS.iter().find(|info| info.itag == itag).copied()
+}
@@ -1,13 +1,27 @@
-mod cache;
 mod cipher;
 mod extractor;
 mod utils;
 
+pub mod cache;
 pub mod cookies;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod download;
+pub mod error;
+pub mod itag;
 #[cfg(feature = "logging")]
 pub mod logger;
+#[cfg(all(feature = "mux", not(target_arch = "wasm32")))]
+pub mod mux;
+#[cfg(feature = "oauth")]
+pub mod oauth;
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod test_server;
 pub mod tydle;
 pub mod yt_interface;
 
 pub use crate::tydle::*;
+pub use crate::utils::sapisid_hash;
 pub use crate::yt_interface::*;
+
+#[cfg(all(feature = "mux", not(target_arch = "wasm32")))]
+pub use crate::mux::mux;
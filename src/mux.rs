@@ -0,0 +1,459 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use anyhow::{Context, Result, bail};
+use tokio::{fs, process::Command};
+
+use crate::{
+    download::{DownloadOptions, DownloadProgress},
+    error::{FfmpegNotFound, UnsupportedCodec},
+    yt_interface::YtStream,
+};
+
+#[cfg(windows)]
+const FFMPEG: &str = "ffmpeg.exe";
+#[cfg(not(windows))]
+const FFMPEG: &str = "ffmpeg";
+
+/// Where package managers put `ffmpeg`, looked in after the directories in `PATH`.
+#[cfg(not(windows))]
+const FFMPEG_DIRS: [&str; 4] = [
+    "/usr/local/bin",
+    "/usr/bin",
+    "/opt/homebrew/bin",
+    "/snap/bin",
+];
+#[cfg(windows)]
+const FFMPEG_DIRS: [&str; 1] = [r"C:\ffmpeg\bin"];
+
+/// The container `mux` merges a video and an audio stream into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MuxContainer {
+    /// Takes H.264, AV1 and VP9 video, but only AAC audio.
+    Mp4,
+    /// Takes any codec.
+    Mkv,
+    /// Takes VP9 and AV1 video with Opus or Vorbis audio.
+    Webm,
+}
+
+impl MuxContainer {
+    /// The container of `path` by its extension, like `mp4` for `video.mp4`.
+    pub fn from_path(path: &Path) -> Result<Self> {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("mp4" | "m4v") => Ok(Self::Mp4),
+            Some("mkv") => Ok(Self::Mkv),
+            Some("webm") => Ok(Self::Webm),
+            _ => bail!(
+                "Failed to tell the container of {} by its extension, use .mp4, .mkv or .webm.",
+                path.display()
+            ),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Mp4 => "mp4",
+            Self::Mkv => "mkv",
+            Self::Webm => "webm",
+        }
+    }
+
+    /// The name `ffmpeg -f` knows the container by.
+    fn ffmpeg_format(&self) -> &'static str {
+        match self {
+            Self::Mp4 => "mp4",
+            Self::Mkv => "matroska",
+            Self::Webm => "webm",
+        }
+    }
+
+    /// Whether `codec`, like `avc1.640028` or `opus`, can be copied into the container as it is.
+    pub fn supports(&self, codec: &str) -> bool {
+        let family = codec.split('.').next().unwrap_or(codec);
+
+        match self {
+            Self::Mkv => true,
+            Self::Mp4 => matches!(
+                family,
+                "avc1" | "avc3" | "hev1" | "hvc1" | "av01" | "vp9" | "vp09" | "mp4a"
+            ),
+            Self::Webm => matches!(family, "vp8" | "vp9" | "vp09" | "av01" | "opus" | "vorbis"),
+        }
+    }
+
+    /// Fail with a `tydle::error::UnsupportedCodec` if `codec` can't be copied into the container.
+    pub fn check(&self, codec: &str) -> Result<()> {
+        if !self.supports(codec) {
+            return Err(UnsupportedCodec {
+                codec: codec.to_string(),
+                container: self.as_str().to_string(),
+            }
+            .into());
+        }
+
+        Ok(())
+    }
+
+    /// Whether all the codecs of `stream` can be copied into the container.
+    pub fn can_hold(&self, stream: &YtStream) -> bool {
+        [&stream.video_codec, &stream.audio_codec]
+            .into_iter()
+            .flatten()
+            .all(|codec| self.supports(codec))
+    }
+}
+
+/// The codec a stream downloaded to `path` most likely has, going by its extension.
+/// YouTube only puts Opus audio into WebM and AAC audio into MP4.
+fn guess_codec(path: &Path, is_audio: bool) -> Option<&'static str> {
+    match (path.extension()?.to_str()?, is_audio) {
+        ("webm" | "opus", true) => Some("opus"),
+        ("mp4" | "m4a", true) => Some("mp4a"),
+        ("webm", false) => Some("vp9"),
+        ("mp4", false) => Some("avc1"),
+        _ => None,
+    }
+}
+
+/// Look for `ffmpeg` in the directories of `PATH`, then where package managers usually put it.
+fn find_ffmpeg() -> Result<PathBuf> {
+    let dirs = std::env::var_os("PATH")
+        .map(|path| std::env::split_paths(&path).collect::<Vec<_>>())
+        .unwrap_or_default();
+
+    find_ffmpeg_in(
+        dirs.into_iter()
+            .chain(FFMPEG_DIRS.into_iter().map(PathBuf::from)),
+    )
+}
+
+/// Look for `ffmpeg` in `dirs` in order, skipping the ones that were already looked in.
+fn find_ffmpeg_in(dirs: impl IntoIterator<Item = PathBuf>) -> Result<PathBuf> {
+    let mut searched = Vec::new();
+
+    for dir in dirs {
+        if searched.contains(&dir) {
+            continue;
+        }
+
+        let ffmpeg = dir.join(FFMPEG);
+        if ffmpeg.is_file() {
+            return Ok(ffmpeg);
+        }
+
+        searched.push(dir);
+    }
+
+    Err(FfmpegNotFound {
+        binary: FFMPEG.to_string(),
+        searched,
+    }
+    .into())
+}
+
+/// Merge a video-only and an audio-only stream into `out_path` with `ffmpeg`, copying both as they
+/// are without encoding them again. The two files are removed once `out_path` is written.
+///
+/// Fails with a `tydle::error::UnsupportedCodec` if a stream can't go into `container`, going by the
+/// extensions of the files, and with a `tydle::error::FfmpegNotFound` if `ffmpeg` isn't installed.
+pub async fn mux(
+    video_path: &Path,
+    audio_path: &Path,
+    out_path: &Path,
+    container: MuxContainer,
+) -> Result<()> {
+    for (path, is_audio) in [(video_path, false), (audio_path, true)] {
+        if let Some(codec) = guess_codec(path, is_audio) {
+            container.check(codec)?;
+        }
+    }
+
+    mux_with(&find_ffmpeg()?, video_path, audio_path, out_path, container).await
+}
+
+/// `mux` with the `ffmpeg` at `ffmpeg`, without checking the codecs.
+async fn mux_with(
+    ffmpeg: &Path,
+    video_path: &Path,
+    audio_path: &Path,
+    out_path: &Path,
+    container: MuxContainer,
+) -> Result<()> {
+    let mut command = Command::new(ffmpeg);
+    command
+        .args(["-y", "-loglevel", "error", "-i"])
+        .arg(video_path)
+        .arg("-i")
+        .arg(audio_path)
+        .args(["-map", "0:v:0", "-map", "1:a:0", "-c", "copy"]);
+    if container == MuxContainer::Mp4 {
+        command.args(["-movflags", "+faststart"]);
+    }
+    command
+        .args(["-f", container.ffmpeg_format()])
+        .arg(out_path);
+
+    let output = command
+        .output()
+        .await
+        .with_context(|| format!("Failed to run {}.", ffmpeg.display()))?;
+
+    if !output.status.success() {
+        let _ = fs::remove_file(out_path).await;
+        bail!(
+            "ffmpeg failed to mux the streams into {} ({}): {}",
+            out_path.display(),
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )
+    }
+
+    let _ = fs::remove_file(video_path).await;
+    let _ = fs::remove_file(audio_path).await;
+
+    Ok(())
+}
+
+/// Where `Tydle::download_muxed` downloads `stream` before muxing it into `out_path`,
+/// like `video.f137.mp4` for `video.mkv`.
+pub(crate) fn intermediate_path(out_path: &Path, stream: &YtStream) -> PathBuf {
+    out_path.with_extension(format!(
+        "f{}.{}",
+        stream.itag.as_deref().unwrap_or("unknown"),
+        stream.container.as_deref().unwrap_or("part")
+    ))
+}
+
+/// The `on_progress` of `Tydle::download_muxed`, shared by the downloads of both streams.
+pub(crate) type SharedProgress = Arc<Mutex<Box<dyn FnMut(DownloadProgress) + Send>>>;
+
+/// `options` for one of the streams `Tydle::download_muxed` downloads one after the other.
+/// The progress is reported to `on_progress` as `offset` bytes more than that download has,
+/// out of the `total` of both streams.
+pub(crate) fn stream_options(
+    options: &DownloadOptions,
+    on_progress: Option<SharedProgress>,
+    offset: u64,
+    total: Option<u64>,
+) -> DownloadOptions {
+    DownloadOptions {
+        on_progress: on_progress.map(|on_progress| {
+            Box::new(move |progress: DownloadProgress| {
+                if let Ok(mut on_progress) = on_progress.lock() {
+                    on_progress(DownloadProgress {
+                        bytes_done: offset + progress.bytes_done,
+                        total,
+                        speed_bps: progress.speed_bps,
+                    });
+                }
+            }) as Box<dyn FnMut(DownloadProgress) + Send>
+        }),
+        progress_interval: options.progress_interval,
+        chunk_size: options.chunk_size,
+        parallelism: options.parallelism,
+        max_rate: options.max_rate,
+        resume_policy: options.resume_policy.clone(),
+        verify_length: options.verify_length,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::yt_interface::YtStreamSource;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("tydle-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Write an executable `ffmpeg` into `dir` that runs `script` after saving its arguments to `args.txt`.
+    #[cfg(unix)]
+    fn fake_ffmpeg(dir: &Path, script: &str) -> PathBuf {
+        use std::os::unix::fs::PermissionsExt;
+
+        let ffmpeg = dir.join(FFMPEG);
+        std::fs::write(
+            &ffmpeg,
+            format!(
+                "#!/bin/sh\nprintf '%s\\n' \"$@\" > \"$(dirname \"$0\")/args.txt\"\nfor out; do :; done\n{}\n",
+                script
+            ),
+        )
+        .unwrap();
+        std::fs::set_permissions(&ffmpeg, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        ffmpeg
+    }
+
+    #[test]
+    fn containers_only_take_the_codecs_they_can_hold() {
+        assert!(MuxContainer::Mp4.supports("avc1.640028"));
+        assert!(MuxContainer::Mp4.supports("av01.0.08M.08"));
+        assert!(MuxContainer::Mp4.supports("mp4a.40.2"));
+        assert!(!MuxContainer::Mp4.supports("opus"));
+        assert!(MuxContainer::Webm.supports("vp9"));
+        assert!(MuxContainer::Webm.supports("opus"));
+        assert!(!MuxContainer::Webm.supports("avc1.640028"));
+        assert!(!MuxContainer::Webm.supports("mp4a.40.2"));
+        assert!(MuxContainer::Mkv.supports("opus"));
+        assert!(MuxContainer::Mkv.supports("avc1.640028"));
+
+        let stream = |video_codec: Option<&str>, audio_codec: Option<&str>| {
+            let mut stream = YtStream::new(
+                None,
+                None,
+                None,
+                None,
+                YtStreamSource::URL("https://a.googlevideo.com/videoplayback".into()),
+                1000.0,
+            );
+            stream.video_codec = video_codec.map(|codec| codec.to_string());
+            stream.audio_codec = audio_codec.map(|codec| codec.to_string());
+            stream
+        };
+        assert!(MuxContainer::Mp4.can_hold(&stream(Some("avc1.42001E"), Some("mp4a.40.2"))));
+        assert!(!MuxContainer::Mp4.can_hold(&stream(None, Some("opus"))));
+        assert!(MuxContainer::Webm.can_hold(&stream(Some("vp9"), None)));
+    }
+
+    #[test]
+    fn container_is_told_by_the_extension() {
+        let container = |path: &str| MuxContainer::from_path(Path::new(path)).ok();
+
+        assert_eq!(container("video.mp4"), Some(MuxContainer::Mp4));
+        assert_eq!(container("video.m4v"), Some(MuxContainer::Mp4));
+        assert_eq!(container("dir/video.mkv"), Some(MuxContainer::Mkv));
+        assert_eq!(container("video.webm"), Some(MuxContainer::Webm));
+        assert_eq!(container("video.avi"), None);
+        assert_eq!(container("video"), None);
+    }
+
+    #[tokio::test]
+    async fn opus_in_mp4_is_rejected_before_looking_for_ffmpeg() {
+        let error = mux(
+            Path::new("video.f137.mp4"),
+            Path::new("video.f251.webm"),
+            Path::new("video.mp4"),
+            MuxContainer::Mp4,
+        )
+        .await
+        .unwrap_err();
+
+        assert_eq!(
+            error.downcast_ref::<UnsupportedCodec>(),
+            Some(&UnsupportedCodec {
+                codec: "opus".into(),
+                container: "mp4".into(),
+            })
+        );
+        assert!(error.to_string().contains("opus"));
+    }
+
+    #[test]
+    fn missing_ffmpeg_names_the_binary_and_every_directory_searched() {
+        let dir = temp_dir("mux-not-found");
+        let (first, second) = (dir.join("first"), dir.join("second"));
+
+        let error = find_ffmpeg_in([first.clone(), second.clone(), first.clone()]).unwrap_err();
+        let not_found = error.downcast_ref::<FfmpegNotFound>().unwrap();
+
+        assert_eq!(not_found.binary, FFMPEG);
+        assert_eq!(not_found.searched, [first.clone(), second.clone()]);
+        assert!(error.to_string().contains(&second.display().to_string()));
+
+        std::fs::create_dir_all(&second).unwrap();
+        std::fs::write(second.join(FFMPEG), "").unwrap();
+        assert_eq!(
+            find_ffmpeg_in([first, second.clone()]).unwrap(),
+            second.join(FFMPEG)
+        );
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn streams_are_copied_and_removed_once_muxed() {
+        let dir = temp_dir("mux-copied");
+        let ffmpeg = fake_ffmpeg(&dir, "echo muxed > \"$out\"");
+        let (video, audio, out) = (
+            dir.join("video.f137.mp4"),
+            dir.join("video.f140.mp4"),
+            dir.join("video.mp4"),
+        );
+        std::fs::write(&video, "video").unwrap();
+        std::fs::write(&audio, "audio").unwrap();
+
+        mux_with(&ffmpeg, &video, &audio, &out, MuxContainer::Mp4)
+            .await
+            .unwrap();
+
+        let args = std::fs::read_to_string(dir.join("args.txt")).unwrap();
+        let args: Vec<&str> = args.lines().collect();
+        assert_eq!(
+            args,
+            [
+                "-y",
+                "-loglevel",
+                "error",
+                "-i",
+                video.to_str().unwrap(),
+                "-i",
+                audio.to_str().unwrap(),
+                "-map",
+                "0:v:0",
+                "-map",
+                "1:a:0",
+                "-c",
+                "copy",
+                "-movflags",
+                "+faststart",
+                "-f",
+                "mp4",
+                out.to_str().unwrap(),
+            ]
+        );
+        assert_eq!(std::fs::read_to_string(&out).unwrap(), "muxed\n");
+        assert!(!video.exists() && !audio.exists());
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn failed_ffmpeg_keeps_the_streams_and_removes_the_output() {
+        let dir = temp_dir("mux-failed");
+        let ffmpeg = fake_ffmpeg(
+            &dir,
+            "echo half > \"$out\"\necho 'Invalid data found when processing input' >&2\nexit 1",
+        );
+        let (video, audio, out) = (
+            dir.join("video.f248.webm"),
+            dir.join("video.f251.webm"),
+            dir.join("video.mkv"),
+        );
+        std::fs::write(&video, "video").unwrap();
+        std::fs::write(&audio, "audio").unwrap();
+
+        let error = mux_with(&ffmpeg, &video, &audio, &out, MuxContainer::Mkv)
+            .await
+            .unwrap_err();
+
+        assert!(error.to_string().contains("Invalid data found"));
+        assert!(!out.exists());
+        assert!(video.exists() && audio.exists());
+        assert!(
+            std::fs::read_to_string(dir.join("args.txt"))
+                .unwrap()
+                .contains("matroska")
+        );
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,447 @@
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::PathBuf;
+use std::{
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::{Duration, UNIX_EPOCH},
+};
+
+use anyhow::{Result, anyhow, bail};
+use futures::lock::Mutex as AsyncMutex;
+use reqwest::{RequestBuilder, Response, StatusCode};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use sha1::{Digest, Sha1};
+
+use crate::utils::now;
+
+/// Credentials of the YouTube TV app, the only client YouTube lets sign in with a device code.
+const CLIENT_ID: &str = "861556708454-d6dlm3lh05idd8npek18k6be8ba3oc68.apps.googleusercontent.com";
+const CLIENT_SECRET: &str = "SboVhoG9s0rNafixCSGGKXAT";
+const SCOPES: &str = "http://gdata.youtube.com https://www.googleapis.com/auth/youtube";
+const DEVICE_CODE_URL: &str = "https://www.youtube.com/o/oauth2/device/code";
+const TOKEN_URL: &str = "https://www.youtube.com/o/oauth2/token";
+const DEVICE_GRANT_TYPE: &str = "http://oauth.net/grant_type/device/1.0";
+/// Tokens are refreshed this long before they expire, so they don't expire in the middle of a request.
+const EXPIRY_MARGIN_SECS: u64 = 60;
+
+/// Access and refresh tokens of a signed in account.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct OAuthToken {
+    pub access_token: String,
+    pub refresh_token: String,
+    /// Expiry of `access_token` as seconds since the unix epoch.
+    pub expires_at: u64,
+}
+
+impl OAuthToken {
+    fn is_expired(&self) -> bool {
+        now_secs() + EXPIRY_MARGIN_SECS >= self.expires_at
+    }
+}
+
+/// Code to sign in with on another device, returned by `Tydle::oauth_device_flow`.
+#[cfg_attr(
+    target_arch = "wasm32",
+    derive(serde::Serialize, serde::Deserialize, tsify::Tsify),
+    tsify(into_wasm_abi, from_wasm_abi),
+    serde(rename_all = "camelCase")
+)]
+#[derive(Debug, Clone)]
+pub struct OAuthDeviceCode {
+    /// Page the user has to open to enter `user_code`, usually `https://www.google.com/device`.
+    pub verification_url: String,
+    pub user_code: String,
+    /// Code to poll for the token with, not meant to be shown to the user.
+    pub device_code: String,
+    /// Seconds to wait between polls.
+    pub interval: u64,
+    /// Seconds the codes are valid for.
+    pub expires_in: u64,
+}
+
+/// Storage of the OAuth token of a `Tydle` instance, implement it to keep the token in a database
+/// or share it between processes. Tokens are stored whenever they are issued or refreshed.
+pub trait TokenStore {
+    fn load(&self) -> Result<Option<OAuthToken>>;
+    fn save(&self, token: &OAuthToken) -> Result<()>;
+    /// Forget the stored token, after signing out.
+    fn clear(&self) -> Result<()>;
+}
+
+/// Keeps the token in memory only, the account has to sign in again after a restart. Used by default.
+#[derive(Default)]
+pub struct MemoryTokenStore {
+    token: Mutex<Option<OAuthToken>>,
+}
+
+impl TokenStore for MemoryTokenStore {
+    fn load(&self) -> Result<Option<OAuthToken>> {
+        Ok(self
+            .token
+            .lock()
+            .map_err(|_| anyhow!("Token store lock poisoned."))?
+            .clone())
+    }
+
+    fn save(&self, token: &OAuthToken) -> Result<()> {
+        *self
+            .token
+            .lock()
+            .map_err(|_| anyhow!("Token store lock poisoned."))? = Some(token.clone());
+        Ok(())
+    }
+
+    fn clear(&self) -> Result<()> {
+        *self
+            .token
+            .lock()
+            .map_err(|_| anyhow!("Token store lock poisoned."))? = None;
+        Ok(())
+    }
+}
+
+/// Keeps the token in a JSON file, so the account stays signed in across restarts.
+#[cfg(not(target_arch = "wasm32"))]
+pub struct FileTokenStore {
+    path: PathBuf,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl FileTokenStore {
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl TokenStore for FileTokenStore {
+    fn load(&self) -> Result<Option<OAuthToken>> {
+        match std::fs::read_to_string(&self.path) {
+            Ok(contents) => Ok(Some(serde_json::from_str(&contents)?)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Write to a temporary file first and then rename it, so a crash never leaves a truncated token behind.
+    fn save(&self, token: &OAuthToken) -> Result<()> {
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+
+        let temp_path = self.path.with_extension("tmp");
+
+        std::fs::write(&temp_path, serde_json::to_string(token)?)?;
+        std::fs::rename(&temp_path, &self.path)?;
+
+        Ok(())
+    }
+
+    fn clear(&self) -> Result<()> {
+        match std::fs::remove_file(&self.path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn now_secs() -> u64 {
+    now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+async fn sleep(duration: Duration) {
+    #[cfg(not(target_arch = "wasm32"))]
+    tokio::time::sleep(duration).await;
+
+    #[cfg(target_arch = "wasm32")]
+    {
+        use wasm_bindgen::{JsCast, JsValue};
+
+        let promise = js_sys::Promise::new(&mut |resolve, _| {
+            if let Some(set_timeout) =
+                js_sys::Reflect::get(&js_sys::global(), &JsValue::from_str("setTimeout"))
+                    .ok()
+                    .and_then(|f| f.dyn_into::<js_sys::Function>().ok())
+            {
+                let _ = set_timeout.call2(
+                    &JsValue::NULL,
+                    &resolve,
+                    &JsValue::from_f64(duration.as_millis() as f64),
+                );
+            }
+        });
+        let _ = wasm_bindgen_futures::JsFuture::from(promise).await;
+    }
+}
+
+/// Token of a signed in account, refreshed once it expires or YouTube rejects it.
+pub(crate) struct OAuthSession {
+    store: Arc<dyn TokenStore + Send + Sync>,
+    /// Held while refreshing, so concurrent requests wait for a single refresh rather than each refreshing.
+    token: AsyncMutex<Option<OAuthToken>>,
+    has_token: AtomicBool,
+}
+
+impl OAuthSession {
+    pub fn new(store: Arc<dyn TokenStore + Send + Sync>) -> Result<Self> {
+        let token = store.load()?;
+
+        Ok(Self {
+            has_token: AtomicBool::new(token.is_some()),
+            token: AsyncMutex::new(token),
+            store,
+        })
+    }
+
+    pub fn has_token(&self) -> bool {
+        self.has_token.load(Ordering::Relaxed)
+    }
+
+    async fn request_token(
+        &self,
+        http_client: &reqwest::Client,
+        body: Value,
+    ) -> Result<Result<Value, String>> {
+        let response: Value = http_client
+            .post(TOKEN_URL)
+            .json(&body)
+            .send()
+            .await?
+            .json()
+            .await?;
+
+        match response.get("error").and_then(|e| e.as_str()) {
+            Some(error) => Ok(Err(error.to_string())),
+            None => Ok(Ok(response)),
+        }
+    }
+
+    fn store_token(&self, slot: &mut Option<OAuthToken>, token: OAuthToken) -> Result<()> {
+        self.store.save(&token)?;
+        *slot = Some(token);
+        self.has_token.store(true, Ordering::Relaxed);
+
+        Ok(())
+    }
+
+    fn parse_token(response: &Value, refresh_token: Option<&str>) -> Result<OAuthToken> {
+        let access_token = response
+            .get("access_token")
+            .and_then(|t| t.as_str())
+            .ok_or_else(|| anyhow!("YouTube didn't return an access token."))?;
+        // Refreshing keeps the refresh token the same, so YouTube doesn't send it again.
+        let refresh_token = response
+            .get("refresh_token")
+            .and_then(|t| t.as_str())
+            .or(refresh_token)
+            .ok_or_else(|| anyhow!("YouTube didn't return a refresh token."))?;
+        let expires_in = response
+            .get("expires_in")
+            .and_then(|e| e.as_u64())
+            .unwrap_or_default();
+
+        Ok(OAuthToken {
+            access_token: access_token.to_string(),
+            refresh_token: refresh_token.to_string(),
+            expires_at: now_secs() + expires_in,
+        })
+    }
+
+    pub async fn request_device_code(
+        &self,
+        http_client: &reqwest::Client,
+    ) -> Result<OAuthDeviceCode> {
+        // Only has to be unique per sign in, YouTube lists it among the devices of the account.
+        let device_id = format!(
+            "{:x}",
+            Sha1::digest(
+                now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_nanos()
+                    .to_le_bytes()
+            )
+        );
+
+        let response: Value = http_client
+            .post(DEVICE_CODE_URL)
+            .json(&json!({
+                "client_id": CLIENT_ID,
+                "scope": SCOPES,
+                "device_id": &device_id[..32],
+                "device_model": "ytlr::",
+            }))
+            .send()
+            .await?
+            .json()
+            .await?;
+
+        let get_str = |key: &str| {
+            response
+                .get(key)
+                .and_then(|v| v.as_str())
+                .map(|v| v.to_string())
+                .ok_or_else(|| anyhow!("YouTube didn't return a device code: {}", response))
+        };
+
+        Ok(OAuthDeviceCode {
+            verification_url: get_str("verification_url")?,
+            user_code: get_str("user_code")?,
+            device_code: get_str("device_code")?,
+            interval: response
+                .get("interval")
+                .and_then(|i| i.as_u64())
+                .unwrap_or(5),
+            expires_in: response
+                .get("expires_in")
+                .and_then(|e| e.as_u64())
+                .unwrap_or(1800),
+        })
+    }
+
+    /// Poll until the user entered the code of `device_code`, then store the token.
+    pub async fn wait_for_token(
+        &self,
+        http_client: &reqwest::Client,
+        device_code: &OAuthDeviceCode,
+    ) -> Result<()> {
+        let mut interval = device_code.interval.max(1);
+        let deadline = now_secs() + device_code.expires_in;
+
+        while now_secs() < deadline {
+            sleep(Duration::from_secs(interval)).await;
+
+            let response = self
+                .request_token(
+                    http_client,
+                    json!({
+                        "client_id": CLIENT_ID,
+                        "client_secret": CLIENT_SECRET,
+                        "code": device_code.device_code,
+                        "grant_type": DEVICE_GRANT_TYPE,
+                    }),
+                )
+                .await?;
+
+            match response {
+                Ok(response) => {
+                    let token = Self::parse_token(&response, None)?;
+                    let mut slot = self.token.lock().await;
+
+                    #[cfg(feature = "logging")]
+                    log::info!("Signed in to YouTube with OAuth.");
+
+                    return self.store_token(&mut slot, token);
+                }
+                Err(error) if error == "authorization_pending" => {}
+                Err(error) if error == "slow_down" => interval += 5,
+                Err(error) if error == "access_denied" => {
+                    bail!("The sign in was denied on the verification page.")
+                }
+                Err(error) => bail!("Failed to sign in with OAuth: {}", error),
+            }
+        }
+
+        bail!("The device code expired before it was entered, request a new one.")
+    }
+
+    async fn refresh(
+        &self,
+        http_client: &reqwest::Client,
+        slot: &mut Option<OAuthToken>,
+    ) -> Result<String> {
+        let Some(refresh_token) = slot.as_ref().map(|token| token.refresh_token.clone()) else {
+            bail!("There is no OAuth token to refresh, sign in again.")
+        };
+
+        #[cfg(feature = "logging")]
+        log::info!("Refreshing OAuth access token.");
+
+        let response = self
+            .request_token(
+                http_client,
+                json!({
+                    "client_id": CLIENT_ID,
+                    "client_secret": CLIENT_SECRET,
+                    "refresh_token": refresh_token,
+                    "grant_type": "refresh_token",
+                }),
+            )
+            .await?
+            .map_err(|error| {
+                anyhow!(
+                    "Failed to refresh the OAuth token, sign in again: {}",
+                    error
+                )
+            })?;
+
+        let token = Self::parse_token(&response, Some(&refresh_token))?;
+        let access_token = token.access_token.clone();
+        self.store_token(slot, token)?;
+
+        Ok(access_token)
+    }
+
+    /// Get a valid access token, refreshing it first if it expired. `None` when not signed in.
+    pub async fn access_token(&self, http_client: &reqwest::Client) -> Result<Option<String>> {
+        let mut slot = self.token.lock().await;
+
+        match slot.as_ref() {
+            None => Ok(None),
+            Some(token) if !token.is_expired() => Ok(Some(token.access_token.clone())),
+            Some(_) => Ok(Some(self.refresh(http_client, &mut slot).await?)),
+        }
+    }
+
+    /// Refresh after YouTube rejected `rejected_token`, unless a concurrent request already did.
+    async fn refresh_rejected(
+        &self,
+        http_client: &reqwest::Client,
+        rejected_token: &str,
+    ) -> Result<String> {
+        let mut slot = self.token.lock().await;
+
+        match slot.as_ref() {
+            Some(token) if token.access_token != rejected_token => Ok(token.access_token.clone()),
+            _ => self.refresh(http_client, &mut slot).await,
+        }
+    }
+
+    /// Send `request` with the access token, refreshing and retrying once if YouTube answers with 401.
+    pub async fn send(
+        &self,
+        http_client: &reqwest::Client,
+        request: RequestBuilder,
+    ) -> Result<Response> {
+        let Some(access_token) = self.access_token(http_client).await? else {
+            return Ok(request.send().await?);
+        };
+
+        let retry = request.try_clone();
+        let response = request.bearer_auth(&access_token).send().await?;
+
+        match retry {
+            Some(retry) if response.status() == StatusCode::UNAUTHORIZED => {
+                let access_token = self.refresh_rejected(http_client, &access_token).await?;
+                Ok(retry.bearer_auth(access_token).send().await?)
+            }
+            _ => Ok(response),
+        }
+    }
+
+    pub async fn sign_out(&self) -> Result<()> {
+        let mut slot = self.token.lock().await;
+
+        self.store.clear()?;
+        *slot = None;
+        self.has_token.store(false, Ordering::Relaxed);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,237 @@
+//! Minimal HTTP/1.1 server for tests that need to count or inspect the requests the crate makes.
+
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+#[derive(Debug, Clone)]
+pub(crate) struct TestRequest {
+    /// Path of the request including its query, like `/watch?v=abc`.
+    pub path: String,
+    /// Headers of the request, keyed by their lowercase name.
+    pub headers: HashMap<String, String>,
+}
+
+impl TestRequest {
+    /// The `start` and inclusive `end` of a `Range: bytes=<start>-<end>` header, `end` being optional.
+    pub fn range(&self) -> Option<(u64, Option<u64>)> {
+        let (start, end) = self
+            .headers
+            .get("range")?
+            .strip_prefix("bytes=")?
+            .split_once('-')?;
+
+        Some((start.parse().ok()?, end.parse().ok()))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct TestResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+    /// Close the connection after sending this many bytes of the body, like a dropped connection.
+    pub truncate_after: Option<usize>,
+    /// Wait this long before responding.
+    pub delay: Option<Duration>,
+}
+
+impl TestResponse {
+    pub fn ok(body: impl Into<Vec<u8>>) -> Self {
+        Self {
+            status: 200,
+            headers: Vec::new(),
+            body: body.into(),
+            truncate_after: None,
+            delay: None,
+        }
+    }
+
+    pub fn status(status: u16) -> Self {
+        Self {
+            status,
+            ..Self::ok(Vec::new())
+        }
+    }
+
+    pub fn redirect(location: &str) -> Self {
+        Self::status(302).header("Location", location)
+    }
+
+    /// Serve `body` honoring the `Range` header of `request`, like googlevideo does.
+    pub fn ranged(request: &TestRequest, body: &[u8]) -> Self {
+        let Some((start, end)) = request.range() else {
+            return Self::ok(body);
+        };
+
+        let length = body.len() as u64;
+        if start >= length {
+            return Self::status(416).header("Content-Range", &format!("bytes */{}", length));
+        }
+
+        let end = end.unwrap_or(length - 1).min(length - 1);
+        Self {
+            status: 206,
+            ..Self::ok(&body[start as usize..=end as usize])
+        }
+        .header(
+            "Content-Range",
+            &format!("bytes {}-{}/{}", start, end, length),
+        )
+    }
+
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    pub fn truncate_after(mut self, bytes: usize) -> Self {
+        self.truncate_after = Some(bytes);
+        self
+    }
+
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.delay = Some(delay);
+        self
+    }
+}
+
+type Handler = dyn Fn(&TestRequest) -> TestResponse + Send + Sync;
+
+pub(crate) struct TestServer {
+    addr: SocketAddr,
+    requests: Arc<Mutex<Vec<TestRequest>>>,
+}
+
+impl TestServer {
+    /// Serve every request with `handler` on a random local port until the runtime shuts down.
+    pub async fn start<F>(handler: F) -> Self
+    where
+        F: Fn(&TestRequest) -> TestResponse + Send + Sync + 'static,
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let handler: Arc<Handler> = Arc::new(handler);
+
+        let server_requests = requests.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let handler = handler.clone();
+                let requests = server_requests.clone();
+
+                tokio::spawn(async move {
+                    let _ = serve(stream, handler, requests).await;
+                });
+            }
+        });
+
+        Self { addr, requests }
+    }
+
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    pub fn url(&self, path: &str) -> String {
+        format!("http://{}{}", self.addr, path)
+    }
+
+    pub fn requests(&self) -> Vec<TestRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// Number of requests received for paths starting with `prefix`.
+    pub fn count(&self, prefix: &str) -> usize {
+        self.requests
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|request| request.path.starts_with(prefix))
+            .count()
+    }
+}
+
+async fn serve(
+    mut stream: TcpStream,
+    handler: Arc<Handler>,
+    requests: Arc<Mutex<Vec<TestRequest>>>,
+) -> std::io::Result<()> {
+    let mut head = Vec::new();
+    let mut byte = [0];
+
+    // Read byte by byte so nothing past the head is consumed, requests never have a body here.
+    while !head.ends_with(b"\r\n\r\n") {
+        if stream.read(&mut byte).await? == 0 {
+            return Ok(());
+        }
+        head.push(byte[0]);
+    }
+
+    let head = String::from_utf8_lossy(&head);
+    let mut lines = head.lines();
+    let path = lines
+        .next()
+        .and_then(|line| line.split(' ').nth(1))
+        .unwrap_or("/")
+        .to_string();
+    let headers = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_ascii_lowercase(), value.trim().to_string()))
+        .collect();
+
+    let request = TestRequest { path, headers };
+    requests.lock().unwrap().push(request.clone());
+    let response = handler(&request);
+
+    if let Some(delay) = response.delay {
+        tokio::time::sleep(delay).await;
+    }
+
+    let mut head = format!(
+        "HTTP/1.1 {} Test\r\nContent-Length: {}\r\nConnection: close\r\n",
+        response.status,
+        response.body.len()
+    );
+    for (name, value) in &response.headers {
+        head.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    head.push_str("\r\n");
+
+    stream.write_all(head.as_bytes()).await?;
+
+    let body = match response.truncate_after {
+        Some(bytes) => &response.body[..bytes.min(response.body.len())],
+        None => &response.body,
+    };
+    stream.write_all(body).await?;
+    stream.flush().await?;
+    stream.shutdown().await
+}
+
+/// `YtExtractor` with in-memory caches and a fresh cookie jar that sends its requests through `http_client`,
+/// including the ones carrying cookies.
+pub(crate) fn test_extractor(
+    options: crate::tydle::TydleOptions,
+    http_client: reqwest::Client,
+) -> crate::extractor::extract::YtExtractor {
+    let mut extractor = crate::extractor::extract::YtExtractor::new(
+        Arc::new(crate::cache::CacheStore::new()),
+        Arc::new(crate::cache::CacheStore::new()),
+        Arc::new(crate::cookies::CookieJar::new()),
+        options,
+    )
+    .unwrap();
+    extractor.cookie_client = http_client.clone();
+    extractor.http_client = http_client;
+
+    extractor
+}
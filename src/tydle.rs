@@ -1,21 +1,94 @@
-use anyhow::{Result, anyhow};
+use anyhow::{Result, anyhow, bail};
+#[cfg(not(target_arch = "wasm32"))]
+use bytes::Bytes;
+#[cfg(not(target_arch = "wasm32"))]
+use futures::{Stream, stream::try_unfold};
 use std::collections::HashMap;
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::{Path, PathBuf};
 use std::pin::Pin;
-use std::{
-    future::Future,
-    sync::{Arc, Mutex},
-};
+use std::sync::Mutex;
+use std::time::Duration;
+use std::time::SystemTime;
+use std::{future::Future, sync::Arc};
 #[cfg(target_arch = "wasm32")]
 use wasm_bindgen::prelude::wasm_bindgen;
 
-use crate::cache::CacheStore;
-use crate::cipher::decipher::{SignatureDecipher, SignatureDecipherHandle};
-use crate::yt_interface::{YtManifest, YtStreamResponse, YtVideoInfo};
+#[cfg(target_arch = "wasm32")]
+use crate::cache::LocalStorageCache;
+use crate::cache::{CacheBackend, CacheStats, SingleFlight};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::cache::{CacheOptions, CacheStore};
+use crate::cipher::{
+    decipher::{SignatureDecipher, SignatureDecipherHandle},
+    js::SignatureJsHandle,
+};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::cookies::save_cookies;
+use crate::cookies::{CookieJar, CookieStore};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::download::{
+    DownloadOptions, DownloadSummary, Progress, RangeReader, ResumePolicy, discard_part,
+    download_to_file,
+};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::error::StreamLengthMismatch;
+use crate::error::{DecipherError, FormatGone, StreamsUnresolved};
+#[cfg(all(feature = "mux", not(target_arch = "wasm32")))]
+use crate::mux::{MuxContainer, intermediate_path, mux, stream_options};
+#[cfg(feature = "oauth")]
+use crate::oauth::{OAuthDeviceCode, OAuthSession, TokenStore};
+use crate::utils::now;
+#[cfg(all(feature = "mux", not(target_arch = "wasm32")))]
+use crate::yt_interface::BestStreamOptions;
+use crate::yt_interface::{
+    ChannelId, ClipId, ClipInfo, Continuation, OEmbedInfo, Page, PlayerInfo, PlaylistId,
+    YtAvailability, YtCaptionCue, YtCaptionTrack, YtChannelVideo, YtChannelVideos,
+    YtChannelVideosOrder, YtChannelVideosPage, YtClient, YtComment, YtCommentPage, YtCommentSort,
+    YtHlsVariant, YtManifest, YtMix, YtPaginated, YtPlaylist, YtPlaylistEntry, YtRelatedVideo,
+    YtRelatedVideos, YtSearchFilters, YtSearchResponse, YtSearchResult, YtStream, YtStreamCheck,
+    YtStreamResponse, YtStreamSource, YtTrending, YtTrendingCategory, YtVideoInfo,
+};
 use crate::{
-    extractor::extract::{InfoExtractor, YtExtractor},
+    extractor::{
+        availability::ExtractorAvailabilityHandle,
+        captions::ExtractorCaptionsHandle,
+        channel::ExtractorChannelHandle,
+        clip::ExtractorClipHandle,
+        comments::ExtractorCommentsHandle,
+        continuation::ExtractorContinuationHandle,
+        cookies::ExtractorCookieHandle,
+        download::ExtractorDownloadHandle,
+        extract::{InfoExtractor, YtExtractor},
+        hls::ExtractorHlsHandle,
+        mix::ExtractorMixHandle,
+        oembed::ExtractorOEmbedHandle,
+        otf::ExtractorOtfHandle,
+        player::ExtractorPlayerHandle,
+        playlist::ExtractorPlaylistHandle,
+        related::ExtractorRelatedHandle,
+        search::ExtractorSearchHandle,
+        trending::ExtractorTrendingHandle,
+    },
     yt_interface::VideoId,
 };
 
+/// A player downloaded again because its stream URLs stopped working isn't downloaded again for this long,
+/// since when that didn't help, doing it again won't either.
+const PLAYER_REFRESH_COOLDOWN: Duration = Duration::from_secs(5 * 60);
+/// YouTube rotates the player regularly, so cached player code and deciphered signatures are refetched after this.
+pub(crate) const DEFAULT_CACHE_TTL: Duration = Duration::from_secs(4 * 60 * 60);
+/// Fits around 20 player.js files, which are a few megabytes each.
+#[cfg(not(target_arch = "wasm32"))]
+const CODE_CACHE_MAX_BYTES: usize = 64 * 1024 * 1024;
+/// Browsers give each origin around 5 MB of `localStorage`, enough for one player.js and the solver.
+#[cfg(target_arch = "wasm32")]
+const CODE_LOCAL_STORAGE_MAX_BYTES: usize = 4 * 1024 * 1024;
+#[cfg(target_arch = "wasm32")]
+const PLAYER_LOCAL_STORAGE_MAX_BYTES: usize = 256 * 1024;
+/// Short enough for a video that was made public again to be picked up soon.
+pub(crate) const DEFAULT_UNAVAILABLE_VIDEO_TTL_SECS: u64 = 5 * 60;
+
 #[cfg_attr(
     target_arch = "wasm32",
     derive(serde::Serialize, serde::Deserialize, tsify::Tsify),
@@ -27,32 +100,826 @@ use crate::{
 pub struct TydleOptions {
     /// Map of cookies extracted from an authenticated YouTube account.
     pub auth_cookies: HashMap<String, String>,
+    /// Send the account cookies (`LOGIN_INFO`, `SAPISID`, ...) and the `SAPISIDHASH` authorization to YouTube.
+    /// Required to extract age-restricted videos, off by default to not hand out a logged-in session unintentionally.
+    pub authenticate: bool,
+    /// Don't accept YouTube's cookie consent automatically by setting the `SOCS` and `CONSENT` cookies,
+    /// for when the cookie store already manages consent.
+    pub disable_consent_bypass: bool,
+    /// Don't retry age-restricted videos with the TV embedded client when not logged in,
+    /// which plays many of them without an account.
+    pub disable_age_gate_bypass: bool,
+    /// Remember videos that are private, deleted or from a terminated account and fail
+    /// with the same `VideoUnavailable` error without requesting YouTube again.
+    pub cache_unavailable_videos: bool,
+    /// How long unavailable videos are remembered for, defaults to 5 minutes.
+    pub unavailable_video_ttl_secs: Option<u64>,
+    /// Check the URLs `resolve_stream_url` returns with a request for their first byte. URLs googlevideo
+    /// rejects are resolved again once with a freshly downloaded player, after which they fail with
+    /// `DecipherError::UrlRejected` instead of later, in the middle of a download.
+    pub validate_stream_urls: bool,
+    /// Language of the text YouTube returns, like titles and view counts, as a code like `en` or `de`.
+    /// Defaults to `en`. Some reasons of `VideoUnavailable` are told apart by YouTube's English messages,
+    /// so they may be less specific in other languages.
+    pub hl: Option<String>,
+    /// Country whose availability and localized titles YouTube returns, as a code like `US` or `DE`.
+    /// Defaults to the one YouTube guesses from the IP address.
+    pub gl: Option<String>,
+    /// Request the player response from this client first, only falling back to `fallback_clients` if it fails.
+    /// Without one, the streams of all the fallback clients are merged instead. Clients differ in the streams they
+    /// return, `YtClient::Android` and `YtClient::IOS` give URLs that don't need deciphering while `YtClient::IOS`
+    /// also has HLS manifests for videos. Age-restricted videos may still be retried with the embedded clients.
+    pub client: Option<YtClient>,
+    /// Clients to try in order when a client's video is unplayable, requires a login or has no streams.
+    /// Defaults to a list picked based on the account, pass an empty list to not fall back at all.
+    pub fallback_clients: Option<Vec<YtClient>>,
+    /// `visitorData` to send to YouTube instead of the one it assigns, required along with `po_token`
+    /// since PO tokens are bound to the visitor they were minted for.
+    /// Without one, the first one YouTube assigns is kept and sent for the lifetime of the instance.
+    pub visitor_data: Option<String>,
+    /// Proof of origin token minted elsewhere, like with BgUtils, sent with the player requests.
+    /// Needed once YouTube starts answering with `VideoUnavailable::BotCheck` or serving streams that 403.
+    pub po_token: Option<String>,
+    /// Stop following the continuations of a playlist after this many pages of around 100 videos each,
+    /// all pages are fetched by default.
+    pub max_playlist_pages: Option<usize>,
+    /// Directory to persist the player and code caches in, so the player doesn't have to be
+    /// downloaded again on every run. Can be shared by multiple processes.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub cache_dir: Option<PathBuf>,
     /// Attempts to fetch over http instead of https.
     pub prefer_insecure: bool,
     /// Provide an address to set it as the `X-Forwarded-For` header when requesting YouTube.
     pub source_address: String,
 }
 
+/// Statistics of the caches a `Tydle` instance uses.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TydleCacheStats {
+    /// Cache of player data, like the signature timestamp and deciphered signatures.
+    pub player: CacheStats,
+    /// Cache of the player JavaScript and the signature solver code.
+    pub code: CacheStats,
+}
+
+/// Clones share the extractor, the cookie store and the caches of the original instance.
 #[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+#[derive(Clone)]
 pub struct Tydle {
-    yt_extractor: Arc<Mutex<YtExtractor>>,
-    signature_decipher: Arc<Mutex<SignatureDecipher>>,
+    yt_extractor: Arc<YtExtractor>,
+    signature_decipher: Arc<SignatureDecipher>,
+    player_cache: Arc<dyn CacheBackend + Send + Sync>,
+    code_cache: Arc<dyn CacheBackend + Send + Sync>,
+    /// When each player was last downloaded again because its stream URLs stopped working.
+    player_refreshes: Arc<Mutex<HashMap<String, SystemTime>>>,
+    player_refresh_flights: Arc<SingleFlight>,
 }
 
 impl Tydle {
     #[cfg(not(target_arch = "wasm32"))]
     pub fn new(options: TydleOptions) -> Result<Self> {
-        let player_cache = Arc::new(CacheStore::new());
-        let code_cache = Arc::new(CacheStore::new());
+        Self::with_cookie_jar(options, CookieJar::new())
+    }
+
+    /// Create a `Tydle` instance that sends the cookies of an existing `CookieJar`,
+    /// for example one imported from a browser with `CookieJar::from_netscape_file`.
+    pub fn with_cookie_jar(options: TydleOptions, cookie_jar: CookieJar) -> Result<Self> {
+        Self::with_cookie_store(options, Arc::new(cookie_jar))
+    }
+
+    /// Create a `Tydle` instance backed by a custom `CookieStore` implementation,
+    /// for example one that shares a YouTube session between multiple processes.
+    ///
+    /// Persist the store on shutdown with `Tydle::save_cookies`,
+    /// or keep a clone of the `Arc` to access it directly.
+    pub fn with_cookie_store(
+        options: TydleOptions,
+        cookie_store: Arc<dyn CookieStore + Send + Sync>,
+    ) -> Result<Self> {
+        let (player_cache, code_cache) = Self::default_caches(&options);
+        Self::from_parts(options, cookie_store, player_cache, code_cache)
+    }
+
+    /// Create a `Tydle` instance that keeps both the player and code caches in a custom `CacheBackend`,
+    /// for example one backed by Redis so multiple workers share the downloaded player.
+    pub fn with_cache_backend(
+        options: TydleOptions,
+        cache_backend: Arc<dyn CacheBackend + Send + Sync>,
+    ) -> Result<Self> {
+        Self::from_parts(
+            options,
+            Arc::new(CookieJar::new()),
+            cache_backend.clone(),
+            cache_backend,
+        )
+    }
+
+    /// Create a `Tydle` instance with existing player and code caches, so the downloaded player
+    /// is shared with other instances, e.g. when creating one instance per request in a web server.
+    ///
+    /// The caches of an instance can be retrieved with `Tydle::player_cache` and `Tydle::code_cache`.
+    pub fn with_caches(
+        options: TydleOptions,
+        player_cache: Arc<dyn CacheBackend + Send + Sync>,
+        code_cache: Arc<dyn CacheBackend + Send + Sync>,
+    ) -> Result<Self> {
+        Self::from_parts(
+            options,
+            Arc::new(CookieJar::new()),
+            player_cache,
+            code_cache,
+        )
+    }
+
+    /// Create a `Tydle` instance that keeps the token of the account signed in with `oauth_device_flow`
+    /// in a custom `TokenStore`, like a `FileTokenStore` so the account stays signed in across restarts.
+    #[cfg(feature = "oauth")]
+    pub fn with_token_store(
+        options: TydleOptions,
+        token_store: Arc<dyn TokenStore + Send + Sync>,
+    ) -> Result<Self> {
+        let mut tydle = Self::with_cookie_jar(options, CookieJar::new())?;
+        let yt_extractor = Arc::get_mut(&mut tydle.yt_extractor)
+            .ok_or_else(|| anyhow!("The extractor of a new instance is shared."))?;
+
+        yt_extractor.oauth = OAuthSession::new(token_store)?;
+
+        Ok(tydle)
+    }
+
+    pub fn player_cache(&self) -> Arc<dyn CacheBackend + Send + Sync> {
+        self.player_cache.clone()
+    }
+
+    pub fn code_cache(&self) -> Arc<dyn CacheBackend + Send + Sync> {
+        self.code_cache.clone()
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn default_caches(
+        options: &TydleOptions,
+    ) -> (
+        Arc<dyn CacheBackend + Send + Sync>,
+        Arc<dyn CacheBackend + Send + Sync>,
+    ) {
+        let player_cache = Arc::new(CacheStore::with_options(CacheOptions {
+            default_ttl: Some(DEFAULT_CACHE_TTL),
+            dir: options.cache_dir.as_ref().map(|dir| dir.join("player")),
+            ..Default::default()
+        }));
+        let code_cache = Arc::new(CacheStore::with_options(CacheOptions {
+            default_ttl: Some(DEFAULT_CACHE_TTL),
+            max_entries: None,
+            max_bytes: Some(CODE_CACHE_MAX_BYTES),
+            dir: options.cache_dir.as_ref().map(|dir| dir.join("code")),
+        }));
 
-        let yt_extractor = YtExtractor::new(player_cache.clone(), code_cache.clone(), options)?;
-        let signature_decipher = SignatureDecipher::new(player_cache, code_cache);
+        (player_cache, code_cache)
+    }
+
+    /// In browsers the caches are persisted in `localStorage`, since a page load would empty them otherwise.
+    #[cfg(target_arch = "wasm32")]
+    fn default_caches(
+        _options: &TydleOptions,
+    ) -> (
+        Arc<dyn CacheBackend + Send + Sync>,
+        Arc<dyn CacheBackend + Send + Sync>,
+    ) {
+        let player_cache = Arc::new(LocalStorageCache::new(
+            "player",
+            Some(DEFAULT_CACHE_TTL),
+            PLAYER_LOCAL_STORAGE_MAX_BYTES,
+        ));
+        let code_cache = Arc::new(LocalStorageCache::new(
+            "code",
+            Some(DEFAULT_CACHE_TTL),
+            CODE_LOCAL_STORAGE_MAX_BYTES,
+        ));
+
+        (player_cache, code_cache)
+    }
+
+    fn from_parts(
+        options: TydleOptions,
+        cookie_store: Arc<dyn CookieStore + Send + Sync>,
+        player_cache: Arc<dyn CacheBackend + Send + Sync>,
+        code_cache: Arc<dyn CacheBackend + Send + Sync>,
+    ) -> Result<Self> {
+        let yt_extractor = YtExtractor::new(
+            player_cache.clone(),
+            code_cache.clone(),
+            cookie_store,
+            options,
+        )?;
+        let signature_decipher = SignatureDecipher::new(player_cache.clone(), code_cache.clone());
 
         Ok(Self {
-            yt_extractor: Arc::new(Mutex::new(yt_extractor)),
-            signature_decipher: Arc::new(Mutex::new(signature_decipher)),
+            yt_extractor: Arc::new(yt_extractor),
+            signature_decipher: Arc::new(signature_decipher),
+            player_cache,
+            code_cache,
+            player_refreshes: Arc::new(Mutex::new(HashMap::new())),
+            player_refresh_flights: Arc::new(SingleFlight::new()),
+        })
+    }
+
+    /// Download the current player and the signature solver and preprocess the player ahead of the first
+    /// extraction, so it only has to make the per-video API calls. Returns the URL of the warmed player.
+    ///
+    /// Calling it again only downloads what has expired from the caches in the meantime.
+    pub async fn warm_up(&self) -> Result<String> {
+        let player_url = self
+            .yt_extractor
+            .download_player_url(None)
+            .await?
+            .ok_or_else(|| anyhow!("Failed to find the URL of the current player."))?;
+        self.warm_up_player(player_url.clone()).await?;
+
+        Ok(player_url)
+    }
+
+    async fn warm_up_player(&self, player_url: String) -> Result<()> {
+        self.yt_extractor.warm_up_player(player_url.clone()).await?;
+        self.signature_decipher
+            .get_preprocessed_player(player_url)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Get the URL and version of the player YouTube serves for `video_id`, e.g. to log which player
+    /// a decipher failure happened with. Videos that were extracted before don't need a request.
+    pub async fn get_player_info(&self, video_id: &VideoId) -> Result<PlayerInfo> {
+        Ok(PlayerInfo::clone(
+            &*self.yt_extractor.get_player_info(video_id).await?,
+        ))
+    }
+
+    /// Put a `player.js` downloaded elsewhere into the code cache, so deciphering with `player_url`
+    /// doesn't download it again.
+    pub fn seed_player(&self, player_url: String, player_js: String) -> Result<()> {
+        self.signature_decipher.seed_player(player_url, player_js)
+    }
+
+    /// Put the ejs solver modules (`yt.solver.lib.min.js` and `yt.solver.core.min.js`) into the code cache,
+    /// for environments that can't download them from GitHub.
+    pub fn seed_solver(&self, lib_code: String, core_code: String) -> Result<()> {
+        self.signature_decipher.seed_js_modules(lib_code, core_code)
+    }
+
+    /// Serialize what was extracted from the player of `player_version` to decipher with it,
+    /// so it can be stored and imported with `import_plan` after a restart instead of parsing the player again.
+    pub fn export_plan(&self, player_version: &str) -> Result<Vec<u8>> {
+        self.signature_decipher.export_plan(player_version)
+    }
+
+    /// Restore a plan from `export_plan` for the player of `player_version`, see `PlayerInfo::version`.
+    /// Plans of other players are rejected, corrupted ones are ignored and return `false`.
+    pub fn import_plan(&self, bytes: &[u8], player_version: &str) -> Result<bool> {
+        self.signature_decipher.import_plan(bytes, player_version)
+    }
+
+    /// Drop everything cached about the player, so the next extraction downloads it again.
+    /// Useful when deciphered URLs start returning 403 because YouTube rotated the player.
+    pub fn purge_caches(&self) -> Result<()> {
+        self.player_cache.clear()?;
+        self.code_cache.clear()?;
+        self.signature_decipher.preprocessed_players.clear()?;
+        self.signature_decipher.signature_strategies.clear()?;
+        self.signature_decipher.player_keys.clear()
+    }
+
+    /// Start signing in to an account with YouTube's TV sign in, which doesn't need cookies from a browser.
+    /// Show the user `verification_url` and `user_code` of the returned code, then wait for them to enter it
+    /// with `oauth_wait_for_authorization`. The token is only sent to YouTube when `authenticate` is enabled.
+    ///
+    /// ```no_run
+    /// use tydle::{Tydle, TydleOptions};
+    /// use anyhow::Result;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///   let ty = Tydle::new(TydleOptions { authenticate: true, ..Default::default() })?;
+    ///
+    ///   let device_code = ty.oauth_device_flow().await?;
+    ///   println!("Enter {} at {}", device_code.user_code, device_code.verification_url);
+    ///
+    ///   ty.oauth_wait_for_authorization(&device_code).await?;
+    ///   Ok(())
+    /// }
+    /// ```
+    #[cfg(feature = "oauth")]
+    pub async fn oauth_device_flow(&self) -> Result<OAuthDeviceCode> {
+        self.yt_extractor
+            .oauth
+            .request_device_code(&self.yt_extractor.http_client)
+            .await
+    }
+
+    /// Poll until the user entered the code from `oauth_device_flow`, then store the token in the `TokenStore`.
+    /// Fails once the code expires or the user denies the sign in.
+    #[cfg(feature = "oauth")]
+    pub async fn oauth_wait_for_authorization(&self, device_code: &OAuthDeviceCode) -> Result<()> {
+        self.yt_extractor
+            .oauth
+            .wait_for_token(&self.yt_extractor.http_client, device_code)
+            .await
+    }
+
+    /// Forget the OAuth token, removing it from the `TokenStore` too.
+    #[cfg(feature = "oauth")]
+    pub async fn oauth_sign_out(&self) -> Result<()> {
+        self.yt_extractor.oauth.sign_out().await
+    }
+
+    /// Forget that `video_id` was unavailable, so the next extraction requests YouTube again.
+    /// Returns whether the video was remembered as unavailable.
+    pub fn clear_unavailable_video(&self, video_id: &VideoId) -> Result<bool> {
+        self.yt_extractor.unavailable_videos.invalidate(video_id)
+    }
+
+    /// Download a resolved stream to `dest`, creating the directories it's in. The stream is written to a
+    /// `.part` file next to `dest` first, which is only renamed to `dest` once all of it is there.
+    /// A `.part` file left behind by a download of the same stream that failed is resumed.
+    ///
+    /// A URL googlevideo refuses is refreshed or fails with `tydle::error::DecipherError::UrlRejected`,
+    /// depending on `DownloadOptions::resume_policy`.
+    ///
+    /// ```no_run
+    /// use std::path::Path;
+    /// use tydle::{Tydle, TydleOptions, Cipher, Extract, VideoId, download::DownloadOptions};
+    /// use anyhow::Result;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///   let ty = Tydle::new(TydleOptions{ ..Default::default() })?;
+    ///
+    ///   let mut stream_response = ty.get_streams(&VideoId::new("dQw4w9WgXcQ")?).await?;
+    ///   ty.resolve_streams(&mut stream_response).await?;
+    ///
+    ///   let options = DownloadOptions {
+    ///     on_progress: Some(Box::new(|progress| println!("{} bytes", progress.bytes_done))),
+    ///     ..Default::default()
+    ///   };
+    ///   let stream = stream_response.best_audio().unwrap();
+    ///   let summary = ty.download(stream, Path::new("audio.webm"), options).await?;
+    ///
+    ///   println!("Downloaded {} bytes in {:?}", summary.bytes, summary.elapsed);
+    ///   Ok(())
+    /// }
+    /// ```
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn download(
+        &self,
+        stream: &YtStream,
+        dest: &Path,
+        options: DownloadOptions,
+    ) -> Result<DownloadSummary> {
+        let YtStreamSource::URL(url) = &stream.source else {
+            bail!(
+                "Failed to download the stream because its signature isn't deciphered yet, resolve it with `resolve_stream_url` first."
+            )
+        };
+
+        let mut url = url.clone();
+        let mut progress = Progress::new(options)?;
+        let mut refreshed = false;
+
+        loop {
+            let verified = match progress.verify_length() {
+                true => self.verify_length(stream, &url).await,
+                false => Ok(()),
+            };
+            let e = match verified {
+                Ok(()) => match download_to_file(
+                    &self.yt_extractor.http_client,
+                    &url,
+                    stream,
+                    dest,
+                    &mut progress,
+                )
+                .await
+                {
+                    Ok(summary) => return Ok(summary),
+                    Err(e) => e,
+                },
+                Err(e) => e,
+            };
+
+            if *progress.resume_policy() == ResumePolicy::Restart
+                && matches!(
+                    e.downcast_ref::<DecipherError>(),
+                    Some(DecipherError::UrlRejected { .. })
+                )
+            {
+                discard_part(dest).await;
+                return Err(e);
+            }
+
+            // Keeps the `.part` file when the refresh fails, a later download may have better luck.
+            url = self
+                .refresh_rejected_url(e, stream, progress.resume_policy(), &mut refreshed)
+                .await?;
+        }
+    }
+
+    /// Download the best video-only and audio-only streams of a video that `out_path` can hold, going by its
+    /// extension, and mux them into it with `ffmpeg`. Progress is reported for both downloads as one.
+    ///
+    /// The streams are downloaded next to `out_path` first, like `video.f137.mp4`, and removed once
+    /// they're muxed. See `tydle::mux` for what's needed of `ffmpeg`.
+    ///
+    /// ```no_run
+    /// use std::path::Path;
+    /// use tydle::{Tydle, TydleOptions, VideoId, BestStreamOptions, download::DownloadOptions};
+    /// use anyhow::Result;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///   let ty = Tydle::new(TydleOptions{ ..Default::default() })?;
+    ///
+    ///   let selection = BestStreamOptions { max_height: Some(1080), ..Default::default() };
+    ///   let summary = ty
+    ///     .download_muxed(&VideoId::new("dQw4w9WgXcQ")?, &selection, Path::new("video.mp4"), DownloadOptions::default())
+    ///     .await?;
+    ///
+    ///   println!("Downloaded {} bytes in {:?}", summary.bytes, summary.elapsed);
+    ///   Ok(())
+    /// }
+    /// ```
+    #[cfg(all(feature = "mux", not(target_arch = "wasm32")))]
+    pub async fn download_muxed(
+        &self,
+        video_id: &VideoId,
+        selection: &BestStreamOptions,
+        out_path: &Path,
+        mut options: DownloadOptions,
+    ) -> Result<DownloadSummary> {
+        let container = MuxContainer::from_path(out_path)?;
+
+        let mut stream_response = self.get_streams(video_id).await?;
+        stream_response
+            .streams
+            .retain(|stream| container.can_hold(stream));
+
+        let (Some(video), Some(audio)) = (
+            stream_response.best_video_with(selection),
+            stream_response.best_audio_with(selection),
+        ) else {
+            bail!(
+                "Failed to find a video and an audio stream of {} that can be muxed into {}.",
+                video_id.as_str(),
+                container.as_str()
+            )
+        };
+
+        let mut streams = [video.clone(), audio.clone()];
+        for stream in &mut streams {
+            let url = self
+                .resolve_stream_url(stream, stream_response.player_url.clone())
+                .await?;
+            stream.source = YtStreamSource::URL(url);
+        }
+        let [video, audio] = streams;
+
+        let on_progress = options
+            .on_progress
+            .take()
+            .map(|on_progress| Arc::new(Mutex::new(on_progress)));
+        let total = video.file_size.zip(audio.file_size).map(|(v, a)| v + a);
+        let video_path = intermediate_path(out_path, &video);
+        let audio_path = intermediate_path(out_path, &audio);
+
+        let video_summary = self
+            .download(
+                &video,
+                &video_path,
+                stream_options(&options, on_progress.clone(), 0, total),
+            )
+            .await?;
+        let audio_summary = self
+            .download(
+                &audio,
+                &audio_path,
+                stream_options(&options, on_progress, video_summary.bytes, total),
+            )
+            .await?;
+
+        mux(&video_path, &audio_path, out_path, container).await?;
+
+        Ok(video_summary.merged(audio_summary))
+    }
+
+    /// Read a resolved stream as it downloads, to pass it on somewhere other than a file, like a response body.
+    /// The stream is requested in ranges of `DownloadOptions::chunk_size` bytes just like `download` does,
+    /// but only as fast as the chunks are taken, nothing is buffered ahead of a slow reader.
+    ///
+    /// Progress is reported and `max_rate` and `resume_policy` are followed the same way, though
+    /// `parallelism` and `verify_length` are ignored and nothing is left behind to resume if the stream
+    /// fails midway. Call `Cipher::verify_stream` first to check the length.
+    ///
+    /// ```no_run
+    /// use futures::StreamExt;
+    /// use tydle::{Tydle, TydleOptions, Cipher, Extract, VideoId, download::DownloadOptions};
+    /// use anyhow::Result;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///   let ty = Tydle::new(TydleOptions{ ..Default::default() })?;
+    ///
+    ///   let mut stream_response = ty.get_streams(&VideoId::new("dQw4w9WgXcQ")?).await?;
+    ///   ty.resolve_streams(&mut stream_response).await?;
+    ///
+    ///   let stream = stream_response.best_audio().unwrap();
+    ///   let mut bytes = Box::pin(ty.stream_bytes(stream, DownloadOptions::default())?);
+    ///
+    ///   while let Some(chunk) = bytes.next().await {
+    ///     println!("Got {} bytes", chunk?.len());
+    ///   }
+    ///   Ok(())
+    /// }
+    /// ```
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn stream_bytes<'a>(
+        &'a self,
+        stream: &'a YtStream,
+        options: DownloadOptions,
+    ) -> Result<impl Stream<Item = Result<Bytes>> + 'a> {
+        let YtStreamSource::URL(url) = &stream.source else {
+            bail!(
+                "Failed to stream the bytes of the stream because its signature isn't deciphered yet, resolve it with `resolve_stream_url` first."
+            )
+        };
+
+        let reader = RangeReader::new(
+            self.yt_extractor.http_client.clone(),
+            url.clone(),
+            options.chunk_size,
+            0,
+        );
+        let progress = Progress::new(options)?;
+
+        Ok(try_unfold(
+            (reader, progress, false),
+            move |(mut reader, mut progress, mut refreshed)| async move {
+                loop {
+                    match reader.next_chunk(&mut progress).await {
+                        Ok(Some(chunk)) => return Ok(Some((chunk, (reader, progress, refreshed)))),
+                        Ok(None) => {
+                            progress.report();
+                            return Ok(None);
+                        }
+                        Err(e) => {
+                            let url = self
+                                .refresh_rejected_url(
+                                    e,
+                                    stream,
+                                    progress.resume_policy(),
+                                    &mut refreshed,
+                                )
+                                .await?;
+                            reader.set_url(url);
+                        }
+                    }
+                }
+            },
+        ))
+    }
+
+    /// The streams of `stream_response` with `itags` with fresh URLs, see `Cipher::refresh_streams`.
+    async fn refreshed_streams(
+        &self,
+        stream_response: YtStreamResponse,
+        itags: &[u32],
+    ) -> Result<Vec<Result<YtStream>>> {
+        // DRC variants share the `itag` of the regular stream, which is what's asked for.
+        let found: Vec<Option<YtStream>> = itags
+            .iter()
+            .map(|itag| {
+                let itag = itag.to_string();
+                let mut matching = stream_response
+                    .streams
+                    .iter()
+                    .filter(|stream| stream.itag.as_deref() == Some(itag.as_str()));
+
+                matching
+                    .clone()
+                    .find(|stream| !stream.is_drc)
+                    .or_else(|| matching.next())
+                    .cloned()
+            })
+            .collect();
+
+        let streams: Vec<YtStream> = found.iter().flatten().cloned().collect();
+        let mut urls = self
+            .resolve_stream_urls(&streams, stream_response.player_url.clone())
+            .await?
+            .into_iter();
+
+        Ok(found
+            .into_iter()
+            .zip(itags)
+            .map(|(stream, &itag)| {
+                let mut stream = stream.ok_or(FormatGone { itag })?;
+                stream.source = YtStreamSource::URL(
+                    urls.next()
+                        .ok_or_else(|| anyhow!("Missing the URL of a stream."))?,
+                );
+                Ok(stream)
+            })
+            .collect())
+    }
+
+    /// The stream of `stream_response` matching `stream` with a fresh URL, see `Cipher::refresh_stream`.
+    async fn refreshed_stream(
+        &self,
+        stream: &YtStream,
+        itag: u32,
+        stream_response: YtStreamResponse,
+    ) -> Result<YtStream> {
+        let audio_track_id =
+            |stream: &YtStream| stream.audio_track.as_ref().map(|track| track.id.clone());
+
+        let Some(fresh_stream) = stream_response.streams.iter().find(|fresh_stream| {
+            fresh_stream.itag == stream.itag
+                && fresh_stream.is_drc == stream.is_drc
+                && audio_track_id(fresh_stream) == audio_track_id(stream)
+        }) else {
+            return Err(FormatGone { itag }.into());
+        };
+
+        let mut fresh_stream = fresh_stream.clone();
+        fresh_stream.source = YtStreamSource::URL(
+            self.resolve_stream_url(&fresh_stream, stream_response.player_url.clone())
+                .await?,
+        );
+
+        Ok(fresh_stream)
+    }
+
+    /// Compare the size googlevideo serves `url` of `stream` with to the `file_size` of `stream`,
+    /// see `Cipher::verify_stream`.
+    async fn check_stream_url(&self, stream: &YtStream, url: &str) -> Result<YtStreamCheck> {
+        let actual = self.yt_extractor.fetch_stream_length(url).await?;
+
+        Ok(YtStreamCheck {
+            declared: stream.file_size,
+            actual,
+            is_ok: stream
+                .file_size
+                .zip(actual)
+                .is_none_or(|(declared, actual)| declared == actual),
         })
     }
+
+    /// Fail with a `StreamLengthMismatch` if googlevideo serves `url` of `stream` with another size than
+    /// YouTube declared, see `DownloadOptions::verify_length`.
+    #[cfg(not(target_arch = "wasm32"))]
+    async fn verify_length(&self, stream: &YtStream, url: &str) -> Result<()> {
+        let check = self.check_stream_url(stream, url).await?;
+
+        match (check.declared, check.actual) {
+            (Some(declared), Some(actual)) if !check.is_ok => Err(StreamLengthMismatch {
+                itag: stream.itag.clone(),
+                declared,
+                actual,
+            }
+            .into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Get a fresh URL for `stream` after googlevideo rejected its URL with `e`, if `resume_policy` says to
+    /// and it wasn't done already according to `refreshed`. Fails with `e` otherwise.
+    #[cfg(not(target_arch = "wasm32"))]
+    async fn refresh_rejected_url(
+        &self,
+        e: anyhow::Error,
+        stream: &YtStream,
+        resume_policy: &ResumePolicy,
+        refreshed: &mut bool,
+    ) -> Result<String> {
+        let ResumePolicy::Refresh(video_id) = resume_policy else {
+            return Err(e);
+        };
+
+        if *refreshed
+            || !matches!(
+                e.downcast_ref::<DecipherError>(),
+                Some(DecipherError::UrlRejected { .. })
+            )
+        {
+            return Err(e);
+        }
+
+        #[cfg(feature = "logging")]
+        log::warn!("{}, refreshing the stream URL.", e);
+
+        *refreshed = true;
+        let fresh_stream = self.refresh_stream(stream, video_id).await?;
+        let YtStreamSource::URL(url) = fresh_stream.source else {
+            bail!("Failed to refresh the stream URL.")
+        };
+
+        Ok(url)
+    }
+
+    /// Validate a resolved URL of `stream`, see `TydleOptions::validate_stream_urls`.
+    /// The player is downloaded again only if `refetched` says it wasn't already.
+    async fn validate_resolved_url(
+        &self,
+        stream: &YtStream,
+        url: String,
+        player_url: &str,
+        refetched: &mut bool,
+    ) -> Result<String> {
+        let Err(e) = self.yt_extractor.validate_stream_url(&url).await else {
+            return Ok(url);
+        };
+
+        if *refetched
+            || !matches!(
+                e.downcast_ref::<DecipherError>(),
+                Some(DecipherError::UrlRejected { .. })
+            )
+        {
+            return Err(e);
+        }
+
+        #[cfg(feature = "logging")]
+        log::warn!("{}, refreshing the stream URL.", e);
+
+        *refetched = true;
+        self.refresh_player(&stream.source, player_url).await?;
+
+        let url = self
+            .signature_decipher
+            .resolve_stream_url(&stream.source, player_url.to_string())
+            .await?;
+        self.yt_extractor.validate_stream_url(&url).await?;
+
+        Ok(url)
+    }
+
+    /// Forget what was deciphered for `source` and download the player again, unless that was
+    /// already done within `PLAYER_REFRESH_COOLDOWN`. Streams that fail at the same time share one download.
+    async fn refresh_player(&self, source: &YtStreamSource, player_url: &str) -> Result<()> {
+        self.signature_decipher
+            .invalidate_source(source, player_url.to_string())?;
+
+        let refreshed_recently = self
+            .player_refreshes
+            .lock()
+            .map_err(|e| anyhow!(e.to_string()))?
+            .get(player_url)
+            .and_then(|refreshed_at| now().duration_since(*refreshed_at).ok())
+            .is_some_and(|elapsed| elapsed < PLAYER_REFRESH_COOLDOWN);
+
+        if refreshed_recently {
+            return Ok(());
+        }
+
+        self.player_refresh_flights
+            .run(player_url, || async {
+                #[cfg(feature = "logging")]
+                log::warn!("Downloading player {} again.", player_url);
+
+                self.signature_decipher
+                    .invalidate_player(player_url.to_string())?;
+                self.yt_extractor
+                    .load_player(None, player_url.to_string())
+                    .await?;
+                self.player_refreshes
+                    .lock()
+                    .map_err(|e| anyhow!(e.to_string()))?
+                    .insert(player_url.to_string(), now());
+
+                Ok(String::new())
+            })
+            .await?;
+
+        Ok(())
+    }
+
+    /// Get the hit, miss, insertion and eviction counts of the player and code caches.
+    pub fn cache_stats(&self) -> TydleCacheStats {
+        TydleCacheStats {
+            player: self.player_cache.stats(),
+            code: self.code_cache.stats(),
+        }
+    }
+
+    /// Get the cookies currently held for `domain`, including those captured from YouTube's responses.
+    pub fn get_cookies(&self, domain: &str) -> Result<HashMap<String, String>> {
+        self.yt_extractor.get_cookies(domain)
+    }
+
+    /// Persist every cookie of the cookie store, including those YouTube handed out so far, to `path`.
+    /// Restore them on the next startup with `CookieJar::load` and `Tydle::with_cookie_jar`.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn save_cookies<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        save_cookies(self.yt_extractor.cookie_store.snapshot()?, path)
+    }
 }
 
 pub trait Extract {
@@ -184,75 +1051,855 @@ pub trait Extract {
     /// }
     /// ```
     fn get_streams<'a>(&'a self, video_id: &'a VideoId) -> Self::ExtractStreamFut<'a>;
-
-    type ExtractStreamFut<'a>: Future<Output = Result<YtStreamResponse>> + 'a
-    where
-        Self: 'a;
-    type ExtractInfoFut<'a>: Future<Output = Result<YtVideoInfo>> + 'a
-    where
-        Self: 'a;
-    type ExtractManifestFut<'a>: Future<Output = Result<YtManifest>> + 'a
-    where
-        Self: 'a;
-}
-
-pub trait Cipher {
-    /// Deciphers a stream's signature and returns it's URL.
-    fn decipher_signature<'a>(
+    /// Extract a playlist with all of its videos, following its pages up to `TydleOptions::max_playlist_pages`.
+    ///
+    /// ```
+    /// use tydle::{Tydle, TydleOptions, Extract, PlaylistId};
+    /// use anyhow::Result;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///   let ty = Tydle::new(TydleOptions{ ..Default::default() })?;
+    ///
+    ///   let playlist_id = PlaylistId::new("https://www.youtube.com/playlist?list=PLFgquLnL59alCl_2TQvOiD5Vgm1hCaGSI")?;
+    ///   let playlist = ty.get_playlist(&playlist_id).await?;
+    ///
+    ///   println!("{}: {} videos", playlist.title, playlist.entries.len());
+    ///   Ok(())
+    /// }
+    /// ```
+    fn get_playlist<'a>(&'a self, playlist_id: &'a PlaylistId) -> Self::ExtractPlaylistFut<'a>;
+    /// Extract a channel along with the first page of the videos on its Videos tab.
+    /// The next pages are fetched with `get_channel_videos_continuation`.
+    ///
+    /// ```
+    /// use tydle::{Tydle, TydleOptions, Extract, ChannelId, YtChannelVideosOrder};
+    /// use anyhow::Result;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///   let ty = Tydle::new(TydleOptions{ ..Default::default() })?;
+    ///
+    ///   let channel_id = ChannelId::new("@YouTube")?;
+    ///   let channel = ty.get_channel_videos(&channel_id, YtChannelVideosOrder::Latest).await?;
+    ///   let mut continuation = channel.videos.continuation;
+    ///
+    ///   while let Some(token) = continuation {
+    ///     let page = ty.get_channel_videos_continuation(&token).await?;
+    ///     continuation = page.continuation;
+    ///   }
+    ///
+    ///   Ok(())
+    /// }
+    /// ```
+    fn get_channel_videos<'a>(
         &'a self,
-        signature: String,
-        player_url: String,
-    ) -> Self::DecipherFut<'a>;
-    type DecipherFut<'a>: Future<Output = Result<String>> + 'a
-    where
-        Self: 'a;
-}
-
-impl Extract for Tydle {
-    type ExtractStreamFut<'a> = Pin<Box<dyn Future<Output = Result<YtStreamResponse>> + 'a>>;
-    type ExtractInfoFut<'a> = Pin<Box<dyn Future<Output = Result<YtVideoInfo>> + 'a>>;
-    type ExtractManifestFut<'a> = Pin<Box<dyn Future<Output = Result<YtManifest>> + 'a>>;
+        channel_id: &'a ChannelId,
+        order: YtChannelVideosOrder,
+    ) -> Self::ExtractChannelFut<'a>;
+    /// Get the next page of the videos of a channel with the `continuation` of the previous one.
+    fn get_channel_videos_continuation<'a>(
+        &'a self,
+        continuation: &'a Continuation<YtChannelVideo>,
+    ) -> Self::ExtractChannelPageFut<'a>;
 
-    fn get_streams<'a>(&'a self, video_id: &'a VideoId) -> Self::ExtractStreamFut<'a> {
-        Box::pin(async move {
-            let extractor = self
-                .yt_extractor
-                .lock()
-                .map_err(|e| anyhow!(e.to_string()))?;
-            extractor.extract_streams(video_id).await
-        })
+    /// Search YouTube for videos, channels and playlists, returning the first page of results.
+    /// The next pages are fetched with `search_continue`.
+    ///
+    /// ```
+    /// use tydle::{Tydle, TydleOptions, Extract, YtSearchResult};
+    /// use anyhow::Result;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///   let ty = Tydle::new(TydleOptions{ ..Default::default() })?;
+    ///
+    ///   let search_response = ty.search("rust programming").await?;
+    ///
+    ///   for result in search_response.results {
+    ///     if let YtSearchResult::Video(video) = result {
+    ///       println!("{}: {}", video.video_id, video.title);
+    ///     }
+    ///   }
+    ///
+    ///   Ok(())
+    /// }
+    /// ```
+    fn search<'a>(&'a self, query: &'a str) -> Self::SearchFut<'a>;
+    /// Search YouTube with the filters of its filter menu, like only videos under 4 minutes sorted by views.
+    ///
+    /// ```
+    /// use tydle::{Tydle, TydleOptions, Extract, YtSearchFilters, YtSearchType, YtSearchDuration, YtSearchUploadDate, YtSearchSort};
+    /// use anyhow::Result;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///   let ty = Tydle::new(TydleOptions{ ..Default::default() })?;
+    ///
+    ///   let filters = YtSearchFilters {
+    ///     result_type: Some(YtSearchType::Video),
+    ///     duration: Some(YtSearchDuration::Short),
+    ///     upload_date: Some(YtSearchUploadDate::ThisWeek),
+    ///     sort: YtSearchSort::ViewCount,
+    ///     ..Default::default()
+    ///   };
+    ///   let search_response = ty.search_with_filters("rust programming", &filters).await?;
+    ///
+    ///   Ok(())
+    /// }
+    /// ```
+    fn search_with_filters<'a>(
+        &'a self,
+        query: &'a str,
+        filters: &'a YtSearchFilters,
+    ) -> Self::SearchFut<'a>;
+    /// Get the autocomplete suggestions YouTube shows while typing `query` into its search box,
+    /// in the language and country of `hl` and `gl`. Empty queries have no suggestions.
+    fn search_suggestions<'a>(&'a self, query: &'a str) -> Self::SearchSuggestionsFut<'a>;
+    /// Get the next page of results of a search with the `continuation` of the previous one.
+    fn search_continue<'a>(
+        &'a self,
+        continuation: &'a Continuation<YtSearchResult>,
+    ) -> Self::SearchFut<'a>;
+
+    /// Download a caption track of `YtVideoInfo::captions` and parse it into cues.
+    ///
+    /// ```
+    /// use tydle::{Tydle, TydleOptions, Extract, VideoId};
+    /// use anyhow::Result;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///   let ty = Tydle::new(TydleOptions{ ..Default::default() })?;
+    ///
+    ///   let video_info = ty.get_video_info(&VideoId::new("dQw4w9WgXcQ")?).await?;
+    ///
+    ///   if let Some(track) = video_info.captions.iter().find(|t| t.language_code == "en") {
+    ///     for cue in ty.get_caption_track(track).await? {
+    ///       println!("{}ms: {}", cue.start_ms, cue.text);
+    ///     }
+    ///   }
+    ///
+    ///   Ok(())
+    /// }
+    /// ```
+    fn get_caption_track<'a>(&'a self, track: &'a YtCaptionTrack) -> Self::ExtractCaptionsFut<'a>;
+
+    /// List the qualities of a live stream from the `hls_manifest_url` of its `YtStreamResponse`.
+    fn get_hls_variants<'a>(&'a self, manifest_url: &'a str) -> Self::ExtractHlsFut<'a>;
+
+    /// Turn the variants and audio renditions of an HLS master playlist into streams, whose `source`
+    /// is the URL of their media playlist. `get_streams` does this by itself for videos that only have HLS.
+    ///
+    /// ```
+    /// use tydle::{Tydle, TydleOptions, Extract, VideoId};
+    /// use anyhow::Result;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///   let ty = Tydle::new(TydleOptions{ ..Default::default() })?;
+    ///
+    ///   let stream_response = ty.get_streams(&VideoId::new("jfKfPfyJRdk")?).await?;
+    ///
+    ///   if let Some(manifest_url) = &stream_response.hls_manifest_url {
+    ///     for stream in ty.get_streams_from_hls(manifest_url).await? {
+    ///       println!("{:?} {:?}p", stream.itag, stream.height);
+    ///     }
+    ///   }
+    ///
+    ///   Ok(())
+    /// }
+    /// ```
+    fn get_streams_from_hls<'a>(&'a self, manifest_url: &'a str) -> Self::ExtractHlsStreamsFut<'a>;
+
+    /// List the URLs of the segments of a stream with `is_otf` set, in the order they have to be joined in.
+    /// `stream_url` is the resolved URL of the stream, the first segment is downloaded to find out how many there are.
+    ///
+    /// ```
+    /// use tydle::{Tydle, TydleOptions, Cipher, Extract, VideoId};
+    /// use anyhow::Result;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///   let ty = Tydle::new(TydleOptions{ ..Default::default() })?;
+    ///
+    ///   let stream_response = ty.get_streams(&VideoId::new("dQw4w9WgXcQ")?).await?;
+    ///
+    ///   if let Some(stream) = stream_response.streams.iter().find(|s| s.is_otf) {
+    ///     let url = ty.resolve_stream_url(stream, stream_response.player_url.clone()).await?;
+    ///     let segment_urls = ty.get_otf_segments(&url).await?;
+    ///   }
+    ///
+    ///   Ok(())
+    /// }
+    /// ```
+    fn get_otf_segments<'a>(&'a self, stream_url: &'a str) -> Self::ExtractOtfFut<'a>;
+
+    /// Get the first page of the videos YouTube shows next to a video, around 20 of them.
+    /// More are fetched with `get_related_continue`.
+    ///
+    /// ```
+    /// use tydle::{Tydle, TydleOptions, Extract, VideoId};
+    /// use anyhow::Result;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///   let ty = Tydle::new(TydleOptions{ ..Default::default() })?;
+    ///
+    ///   let related = ty.get_related(&VideoId::new("dQw4w9WgXcQ")?).await?;
+    ///
+    ///   for video in related.videos {
+    ///     println!("{}: {}", video.video_id, video.title);
+    ///   }
+    ///
+    ///   Ok(())
+    /// }
+    /// ```
+    fn get_related<'a>(&'a self, video_id: &'a VideoId) -> Self::ExtractRelatedFut<'a>;
+    /// Get more related videos with the `continuation` of the previous page.
+    fn get_related_continue<'a>(
+        &'a self,
+        continuation: &'a Continuation<YtRelatedVideo>,
+    ) -> Self::ExtractRelatedFut<'a>;
+
+    /// Get the first page of the mix YouTube generates from a video, the endless playlist it autoplays.
+    /// Every call to `get_mix_continue` with the `continuation` of the previous page returns the entries after it.
+    ///
+    /// ```
+    /// use tydle::{Tydle, TydleOptions, Extract, VideoId};
+    /// use anyhow::Result;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///   let ty = Tydle::new(TydleOptions{ ..Default::default() })?;
+    ///
+    ///   let mix = ty.get_mix(&VideoId::new("dQw4w9WgXcQ")?).await?;
+    ///
+    ///   if let Some(token) = &mix.continuation {
+    ///     let next_page = ty.get_mix_continue(token).await?;
+    ///   }
+    ///
+    ///   Ok(())
+    /// }
+    /// ```
+    fn get_mix<'a>(&'a self, video_id: &'a VideoId) -> Self::ExtractMixFut<'a>;
+    fn get_mix_continue<'a>(
+        &'a self,
+        continuation: &'a Continuation<YtPlaylistEntry>,
+    ) -> Self::ExtractMixFut<'a>;
+
+    /// Get the videos of a tab of the trending page, for the country of `TydleOptions::gl`.
+    /// Falls back to the `Now` tab when the requested one doesn't exist in the country.
+    ///
+    /// ```
+    /// use tydle::{Extract, Ty, YtTrendingCategory};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> anyhow::Result<()> {
+    ///   let ty = Ty::new()?;
+    ///   let trending = ty.get_trending(YtTrendingCategory::Music).await?;
+    ///
+    ///   for section in &trending.sections {
+    ///     println!("{:?}: {} videos", section.title, section.videos.len());
+    ///   }
+    ///
+    ///   Ok(())
+    /// }
+    /// ```
+    fn get_trending<'a>(&'a self, category: YtTrendingCategory) -> Self::ExtractTrendingFut<'a>;
+
+    /// Resolve a clip to the video it's cut from and the part of the video it is,
+    /// so the streams of the video can be downloaded and trimmed to it.
+    ///
+    /// ```
+    /// use tydle::{Tydle, TydleOptions, Extract, ClipId};
+    /// use anyhow::Result;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///   let ty = Tydle::new(TydleOptions{ ..Default::default() })?;
+    ///
+    ///   let clip_id = ClipId::new("https://www.youtube.com/clip/UgkxU2HSeGL_NvmDJ-nQJrlLwllwMDBdGZFs")?;
+    ///   let clip = ty.resolve_clip(&clip_id).await?;
+    ///
+    ///   println!("{}: {:?} to {:?} of {}", clip.title, clip.start, clip.end, clip.video_id);
+    ///   Ok(())
+    /// }
+    /// ```
+    fn resolve_clip<'a>(&'a self, clip_id: &'a ClipId) -> Self::ExtractClipFut<'a>;
+
+    /// Get the title, uploader and thumbnail of a video from the oEmbed endpoint, a single small request
+    /// that doesn't involve the player or the caches. Fails with `tydle::error::OEmbedError` telling
+    /// videos that don't exist apart from ones that can't be embedded.
+    ///
+    /// ```
+    /// use tydle::{Tydle, TydleOptions, Extract, VideoId};
+    /// use anyhow::Result;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///   let ty = Tydle::new(TydleOptions{ ..Default::default() })?;
+    ///
+    ///   let oembed = ty.get_oembed(&VideoId::new("dQw4w9WgXcQ")?).await?;
+    ///   println!("{} by {}", oembed.title, oembed.author_name);
+    ///
+    ///   Ok(())
+    /// }
+    /// ```
+    fn get_oembed<'a>(&'a self, video_id: &'a VideoId) -> Self::ExtractOEmbedFut<'a>;
+
+    /// Check whether a video can be played without extracting it. Takes an oEmbed request and, unless
+    /// the video is gone, a player request from a client that needs no player JS, so nothing is deciphered.
+    ///
+    /// ```
+    /// use tydle::{Tydle, TydleOptions, Extract, VideoId, YtAvailability};
+    /// use anyhow::Result;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///   let ty = Tydle::new(TydleOptions{ ..Default::default() })?;
+    ///
+    ///   if ty.check_availability(&VideoId::new("dQw4w9WgXcQ")?).await? == YtAvailability::Playable {
+    ///     println!("Still up");
+    ///   }
+    ///
+    ///   Ok(())
+    /// }
+    /// ```
+    fn check_availability<'a>(&'a self, video_id: &'a VideoId) -> Self::CheckAvailabilityFut<'a>;
+    /// `check_availability` of many videos, a few at a time. The results are in the order of `video_ids`.
+    fn check_availability_many<'a>(
+        &'a self,
+        video_ids: &'a [VideoId],
+    ) -> Self::CheckAvailabilityManyFut<'a>;
+
+    /// Get the first page of the comments of a video, failing with `tydle::error::CommentsDisabled`
+    /// if they are turned off. The next pages are fetched with `get_comments_continue`.
+    ///
+    /// ```
+    /// use tydle::{Tydle, TydleOptions, Extract, VideoId, YtCommentSort};
+    /// use anyhow::Result;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///   let ty = Tydle::new(TydleOptions{ ..Default::default() })?;
+    ///
+    ///   let comment_page = ty.get_comments(&VideoId::new("dQw4w9WgXcQ")?, YtCommentSort::Newest).await?;
+    ///
+    ///   for comment in comment_page.comments {
+    ///     println!("{}: {}", comment.author_name, comment.text);
+    ///
+    ///     if let Some(token) = &comment.replies_continuation {
+    ///       let replies = ty.get_comment_replies(token).await?;
+    ///     }
+    ///   }
+    ///
+    ///   Ok(())
+    /// }
+    /// ```
+    fn get_comments<'a>(
+        &'a self,
+        video_id: &'a VideoId,
+        sort: YtCommentSort,
+    ) -> Self::ExtractCommentsFut<'a>;
+    /// Get the next page of comments with the `continuation` of the previous one.
+    fn get_comments_continue<'a>(
+        &'a self,
+        continuation: &'a Continuation<YtComment>,
+    ) -> Self::ExtractCommentsFut<'a>;
+    /// Get the replies of a comment with its `replies_continuation`, or their next page with the `continuation` of the previous one.
+    fn get_comment_replies<'a>(
+        &'a self,
+        continuation: &'a Continuation<YtComment>,
+    ) -> Self::ExtractCommentsFut<'a>;
+
+    /// Get the next page of any listing with the `continuation` of the previous one,
+    /// failing with `tydle::error::ContinuationExpired` if YouTube doesn't accept it anymore.
+    ///
+    /// ```
+    /// use tydle::{Tydle, TydleOptions, Extract, Continuation, YtSearchResult};
+    /// use anyhow::Result;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///   let ty = Tydle::new(TydleOptions{ ..Default::default() })?;
+    ///
+    ///   let first_page = ty.search("lofi hip hop").await?;
+    ///
+    ///   if let Some(continuation) = first_page.continuation {
+    ///     // Tokens survive being stored as strings, like in a cursor of a web service.
+    ///     let token = continuation.to_string();
+    ///     let continuation: Continuation<YtSearchResult> = token.parse()?;
+    ///
+    ///     let second_page = ty.continue_page(&continuation).await?;
+    ///     println!("{} more results", second_page.items.len());
+    ///   }
+    ///
+    ///   Ok(())
+    /// }
+    /// ```
+    fn continue_page<'a, T: YtPaginated>(
+        &'a self,
+        continuation: &'a Continuation<T>,
+    ) -> Self::ContinuePageFut<'a, T>;
+
+    type ExtractStreamFut<'a>: Future<Output = Result<YtStreamResponse>> + 'a
+    where
+        Self: 'a;
+    type ExtractInfoFut<'a>: Future<Output = Result<YtVideoInfo>> + 'a
+    where
+        Self: 'a;
+    type ExtractManifestFut<'a>: Future<Output = Result<YtManifest>> + 'a
+    where
+        Self: 'a;
+    type ExtractPlaylistFut<'a>: Future<Output = Result<YtPlaylist>> + 'a
+    where
+        Self: 'a;
+    type ExtractChannelFut<'a>: Future<Output = Result<YtChannelVideos>> + 'a
+    where
+        Self: 'a;
+    type ExtractChannelPageFut<'a>: Future<Output = Result<YtChannelVideosPage>> + 'a
+    where
+        Self: 'a;
+    type SearchFut<'a>: Future<Output = Result<YtSearchResponse>> + 'a
+    where
+        Self: 'a;
+    type ExtractCaptionsFut<'a>: Future<Output = Result<Vec<YtCaptionCue>>> + 'a
+    where
+        Self: 'a;
+    type ExtractHlsFut<'a>: Future<Output = Result<Vec<YtHlsVariant>>> + 'a
+    where
+        Self: 'a;
+    type ExtractHlsStreamsFut<'a>: Future<Output = Result<Vec<YtStream>>> + 'a
+    where
+        Self: 'a;
+    type ExtractOtfFut<'a>: Future<Output = Result<Vec<String>>> + 'a
+    where
+        Self: 'a;
+    type ExtractRelatedFut<'a>: Future<Output = Result<YtRelatedVideos>> + 'a
+    where
+        Self: 'a;
+    type ExtractCommentsFut<'a>: Future<Output = Result<YtCommentPage>> + 'a
+    where
+        Self: 'a;
+    type ExtractMixFut<'a>: Future<Output = Result<YtMix>> + 'a
+    where
+        Self: 'a;
+    type ExtractTrendingFut<'a>: Future<Output = Result<YtTrending>> + 'a
+    where
+        Self: 'a;
+    type ExtractClipFut<'a>: Future<Output = Result<ClipInfo>> + 'a
+    where
+        Self: 'a;
+    type ExtractOEmbedFut<'a>: Future<Output = Result<OEmbedInfo>> + 'a
+    where
+        Self: 'a;
+    type SearchSuggestionsFut<'a>: Future<Output = Result<Vec<String>>> + 'a
+    where
+        Self: 'a;
+    type CheckAvailabilityFut<'a>: Future<Output = Result<YtAvailability>> + 'a
+    where
+        Self: 'a;
+    type CheckAvailabilityManyFut<'a>: Future<Output = Vec<Result<YtAvailability>>> + 'a
+    where
+        Self: 'a;
+    type ContinuePageFut<'a, T: YtPaginated>: Future<Output = Result<Page<T>>> + 'a
+    where
+        Self: 'a;
+}
+
+pub trait Cipher {
+    /// Deciphers a stream's signature and returns it's URL.
+    fn decipher_signature<'a>(
+        &'a self,
+        signature: String,
+        player_url: String,
+    ) -> Self::DecipherFut<'a>;
+    /// Transforms the `n` parameter of a stream URL, which YouTube throttles downloads without.
+    ///
+    /// `decipher_signature` already does this for the URLs it returns.
+    fn decipher_n<'a>(&'a self, n: String, player_url: String) -> Self::DecipherFut<'a>;
+    /// Get the URL a stream can be downloaded from.
+    ///
+    /// Streams that come with a URL only get their `n` parameter transformed, streams with a signature
+    /// are deciphered and get the signature appended under the parameter named by `sp` (`signature` if missing).
+    ///
+    /// ```
+    /// use tydle::{Tydle, TydleOptions, Cipher, Extract, VideoId};
+    /// use anyhow::Result;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///   let ty = Tydle::new(TydleOptions{ ..Default::default() })?;
+    ///
+    ///   let stream_response = ty.get_streams(&VideoId::new("dQw4w9WgXcQ")?).await?;
+    ///   let stream = stream_response.streams.first().unwrap();
+    ///
+    ///   let url = ty.resolve_stream_url(stream, stream_response.player_url.clone()).await?;
+    ///
+    ///   println!("URL: {}", url);
+    ///   Ok(())
+    /// }
+    /// ```
+    fn resolve_stream_url<'a>(
+        &'a self,
+        stream: &'a YtStream,
+        player_url: String,
+    ) -> Self::DecipherFut<'a>;
+    /// Get a fresh URL for a stream whose URL got throttled or started failing with 403s midway,
+    /// which happens when the `n` parameter was transformed with a player that's gone stale.
+    ///
+    /// The player is downloaded again and the signature and `n` parameter are solved anew. When many
+    /// streams of the same player fail together, the player is only downloaded once for all of them.
+    fn refresh_stream_url<'a>(
+        &'a self,
+        stream: &'a YtStream,
+        player_url: String,
+    ) -> Self::DecipherFut<'a>;
+    /// Deciphers `(signature, player_url)` pairs and returns their URLs in the same order.
+    ///
+    /// Faster than calling `decipher_signature` for each stream of a manifest,
+    /// since the player only has to solve the signatures once for all of them.
+    fn decipher_all<'a>(&'a self, signatures: Vec<(String, String)>) -> Self::DecipherAllFut<'a>;
+    /// Transforms `(n, player_url)` pairs and returns the values in the same order.
+    fn decipher_n_all<'a>(&'a self, ns: Vec<(String, String)>) -> Self::DecipherAllFut<'a>;
+    /// Get the URLs the streams can be downloaded from, in the same order.
+    /// See `resolve_stream_url`, `YtStreamResponse::resolve_all` does this for every stream of a response.
+    fn resolve_stream_urls<'a>(
+        &'a self,
+        streams: &'a [YtStream],
+        player_url: String,
+    ) -> Self::DecipherAllFut<'a>;
+    /// Resolve every stream of `stream_response` in place, so that all of their sources are a
+    /// `YtStreamSource::URL` that can be downloaded from. See `resolve_stream_url`.
+    ///
+    /// The signatures are deciphered in one go like `resolve_stream_urls`. If that fails, the streams
+    /// are resolved one by one instead, and the ones that still fail are left as they were and listed in a
+    /// `tydle::error::StreamsUnresolved`.
+    ///
+    /// ```
+    /// use tydle::{Tydle, TydleOptions, Cipher, Extract, VideoId};
+    /// use anyhow::Result;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///   let ty = Tydle::new(TydleOptions{ ..Default::default() })?;
+    ///
+    ///   let mut stream_response = ty.get_streams(&VideoId::new("dQw4w9WgXcQ")?).await?;
+    ///   ty.resolve_streams(&mut stream_response).await?;
+    ///
+    ///   for stream in stream_response.streams.iter() {
+    ///     println!("{:?}", stream.source);
+    ///   }
+    ///   Ok(())
+    /// }
+    /// ```
+    fn resolve_streams<'a>(
+        &'a self,
+        stream_response: &'a mut YtStreamResponse,
+    ) -> Self::ResolveStreamsFut<'a>;
+    /// Check that a resolved stream can be downloaded in full before downloading it, by requesting its first
+    /// byte and comparing the size googlevideo gives with the `file_size` YouTube did.
+    ///
+    /// A different size comes back as a `YtStreamCheck` that isn't `is_ok`. A URL googlevideo refuses,
+    /// which usually means the signature was deciphered with a stale player, fails with
+    /// `tydle::error::DecipherError::UrlRejected` instead, see `refresh_stream_url`.
+    ///
+    /// ```
+    /// use tydle::{Tydle, TydleOptions, Cipher, Extract, VideoId};
+    /// use anyhow::Result;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///   let ty = Tydle::new(TydleOptions{ ..Default::default() })?;
+    ///
+    ///   let mut stream_response = ty.get_streams(&VideoId::new("dQw4w9WgXcQ")?).await?;
+    ///   ty.resolve_streams(&mut stream_response).await?;
+    ///
+    ///   let stream = stream_response.best_audio().unwrap();
+    ///   if !ty.verify_stream(stream).await?.is_ok {
+    ///     println!("The stream would be cut short.");
+    ///   }
+    ///   Ok(())
+    /// }
+    /// ```
+    fn verify_stream<'a>(&'a self, stream: &'a YtStream) -> Self::VerifyStreamFut<'a>;
+    /// Extract the streams of a video again and get the ones with `itags` with fresh URLs, in the same order.
+    /// For URLs that expired, see `YtStream::is_expired`, rather than ones that got throttled, which
+    /// `refresh_stream_url` is for.
+    ///
+    /// An itag the video doesn't have anymore gets a `tydle::error::FormatGone` in its place,
+    /// the others are refreshed all the same.
+    ///
+    /// ```
+    /// use tydle::{Tydle, TydleOptions, Cipher, VideoId};
+    /// use anyhow::Result;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///   let ty = Tydle::new(TydleOptions{ ..Default::default() })?;
+    ///
+    ///   for stream in ty.refresh_streams(&VideoId::new("dQw4w9WgXcQ")?, &[137, 140]).await? {
+    ///     println!("{:?}", stream?.source);
+    ///   }
+    ///   Ok(())
+    /// }
+    /// ```
+    fn refresh_streams<'a>(
+        &'a self,
+        video_id: &'a VideoId,
+        itags: &'a [u32],
+    ) -> Self::RefreshStreamsFut<'a>;
+    /// Extract `stream` of the video again with a fresh URL, see `refresh_streams`. Dubs and DRC
+    /// variants share their `itag`, so the stream with the same audio track and `is_drc` is picked.
+    /// Fails with a `tydle::error::FormatGone` if the video doesn't have it anymore.
+    fn refresh_stream<'a>(
+        &'a self,
+        stream: &'a YtStream,
+        video_id: &'a VideoId,
+    ) -> Self::RefreshStreamFut<'a>;
+    /// Deciphers a stream's signature with the code of a `player.js` the caller already has and returns it's URL.
+    ///
+    /// Nothing is downloaded, so the `n` parameter is only transformed if the solver modules were
+    /// seeded with `Tydle::seed_solver` or downloaded before, the signature falls back to the
+    /// pattern-based strategies otherwise.
+    fn decipher_with_player_source<'a>(
+        &'a self,
+        signature: &'a str,
+        player_js: &'a str,
+    ) -> Self::DecipherFut<'a>;
+    /// Transforms the `n` parameter with the code of a `player.js` the caller already has.
+    /// Nothing is downloaded, which needs the solver modules to be seeded with `Tydle::seed_solver`.
+    fn decipher_n_with_player_source<'a>(
+        &'a self,
+        n: &'a str,
+        player_js: &'a str,
+    ) -> Self::DecipherFut<'a>;
+    type DecipherFut<'a>: Future<Output = Result<String>> + 'a
+    where
+        Self: 'a;
+    type DecipherAllFut<'a>: Future<Output = Result<Vec<String>>> + 'a
+    where
+        Self: 'a;
+    type ResolveStreamsFut<'a>: Future<Output = Result<()>> + 'a
+    where
+        Self: 'a;
+    type RefreshStreamsFut<'a>: Future<Output = Result<Vec<Result<YtStream>>>> + 'a
+    where
+        Self: 'a;
+    type RefreshStreamFut<'a>: Future<Output = Result<YtStream>> + 'a
+    where
+        Self: 'a;
+    type VerifyStreamFut<'a>: Future<Output = Result<YtStreamCheck>> + 'a
+    where
+        Self: 'a;
+}
+
+impl Extract for Tydle {
+    type ExtractStreamFut<'a> = Pin<Box<dyn Future<Output = Result<YtStreamResponse>> + 'a>>;
+    type ExtractInfoFut<'a> = Pin<Box<dyn Future<Output = Result<YtVideoInfo>> + 'a>>;
+    type ExtractManifestFut<'a> = Pin<Box<dyn Future<Output = Result<YtManifest>> + 'a>>;
+    type ExtractPlaylistFut<'a> = Pin<Box<dyn Future<Output = Result<YtPlaylist>> + 'a>>;
+    type ExtractChannelFut<'a> = Pin<Box<dyn Future<Output = Result<YtChannelVideos>> + 'a>>;
+    type ExtractChannelPageFut<'a> =
+        Pin<Box<dyn Future<Output = Result<YtChannelVideosPage>> + 'a>>;
+    type SearchFut<'a> = Pin<Box<dyn Future<Output = Result<YtSearchResponse>> + 'a>>;
+    type ExtractCaptionsFut<'a> = Pin<Box<dyn Future<Output = Result<Vec<YtCaptionCue>>> + 'a>>;
+    type ExtractHlsFut<'a> = Pin<Box<dyn Future<Output = Result<Vec<YtHlsVariant>>> + 'a>>;
+    type ExtractHlsStreamsFut<'a> = Pin<Box<dyn Future<Output = Result<Vec<YtStream>>> + 'a>>;
+    type ExtractOtfFut<'a> = Pin<Box<dyn Future<Output = Result<Vec<String>>> + 'a>>;
+    type ExtractRelatedFut<'a> = Pin<Box<dyn Future<Output = Result<YtRelatedVideos>> + 'a>>;
+    type ExtractCommentsFut<'a> = Pin<Box<dyn Future<Output = Result<YtCommentPage>> + 'a>>;
+    type ExtractMixFut<'a> = Pin<Box<dyn Future<Output = Result<YtMix>> + 'a>>;
+    type ExtractTrendingFut<'a> = Pin<Box<dyn Future<Output = Result<YtTrending>> + 'a>>;
+    type ExtractClipFut<'a> = Pin<Box<dyn Future<Output = Result<ClipInfo>> + 'a>>;
+    type ExtractOEmbedFut<'a> = Pin<Box<dyn Future<Output = Result<OEmbedInfo>> + 'a>>;
+    type SearchSuggestionsFut<'a> = Pin<Box<dyn Future<Output = Result<Vec<String>>> + 'a>>;
+    type CheckAvailabilityFut<'a> = Pin<Box<dyn Future<Output = Result<YtAvailability>> + 'a>>;
+    type CheckAvailabilityManyFut<'a> =
+        Pin<Box<dyn Future<Output = Vec<Result<YtAvailability>>> + 'a>>;
+    type ContinuePageFut<'a, T: YtPaginated> = Pin<Box<dyn Future<Output = Result<Page<T>>> + 'a>>;
+
+    fn get_streams<'a>(&'a self, video_id: &'a VideoId) -> Self::ExtractStreamFut<'a> {
+        Box::pin(async move { self.yt_extractor.extract_streams(video_id).await })
     }
 
     fn get_manifest<'a>(&'a self, video_id: &'a VideoId) -> Self::ExtractManifestFut<'a> {
+        Box::pin(async move { self.yt_extractor.extract_manifest(video_id).await })
+    }
+
+    fn get_video_info<'a>(&'a self, video_id: &'a VideoId) -> Self::ExtractInfoFut<'a> {
+        Box::pin(async move { self.yt_extractor.extract_video_info(video_id).await })
+    }
+
+    fn get_playlist<'a>(&'a self, playlist_id: &'a PlaylistId) -> Self::ExtractPlaylistFut<'a> {
+        Box::pin(async move { self.yt_extractor.extract_playlist(playlist_id).await })
+    }
+
+    fn get_channel_videos<'a>(
+        &'a self,
+        channel_id: &'a ChannelId,
+        order: YtChannelVideosOrder,
+    ) -> Self::ExtractChannelFut<'a> {
         Box::pin(async move {
-            let extractor = self
-                .yt_extractor
-                .lock()
-                .map_err(|e| anyhow!(e.to_string()))?;
-            extractor.extract_manifest(video_id).await
+            self.yt_extractor
+                .extract_channel_videos(channel_id, order)
+                .await
         })
     }
 
-    fn get_video_info<'a>(&'a self, video_id: &'a VideoId) -> Self::ExtractInfoFut<'a> {
+    fn get_channel_videos_continuation<'a>(
+        &'a self,
+        continuation: &'a Continuation<YtChannelVideo>,
+    ) -> Self::ExtractChannelPageFut<'a> {
         Box::pin(async move {
-            let extractor = self
-                .yt_extractor
-                .lock()
-                .map_err(|e| anyhow!(e.to_string()))?;
-            extractor.extract_video_info(video_id).await
+            self.yt_extractor
+                .extract_channel_videos_continuation(continuation.token())
+                .await
+        })
+    }
+
+    fn search<'a>(&'a self, query: &'a str) -> Self::SearchFut<'a> {
+        Box::pin(async move { self.yt_extractor.search(query, None).await })
+    }
+
+    fn search_with_filters<'a>(
+        &'a self,
+        query: &'a str,
+        filters: &'a YtSearchFilters,
+    ) -> Self::SearchFut<'a> {
+        Box::pin(async move { self.yt_extractor.search(query, Some(filters)).await })
+    }
+
+    fn search_continue<'a>(
+        &'a self,
+        continuation: &'a Continuation<YtSearchResult>,
+    ) -> Self::SearchFut<'a> {
+        Box::pin(async move {
+            self.yt_extractor
+                .search_continuation(continuation.token())
+                .await
+        })
+    }
+
+    fn get_caption_track<'a>(&'a self, track: &'a YtCaptionTrack) -> Self::ExtractCaptionsFut<'a> {
+        Box::pin(async move { self.yt_extractor.fetch_caption_track(track).await })
+    }
+
+    fn get_hls_variants<'a>(&'a self, manifest_url: &'a str) -> Self::ExtractHlsFut<'a> {
+        Box::pin(async move { self.yt_extractor.fetch_hls_variants(manifest_url).await })
+    }
+
+    fn get_streams_from_hls<'a>(&'a self, manifest_url: &'a str) -> Self::ExtractHlsStreamsFut<'a> {
+        Box::pin(async move { self.yt_extractor.fetch_hls_streams(manifest_url).await })
+    }
+
+    fn get_otf_segments<'a>(&'a self, stream_url: &'a str) -> Self::ExtractOtfFut<'a> {
+        Box::pin(async move { self.yt_extractor.fetch_otf_segments(stream_url).await })
+    }
+
+    fn get_related<'a>(&'a self, video_id: &'a VideoId) -> Self::ExtractRelatedFut<'a> {
+        Box::pin(async move { self.yt_extractor.extract_related(video_id).await })
+    }
+
+    fn get_related_continue<'a>(
+        &'a self,
+        continuation: &'a Continuation<YtRelatedVideo>,
+    ) -> Self::ExtractRelatedFut<'a> {
+        Box::pin(async move {
+            self.yt_extractor
+                .extract_related_continuation(continuation.token())
+                .await
+        })
+    }
+
+    fn get_comments<'a>(
+        &'a self,
+        video_id: &'a VideoId,
+        sort: YtCommentSort,
+    ) -> Self::ExtractCommentsFut<'a> {
+        Box::pin(async move { self.yt_extractor.extract_comments(video_id, sort).await })
+    }
+
+    fn get_comments_continue<'a>(
+        &'a self,
+        continuation: &'a Continuation<YtComment>,
+    ) -> Self::ExtractCommentsFut<'a> {
+        Box::pin(async move {
+            self.yt_extractor
+                .extract_comments_continuation(continuation.token())
+                .await
+        })
+    }
+
+    fn get_comment_replies<'a>(
+        &'a self,
+        continuation: &'a Continuation<YtComment>,
+    ) -> Self::ExtractCommentsFut<'a> {
+        Box::pin(async move {
+            self.yt_extractor
+                .extract_comments_continuation(continuation.token())
+                .await
+        })
+    }
+
+    fn continue_page<'a, T: YtPaginated>(
+        &'a self,
+        continuation: &'a Continuation<T>,
+    ) -> Self::ContinuePageFut<'a, T> {
+        Box::pin(async move { self.yt_extractor.extract_page(continuation).await })
+    }
+
+    fn get_mix<'a>(&'a self, video_id: &'a VideoId) -> Self::ExtractMixFut<'a> {
+        Box::pin(async move { self.yt_extractor.extract_mix(video_id).await })
+    }
+
+    fn get_mix_continue<'a>(
+        &'a self,
+        continuation: &'a Continuation<YtPlaylistEntry>,
+    ) -> Self::ExtractMixFut<'a> {
+        Box::pin(async move {
+            self.yt_extractor
+                .extract_mix_continuation(continuation.token())
+                .await
         })
     }
 
+    fn get_trending<'a>(&'a self, category: YtTrendingCategory) -> Self::ExtractTrendingFut<'a> {
+        Box::pin(async move { self.yt_extractor.extract_trending(category).await })
+    }
+
+    fn resolve_clip<'a>(&'a self, clip_id: &'a ClipId) -> Self::ExtractClipFut<'a> {
+        Box::pin(async move { self.yt_extractor.resolve_clip(clip_id).await })
+    }
+
+    fn get_oembed<'a>(&'a self, video_id: &'a VideoId) -> Self::ExtractOEmbedFut<'a> {
+        Box::pin(async move { self.yt_extractor.extract_oembed(video_id).await })
+    }
+
+    fn check_availability<'a>(&'a self, video_id: &'a VideoId) -> Self::CheckAvailabilityFut<'a> {
+        Box::pin(async move { self.yt_extractor.check_availability(video_id).await })
+    }
+
+    fn check_availability_many<'a>(
+        &'a self,
+        video_ids: &'a [VideoId],
+    ) -> Self::CheckAvailabilityManyFut<'a> {
+        Box::pin(async move { self.yt_extractor.check_availability_many(video_ids).await })
+    }
+
+    fn search_suggestions<'a>(&'a self, query: &'a str) -> Self::SearchSuggestionsFut<'a> {
+        Box::pin(async move { self.yt_extractor.search_suggestions(query).await })
+    }
+
     fn get_streams_from_manifest<'a>(
         &'a self,
         manifest: &'a YtManifest,
     ) -> Self::ExtractStreamFut<'a> {
         Box::pin(async move {
-            let extractor = self
-                .yt_extractor
-                .lock()
-                .map_err(|e| anyhow!(e.to_string()))?;
-            extractor.extract_streams_from_manifest(manifest).await
+            self.yt_extractor
+                .extract_streams_from_manifest(manifest)
+                .await
         })
     }
 
@@ -261,17 +1908,20 @@ impl Extract for Tydle {
         manifest: &'a YtManifest,
     ) -> Self::ExtractInfoFut<'a> {
         Box::pin(async move {
-            let extractor = self
-                .yt_extractor
-                .lock()
-                .map_err(|e| anyhow!(e.to_string()))?;
-            extractor.extract_video_info_from_manifest(manifest).await
+            self.yt_extractor
+                .extract_video_info_from_manifest(manifest)
+                .await
         })
     }
 }
 
 impl Cipher for Tydle {
     type DecipherFut<'a> = Pin<Box<dyn Future<Output = Result<String>> + 'a>>;
+    type DecipherAllFut<'a> = Pin<Box<dyn Future<Output = Result<Vec<String>>> + 'a>>;
+    type ResolveStreamsFut<'a> = Pin<Box<dyn Future<Output = Result<()>> + 'a>>;
+    type RefreshStreamsFut<'a> = Pin<Box<dyn Future<Output = Result<Vec<Result<YtStream>>>> + 'a>>;
+    type RefreshStreamFut<'a> = Pin<Box<dyn Future<Output = Result<YtStream>> + 'a>>;
+    type VerifyStreamFut<'a> = Pin<Box<dyn Future<Output = Result<YtStreamCheck>> + 'a>>;
 
     fn decipher_signature<'a>(
         &'a self,
@@ -279,15 +1929,209 @@ impl Cipher for Tydle {
         player_url: String,
     ) -> Self::DecipherFut<'a> {
         Box::pin(async move {
-            let signature_decipher = self
+            self.signature_decipher
+                .decipher(signature, player_url)
+                .await
+        })
+    }
+
+    fn decipher_n<'a>(&'a self, n: String, player_url: String) -> Self::DecipherFut<'a> {
+        Box::pin(async move { self.signature_decipher.decipher_n(n, player_url).await })
+    }
+
+    fn resolve_stream_url<'a>(
+        &'a self,
+        stream: &'a YtStream,
+        player_url: String,
+    ) -> Self::DecipherFut<'a> {
+        Box::pin(async move {
+            let url = self
+                .signature_decipher
+                .resolve_stream_url(&stream.source, player_url.clone())
+                .await?;
+
+            if !self.yt_extractor.tydle_options.validate_stream_urls {
+                return Ok(url);
+            }
+
+            self.validate_resolved_url(stream, url, &player_url, &mut false)
+                .await
+        })
+    }
+
+    fn refresh_stream_url<'a>(
+        &'a self,
+        stream: &'a YtStream,
+        player_url: String,
+    ) -> Self::DecipherFut<'a> {
+        Box::pin(async move {
+            self.refresh_player(&stream.source, &player_url).await?;
+
+            self.signature_decipher
+                .resolve_stream_url(&stream.source, player_url)
+                .await
+        })
+    }
+
+    fn decipher_all<'a>(&'a self, signatures: Vec<(String, String)>) -> Self::DecipherAllFut<'a> {
+        Box::pin(async move { self.signature_decipher.decipher_all(signatures).await })
+    }
+
+    fn decipher_n_all<'a>(&'a self, ns: Vec<(String, String)>) -> Self::DecipherAllFut<'a> {
+        Box::pin(async move { self.signature_decipher.decipher_n_all(ns).await })
+    }
+
+    fn resolve_stream_urls<'a>(
+        &'a self,
+        streams: &'a [YtStream],
+        player_url: String,
+    ) -> Self::DecipherAllFut<'a> {
+        Box::pin(async move {
+            let urls = self
                 .signature_decipher
-                .lock()
-                .map_err(|e| anyhow!(e.to_string()))?;
-            signature_decipher.decipher(signature, player_url).await
+                .resolve_stream_urls(streams, player_url.clone())
+                .await?;
+
+            if !self.yt_extractor.tydle_options.validate_stream_urls {
+                return Ok(urls);
+            }
+
+            // One rejected URL is enough to tell the player is outdated, so it's only downloaded again once.
+            let mut refetched = false;
+            let mut validated = Vec::with_capacity(urls.len());
+
+            for (stream, url) in streams.iter().zip(urls) {
+                validated.push(
+                    self.validate_resolved_url(stream, url, &player_url, &mut refetched)
+                        .await?,
+                );
+            }
+
+            Ok(validated)
+        })
+    }
+
+    fn resolve_streams<'a>(
+        &'a self,
+        stream_response: &'a mut YtStreamResponse,
+    ) -> Self::ResolveStreamsFut<'a> {
+        Box::pin(async move {
+            let player_url = stream_response.player_url.clone();
+
+            let urls = match self
+                .resolve_stream_urls(&stream_response.streams, player_url.clone())
+                .await
+            {
+                Ok(urls) => urls.into_iter().map(Ok).collect(),
+                Err(_e) => {
+                    #[cfg(feature = "logging")]
+                    log::warn!(
+                        "Resolving the streams together failed, resolving them one by one: {}",
+                        _e
+                    );
+
+                    // The player is cached by now, so only the failing streams are solved again.
+                    let mut urls = Vec::with_capacity(stream_response.streams.len());
+
+                    for stream in stream_response.streams.iter() {
+                        urls.push(self.resolve_stream_url(stream, player_url.clone()).await);
+                    }
+
+                    urls
+                }
+            };
+
+            let mut failures = Vec::new();
+
+            for (stream, url) in stream_response.streams.iter_mut().zip(urls) {
+                match url {
+                    Ok(url) => stream.source = YtStreamSource::URL(url),
+                    Err(e) => failures.push((stream.itag.clone(), e.to_string())),
+                }
+            }
+
+            if !failures.is_empty() {
+                return Err(StreamsUnresolved { failures }.into());
+            }
+
+            Ok(())
+        })
+    }
+
+    fn verify_stream<'a>(&'a self, stream: &'a YtStream) -> Self::VerifyStreamFut<'a> {
+        Box::pin(async move {
+            let YtStreamSource::URL(url) = &stream.source else {
+                bail!(
+                    "Failed to verify the stream because its signature isn't deciphered yet, resolve it with `resolve_stream_url` first."
+                )
+            };
+
+            self.check_stream_url(stream, url).await
+        })
+    }
+
+    fn refresh_streams<'a>(
+        &'a self,
+        video_id: &'a VideoId,
+        itags: &'a [u32],
+    ) -> Self::RefreshStreamsFut<'a> {
+        Box::pin(async move {
+            let stream_response = self.get_streams(video_id).await?;
+
+            self.refreshed_streams(stream_response, itags).await
+        })
+    }
+
+    fn refresh_stream<'a>(
+        &'a self,
+        stream: &'a YtStream,
+        video_id: &'a VideoId,
+    ) -> Self::RefreshStreamFut<'a> {
+        Box::pin(async move {
+            let Some(itag) = stream.itag.as_deref().and_then(|itag| itag.parse().ok()) else {
+                bail!("Failed to refresh the stream because it has no itag to find it by.")
+            };
+
+            let stream_response = self.get_streams(video_id).await?;
+
+            self.refreshed_stream(stream, itag, stream_response).await
+        })
+    }
+
+    fn decipher_with_player_source<'a>(
+        &'a self,
+        signature: &'a str,
+        player_js: &'a str,
+    ) -> Self::DecipherFut<'a> {
+        Box::pin(async move {
+            self.signature_decipher
+                .decipher_with_player_source(signature, player_js)
+                .await
+        })
+    }
+
+    fn decipher_n_with_player_source<'a>(
+        &'a self,
+        n: &'a str,
+        player_js: &'a str,
+    ) -> Self::DecipherFut<'a> {
+        Box::pin(async move {
+            self.signature_decipher
+                .decipher_n_with_player_source(n, player_js)
+                .await
         })
     }
 }
 
+// `Tydle` is meant to be shared between threads and async tasks (e.g. as web server state),
+// so this fails to compile if it or its cookie jar ever stop being `Send + Sync`.
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+
+    assert_send_sync::<CookieJar>();
+    assert_send_sync::<Tydle>();
+};
+
 #[cfg(target_arch = "wasm32")]
 mod wasm_api {
     use super::*;
@@ -297,22 +2141,8 @@ mod wasm_api {
     impl Tydle {
         #[wasm_bindgen(constructor)]
         pub fn new(options: Option<TydleOptions>) -> Result<Tydle, JsValue> {
-            let player_cache = Arc::new(CacheStore::new());
-            let code_cache = Arc::new(CacheStore::new());
-
-            let yt_extractor = YtExtractor::new(
-                player_cache.clone(),
-                code_cache.clone(),
-                options.unwrap_or_default(),
-            )
-            .map_err(|e| JsValue::from_str(&e.to_string()))?;
-
-            let signature_decipher = SignatureDecipher::new(player_cache, code_cache);
-
-            Ok(Tydle {
-                yt_extractor: Arc::new(Mutex::new(yt_extractor)),
-                signature_decipher: Arc::new(Mutex::new(signature_decipher)),
-            })
+            Tydle::with_cookie_jar(options.unwrap_or_default(), CookieJar::new())
+                .map_err(|e| JsValue::from_str(&e.to_string()))
         }
 
         #[wasm_bindgen(js_name = "fetchStreams")]
@@ -376,6 +2206,279 @@ mod wasm_api {
                 .map_err(|e| JsValue::from_str(&e.to_string()))?)
         }
 
+        #[wasm_bindgen(js_name = "fetchPlaylist")]
+        pub async fn fetch_playlist(
+            &self,
+            #[wasm_bindgen(js_name = "playlistId")] playlist_id: String,
+        ) -> Result<YtPlaylist, JsValue> {
+            let id = PlaylistId::new(playlist_id).map_err(|e| JsValue::from_str(&e.to_string()))?;
+
+            self.get_playlist(&id)
+                .await
+                .map_err(|e| JsValue::from_str(&e.to_string()))
+        }
+
+        #[wasm_bindgen(js_name = "fetchChannelVideos")]
+        pub async fn fetch_channel_videos(
+            &self,
+            #[wasm_bindgen(js_name = "channelId")] channel_id: String,
+            order: Option<YtChannelVideosOrder>,
+        ) -> Result<YtChannelVideos, JsValue> {
+            let id = ChannelId::new(channel_id).map_err(|e| JsValue::from_str(&e.to_string()))?;
+
+            self.get_channel_videos(&id, order.unwrap_or_default())
+                .await
+                .map_err(|e| JsValue::from_str(&e.to_string()))
+        }
+
+        #[wasm_bindgen(js_name = "fetchChannelVideosContinuation")]
+        pub async fn fetch_channel_videos_continuation(
+            &self,
+            continuation: String,
+        ) -> Result<YtChannelVideosPage, JsValue> {
+            let continuation = continuation
+                .parse()
+                .map_err(|e: anyhow::Error| JsValue::from_str(&e.to_string()))?;
+
+            self.get_channel_videos_continuation(&continuation)
+                .await
+                .map_err(|e| JsValue::from_str(&e.to_string()))
+        }
+
+        #[wasm_bindgen(js_name = "search")]
+        pub async fn search_js(&self, query: String) -> Result<YtSearchResponse, JsValue> {
+            self.search(&query)
+                .await
+                .map_err(|e| JsValue::from_str(&e.to_string()))
+        }
+
+        #[wasm_bindgen(js_name = "searchWithFilters")]
+        pub async fn search_with_filters_js(
+            &self,
+            query: String,
+            filters: YtSearchFilters,
+        ) -> Result<YtSearchResponse, JsValue> {
+            self.search_with_filters(&query, &filters)
+                .await
+                .map_err(|e| JsValue::from_str(&e.to_string()))
+        }
+
+        #[wasm_bindgen(js_name = "searchSuggestions")]
+        pub async fn search_suggestions_js(&self, query: String) -> Result<Vec<String>, JsValue> {
+            self.search_suggestions(&query)
+                .await
+                .map_err(|e| JsValue::from_str(&e.to_string()))
+        }
+
+        #[wasm_bindgen(js_name = "searchContinue")]
+        pub async fn search_continue_js(
+            &self,
+            continuation: String,
+        ) -> Result<YtSearchResponse, JsValue> {
+            let continuation = continuation
+                .parse()
+                .map_err(|e: anyhow::Error| JsValue::from_str(&e.to_string()))?;
+
+            self.search_continue(&continuation)
+                .await
+                .map_err(|e| JsValue::from_str(&e.to_string()))
+        }
+
+        #[wasm_bindgen(js_name = "fetchCaptionTrack")]
+        pub async fn fetch_caption_track(
+            &self,
+            track: YtCaptionTrack,
+        ) -> Result<Vec<YtCaptionCue>, JsValue> {
+            self.get_caption_track(&track)
+                .await
+                .map_err(|e| JsValue::from_str(&e.to_string()))
+        }
+
+        #[wasm_bindgen(js_name = "fetchHlsVariants")]
+        pub async fn fetch_hls_variants(
+            &self,
+            #[wasm_bindgen(js_name = "manifestUrl")] manifest_url: String,
+        ) -> Result<Vec<YtHlsVariant>, JsValue> {
+            self.get_hls_variants(&manifest_url)
+                .await
+                .map_err(|e| JsValue::from_str(&e.to_string()))
+        }
+
+        #[wasm_bindgen(js_name = "fetchStreamsFromHls")]
+        pub async fn fetch_streams_from_hls(
+            &self,
+            #[wasm_bindgen(js_name = "manifestUrl")] manifest_url: String,
+        ) -> Result<Vec<YtStream>, JsValue> {
+            self.get_streams_from_hls(&manifest_url)
+                .await
+                .map_err(|e| JsValue::from_str(&e.to_string()))
+        }
+
+        #[wasm_bindgen(js_name = "fetchOtfSegments")]
+        pub async fn fetch_otf_segments(
+            &self,
+            #[wasm_bindgen(js_name = "streamUrl")] stream_url: String,
+        ) -> Result<Vec<String>, JsValue> {
+            self.get_otf_segments(&stream_url)
+                .await
+                .map_err(|e| JsValue::from_str(&e.to_string()))
+        }
+
+        #[wasm_bindgen(js_name = "fetchRelated")]
+        pub async fn fetch_related(
+            &self,
+            #[wasm_bindgen(js_name = "videoId")] video_id: String,
+        ) -> Result<YtRelatedVideos, JsValue> {
+            let id = VideoId::new(video_id).map_err(|e| JsValue::from_str(&e.to_string()))?;
+
+            self.get_related(&id)
+                .await
+                .map_err(|e| JsValue::from_str(&e.to_string()))
+        }
+
+        #[wasm_bindgen(js_name = "fetchRelatedContinue")]
+        pub async fn fetch_related_continue(
+            &self,
+            continuation: String,
+        ) -> Result<YtRelatedVideos, JsValue> {
+            let continuation = continuation
+                .parse()
+                .map_err(|e: anyhow::Error| JsValue::from_str(&e.to_string()))?;
+
+            self.get_related_continue(&continuation)
+                .await
+                .map_err(|e| JsValue::from_str(&e.to_string()))
+        }
+
+        #[wasm_bindgen(js_name = "fetchComments")]
+        pub async fn fetch_comments(
+            &self,
+            #[wasm_bindgen(js_name = "videoId")] video_id: String,
+            sort: Option<YtCommentSort>,
+        ) -> Result<YtCommentPage, JsValue> {
+            let id = VideoId::new(video_id).map_err(|e| JsValue::from_str(&e.to_string()))?;
+
+            self.get_comments(&id, sort.unwrap_or_default())
+                .await
+                .map_err(|e| JsValue::from_str(&e.to_string()))
+        }
+
+        #[wasm_bindgen(js_name = "fetchCommentsContinue")]
+        pub async fn fetch_comments_continue(
+            &self,
+            continuation: String,
+        ) -> Result<YtCommentPage, JsValue> {
+            let continuation = continuation
+                .parse()
+                .map_err(|e: anyhow::Error| JsValue::from_str(&e.to_string()))?;
+
+            self.get_comments_continue(&continuation)
+                .await
+                .map_err(|e| JsValue::from_str(&e.to_string()))
+        }
+
+        #[wasm_bindgen(js_name = "fetchCommentReplies")]
+        pub async fn fetch_comment_replies(
+            &self,
+            continuation: String,
+        ) -> Result<YtCommentPage, JsValue> {
+            let continuation = continuation
+                .parse()
+                .map_err(|e: anyhow::Error| JsValue::from_str(&e.to_string()))?;
+
+            self.get_comment_replies(&continuation)
+                .await
+                .map_err(|e| JsValue::from_str(&e.to_string()))
+        }
+
+        #[wasm_bindgen(js_name = "fetchMix")]
+        pub async fn fetch_mix(
+            &self,
+            #[wasm_bindgen(js_name = "videoId")] video_id: String,
+        ) -> Result<YtMix, JsValue> {
+            let id = VideoId::new(video_id).map_err(|e| JsValue::from_str(&e.to_string()))?;
+
+            self.get_mix(&id)
+                .await
+                .map_err(|e| JsValue::from_str(&e.to_string()))
+        }
+
+        #[wasm_bindgen(js_name = "fetchMixContinue")]
+        pub async fn fetch_mix_continue(&self, continuation: String) -> Result<YtMix, JsValue> {
+            let continuation = continuation
+                .parse()
+                .map_err(|e: anyhow::Error| JsValue::from_str(&e.to_string()))?;
+
+            self.get_mix_continue(&continuation)
+                .await
+                .map_err(|e| JsValue::from_str(&e.to_string()))
+        }
+
+        #[wasm_bindgen(js_name = "resolveClip")]
+        pub async fn resolve_clip_js(
+            &self,
+            #[wasm_bindgen(js_name = "clipId")] clip_id: String,
+        ) -> Result<ClipInfo, JsValue> {
+            let id = ClipId::new(clip_id).map_err(|e| JsValue::from_str(&e.to_string()))?;
+
+            self.resolve_clip(&id)
+                .await
+                .map_err(|e| JsValue::from_str(&e.to_string()))
+        }
+
+        #[wasm_bindgen(js_name = "fetchOEmbed")]
+        pub async fn fetch_oembed(
+            &self,
+            #[wasm_bindgen(js_name = "videoId")] video_id: String,
+        ) -> Result<OEmbedInfo, JsValue> {
+            let id = VideoId::new(video_id).map_err(|e| JsValue::from_str(&e.to_string()))?;
+
+            self.get_oembed(&id)
+                .await
+                .map_err(|e| JsValue::from_str(&e.to_string()))
+        }
+
+        #[wasm_bindgen(js_name = "checkAvailability")]
+        pub async fn check_availability_js(
+            &self,
+            #[wasm_bindgen(js_name = "videoId")] video_id: String,
+        ) -> Result<YtAvailability, JsValue> {
+            let id = VideoId::new(video_id).map_err(|e| JsValue::from_str(&e.to_string()))?;
+
+            self.check_availability(&id)
+                .await
+                .map_err(|e| JsValue::from_str(&e.to_string()))
+        }
+
+        #[wasm_bindgen(js_name = "fetchTrending")]
+        pub async fn fetch_trending(
+            &self,
+            category: Option<YtTrendingCategory>,
+        ) -> Result<YtTrending, JsValue> {
+            self.get_trending(category.unwrap_or_default())
+                .await
+                .map_err(|e| JsValue::from_str(&e.to_string()))
+        }
+
+        #[cfg(feature = "oauth")]
+        #[wasm_bindgen(js_name = "oauthDeviceFlow")]
+        pub async fn oauth_device_flow_js(&self) -> Result<OAuthDeviceCode, JsValue> {
+            self.oauth_device_flow()
+                .await
+                .map_err(|e| JsValue::from_str(&e.to_string()))
+        }
+
+        #[cfg(feature = "oauth")]
+        #[wasm_bindgen(js_name = "oauthWaitForAuthorization")]
+        pub async fn oauth_wait_for_authorization_js(
+            &self,
+            #[wasm_bindgen(js_name = "deviceCode")] device_code: OAuthDeviceCode,
+        ) -> Result<(), JsValue> {
+            self.oauth_wait_for_authorization(&device_code)
+                .await
+                .map_err(|e| JsValue::from_str(&e.to_string()))
+        }
+
         #[wasm_bindgen(js_name = "decipherSignature")]
         pub async fn decipher_signature_js(
             &self,
@@ -388,5 +2491,615 @@ mod wasm_api {
                 .map_err(|e| JsValue::from_str(&e.to_string()))?;
             Ok(res)
         }
+
+        #[wasm_bindgen(js_name = "resolveStreams")]
+        pub async fn resolve_streams_js(
+            &self,
+            #[wasm_bindgen(js_name = "streamResponse")] mut stream_response: YtStreamResponse,
+        ) -> Result<YtStreamResponse, JsValue> {
+            self.resolve_streams(&mut stream_response)
+                .await
+                .map_err(|e| JsValue::from_str(&e.to_string()))?;
+
+            Ok(stream_response)
+        }
+
+        #[wasm_bindgen(js_name = "refreshStreamUrl")]
+        pub async fn refresh_stream_url_js(
+            &self,
+            stream: YtStream,
+            #[wasm_bindgen(js_name = "playerUrl")] player_url: String,
+        ) -> Result<String, JsValue> {
+            self.refresh_stream_url(&stream, player_url)
+                .await
+                .map_err(|e| JsValue::from_str(&e.to_string()))
+        }
+
+        #[wasm_bindgen(js_name = "verifyStream")]
+        pub async fn verify_stream_js(&self, stream: YtStream) -> Result<YtStreamCheck, JsValue> {
+            self.verify_stream(&stream)
+                .await
+                .map_err(|e| JsValue::from_str(&e.to_string()))
+        }
+
+        #[wasm_bindgen(js_name = "refreshStream")]
+        pub async fn refresh_stream_js(
+            &self,
+            stream: YtStream,
+            #[wasm_bindgen(js_name = "videoId")] video_id: String,
+        ) -> Result<YtStream, JsValue> {
+            let id = VideoId::new(video_id).map_err(|e| JsValue::from_str(&e.to_string()))?;
+
+            self.refresh_stream(&stream, &id)
+                .await
+                .map_err(|e| JsValue::from_str(&e.to_string()))
+        }
+
+        #[wasm_bindgen(js_name = "resolveStreamUrl")]
+        pub async fn resolve_stream_url_js(
+            &self,
+            stream: YtStream,
+            #[wasm_bindgen(js_name = "playerUrl")] player_url: String,
+        ) -> Result<String, JsValue> {
+            self.resolve_stream_url(&stream, player_url)
+                .await
+                .map_err(|e| JsValue::from_str(&e.to_string()))
+        }
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use futures::StreamExt;
+
+    use super::*;
+    use crate::{
+        download::DownloadProgress,
+        test_server::{TestResponse, TestServer},
+    };
+
+    const PLAYER_PATH: &str = "/s/player/0123abcd/player_ias.vflset/en_US/base.js";
+
+    /// Server handing out a fake player.js, counting how often it was downloaded.
+    async fn player_server() -> TestServer {
+        TestServer::start(|_| TestResponse::ok("var player = 1;").delay(Duration::from_millis(50)))
+            .await
+    }
+
+    #[test]
+    fn save_cookies_persists_a_custom_store() {
+        let path = std::env::temp_dir().join(format!("tydle-save-{}.json", std::process::id()));
+        let store = Arc::new(CookieJar::new());
+        store
+            .set("https://www.youtube.com", "VISITOR_INFO1_LIVE", "abc")
+            .unwrap();
+
+        let tydle = Tydle::with_cookie_store(TydleOptions::default(), store).unwrap();
+        tydle.save_cookies(&path).unwrap();
+
+        let loaded = CookieJar::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let cookies = loaded.get_all("https://www.youtube.com").unwrap().unwrap();
+        assert_eq!(
+            cookies.get("VISITOR_INFO1_LIVE").map(String::as_str),
+            Some("abc")
+        );
+    }
+
+    #[tokio::test]
+    async fn concurrent_extractions_download_the_player_once() {
+        let server = player_server().await;
+        let tydle = Tydle::new(TydleOptions::default()).unwrap();
+        let player_url = server.url(PLAYER_PATH);
+
+        let players = futures::future::join_all(
+            (0..10).map(|_| tydle.yt_extractor.load_player(None, player_url.clone())),
+        )
+        .await;
+
+        assert_eq!(server.count("/s/player/"), 1);
+        for player in players {
+            assert_eq!(player.unwrap(), "var player = 1;");
+        }
+    }
+
+    #[tokio::test]
+    async fn second_player_load_is_a_cache_hit() {
+        let server = player_server().await;
+        let tydle = Tydle::new(TydleOptions::default()).unwrap();
+        let player_url = server.url(PLAYER_PATH);
+
+        tydle
+            .yt_extractor
+            .load_player(None, player_url.clone())
+            .await
+            .unwrap();
+        let first = tydle.cache_stats().code;
+        tydle
+            .yt_extractor
+            .load_player(None, player_url)
+            .await
+            .unwrap();
+        let second = tydle.cache_stats().code;
+
+        assert_eq!(server.count("/s/player/"), 1);
+        assert_eq!((first.hits, first.insertions), (0, 1));
+        assert!(first.misses > 0);
+        assert_eq!(second.hits, 1);
+        assert_eq!(second.misses, first.misses);
+    }
+
+    #[tokio::test]
+    async fn expired_player_is_a_miss_and_downloaded_again() {
+        let server = player_server().await;
+        let code_cache: Arc<CacheStore> =
+            Arc::new(CacheStore::with_ttl(Duration::from_millis(200)));
+        let tydle = Tydle::with_caches(
+            TydleOptions::default(),
+            Arc::new(CacheStore::new()),
+            code_cache.clone(),
+        )
+        .unwrap();
+        let player_url = server.url(PLAYER_PATH);
+
+        tydle
+            .yt_extractor
+            .load_player(None, player_url.clone())
+            .await
+            .unwrap();
+        tydle
+            .yt_extractor
+            .load_player(None, player_url.clone())
+            .await
+            .unwrap();
+        assert_eq!(server.count("/s/player/"), 1);
+
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        let before = CacheBackend::stats(code_cache.as_ref());
+        let player = tydle
+            .yt_extractor
+            .load_player(None, player_url)
+            .await
+            .unwrap();
+        let after = CacheBackend::stats(code_cache.as_ref());
+
+        assert_eq!(player, "var player = 1;");
+        assert_eq!(server.count("/s/player/"), 2);
+        assert_eq!(after.hits, before.hits);
+        assert!(after.misses > before.misses);
+        assert_eq!(after.evictions, before.evictions + 1);
+        assert_eq!(after.insertions, before.insertions + 1);
+    }
+
+    /// Runs the fake solver modules below in the JS runtime.
+    #[tokio::test]
+    async fn first_extraction_after_warm_up_is_a_player_cache_hit() {
+        // Stand-in for the ejs solver modules, "preprocessing" a player by prefixing it.
+        const SOLVER_LIB: &str = "var lib = {};";
+        const SOLVER_CORE: &str = r#"var jsc = function (input) {
+            return { type: "result", preprocessed_player: "/* preprocessed */ " + input.player, responses: [] };
+        };"#;
+
+        let server = player_server().await;
+        let tydle = Tydle::new(TydleOptions::default()).unwrap();
+        tydle
+            .seed_solver(SOLVER_LIB.into(), SOLVER_CORE.into())
+            .unwrap();
+        let player_url = server.url(PLAYER_PATH);
+
+        tydle.warm_up_player(player_url.clone()).await.unwrap();
+        let warmed = tydle.cache_stats().code;
+
+        assert_eq!(server.count("/s/player/"), 1);
+        assert_eq!(tydle.signature_decipher.parses.load(Ordering::SeqCst), 1);
+
+        // What the first extraction does with the player of its video.
+        tydle
+            .yt_extractor
+            .load_player(None, player_url.clone())
+            .await
+            .unwrap();
+        let preprocessed = tydle
+            .signature_decipher
+            .get_preprocessed_player(player_url)
+            .await
+            .unwrap();
+
+        assert_eq!(preprocessed.0, "/* preprocessed */ var player = 1;");
+        assert_eq!(server.count("/s/player/"), 1);
+        assert_eq!(tydle.signature_decipher.parses.load(Ordering::SeqCst), 1);
+        assert_eq!(tydle.cache_stats().code.misses, warmed.misses);
+    }
+
+    /// Stream of `server` whose URL needs no deciphering.
+    fn plain_stream(server: &TestServer, itag: &str) -> YtStream {
+        YtStream::new(
+            None,
+            None,
+            Some(itag.into()),
+            None,
+            YtStreamSource::URL(server.url(&format!("/videoplayback?itag={}", itag))),
+            0.0,
+        )
+    }
+
+    #[tokio::test]
+    async fn failed_validations_download_the_player_again_once() {
+        let validations = Arc::new(AtomicUsize::new(0));
+        let server = TestServer::start({
+            let validations = validations.clone();
+            move |request| {
+                if request.path.starts_with("/s/player/") {
+                    return TestResponse::ok("var player = 1;");
+                }
+
+                // itag 18 is only rejected until the player is downloaded again, itag 22 always is.
+                match request.path.ends_with("itag=18")
+                    && validations.fetch_add(1, Ordering::SeqCst) > 0
+                {
+                    true => TestResponse::status(206),
+                    false => TestResponse::status(403),
+                }
+            }
+        })
+        .await;
+        let options = TydleOptions {
+            validate_stream_urls: true,
+            ..Default::default()
+        };
+        let tydle = Tydle::new(options).unwrap();
+        let streams = [plain_stream(&server, "18"), plain_stream(&server, "22")];
+
+        let error = tydle
+            .resolve_stream_urls(&streams, server.url(PLAYER_PATH))
+            .await
+            .unwrap_err();
+
+        assert!(matches!(
+            error.downcast_ref::<DecipherError>(),
+            Some(DecipherError::UrlRejected { status: 403 })
+        ));
+        assert_eq!(server.count("/s/player/"), 1);
+        assert_eq!(server.count("/videoplayback?itag=18"), 2);
+        assert_eq!(server.count("/videoplayback?itag=22"), 1);
+    }
+
+    #[tokio::test]
+    async fn url_rejected_again_after_refetching_the_player_fails() {
+        let server = TestServer::start(|request| match request.path.starts_with("/s/player/") {
+            true => TestResponse::ok("var player = 1;"),
+            false => TestResponse::status(410),
+        })
+        .await;
+        let options = TydleOptions {
+            validate_stream_urls: true,
+            ..Default::default()
+        };
+        let tydle = Tydle::new(options).unwrap();
+        let stream = plain_stream(&server, "18");
+
+        let error = tydle
+            .resolve_stream_url(&stream, server.url(PLAYER_PATH))
+            .await
+            .unwrap_err();
+
+        assert!(matches!(
+            error.downcast_ref::<DecipherError>(),
+            Some(DecipherError::UrlRejected { status: 410 })
+        ));
+        assert_eq!(server.count("/s/player/"), 1);
+        assert_eq!(server.count("/videoplayback"), 2);
+    }
+
+    /// Download `stream` into a fresh temporary directory with `DownloadOptions::verify_length`.
+    async fn verified_download(
+        name: &str,
+        stream: &YtStream,
+    ) -> (Result<DownloadSummary>, PathBuf) {
+        let dir = std::env::temp_dir().join(format!("tydle-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let dest = dir.join("stream.mp4");
+        let options = DownloadOptions {
+            verify_length: true,
+            ..Default::default()
+        };
+
+        let result = Tydle::new(TydleOptions::default())
+            .unwrap()
+            .download(stream, &dest, options)
+            .await;
+
+        (result, dest)
+    }
+
+    #[tokio::test]
+    async fn verified_download_of_a_matching_stream_goes_ahead() {
+        let body = vec![7u8; 64 * 1024];
+        let server = TestServer::start({
+            let body = body.clone();
+            move |request| TestResponse::ranged(request, &body)
+        })
+        .await;
+        let mut stream = plain_stream(&server, "140");
+        stream.file_size = Some(body.len() as u64);
+
+        let (result, dest) = verified_download("verified-ok", &stream).await;
+
+        assert_eq!(result.unwrap().bytes, body.len() as u64);
+        assert_eq!(std::fs::read(&dest).unwrap(), body);
+        // The first byte is requested before the download.
+        let requests = server.requests();
+        assert_eq!(requests[0].range(), Some((0, Some(0))));
+        assert_eq!(requests.len(), 2);
+        std::fs::remove_dir_all(dest.parent().unwrap()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn verified_download_of_a_mismatched_stream_aborts_before_writing() {
+        let server = TestServer::start(|request| TestResponse::ranged(request, &[7u8; 1000])).await;
+        let mut stream = plain_stream(&server, "140");
+        stream.file_size = Some(64 * 1024);
+
+        let (result, dest) = verified_download("verified-mismatch", &stream).await;
+
+        let error = result.unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<StreamLengthMismatch>(),
+            Some(&StreamLengthMismatch {
+                itag: Some("140".into()),
+                declared: 64 * 1024,
+                actual: 1000,
+            })
+        );
+        assert_eq!(server.requests().len(), 1);
+        assert!(!dest.parent().unwrap().exists());
+    }
+
+    #[tokio::test]
+    async fn verified_download_of_a_rejected_url_fails_with_url_rejected() {
+        let server = TestServer::start(|_| TestResponse::status(403)).await;
+        let mut stream = plain_stream(&server, "140");
+        stream.file_size = Some(64 * 1024);
+
+        let (result, dest) = verified_download("verified-rejected", &stream).await;
+
+        let error = result.unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<DecipherError>(),
+            Some(DecipherError::UrlRejected { status: 403 })
+        ));
+        assert!(error.downcast_ref::<StreamLengthMismatch>().is_none());
+        assert_eq!(server.requests().len(), 1);
+        assert!(!dest.parent().unwrap().exists());
+    }
+
+    /// Options reading `chunk_size` bytes at a time, keeping every progress report in `reports`.
+    fn reporting_options(
+        chunk_size: u64,
+        reports: &Arc<Mutex<Vec<DownloadProgress>>>,
+    ) -> DownloadOptions {
+        let reports = reports.clone();
+
+        DownloadOptions {
+            chunk_size,
+            on_progress: Some(Box::new(move |progress| {
+                reports.lock().unwrap().push(progress)
+            })),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn stream_bytes_only_requests_ranges_as_fast_as_they_are_read() {
+        const CHUNK_SIZE: u64 = 64 * 1024;
+        let body: Vec<u8> = (0..1024 * 1024).map(|i| (i % 251) as u8).collect();
+        let server = TestServer::start({
+            let body = body.clone();
+            move |request| TestResponse::ranged(request, &body)
+        })
+        .await;
+        let mut stream = plain_stream(&server, "140");
+        stream.file_size = Some(body.len() as u64);
+        let tydle = Tydle::new(TydleOptions::default()).unwrap();
+
+        let streamed_reports = Arc::new(Mutex::new(Vec::new()));
+        let mut bytes = Box::pin(
+            tydle
+                .stream_bytes(&stream, reporting_options(CHUNK_SIZE, &streamed_reports))
+                .unwrap(),
+        );
+        let mut streamed = Vec::new();
+
+        while let Some(chunk) = bytes.next().await {
+            streamed.extend_from_slice(&chunk.unwrap());
+            // A slow reader, the next range mustn't be asked for before this one is read.
+            tokio::time::sleep(Duration::from_millis(2)).await;
+            let read_ranges = (streamed.len() as u64).div_ceil(CHUNK_SIZE).max(1);
+            assert!(server.count("/videoplayback") as u64 <= read_ranges);
+        }
+        drop(bytes);
+
+        assert_eq!(streamed, body);
+        assert_eq!(
+            server.count("/videoplayback") as u64,
+            body.len() as u64 / CHUNK_SIZE
+        );
+
+        let dest = std::env::temp_dir().join(format!("tydle-streamed-{}.mp4", std::process::id()));
+        let downloaded_reports = Arc::new(Mutex::new(Vec::new()));
+        tydle
+            .download(
+                &stream,
+                &dest,
+                reporting_options(CHUNK_SIZE, &downloaded_reports),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(std::fs::read(&dest).unwrap(), streamed);
+        std::fs::remove_file(&dest).unwrap();
+
+        // Both end on the same report of the whole stream.
+        let last = |reports: &Mutex<Vec<DownloadProgress>>| {
+            let progress = *reports.lock().unwrap().last().unwrap();
+            (progress.bytes_done, progress.total)
+        };
+        assert_eq!(
+            last(&streamed_reports),
+            (body.len() as u64, Some(body.len() as u64))
+        );
+        assert_eq!(last(&streamed_reports), last(&downloaded_reports));
+    }
+
+    #[tokio::test]
+    async fn purge_caches_downloads_the_player_again() {
+        let server = player_server().await;
+        let tydle = Tydle::new(TydleOptions::default()).unwrap();
+        let player_url = server.url(PLAYER_PATH);
+
+        tydle
+            .yt_extractor
+            .load_player(None, player_url.clone())
+            .await
+            .unwrap();
+        tydle
+            .signature_decipher
+            .signature_strategies
+            .insert("strategy".to_string(), 1u8)
+            .unwrap();
+
+        tydle.purge_caches().unwrap();
+        tydle
+            .yt_extractor
+            .load_player(None, player_url)
+            .await
+            .unwrap();
+
+        assert_eq!(server.count("/s/player/"), 2);
+        assert!(
+            tydle
+                .signature_decipher
+                .signature_strategies
+                .is_empty()
+                .unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn instances_with_shared_caches_download_the_player_once() {
+        let server = player_server().await;
+        let first = Tydle::new(TydleOptions::default()).unwrap();
+        let second = Tydle::with_caches(
+            TydleOptions::default(),
+            first.player_cache(),
+            first.code_cache(),
+        )
+        .unwrap();
+        let clone = first.clone();
+        let player_url = server.url(PLAYER_PATH);
+
+        for tydle in [&first, &second, &clone] {
+            tydle
+                .yt_extractor
+                .load_player(None, player_url.clone())
+                .await
+                .unwrap();
+        }
+
+        assert_eq!(server.count("/s/player/"), 1);
+    }
+
+    /// Stream with a URL of the extraction `generation`, which needs no deciphering.
+    fn extracted_stream(itag: &str, generation: u32) -> YtStream {
+        YtStream::new(
+            None,
+            None,
+            Some(itag.into()),
+            None,
+            YtStreamSource::URL(format!(
+                "https://rr1---sn-test.googlevideo.com/videoplayback?itag={}&expire={}",
+                itag, generation
+            )),
+            0.0,
+        )
+    }
+
+    fn refreshed_response() -> YtStreamResponse {
+        let mut drc = extracted_stream("251", 2);
+        drc.is_drc = true;
+
+        YtStreamResponse::new(
+            format!("https://www.youtube.com{}", PLAYER_PATH),
+            vec![
+                extracted_stream("137", 2),
+                drc,
+                extracted_stream("251", 2),
+                extracted_stream("140", 2),
+            ],
+        )
+    }
+
+    #[tokio::test]
+    async fn gone_itag_does_not_keep_the_others_from_refreshing() {
+        let tydle = Tydle::new(TydleOptions::default()).unwrap();
+
+        let refreshed = tydle
+            .refreshed_streams(refreshed_response(), &[137, 22, 251, 140])
+            .await
+            .unwrap();
+
+        assert_eq!(refreshed.len(), 4);
+        assert_eq!(
+            refreshed[1]
+                .as_ref()
+                .unwrap_err()
+                .downcast_ref::<FormatGone>(),
+            Some(&FormatGone { itag: 22 })
+        );
+
+        for (result, itag) in [
+            (&refreshed[0], "137"),
+            (&refreshed[2], "251"),
+            (&refreshed[3], "140"),
+        ] {
+            let stream = result.as_ref().unwrap();
+            assert_eq!(stream.itag.as_deref(), Some(itag));
+            assert_eq!(stream.source, extracted_stream(itag, 2).source);
+        }
+        // The regular stream is the one asked for by its itag, not its DRC variant.
+        assert!(!refreshed[2].as_ref().unwrap().is_drc);
+    }
+
+    #[tokio::test]
+    async fn gone_stream_fails_to_refresh_with_its_itag() {
+        let tydle = Tydle::new(TydleOptions::default()).unwrap();
+
+        let err = tydle
+            .refreshed_stream(&extracted_stream("22", 1), 22, refreshed_response())
+            .await
+            .unwrap_err();
+
+        assert_eq!(
+            err.downcast_ref::<FormatGone>(),
+            Some(&FormatGone { itag: 22 })
+        );
+    }
+
+    #[tokio::test]
+    async fn refreshed_stream_keeps_its_drc_variant() {
+        let tydle = Tydle::new(TydleOptions::default()).unwrap();
+        let mut stream = extracted_stream("251", 1);
+        stream.is_drc = true;
+
+        let fresh_stream = tydle
+            .refreshed_stream(&stream, 251, refreshed_response())
+            .await
+            .unwrap();
+
+        assert!(fresh_stream.is_drc);
+        assert_eq!(fresh_stream.source, extracted_stream("251", 2).source);
     }
 }
@@ -1,5 +1,9 @@
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
+use sha1::{Digest, Sha1};
 use url::{Url, form_urlencoded};
 
 pub fn parse_query_string(qs: &str) -> Option<HashMap<String, String>> {
@@ -21,12 +25,429 @@ pub fn replace_n_sig_query_param(
 ) -> Result<String, url::ParseError> {
     let mut url = Url::parse(url_with_sig)?;
 
-    let mut query_pairs: HashMap<_, _> = url.query_pairs().into_owned().collect();
-
-    if let Some(_) = query_pairs.remove("n") {
-        query_pairs.insert("n".to_string(), deciphered_n);
-    }
+    // Rebuilt in the original order, some CDNs are picky about the parameters being reordered.
+    let query_pairs: Vec<(String, String)> = url
+        .query_pairs()
+        .into_owned()
+        .map(|(key, value)| match key.as_str() {
+            "n" => (key, deciphered_n.clone()),
+            _ => (key, value),
+        })
+        .collect();
     url.query_pairs_mut().clear().extend_pairs(query_pairs);
 
     Ok(url.to_string())
 }
+
+/// Parse the counts YouTube formats for display, like `1,234 videos` or `1.2M views`.
+/// Handles the separators and abbreviations of other languages too, like `1.234 Aufrufe` or `1,2 Mio.`.
+pub fn parse_count(text: &str) -> Option<u64> {
+    let start = text.find(|c: char| c.is_ascii_digit())?;
+    let text = &text[start..];
+    let is_separator = |c: char| matches!(c, ',' | '.' | '\'' | '\u{a0}' | '\u{202f}' | ' ');
+
+    let mut number_end = 0;
+    let mut groups: Vec<&str> = Vec::new();
+    let mut group_start = 0;
+
+    for (index, c) in text.char_indices() {
+        if c.is_ascii_digit() {
+            number_end = index + 1;
+        } else if is_separator(c)
+            && text[index + c.len_utf8()..].starts_with(|c: char| c.is_ascii_digit())
+        {
+            groups.push(&text[group_start..index]);
+            group_start = index + c.len_utf8();
+        } else {
+            break;
+        }
+    }
+    groups.push(&text[group_start..number_end]);
+
+    // The last group is the fraction unless it's a group of thousands, like in `1,234` but not `1,2`.
+    let number: f64 = match groups.split_last() {
+        Some((last, rest)) if !rest.is_empty() && last.len() != 3 => {
+            format!("{}.{}", rest.concat(), last).parse().ok()?
+        }
+        _ => groups.concat().parse().ok()?,
+    };
+
+    let suffix = text[number_end..]
+        .trim_start_matches(is_separator)
+        .split(|c: char| c.is_whitespace())
+        .next()
+        .unwrap_or_default()
+        .trim_end_matches('.')
+        .to_lowercase();
+
+    let multiplier = match suffix.as_str() {
+        "k" | "tsd" | "mil" | "tys" | "тыс" => 1e3,
+        "m" | "mio" | "mln" | "mi" | "млн" => 1e6,
+        "b" | "bn" | "mrd" | "md" | "млрд" => 1e9,
+        _ if suffix.starts_with(['万', '萬']) => 1e4,
+        _ if suffix.starts_with(['億', '亿']) => 1e8,
+        _ => 1.0,
+    };
+
+    Some((number * multiplier).round() as u64)
+}
+
+/// Parse durations formatted like `4:13` or `1:02:03` into seconds.
+pub fn parse_duration(text: &str) -> Option<u64> {
+    text.trim().split(':').try_fold(0, |total, part| {
+        Some(total * 60 + part.parse::<u64>().ok()?)
+    })
+}
+
+/// Build the `SAPISIDHASH` value of the `Authorization` header from the `SAPISID` cookie.
+/// The current time is passed in so the hash stays reproducible.
+pub fn sapisid_hash(sapisid: &str, origin: &str, now: SystemTime) -> String {
+    sid_authorization("SAPISIDHASH", sapisid, origin, &[], now)
+}
+
+/// Build a `<scheme> <timestamp>_<sha1>` authorization, where the SHA1 is of `"<timestamp> <sid> <origin>"`.
+/// The values of `additional_parts` are prepended to the hashed string joined with `:`,
+/// and their keys are appended to the result, like yt-dlp does.
+pub fn sid_authorization(
+    scheme: &str,
+    sid: &str,
+    origin: &str,
+    additional_parts: &[(&str, &str)],
+    now: SystemTime,
+) -> String {
+    let time_stamp = now
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+        .to_string();
+
+    let mut hash_parts: Vec<String> = Vec::new();
+
+    if !additional_parts.is_empty() {
+        hash_parts.push(
+            additional_parts
+                .iter()
+                .map(|(_, value)| *value)
+                .collect::<Vec<_>>()
+                .join(":"),
+        );
+    }
+
+    hash_parts.extend_from_slice(&[time_stamp.clone(), sid.to_string(), origin.to_string()]);
+
+    let mut hasher = Sha1::new();
+    hasher.update(hash_parts.join(" ").as_bytes());
+    let sid_hash = format!("{:x}", hasher.finalize());
+
+    let mut parts: Vec<String> = vec![time_stamp, sid_hash];
+
+    if !additional_parts.is_empty() {
+        parts.push(additional_parts.iter().map(|(key, _)| *key).collect());
+    }
+
+    format!("{} {}", scheme, parts.join("_"))
+}
+
+/// Encode the characters that can't appear as they are in XML text or attribute values.
+pub fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for character in text.chars() {
+        match character {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            character => escaped.push(character),
+        }
+    }
+
+    escaped
+}
+
+/// Decode the entities of XML text, like `&amp;` and `&#39;`.
+pub fn unescape_xml(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find('&') {
+        unescaped.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let decoded = rest.find(';').and_then(|end| {
+            let character = match &rest[1..end] {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                entity => entity
+                    .strip_prefix("#x")
+                    .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                    .or_else(|| entity.strip_prefix('#')?.parse().ok())
+                    .and_then(char::from_u32),
+            }?;
+
+            Some((character, end))
+        });
+
+        match decoded {
+            Some((character, end)) => {
+                unescaped.push(character);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                unescaped.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    unescaped.push_str(rest);
+    unescaped
+}
+
+/// Current time, read from `Date.now()` on WebAssembly where `SystemTime::now` is unavailable.
+pub fn now() -> SystemTime {
+    #[cfg(target_arch = "wasm32")]
+    return UNIX_EPOCH + Duration::from_millis(js_sys::Date::now() as u64);
+    #[cfg(not(target_arch = "wasm32"))]
+    return SystemTime::now();
+}
+
+/// Parse an HTTP date as used by the `Expires` cookie attribute, e.g. `Wed, 21 Oct 2015 07:28:00 GMT`.
+/// The dashed `21-Oct-2015` form YouTube sends is accepted as well.
+pub fn parse_http_date(date: &str) -> Option<SystemTime> {
+    const MONTHS: [&str; 12] = [
+        "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+    ];
+
+    let date = date.split_once(',').map(|(_, d)| d).unwrap_or(date);
+    let parts: Vec<&str> = date
+        .split(|c: char| c.is_whitespace() || c == '-')
+        .filter(|p| !p.is_empty())
+        .collect();
+
+    let [day, month, year, time, ..] = parts.as_slice() else {
+        return None;
+    };
+
+    let day: u32 = day.parse().ok()?;
+    let month = MONTHS
+        .iter()
+        .position(|m| month.to_ascii_lowercase().starts_with(m))? as u32
+        + 1;
+    let mut year: i64 = year.parse().ok()?;
+
+    // Two digit years are still sent by some servers.
+    if year < 100 {
+        year += if year < 70 { 2000 } else { 1900 };
+    }
+
+    let mut time_parts = time.split(':').map(|t| t.parse::<u64>().ok());
+    let (Some(Some(hours)), Some(Some(minutes)), Some(Some(seconds))) =
+        (time_parts.next(), time_parts.next(), time_parts.next())
+    else {
+        return None;
+    };
+
+    // A leap second can show up as the 61st second of a minute.
+    if !(1601..=9999).contains(&year)
+        || !(1..=31).contains(&day)
+        || hours >= 24
+        || minutes >= 60
+        || seconds > 60
+    {
+        return None;
+    }
+
+    let days = days_from_civil(year, month, day);
+    if days < 0 {
+        return Some(UNIX_EPOCH);
+    }
+
+    let secs = (days as u64)
+        .checked_mul(86400)?
+        .checked_add(hours * 3600 + minutes * 60 + seconds)?;
+    UNIX_EPOCH.checked_add(Duration::from_secs(secs))
+}
+
+/// Number of days since the unix epoch for a date in the proleptic Gregorian calendar.
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = (if year >= 0 { year } else { year - 399 }) / 400;
+    let year_of_era = year - era * 400;
+    let month = month as i64;
+    let day_of_year =
+        (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    era * 146097 + day_of_era - 719468
+}
+
+/// Format a size in bytes with binary units, like `3.45MiB`.
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+
+    if bytes < 1024 {
+        return format!("{}B", bytes);
+    }
+
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    format!("{:.2}{}", size, UNITS[unit])
+}
+
+/// Format seconds like `4:13` or `1:02:03`, the opposite of `parse_duration`.
+pub fn format_duration(seconds: u64) -> String {
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+
+    if hours > 0 {
+        format!("{}:{:02}:{:02}", hours, minutes, seconds)
+    } else {
+        format!("{}:{:02}", minutes, seconds)
+    }
+}
+
+/// Format a number with commas between the thousands, like `1,234,567`.
+pub fn format_count(count: u64) -> String {
+    let digits = count.to_string();
+    let mut formatted = String::with_capacity(digits.len() + digits.len() / 3);
+
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            formatted.push(',');
+        }
+        formatted.push(digit);
+    }
+
+    formatted
+}
+
+/// Convert how loud a video is compared to YouTube's target, in dB, to the gain its volume is multiplied with
+/// to reach the target. YouTube only ever turns videos down, so the gain is capped at 1.0 unless `allow_boost`.
+pub fn loudness_to_gain(loudness_db: f32, allow_boost: bool) -> f32 {
+    let gain = 10f32.powf(-loudness_db / 20.0);
+
+    match allow_boost {
+        true => gain,
+        false => gain.min(1.0),
+    }
+}
+
+/// Get the decoded value of the `key` parameter of the query of `url`.
+pub fn get_url_query_param(url: &str, key: &str) -> Option<String> {
+    let url = Url::parse(url).ok()?;
+    let (_, value) = url.query_pairs().find(|(k, _)| k == key)?;
+
+    Some(value.into_owned())
+}
+
+/// Read when a googlevideo URL stops working from its `expire` parameter, in seconds since the unix epoch.
+pub fn get_url_expiry(url: &str) -> Option<SystemTime> {
+    let expire = get_url_query_param(url, "expire")?;
+
+    UNIX_EPOCH.checked_add(Duration::from_secs(expire.parse().ok()?))
+}
+
+/// Seconds left until `expires_at`, 0 once it has passed.
+pub fn seconds_until(expires_at: SystemTime, now: SystemTime) -> u64 {
+    expires_at
+        .duration_since(now)
+        .map(|remaining| remaining.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_http_date_formats() {
+        let expected = UNIX_EPOCH + Duration::from_secs(1445412480);
+
+        assert_eq!(
+            parse_http_date("Wed, 21 Oct 2015 07:28:00 GMT"),
+            Some(expected)
+        );
+        assert_eq!(
+            parse_http_date("Wed, 21-Oct-2015 07:28:00 GMT"),
+            Some(expected)
+        );
+        assert_eq!(
+            parse_http_date("Wed, 21-Oct-15 07:28:00 GMT"),
+            Some(expected)
+        );
+    }
+
+    #[test]
+    fn parse_http_date_rejects_out_of_range_fields() {
+        for date in [
+            "Wed, 21 Oct 2015 24:00:00 GMT",
+            "Wed, 21 Oct 2015 07:60:00 GMT",
+            "Wed, 21 Oct 2015 07:28:61 GMT",
+            "Wed, 32 Oct 2015 07:28:00 GMT",
+            "Wed, 21 Oct 2015 99999999999999:00:00 GMT",
+            "Wed, 21 Oct 9223372036854775807 07:28:00 GMT",
+        ] {
+            assert_eq!(parse_http_date(date), None, "{}", date);
+        }
+    }
+
+    #[test]
+    fn get_url_expiry_out_of_range() {
+        assert_eq!(
+            get_url_expiry("https://rr1.googlevideo.com/videoplayback?expire=1700000000&itag=18"),
+            Some(UNIX_EPOCH + Duration::from_secs(1700000000))
+        );
+        assert_eq!(
+            get_url_expiry(&format!(
+                "https://rr1.googlevideo.com/videoplayback?expire={}",
+                u64::MAX
+            )),
+            None
+        );
+        assert_eq!(
+            get_url_expiry("https://rr1.googlevideo.com/videoplayback?itag=18"),
+            None
+        );
+    }
+
+    // Expected values computed like yt-dlp's `_make_sid_authorization`.
+    #[test]
+    fn sapisid_hash_matches_yt_dlp() {
+        let now = UNIX_EPOCH + Duration::from_secs(1700000000);
+
+        assert_eq!(
+            sapisid_hash("abcDEF/ghi", "https://www.youtube.com", now),
+            "SAPISIDHASH 1700000000_f09245f47c3c8f151971b883e625628368e1e5f9"
+        );
+        assert_eq!(
+            sid_authorization(
+                "SAPISID1PHASH",
+                "abcDEF/ghi",
+                "https://www.youtube.com",
+                &[("u", "session")],
+                now
+            ),
+            "SAPISID1PHASH 1700000000_69ff5bf82830ac3bd4c10e2f4d1d593b5f5a486e_u"
+        );
+    }
+
+    #[test]
+    fn loudness_gain_is_capped_at_one_unless_boosting() {
+        assert_eq!(loudness_to_gain(0.0, false), 1.0);
+        assert_eq!(loudness_to_gain(0.0, true), 1.0);
+        // 20 dB is a factor of 10 in amplitude.
+        assert!((loudness_to_gain(20.0, false) - 0.1).abs() < 1e-6);
+        assert_eq!(loudness_to_gain(-20.0, false), 1.0);
+        assert!((loudness_to_gain(-20.0, true) - 10.0).abs() < 1e-4);
+    }
+}
@@ -1,26 +1,52 @@
 use core::fmt;
-use std::{collections::HashMap, ops::Deref, str::FromStr};
+use std::{
+    collections::HashMap,
+    marker::PhantomData,
+    ops::Deref,
+    str::FromStr,
+    time::{Duration, SystemTime},
+};
 
 use anyhow::{Result, anyhow, bail};
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
 use serde_json::Value;
+use url::Url;
+
+use crate::error::{ContinuationExpired, IsClip};
+use crate::itag;
+use crate::tydle::{Cipher, Tydle};
+use crate::utils::{
+    escape_xml, format_bytes, format_count, format_duration, get_url_expiry, get_url_query_param,
+    loudness_to_gain, now, seconds_until,
+};
 
 #[derive(Debug)]
 pub enum YtEndpoint {
-    // Browse,
+    Browse,
     Player,
     Next,
+    ResolveUrl,
+    Search,
 }
 
 impl YtEndpoint {
     pub fn as_str(&self) -> &'static str {
         match self {
-            // Self::Browse => "browse",
+            Self::Browse => "browse",
             Self::Player => "player",
             Self::Next => "next",
+            Self::ResolveUrl => "navigation/resolve_url",
+            Self::Search => "search",
         }
     }
 }
 
+#[cfg_attr(
+    target_arch = "wasm32",
+    derive(serde::Serialize, serde::Deserialize, tsify::Tsify),
+    tsify(into_wasm_abi, from_wasm_abi),
+    serde(rename_all = "snake_case")
+)]
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
 pub enum YtClient {
     Web,
@@ -36,8 +62,10 @@ pub enum YtClient {
     /// YouTube Kids videos aren't returned on this client.
     AndroidVr,
     /// iOS clients have HLS live streams. Setting device model to get 60fps formats.
+    #[cfg_attr(target_arch = "wasm32", serde(rename = "ios"))]
     IOS,
     // mweb has 'ultralow' formats.
+    #[cfg_attr(target_arch = "wasm32", serde(rename = "mweb"))]
     MWeb,
     Tv,
     /// This client now requires sign-in for every video.
@@ -82,6 +110,8 @@ impl YtClient {
 pub(crate) const DEFAULT_YT_CLIENT: YtClient = YtClient::Web;
 pub(crate) const PREFERRED_LOCALE: &str = "en";
 pub(crate) const YT_URL: &str = "https://www.youtube.com";
+pub(crate) const YT_DOMAIN: &str = "youtube.com";
+pub(crate) const YT_CONSENT_HOST: &str = "consent.youtube.com";
 
 pub const AUDIO_ONLY_FORMATS: [&str; 4] = [
     "audio_quality_ultralow",
@@ -138,12 +168,23 @@ pub enum PlayerIdentifier {
     PlayerUrl(String),
 }
 
+#[cfg_attr(
+    target_arch = "wasm32",
+    derive(serde::Serialize, serde::Deserialize, tsify::Tsify),
+    tsify(into_wasm_abi, from_wasm_abi)
+)]
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Ord, PartialOrd)]
 pub struct VideoId(String);
 
 impl VideoId {
+    /// Clips and their URLs fail with `tydle::error::IsClip`, resolve them with `resolve_clip` instead.
     pub fn new<S: Into<String>>(s: S) -> Result<Self> {
         let s = s.into();
+
+        if s.contains("/clip/") || is_clip_id(&s) {
+            return Err(IsClip { input: s }.into());
+        }
+
         if s.len() != 11 {
             return Err(anyhow!(
                 "invalid length: expected 11 characters, got {}",
@@ -186,6 +227,322 @@ impl fmt::Display for VideoId {
     }
 }
 
+const PLAYLIST_ID_PREFIXES: [&str; 4] = ["PL", "UU", "OLAK5uy_", "RD"];
+
+/// ID of a playlist, `PL...` for playlists of users, `UU...` for the uploads of a channel,
+/// `OLAK5uy_...` for albums and `RD...` for mixes.
+#[cfg_attr(
+    target_arch = "wasm32",
+    derive(serde::Serialize, serde::Deserialize, tsify::Tsify),
+    tsify(into_wasm_abi, from_wasm_abi)
+)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Ord, PartialOrd)]
+pub struct PlaylistId(String);
+
+impl PlaylistId {
+    /// Accepts the ID itself or any URL with a `list` parameter, like `https://www.youtube.com/playlist?list=PL...`.
+    pub fn new<S: Into<String>>(s: S) -> Result<Self> {
+        let s = s.into();
+        let id = match Url::parse(&s) {
+            Ok(url) => url
+                .query_pairs()
+                .find(|(key, _)| key == "list")
+                .map(|(_, list)| list.into_owned())
+                .ok_or_else(|| anyhow!("no playlist in URL: {}", s))?,
+            Err(_) => s,
+        };
+
+        if !PLAYLIST_ID_PREFIXES
+            .iter()
+            .any(|prefix| id.starts_with(prefix))
+        {
+            bail!(
+                "invalid playlist ID, expected it to start with one of {}: {}",
+                PLAYLIST_ID_PREFIXES.join(", "),
+                id
+            )
+        }
+
+        if id.len() < 12
+            || !id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            bail!("invalid playlist ID: {}", id)
+        }
+
+        Ok(Self(id))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl FromStr for PlaylistId {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::new(s)
+    }
+}
+
+impl fmt::Display for PlaylistId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// Clip IDs are `Ugkx` or `Ugw` followed by more characters, longer than the IDs of videos.
+fn is_clip_id(s: &str) -> bool {
+    s.len() >= 26
+        && s.starts_with("Ug")
+        && s.chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// ID of a clip, a part of a video cut by a viewer, which lives at `https://www.youtube.com/clip/<id>`.
+#[cfg_attr(
+    target_arch = "wasm32",
+    derive(serde::Serialize, serde::Deserialize, tsify::Tsify),
+    tsify(into_wasm_abi, from_wasm_abi)
+)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ClipId(String);
+
+impl ClipId {
+    /// Accepts the ID itself or a `/clip/` URL, like `https://www.youtube.com/clip/Ugkx...`.
+    pub fn new<S: Into<String>>(s: S) -> Result<Self> {
+        let s = s.into();
+        let id = match Url::parse(&s) {
+            Ok(url) => url
+                .path()
+                .strip_prefix("/clip/")
+                .map(|id| id.trim_end_matches('/').to_string())
+                .ok_or_else(|| anyhow!("no clip in URL: {}", s))?,
+            Err(_) => s,
+        };
+
+        if !is_clip_id(&id) {
+            bail!("invalid clip ID: {}", id)
+        }
+
+        Ok(Self(id))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn get_url(&self) -> String {
+        format!("{}/clip/{}", YT_URL, self.0)
+    }
+}
+
+impl FromStr for ClipId {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::new(s)
+    }
+}
+
+impl fmt::Display for ClipId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// Basic metadata of a video from the oEmbed endpoint, see `get_oembed`.
+#[cfg_attr(
+    target_arch = "wasm32",
+    derive(serde::Serialize, serde::Deserialize, tsify::Tsify),
+    tsify(into_wasm_abi, from_wasm_abi),
+    serde(rename_all = "camelCase")
+)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OEmbedInfo {
+    pub video_id: VideoId,
+    pub title: String,
+    /// Name of the channel that uploaded the video.
+    pub author_name: String,
+    /// URL of the channel that uploaded the video, like `https://www.youtube.com/@YouTube`.
+    pub author_url: String,
+    /// URL of the `hqdefault` thumbnail.
+    pub thumbnail_url: String,
+    pub thumbnail_width: u64,
+    pub thumbnail_height: u64,
+    /// `<iframe>` that embeds the video.
+    pub html: String,
+}
+
+/// Whether a video can be played right now, see `check_availability`.
+#[cfg_attr(
+    target_arch = "wasm32",
+    derive(serde::Serialize, serde::Deserialize, tsify::Tsify),
+    tsify(into_wasm_abi, from_wasm_abi),
+    serde(rename_all = "camelCase")
+)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum YtAvailability {
+    Playable,
+    Private,
+    /// Removed by the uploader or YouTube, never existed, or its channel was terminated.
+    Deleted,
+    /// Can only be played with the cookies of a logged-in account.
+    AgeRestricted,
+    /// Not available in the country the request came from.
+    GeoBlocked,
+    /// A premiere or live stream that hasn't started yet.
+    Upcoming,
+    /// A live stream that is on air.
+    LiveNow,
+    /// Unplayable for a reason not covered by the other variants, like being members-only or blocked on copyright grounds.
+    Unavailable,
+}
+
+/// The part of a video a clip is cut from, to download the streams of the video and trim them to it.
+#[cfg_attr(
+    target_arch = "wasm32",
+    derive(serde::Serialize, serde::Deserialize, tsify::Tsify),
+    tsify(into_wasm_abi, from_wasm_abi),
+    serde(rename_all = "camelCase")
+)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClipInfo {
+    /// The video the clip is cut from.
+    pub video_id: VideoId,
+    /// Offset of the start of the clip in the video.
+    #[cfg_attr(
+        target_arch = "wasm32",
+        tsify(type = "{ secs: number, nanos: number }")
+    )]
+    pub start: Duration,
+    /// Offset of the end of the clip in the video.
+    #[cfg_attr(
+        target_arch = "wasm32",
+        tsify(type = "{ secs: number, nanos: number }")
+    )]
+    pub end: Duration,
+    /// Title the viewer gave the clip.
+    pub title: String,
+}
+
+/// A channel as it can be referred to on YouTube. Only `Id` is the canonical `UC...` ID,
+/// the other forms are resolved to it when extracting the channel.
+#[cfg_attr(
+    target_arch = "wasm32",
+    derive(serde::Serialize, serde::Deserialize, tsify::Tsify),
+    tsify(into_wasm_abi, from_wasm_abi),
+    serde(rename_all = "camelCase")
+)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ChannelId {
+    /// `UC...`, from `/channel/UC...` URLs.
+    Id(String),
+    /// `@handle`, stored with the `@`.
+    Handle(String),
+    /// Name of a legacy `/c/...` URL.
+    Custom(String),
+    /// Name of a legacy `/user/...` URL.
+    User(String),
+}
+
+impl ChannelId {
+    /// Accepts `UC...` IDs, `@handle`s and `/channel/`, `/c/`, `/user/` or `/@handle` URLs.
+    pub fn new<S: Into<String>>(s: S) -> Result<Self> {
+        let s = s.into();
+        let s = s.trim();
+
+        let Ok(url) = Url::parse(s) else {
+            return Self::from_path_segments(&[s]);
+        };
+
+        if !url
+            .host_str()
+            .is_some_and(|host| host == "youtube.com" || host.ends_with(".youtube.com"))
+        {
+            bail!("not a YouTube channel URL: {}", s)
+        }
+
+        let segments = url
+            .path_segments()
+            .map(|segments| segments.filter(|s| !s.is_empty()).collect::<Vec<_>>())
+            .unwrap_or_default();
+
+        Self::from_path_segments(&segments)
+    }
+
+    fn from_path_segments(segments: &[&str]) -> Result<Self> {
+        let is_valid_name = |name: &str| {
+            !name.is_empty()
+                && name
+                    .chars()
+                    .all(|c| c.is_alphanumeric() || c == '-' || c == '_' || c == '.')
+        };
+
+        let channel_id = match segments {
+            [id] | ["channel", id, ..] if id.starts_with("UC") => {
+                if id.len() != 24 || !is_valid_name(id) {
+                    bail!("invalid channel ID: {}", id)
+                }
+
+                Self::Id(id.to_string())
+            }
+            [handle, ..] if handle.starts_with('@') => {
+                if !is_valid_name(&handle[1..]) {
+                    bail!("invalid channel handle: {}", handle)
+                }
+
+                Self::Handle(handle.to_string())
+            }
+            ["c", name, ..] if is_valid_name(name) => Self::Custom(name.to_string()),
+            ["user", name, ..] if is_valid_name(name) => Self::User(name.to_string()),
+            _ => bail!(
+                "expected a channel ID, @handle or channel URL: {}",
+                segments.join("/")
+            ),
+        };
+
+        Ok(channel_id)
+    }
+
+    /// Get the `UC...` ID, if this is already the canonical form.
+    pub fn as_canonical(&self) -> Option<&str> {
+        match self {
+            Self::Id(id) => Some(id),
+            _ => None,
+        }
+    }
+
+    pub fn get_url(&self) -> String {
+        match self {
+            Self::Id(id) => format!("{}/channel/{}", YT_URL, id),
+            Self::Handle(handle) => format!("{}/{}", YT_URL, handle),
+            Self::Custom(name) => format!("{}/c/{}", YT_URL, name),
+            Self::User(name) => format!("{}/user/{}", YT_URL, name),
+        }
+    }
+}
+
+impl FromStr for ChannelId {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::new(s)
+    }
+}
+
+impl fmt::Display for ChannelId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Id(id) | Self::Handle(id) => id.fmt(f),
+            Self::Custom(name) => write!(f, "c/{}", name),
+            Self::User(name) => write!(f, "user/{}", name),
+        }
+    }
+}
+
 #[cfg_attr(
     target_arch = "wasm32",
     derive(serde::Serialize, serde::Deserialize, tsify::Tsify),
@@ -217,6 +574,26 @@ impl YtChannel {
     }
 }
 
+/// The uploader of a video as shown under the player.
+#[cfg_attr(
+    target_arch = "wasm32",
+    derive(serde::Serialize, serde::Deserialize, tsify::Tsify),
+    tsify(into_wasm_abi, from_wasm_abi),
+    serde(rename_all = "camelCase")
+)]
+#[derive(Debug)]
+pub struct YtChannelInfo {
+    pub id: ChannelId,
+    pub name: String,
+    /// `@handle` of the channel, with the `@`.
+    pub handle: Option<String>,
+    pub avatar: Vec<YtThumbnail>,
+    /// Like `1.2M subscribers`, missing when the channel hides its subscriber count.
+    pub subscriber_count_text: Option<String>,
+    /// Whether the channel has the verified or the official artist badge.
+    pub is_verified: bool,
+}
+
 #[cfg_attr(
     target_arch = "wasm32",
     derive(serde::Serialize, serde::Deserialize, tsify::Tsify),
@@ -226,7 +603,149 @@ impl YtChannel {
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub enum YtStreamSource {
     URL(String),
-    Signature(String),
+    Signature(SignatureCipher),
+}
+
+/// The parts of a stream's `signatureCipher`, whose signature has to be deciphered to get a playable URL.
+///
+/// Parsed from and displayed as the URL-encoded query string YouTube sends, e.g. `s=...&sp=sig&url=...`.
+#[cfg_attr(
+    target_arch = "wasm32",
+    derive(serde::Serialize, serde::Deserialize, tsify::Tsify),
+    tsify(into_wasm_abi, from_wasm_abi),
+    serde(rename_all = "camelCase")
+)]
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct SignatureCipher {
+    /// The encrypted signature.
+    pub s: String,
+    /// Name of the query parameter the deciphered signature is passed in, `signature` if missing.
+    pub sp: Option<String>,
+    /// The stream URL, without the signature.
+    pub url: String,
+}
+
+impl SignatureCipher {
+    pub fn signature_param(&self) -> &str {
+        self.sp.as_deref().unwrap_or("signature")
+    }
+}
+
+impl FromStr for SignatureCipher {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut params: HashMap<String, String> = url::form_urlencoded::parse(s.as_bytes())
+            .into_owned()
+            .collect();
+
+        let Some(encrypted_sig) = params.remove("s") else {
+            bail!("The signature cipher is missing the encrypted signature `s`.")
+        };
+        let Some(url) = params.remove("url") else {
+            bail!("The signature cipher is missing the stream `url`.")
+        };
+
+        Ok(Self {
+            s: encrypted_sig,
+            sp: params.remove("sp"),
+            url,
+        })
+    }
+}
+
+impl fmt::Display for SignatureCipher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut serializer = url::form_urlencoded::Serializer::new(String::new());
+        serializer.append_pair("s", &self.s);
+
+        if let Some(sp) = &self.sp {
+            serializer.append_pair("sp", sp);
+        }
+
+        serializer.append_pair("url", &self.url);
+
+        f.write_str(&serializer.finish())
+    }
+}
+
+/// A resolved stream URL to download from, which adds the parameters googlevideo understands
+/// without disturbing the others, like the signature and `n`.
+///
+/// Only the parameter that's set is rewritten, the rest of the URL is kept exactly as it was.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StreamUrl(String);
+
+impl StreamUrl {
+    /// Download only bytes `start` to `end` of the stream, both inclusive, replacing any `range` the URL has.
+    pub fn with_range(self, start: u64, end: u64) -> Self {
+        self.with_param("range", &format!("{}-{}", start, end))
+    }
+
+    /// Point the URL at segment `sq` of an OTF stream, see `Extract::get_otf_segments`.
+    pub fn with_segment(self, sq: u64) -> Self {
+        self.with_param("sq", &sq.to_string())
+    }
+
+    /// Ask googlevideo not to throttle the download, which helps with the muxed streams.
+    pub fn with_ratebypass(self) -> Self {
+        self.with_param("ratebypass", "yes")
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Set `key` to `value`, where the parameter already is if it's there and at the end otherwise.
+    /// `value` has to be URL-safe already.
+    fn with_param(self, key: &str, value: &str) -> Self {
+        let (url, fragment) = match self.0.split_once('#') {
+            Some((url, fragment)) => (url, Some(fragment)),
+            None => (self.0.as_str(), None),
+        };
+        let (base, query) = url.split_once('?').unwrap_or((url, ""));
+
+        let param = format!("{}={}", key, value);
+        let mut is_set = false;
+        let mut params: Vec<&str> = Vec::new();
+
+        // The parameters are compared by their raw key so the ones left alone aren't re-encoded.
+        for existing in query.split('&').filter(|existing| !existing.is_empty()) {
+            if existing.split('=').next() != Some(key) {
+                params.push(existing);
+            } else if !is_set {
+                params.push(&param);
+                is_set = true;
+            }
+        }
+
+        if !is_set {
+            params.push(&param);
+        }
+
+        let mut url = format!("{}?{}", base, params.join("&"));
+        if let Some(fragment) = fragment {
+            url.push('#');
+            url.push_str(fragment);
+        }
+
+        Self(url)
+    }
+}
+
+impl FromStr for StreamUrl {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Url::parse(s)?;
+        Ok(Self(s.to_string()))
+    }
+}
+
+impl fmt::Display for StreamUrl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
 }
 
 #[cfg_attr(
@@ -237,12 +756,83 @@ pub enum YtStreamSource {
 )]
 #[derive(Debug, Clone)]
 pub struct YtStream {
+    /// Sample rate of the audio in Hz, the `audioSampleRate` of the format.
     pub asr: Option<u64>,
+    /// Size of the stream in bytes, the `contentLength` of the format.
     pub file_size: Option<u64>,
     pub itag: Option<String>,
     pub quality: Option<String>,
     pub source: YtStreamSource,
+    /// `average_bitrate`, or `bitrate` if it's missing, in bits per second. 1000 if both are.
     pub tbr: f64,
+    /// Peak bitrate in bits per second.
+    pub bitrate: Option<u64>,
+    pub average_bitrate: Option<u64>,
+    /// Codecs of the stream, like `avc1.4d401f` or `mp4a.40.2`.
+    pub codecs: Option<String>,
+    /// The one of `codecs` that encodes the video, missing for audio-only streams.
+    pub video_codec: Option<String>,
+    /// The one of `codecs` that encodes the audio, missing for video-only streams.
+    pub audio_codec: Option<String>,
+    /// Type of the stream as YouTube sends it, like `video/mp4; codecs="avc1.4d401f"`, without the codecs for DASH streams.
+    pub mime_type: Option<String>,
+    /// The container of `mime_type`, like `mp4` or `webm`.
+    pub container: Option<String>,
+    pub width: Option<u64>,
+    pub height: Option<u64>,
+    /// Frames per second of video streams.
+    pub fps: Option<u64>,
+    /// Where `width`, `height` and `fps` came from.
+    pub resolution_source: YtFieldSource,
+    pub kind: YtStreamKind,
+    /// Where `kind`, `container`, `video_codec` and `audio_codec` came from.
+    pub codecs_source: YtFieldSource,
+    /// Quality as shown in the player, like `1080p60`.
+    pub quality_label: Option<String>,
+    /// `quality_label`, or `quality` if it's missing, parsed to compare qualities with.
+    pub parsed_quality: Option<YtQuality>,
+    /// Like `audio_quality_medium`.
+    pub audio_quality: Option<String>,
+    pub audio_channels: Option<u64>,
+    /// Which of the dubs the audio is, for videos with more than one audio track.
+    pub audio_track: Option<YtAudioTrack>,
+    /// Whether the audio went through dynamic range compression, which makes it sound flatter.
+    /// Read from `isDrc` of the format or the `drc` of `xtags`.
+    pub is_drc: bool,
+    /// How much louder than YouTube's target the audio is in dB, negative for quiet audio.
+    /// Only sent for audio streams. See `normalized_gain`.
+    pub loudness_db: Option<f32>,
+    /// How the colors of the video are encoded, missing for audio and for most SDR video.
+    pub color_info: Option<YtColorInfo>,
+    /// Whether the video is HDR, going by `color_info` or else the `HDR` at the end of `quality_label`.
+    pub is_hdr: bool,
+    /// Whether it's one of the `Premium` variants with a higher bitrate, which need YouTube Premium to play in the browser.
+    pub is_premium_bitrate: bool,
+    /// How the video maps onto the screen, anything but `Rectangular` needs a 360° player.
+    pub projection: YtProjection,
+    pub origin: YtStreamOrigin,
+    /// Segments of streams from the DASH manifest that are split into them,
+    /// which have to be downloaded one by one instead of from `source` directly.
+    pub dash_segments: Option<YtDashSegments>,
+    /// Client whose player response had the stream, missing for streams of the DASH manifest.
+    pub client: Option<YtClient>,
+    /// Whether the stream is split into segments that have to be downloaded one by one, which are
+    /// listed by `Extract::get_otf_segments`. Downloading the URL of the stream as is only gets the first.
+    pub is_otf: bool,
+    /// Bytes of the stream that initialize the decoder, from the `initRange` of adaptive streams.
+    pub init_range: Option<YtByteRange>,
+    /// Bytes of the stream that index its segments, from the `indexRange` of adaptive streams.
+    pub index_range: Option<YtByteRange>,
+    /// When the URL of the stream stops working, read from its `expire` parameter.
+    /// Missing if the URL doesn't have one.
+    #[cfg_attr(
+        target_arch = "wasm32",
+        tsify(type = "{ secs_since_epoch: number, nanos_since_epoch: number } | null")
+    )]
+    pub expires_at: Option<SystemTime>,
+    /// Tags that tell apart streams with the same `itag`, from the `xtags` parameter of the URL,
+    /// like `acont=original:lang=en` or `drc=1`. See `xtag`.
+    pub xtags: Option<String>,
 }
 
 impl YtStream {
@@ -254,37 +844,679 @@ impl YtStream {
         source: YtStreamSource,
         tbr: f64,
     ) -> Self {
-        Self {
+        let url = match &source {
+            YtStreamSource::URL(url) => url,
+            YtStreamSource::Signature(signature_cipher) => &signature_cipher.url,
+        };
+        let expires_at = get_url_expiry(url);
+        let xtags = get_url_query_param(url, "xtags");
+
+        let mut stream = Self {
             asr,
             file_size,
             itag,
             quality,
             source,
             tbr,
-        }
+            bitrate: None,
+            average_bitrate: None,
+            codecs: None,
+            video_codec: None,
+            audio_codec: None,
+            mime_type: None,
+            container: None,
+            width: None,
+            height: None,
+            fps: None,
+            resolution_source: Default::default(),
+            kind: Default::default(),
+            codecs_source: Default::default(),
+            quality_label: None,
+            parsed_quality: None,
+            audio_quality: None,
+            audio_channels: None,
+            audio_track: None,
+            is_drc: false,
+            loudness_db: None,
+            color_info: None,
+            is_hdr: false,
+            is_premium_bitrate: false,
+            projection: Default::default(),
+            origin: Default::default(),
+            dash_segments: None,
+            client: None,
+            is_otf: false,
+            init_range: None,
+            index_range: None,
+            expires_at,
+            xtags,
+        };
+        stream.is_drc = stream.xtag("drc") == Some("1");
+
+        stream
     }
-}
 
-#[cfg_attr(target_arch = "wasm32", tsify::declare)]
-pub type YtStreams = Vec<YtStream>;
+    /// Get the value of `key` in `xtags`, like `1` for `drc` or `original` for `acont`.
+    pub fn xtag(&self, key: &str) -> Option<&str> {
+        self.xtags.as_deref()?.split(':').find_map(|tag| {
+            let (tag_key, value) = tag.split_once('=')?;
+            (tag_key == key).then_some(value)
+        })
+    }
 
-#[cfg_attr(
-    target_arch = "wasm32",
-    derive(serde::Serialize, serde::Deserialize, tsify::Tsify),
-    tsify(into_wasm_abi, from_wasm_abi),
-    serde(rename_all = "camelCase")
-)]
-#[derive(Debug)]
-pub struct YtStreamList(YtStreams);
+    /// Get the size of the stream in bytes, exactly from `file_size` or else estimated from the bitrate,
+    /// preferring `average_bitrate` over `bitrate`, and the `duration` of the video.
+    ///
+    /// Live streams have no meaningful duration, pass `Duration::ZERO` for them to get `Unknown`.
+    pub fn size_estimate(&self, duration: Duration) -> YtSizeEstimate {
+        if let Some(file_size) = self.file_size {
+            return YtSizeEstimate::Exact(file_size);
+        }
 
-impl<'a> IntoIterator for &'a YtStreamList {
-    type Item = &'a YtStream;
-    type IntoIter = std::slice::Iter<'a, YtStream>;
+        match self.average_bitrate.or(self.bitrate) {
+            Some(bitrate) if !duration.is_zero() => {
+                YtSizeEstimate::Approx((bitrate as f64 * duration.as_secs_f64() / 8.0) as u64)
+            }
+            _ => YtSizeEstimate::Unknown,
+        }
+    }
 
-    fn into_iter(self) -> Self::IntoIter {
-        self.0.iter()
+    /// Whether the URL of the stream stopped working at `now`.
+    /// Streams whose expiry isn't known are never considered expired, check `expires_at` to tell them apart.
+    pub fn is_expired(&self, now: SystemTime) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
     }
-}
+
+    /// Seconds left until the URL of the stream stops working, missing if its expiry isn't known.
+    pub fn seconds_remaining(&self) -> Option<u64> {
+        self.expires_at
+            .map(|expires_at| seconds_until(expires_at, now()))
+    }
+
+    /// Fill in `kind`, `container`, `video_codec` and `audio_codec` from `mime_type` and `codecs`.
+    /// `is_adaptive` tells whether the stream was listed in `adaptiveFormats` or a DASH manifest.
+    pub(crate) fn parse_mime_type(&mut self, is_adaptive: bool) {
+        let is_audio = match &self.mime_type {
+            Some(mime_type) => mime_type.starts_with("audio/"),
+            None => AUDIO_ONLY_FORMATS.contains(&self.quality.as_deref().unwrap_or_default()),
+        };
+
+        // Muxed streams list a codec for the video and one for the audio, like `avc1.42001E, mp4a.40.2`.
+        // Audio is audio-only even when YouTube lists it in `formats`.
+        self.kind = if is_audio {
+            YtStreamKind::AdaptiveAudio
+        } else if !is_adaptive || self.codecs.as_deref().unwrap_or_default().contains(',') {
+            YtStreamKind::Muxed
+        } else {
+            YtStreamKind::AdaptiveVideo
+        };
+
+        self.container = self
+            .mime_type
+            .as_deref()
+            .and_then(|mime_type| mime_type.split(';').next())
+            .and_then(|mime_type| mime_type.split_once('/'))
+            .map(|(_, container)| container.trim().to_string());
+
+        let codecs: Vec<String> = self
+            .codecs
+            .as_deref()
+            .unwrap_or_default()
+            .split(',')
+            .map(|codec| codec.trim().to_string())
+            .filter(|codec| !codec.is_empty())
+            .collect();
+
+        // Muxed streams list the video codec first.
+        match codecs.as_slice() {
+            [video_codec, audio_codec] => {
+                self.video_codec = Some(video_codec.clone());
+                self.audio_codec = Some(audio_codec.clone());
+            }
+            [codec] if self.is_audio_only() => self.audio_codec = Some(codec.clone()),
+            [codec] => self.video_codec = Some(codec.clone()),
+            _ => {}
+        }
+    }
+
+    /// Fill in what the format didn't say from the `itag` table, marking the values as inferred.
+    pub(crate) fn fill_from_itag_table(&mut self) {
+        let Some(info) = self
+            .itag
+            .as_deref()
+            .and_then(|itag| itag.parse().ok())
+            .and_then(itag::lookup)
+        else {
+            return;
+        };
+
+        if self.mime_type.is_none() && self.codecs.is_none() {
+            self.kind = info.kind;
+            self.container = Some(info.container.to_string());
+            self.video_codec = info.video_codec.map(|codec| codec.to_string());
+            self.audio_codec = info.audio_codec.map(|codec| codec.to_string());
+            self.codecs_source = YtFieldSource::ItagTable;
+        }
+
+        if info.kind != YtStreamKind::AdaptiveAudio
+            && self.width.is_none()
+            && self.height.is_none()
+            && info.height.is_some()
+        {
+            self.width = info.width;
+            self.height = info.height;
+            self.fps = self.fps.or(info.fps);
+            self.resolution_source = YtFieldSource::ItagTable;
+        }
+    }
+
+    pub fn itag(&self) -> Option<&str> {
+        self.itag.as_deref()
+    }
+
+    pub fn height(&self) -> Option<u64> {
+        self.height
+    }
+
+    /// Key that orders streams by quality, for sorting them with `sort_by_key` and the like.
+    /// See `YtStreamQualityKey` for what it compares.
+    pub fn quality_key(&self) -> YtStreamQualityKey {
+        YtStreamQualityKey {
+            height: self.height.unwrap_or_default(),
+            fps: self.fps.unwrap_or_default(),
+            is_not_drc: !self.is_drc,
+            tbr: self.tbr as u64,
+            asr: self.asr.unwrap_or_default(),
+            itag: self.itag_number(),
+        }
+    }
+
+    /// The `itag` as a number, 0 if it's missing.
+    fn itag_number(&self) -> u64 {
+        self.itag
+            .as_deref()
+            .and_then(|itag| itag.parse().ok())
+            .unwrap_or_default()
+    }
+
+    /// Whether the stream has audio and no video.
+    pub fn is_audio_only(&self) -> bool {
+        self.kind == YtStreamKind::AdaptiveAudio
+    }
+
+    /// Whether the stream has video and no audio, unlike the muxed streams that have both.
+    pub fn is_video_only(&self) -> bool {
+        self.kind == YtStreamKind::AdaptiveVideo
+    }
+
+    pub fn is_muxed(&self) -> bool {
+        self.kind == YtStreamKind::Muxed
+    }
+
+    /// Get what to multiply the volume of the stream with to normalize it like YouTube does, see
+    /// `YtVideoInfo::normalized_gain`. Missing if the `loudness_db` of the stream isn't known.
+    pub fn normalized_gain(&self, allow_boost: bool) -> Option<f32> {
+        self.loudness_db
+            .map(|loudness_db| loudness_to_gain(loudness_db, allow_boost))
+    }
+
+    /// Whether the video has 48 frames per second or more, like `1080p60`.
+    pub fn is_high_fps(&self) -> bool {
+        self.fps.is_some_and(|fps| fps >= 48)
+    }
+}
+
+/// The `colorInfo` of a video stream, as YouTube sends it.
+#[cfg_attr(
+    target_arch = "wasm32",
+    derive(serde::Serialize, serde::Deserialize, tsify::Tsify),
+    tsify(into_wasm_abi, from_wasm_abi),
+    serde(rename_all = "camelCase")
+)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct YtColorInfo {
+    /// Like `COLOR_PRIMARIES_BT709` or `COLOR_PRIMARIES_BT2020`.
+    pub primaries: Option<String>,
+    /// Like `COLOR_TRANSFER_CHARACTERISTICS_SMPTEST2084` for PQ or `COLOR_TRANSFER_CHARACTERISTICS_ARIB_STD_B67` for HLG.
+    pub transfer_characteristics: Option<String>,
+    /// Like `COLOR_MATRIX_COEFFICIENTS_BT2020_NCL`.
+    pub matrix_coefficients: Option<String>,
+}
+
+impl YtColorInfo {
+    /// Whether the colors are in the BT.2020 space or use a PQ or HLG transfer function.
+    pub fn is_hdr(&self) -> bool {
+        self.primaries.as_deref() == Some("COLOR_PRIMARIES_BT2020")
+            || matches!(
+                self.transfer_characteristics.as_deref(),
+                Some(
+                    "COLOR_TRANSFER_CHARACTERISTICS_SMPTEST2084"
+                        | "COLOR_TRANSFER_CHARACTERISTICS_ARIB_STD_B67"
+                )
+            )
+    }
+}
+
+/// One of the audio tracks of a video that's dubbed in several languages.
+#[cfg_attr(
+    target_arch = "wasm32",
+    derive(serde::Serialize, serde::Deserialize, tsify::Tsify),
+    tsify(into_wasm_abi, from_wasm_abi),
+    serde(rename_all = "camelCase")
+)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct YtAudioTrack {
+    /// Like `en.4` or `hi-IN.3`.
+    pub id: String,
+    /// Like `English (United States) original`.
+    pub display_name: String,
+    /// Whether it's the track the player picks unless told otherwise, usually the original.
+    pub is_default: bool,
+    /// The language code at the start of `id`, like `en` or `hi-IN`.
+    pub language: Option<String>,
+}
+
+impl YtAudioTrack {
+    pub(crate) fn new(id: String, display_name: String, is_default: bool) -> Self {
+        let language = id
+            .split_once('.')
+            .map(|(language, _)| language.to_string())
+            .filter(|language| !language.is_empty());
+
+        Self {
+            id,
+            display_name,
+            is_default,
+            language,
+        }
+    }
+
+    /// Whether the track is in `language`, either exactly like `en-US` or any variant of it like `en`.
+    pub fn is_in_language(&self, language: &str) -> bool {
+        self.language.as_deref().is_some_and(|track_language| {
+            track_language.eq_ignore_ascii_case(language)
+                || track_language
+                    .split('-')
+                    .next()
+                    .is_some_and(|primary| primary.eq_ignore_ascii_case(language))
+        })
+    }
+}
+
+/// What `Cipher::verify_stream` found out about a stream before downloading it.
+#[cfg_attr(
+    target_arch = "wasm32",
+    derive(serde::Serialize, serde::Deserialize, tsify::Tsify),
+    tsify(into_wasm_abi, from_wasm_abi),
+    serde(rename_all = "camelCase")
+)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct YtStreamCheck {
+    /// Size of the stream in bytes according to YouTube, its `file_size`.
+    pub declared: Option<u64>,
+    /// Size of the stream in bytes according to googlevideo, missing if it didn't say.
+    pub actual: Option<u64>,
+    /// Whether the sizes agree. Only `false` if both are known and differ, which means
+    /// googlevideo would serve something else than the stream YouTube described.
+    pub is_ok: bool,
+}
+
+/// Range of bytes of a stream, including both `start` and `end`.
+#[cfg_attr(
+    target_arch = "wasm32",
+    derive(serde::Serialize, serde::Deserialize, tsify::Tsify),
+    tsify(into_wasm_abi, from_wasm_abi),
+    serde(rename_all = "camelCase")
+)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct YtByteRange {
+    pub start: u64,
+    pub end: u64,
+}
+
+/// Size of a stream in bytes, see `YtStream::file_size`.
+#[cfg_attr(
+    target_arch = "wasm32",
+    derive(serde::Serialize, serde::Deserialize, tsify::Tsify),
+    tsify(into_wasm_abi, from_wasm_abi),
+    serde(rename_all = "camelCase")
+)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum YtSizeEstimate {
+    /// The `contentLength` YouTube sent.
+    Exact(u64),
+    /// Worked out from the bitrate and the duration, usually within a few percent.
+    Approx(u64),
+    /// Neither the size nor the bitrate is known, or the stream is live.
+    Unknown,
+}
+
+impl YtSizeEstimate {
+    /// The size in bytes, whether it's exact or not.
+    pub fn bytes(&self) -> Option<u64> {
+        match self {
+            Self::Exact(bytes) | Self::Approx(bytes) => Some(*bytes),
+            Self::Unknown => None,
+        }
+    }
+}
+
+impl std::ops::Add for YtSizeEstimate {
+    type Output = Self;
+
+    /// Exact only if both sizes are, unknown if either is.
+    fn add(self, other: Self) -> Self {
+        match (self, other) {
+            (Self::Exact(a), Self::Exact(b)) => Self::Exact(a + b),
+            (Self::Unknown, _) | (_, Self::Unknown) => Self::Unknown,
+            (a, b) => Self::Approx(a.bytes().unwrap_or_default() + b.bytes().unwrap_or_default()),
+        }
+    }
+}
+
+/// Where a detail of a stream came from.
+#[cfg_attr(
+    target_arch = "wasm32",
+    derive(serde::Serialize, serde::Deserialize, tsify::Tsify),
+    tsify(into_wasm_abi, from_wasm_abi),
+    serde(rename_all = "camelCase")
+)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum YtFieldSource {
+    /// YouTube sent it along with the stream.
+    #[default]
+    Manifest,
+    /// YouTube left it out, so it was looked up by the `itag` in `tydle::itag`.
+    ItagTable,
+}
+
+/// A quality as the player shows it, parsed from a label like `1080p60 HDR` with `YtQuality::from`.
+///
+/// Qualities compare by height, then frame rate, then HDR and then `Premium`, and
+/// labels that aren't a resolution like `tiny` or `audio only` compare below all of them.
+#[cfg_attr(
+    target_arch = "wasm32",
+    derive(serde::Serialize, serde::Deserialize, tsify::Tsify),
+    tsify(into_wasm_abi, from_wasm_abi),
+    serde(rename_all = "camelCase")
+)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum YtQuality {
+    /// A label that isn't a resolution, kept as it was.
+    Raw(String),
+    Video {
+        height: u32,
+        /// Only in the label for high frame rates, like the `60` of `1080p60`.
+        fps: Option<u32>,
+        hdr: bool,
+        /// Whether it's the `Premium` variant with a higher bitrate.
+        premium: bool,
+    },
+}
+
+impl YtQuality {
+    fn parse_video(label: &str) -> Option<Self> {
+        let mut words = label.split_whitespace();
+        let (height, fps) = words.next()?.split_once('p')?;
+        let (mut hdr, mut premium) = (false, false);
+
+        for word in words {
+            match word {
+                "HDR" => hdr = true,
+                "Premium" => premium = true,
+                _ => return None,
+            }
+        }
+
+        Some(Self::Video {
+            height: height.parse().ok()?,
+            fps: match fps {
+                "" => None,
+                fps => Some(fps.parse().ok()?),
+            },
+            hdr,
+            premium,
+        })
+    }
+}
+
+impl From<&str> for YtQuality {
+    fn from(label: &str) -> Self {
+        Self::parse_video(label).unwrap_or_else(|| Self::Raw(label.to_string()))
+    }
+}
+
+/// How the frames of a video map onto the screen, from the `projectionType` of its streams.
+#[cfg_attr(
+    target_arch = "wasm32",
+    derive(serde::Serialize, serde::Deserialize, tsify::Tsify),
+    tsify(into_wasm_abi, from_wasm_abi),
+    serde(rename_all = "camelCase")
+)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub enum YtProjection {
+    /// A regular flat video.
+    #[default]
+    Rectangular,
+    /// A 360° video, with the whole sphere unrolled onto the frame.
+    Equirectangular,
+    /// A 360° or VR180 video whose frame is mapped onto the sphere by a mesh embedded in the stream.
+    Mesh,
+    /// A `projectionType` that YouTube added after this was written, kept as it was sent.
+    Other(String),
+}
+
+impl YtProjection {
+    pub(crate) fn parse(projection_type: &str) -> Self {
+        match projection_type {
+            "RECTANGULAR" => Self::Rectangular,
+            "EQUIRECTANGULAR" => Self::Equirectangular,
+            "MESH" => Self::Mesh,
+            other => Self::Other(other.to_string()),
+        }
+    }
+}
+
+/// Whether a stream has both video and audio or only one of them.
+#[cfg_attr(
+    target_arch = "wasm32",
+    derive(serde::Serialize, serde::Deserialize, tsify::Tsify),
+    tsify(into_wasm_abi, from_wasm_abi),
+    serde(rename_all = "camelCase")
+)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum YtStreamKind {
+    /// Video and audio in one stream, from the `formats` of the streaming data.
+    /// Plays on its own but only goes up to 720p or so.
+    #[default]
+    Muxed,
+    /// Only video, to be merged with an `AdaptiveAudio` stream.
+    AdaptiveVideo,
+    AdaptiveAudio,
+}
+
+/// Ordered by the height of the video, then its frame rate, then whether the audio isn't DRC,
+/// then the bitrate, then the audio sample rate and last the `itag`, so that only
+/// the same stream from different clients compares equal. Audio-only streams have no height
+/// and order below every video stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct YtStreamQualityKey {
+    height: u64,
+    fps: u64,
+    is_not_drc: bool,
+    tbr: u64,
+    asr: u64,
+    itag: u64,
+}
+
+/// Narrows down streams by the conditions chained onto it, see `YtStreamList::filter`.
+///
+/// Only what YouTube says about the streams is looked at, so streams behind a signature
+/// are filtered the same way as the ones with a URL.
+pub struct YtStreamFilter<'a> {
+    streams: &'a [YtStream],
+    predicates: Vec<StreamPredicate>,
+}
+
+type StreamPredicate = Box<dyn Fn(&YtStream) -> bool>;
+
+impl<'a> YtStreamFilter<'a> {
+    fn with(mut self, predicate: impl Fn(&YtStream) -> bool + 'static) -> Self {
+        self.predicates.push(Box::new(predicate));
+        self
+    }
+
+    /// Keep the streams whose `mime_type` contains `pattern`, like `mp4` or `audio/webm`.
+    pub fn mime_contains(self, pattern: &str) -> Self {
+        let pattern = pattern.to_string();
+        self.with(move |stream| {
+            stream
+                .mime_type
+                .as_deref()
+                .is_some_and(|mime_type| mime_type.contains(&pattern))
+        })
+    }
+
+    /// Keep the streams whose `codecs` contain `pattern`, like `opus` or `avc1`.
+    pub fn codecs_contain(self, pattern: &str) -> Self {
+        let pattern = pattern.to_string();
+        self.with(move |stream| {
+            stream
+                .codecs
+                .as_deref()
+                .is_some_and(|codecs| codecs.contains(&pattern))
+        })
+    }
+
+    pub fn audio_only(self) -> Self {
+        self.with(YtStream::is_audio_only)
+    }
+
+    pub fn video_only(self) -> Self {
+        self.with(YtStream::is_video_only)
+    }
+
+    /// Keep the streams that have both video and audio.
+    pub fn muxed(self) -> Self {
+        self.with(YtStream::is_muxed)
+    }
+
+    /// Keep the video streams at least `height` pixels tall.
+    pub fn min_height(self, height: u64) -> Self {
+        self.with(move |stream| stream.height.is_some_and(|h| h >= height))
+    }
+
+    /// Keep the video streams at most `height` pixels tall.
+    pub fn max_height(self, height: u64) -> Self {
+        self.with(move |stream| stream.height.is_some_and(|h| h <= height))
+    }
+
+    pub fn min_fps(self, fps: u64) -> Self {
+        self.with(move |stream| stream.fps.is_some_and(|f| f >= fps))
+    }
+
+    pub fn max_fps(self, fps: u64) -> Self {
+        self.with(move |stream| stream.fps.is_some_and(|f| f <= fps))
+    }
+
+    /// Keep the streams whose bitrate is at most `tbr` bits per second.
+    pub fn max_bitrate(self, tbr: f64) -> Self {
+        self.with(move |stream| stream.tbr <= tbr)
+    }
+
+    /// Get the streams that meet all of the conditions, in their original order.
+    pub fn collect(self) -> YtStreamList {
+        YtStreamList(
+            self.streams
+                .iter()
+                .filter(|stream| self.predicates.iter().all(|predicate| predicate(stream)))
+                .cloned()
+                .collect(),
+        )
+    }
+}
+
+/// Where a stream was found in the manifest.
+#[cfg_attr(
+    target_arch = "wasm32",
+    derive(serde::Serialize, serde::Deserialize, tsify::Tsify),
+    tsify(into_wasm_abi, from_wasm_abi),
+    serde(rename_all = "lowercase")
+)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum YtStreamOrigin {
+    /// The `formats` and `adaptiveFormats` of the streaming data.
+    #[default]
+    Formats,
+    /// The DASH manifest at `dashManifestUrl`.
+    Dash,
+    /// The HLS master playlist at `hlsManifestUrl`, whose `source` is the URL of a media playlist
+    /// that lists the segments of the stream.
+    Hls,
+}
+
+/// Segments of a DASH representation, either listed one by one or described by a template.
+#[cfg_attr(
+    target_arch = "wasm32",
+    derive(serde::Serialize, serde::Deserialize, tsify::Tsify),
+    tsify(into_wasm_abi, from_wasm_abi),
+    serde(rename_all = "camelCase")
+)]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct YtDashSegments {
+    /// URL of the initialization segment, relative to the URL of the stream.
+    pub initialization: Option<String>,
+    /// URLs of the media segments of a `SegmentList`, relative to the URL of the stream.
+    pub media: Vec<String>,
+    /// URL of the media segments of a `SegmentTemplate` with `$Number$` in place of their number.
+    pub media_template: Option<String>,
+    /// Number of the first segment of `media_template`.
+    pub start_number: u64,
+    /// Number of segments of `media_template`, missing for live streams whose segments are still being added.
+    pub segment_count: Option<u64>,
+}
+
+impl YtDashSegments {
+    /// Get the URLs of the media segments in their order, resolved against the URL of the stream.
+    pub fn segment_urls(&self, stream_url: &str) -> Result<Vec<String>> {
+        let base_url = Url::parse(stream_url)?;
+        let mut urls = self
+            .media
+            .iter()
+            .map(|media| Ok(base_url.join(media)?.to_string()))
+            .collect::<Result<Vec<_>>>()?;
+
+        if let (Some(template), Some(count)) = (&self.media_template, self.segment_count) {
+            for number in self.start_number..self.start_number + count {
+                let media = template.replace("$Number$", &number.to_string());
+                urls.push(base_url.join(&media)?.to_string());
+            }
+        }
+
+        Ok(urls)
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", tsify::declare)]
+pub type YtStreams = Vec<YtStream>;
+
+#[cfg_attr(
+    target_arch = "wasm32",
+    derive(serde::Serialize, serde::Deserialize, tsify::Tsify),
+    tsify(into_wasm_abi, from_wasm_abi),
+    serde(rename_all = "camelCase")
+)]
+#[derive(Debug)]
+pub struct YtStreamList(YtStreams);
+
+impl<'a> IntoIterator for &'a YtStreamList {
+    type Item = &'a YtStream;
+    type IntoIter = std::slice::Iter<'a, YtStream>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
 
 impl IntoIterator for YtStreamList {
     type Item = YtStream;
@@ -295,6 +1527,25 @@ impl IntoIterator for YtStreamList {
     }
 }
 
+impl YtStreamList {
+    pub(crate) fn iter_mut(&mut self) -> std::slice::IterMut<'_, YtStream> {
+        self.0.iter_mut()
+    }
+
+    #[cfg(feature = "mux")]
+    pub(crate) fn retain(&mut self, keep: impl FnMut(&YtStream) -> bool) {
+        self.0.retain(keep)
+    }
+
+    /// Start filtering the streams, like `streams.filter().mime_contains("mp4").video_only().max_height(1080).collect()`.
+    pub fn filter(&self) -> YtStreamFilter<'_> {
+        YtStreamFilter {
+            streams: &self.0,
+            predicates: Vec::new(),
+        }
+    }
+}
+
 impl Deref for YtStreamList {
     type Target = Vec<YtStream>;
 
@@ -519,6 +1770,18 @@ impl Filterable for YtStreamList {
 pub struct YtStreamResponse {
     pub player_url: String,
     pub streams: YtStreamList,
+    /// Master playlist of a live stream, or of a video only some clients play through HLS.
+    /// When the video has no other streams, `streams` are the ones of this playlist.
+    /// Its variants are listed with `Tydle::get_hls_variants`.
+    pub hls_manifest_url: Option<String>,
+    /// DASH manifest of the video, whose streams are already merged into `streams`.
+    pub dash_manifest_url: Option<String>,
+    /// When the earliest of the player responses' streams stop working, from its `expiresInSeconds`.
+    #[cfg_attr(
+        target_arch = "wasm32",
+        tsify(type = "{ secs_since_epoch: number, nanos_since_epoch: number } | null")
+    )]
+    pub expires_at: Option<SystemTime>,
 }
 
 impl YtStreamResponse {
@@ -526,33 +1789,536 @@ impl YtStreamResponse {
         Self {
             player_url,
             streams: YtStreamList(streams),
+            hls_manifest_url: None,
+            dash_manifest_url: None,
+            expires_at: None,
         }
     }
-}
 
-#[cfg_attr(
-    target_arch = "wasm32",
-    derive(serde::Serialize, serde::Deserialize, tsify::Tsify,),
-    tsify(into_wasm_abi, from_wasm_abi),
-    serde(rename_all = "camelCase")
-)]
-#[derive(Debug)]
-pub struct YtManifest {
-    pub extracted_manifest: Vec<HashMap<String, Value>>,
-    pub player_url: String,
-}
+    /// Whether the streams stopped working at `now`, see `YtStream::is_expired`.
+    pub fn is_expired(&self, now: SystemTime) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
 
-impl YtManifest {
-    pub fn new(extracted_manifest: Vec<HashMap<String, Value>>, player_url: String) -> Self {
-        Self {
-            extracted_manifest,
-            player_url,
-        }
+    /// Seconds left until the streams stop working, missing if YouTube didn't say.
+    pub fn seconds_remaining(&self) -> Option<u64> {
+        self.expires_at
+            .map(|expires_at| seconds_until(expires_at, now()))
     }
-}
 
-#[cfg_attr(
-    target_arch = "wasm32",
+    /// Start filtering `streams`, see `YtStreamList::filter`.
+    pub fn filter(&self) -> YtStreamFilter<'_> {
+        self.streams.filter()
+    }
+
+    /// Get the streams from the best quality to the worst, leaving `streams` as they are.
+    /// Ordered by `YtStream::quality_key`, so the order doesn't depend on the client the streams came from.
+    pub fn sorted_by_quality(&self) -> Vec<&YtStream> {
+        let mut streams: Vec<&YtStream> = self.streams.iter().collect();
+        streams.sort_by_key(|stream| std::cmp::Reverse(stream.quality_key()));
+        streams
+    }
+
+    /// Get the streams from the highest bitrate to the lowest, leaving `streams` as they are.
+    /// Streams with the same bitrate are ordered by their `itag`, highest first.
+    pub fn sorted_by_bitrate(&self) -> Vec<&YtStream> {
+        let mut streams: Vec<&YtStream> = self.streams.iter().collect();
+        streams
+            .sort_by(|a, b| compare_tbr(b, a).then_with(|| b.itag_number().cmp(&a.itag_number())));
+        streams
+    }
+
+    /// Get the streams with both video and audio, in the order of `streams`.
+    /// Use `filter().muxed()` to narrow them down further.
+    pub fn muxed(&self) -> Vec<&YtStream> {
+        self.streams.iter().filter(|s| s.is_muxed()).collect()
+    }
+
+    /// Get the adaptive streams with video and no audio, in the order of `streams`.
+    /// Use `filter().video_only()` to narrow them down further.
+    pub fn video_only(&self) -> Vec<&YtStream> {
+        self.streams.iter().filter(|s| s.is_video_only()).collect()
+    }
+
+    /// Get the adaptive streams with audio and no video, in the order of `streams`.
+    /// Use `filter().audio_only()` to narrow them down further.
+    ///
+    /// DRC streams are listed next to the regular ones with the same `itag`,
+    /// tell them apart with `is_drc` or let `best_audio` skip them.
+    pub fn audio_only(&self) -> Vec<&YtStream> {
+        self.streams.iter().filter(|s| s.is_audio_only()).collect()
+    }
+
+    /// Get the distinct audio tracks of a dubbed video in the order they're first listed, empty if it only has one.
+    /// The DRC variant of a dub has the same track as the regular one, so it isn't listed again.
+    pub fn audio_tracks(&self) -> Vec<&YtAudioTrack> {
+        let mut tracks: Vec<&YtAudioTrack> = Vec::new();
+
+        for track in self.streams.iter().filter_map(|s| s.audio_track.as_ref()) {
+            if !tracks.iter().any(|t| t.id == track.id) {
+                tracks.push(track);
+            }
+        }
+
+        tracks
+    }
+
+    /// Get the audio-only stream with the highest bitrate, see `best_audio_with`.
+    pub fn best_audio(&self) -> Option<&YtStream> {
+        self.best_audio_with(&BestStreamOptions::default())
+    }
+
+    /// Get the video-only stream with the highest resolution, see `best_video_with`.
+    pub fn best_video(&self) -> Option<&YtStream> {
+        self.best_video_with(&BestStreamOptions::default())
+    }
+
+    /// Get the stream with both video and audio with the highest resolution, see `best_muxed_with`.
+    /// Unlike the others, it plays in a browser without merging anything.
+    pub fn best_muxed(&self) -> Option<&YtStream> {
+        self.best_muxed_with(&BestStreamOptions::default())
+    }
+
+    /// Get the best audio-only stream that meets `options`. Streams are ranked by their audio track first,
+    /// the one in `options.language` or else the default one, then by the codec in `options.prefer_codec`,
+    /// then by not being DRC, or by being DRC with `options.prefer_drc`, then by bitrate and then by sample rate.
+    pub fn best_audio_with(&self, options: &BestStreamOptions) -> Option<&YtStream> {
+        self.best_stream(options, YtStream::is_audio_only, |a, b| {
+            compare_tbr(a, b).then_with(|| a.asr.cmp(&b.asr))
+        })
+    }
+
+    /// Get the best video-only stream that meets `options`. Streams are ranked by the codec in
+    /// `options.prefer_codec` first, then by not being DRC, then by height, then by frame rate,
+    /// then by being HDR or `Premium` if `options` asks for it and then by bitrate.
+    pub fn best_video_with(&self, options: &BestStreamOptions) -> Option<&YtStream> {
+        self.best_stream(options, YtStream::is_video_only, |a, b| {
+            compare_video(a, b, options)
+        })
+    }
+
+    /// Get the best stream with both video and audio that meets `options`, ranked like `best_video_with`.
+    pub fn best_muxed_with(&self, options: &BestStreamOptions) -> Option<&YtStream> {
+        self.best_stream(options, YtStream::is_muxed, |a, b| {
+            compare_video(a, b, options)
+        })
+    }
+
+    fn best_stream(
+        &self,
+        options: &BestStreamOptions,
+        kind: impl Fn(&YtStream) -> bool,
+        compare: impl Fn(&YtStream, &YtStream) -> std::cmp::Ordering,
+    ) -> Option<&YtStream> {
+        let prefers_codec = |stream: &YtStream| {
+            options.prefer_codec.as_deref().is_some_and(|codec| {
+                stream
+                    .codecs
+                    .as_deref()
+                    .is_some_and(|codecs| codecs.contains(codec))
+            })
+        };
+
+        // Streams without an audio track are the only one there is. The default track is the fallback
+        // for a language the video isn't dubbed in.
+        let prefers_track = |stream: &YtStream| match &stream.audio_track {
+            Some(track) => (
+                options
+                    .language
+                    .as_deref()
+                    .is_some_and(|language| track.is_in_language(language)),
+                track.is_default,
+            ),
+            None => (true, true),
+        };
+
+        self.streams
+            .iter()
+            .filter(|stream| kind(stream))
+            .filter(|stream| {
+                options
+                    .max_height
+                    .is_none_or(|max_height| stream.height.is_none_or(|h| h <= max_height))
+            })
+            // `max_by` keeps the last of equal streams, so ties go to the later one consistently.
+            .max_by(|a, b| {
+                prefers_track(a)
+                    .cmp(&prefers_track(b))
+                    .then_with(|| prefers_codec(a).cmp(&prefers_codec(b)))
+                    .then_with(|| {
+                        (a.is_drc == options.prefer_drc).cmp(&(b.is_drc == options.prefer_drc))
+                    })
+                    .then_with(|| compare(a, b))
+            })
+    }
+
+    /// List the streams in an aligned table like the one `yt-dlp -F` prints, one row per stream
+    /// in the order of `streams`. What YouTube didn't say about a stream is shown as `?`, and
+    /// sizes are only shown when YouTube sent the exact `contentLength`.
+    pub fn format_table(&self) -> String {
+        const HEADER: [&str; 9] = [
+            "ID",
+            "EXT",
+            "RESOLUTION",
+            "FPS",
+            "SIZE",
+            "TBR",
+            "VCODEC",
+            "ACODEC",
+            "MORE INFO",
+        ];
+        // Numbers are aligned to the right, text to the left.
+        const RIGHT_ALIGNED: [bool; 9] =
+            [false, false, false, true, true, true, false, false, false];
+
+        let unknown = || "?".to_string();
+        let rows: Vec<[String; 9]> = self
+            .streams
+            .iter()
+            .map(|stream| {
+                let resolution = match (stream.kind, stream.width, stream.height) {
+                    (YtStreamKind::AdaptiveAudio, _, _) => "audio only".to_string(),
+                    (_, Some(width), Some(height)) => format!("{}x{}", width, height),
+                    (_, None, Some(height)) => format!("{}p", height),
+                    _ => unknown(),
+                };
+                let more_info = [
+                    stream.quality_label.clone(),
+                    stream
+                        .audio_track
+                        .as_ref()
+                        .map(|track| track.display_name.clone()),
+                    stream.is_drc.then(|| "DRC".to_string()),
+                    stream.is_hdr.then(|| "HDR".to_string()),
+                    stream.is_premium_bitrate.then(|| "Premium".to_string()),
+                    stream.is_otf.then(|| "OTF".to_string()),
+                    matches!(stream.source, YtStreamSource::Signature(_))
+                        .then(|| "signature".to_string()),
+                ]
+                .into_iter()
+                .flatten()
+                .collect::<Vec<_>>()
+                .join(", ");
+
+                [
+                    stream.itag.clone().unwrap_or_else(unknown),
+                    stream.container.clone().unwrap_or_else(unknown),
+                    resolution,
+                    match stream.kind {
+                        YtStreamKind::AdaptiveAudio => String::new(),
+                        _ => stream
+                            .fps
+                            .map(|fps| fps.to_string())
+                            .unwrap_or_else(unknown),
+                    },
+                    stream.file_size.map(format_bytes).unwrap_or_else(unknown),
+                    format!("{:.0}k", stream.tbr / 1000.0),
+                    match stream.kind {
+                        YtStreamKind::AdaptiveAudio => "audio only".to_string(),
+                        _ => stream.video_codec.clone().unwrap_or_else(unknown),
+                    },
+                    match stream.kind {
+                        YtStreamKind::AdaptiveVideo => "video only".to_string(),
+                        _ => stream.audio_codec.clone().unwrap_or_else(unknown),
+                    },
+                    more_info,
+                ]
+            })
+            .collect();
+
+        let mut widths = HEADER.map(|title| title.len());
+        for row in &rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+
+        let format_row = |cells: [&str; 9]| {
+            let mut line = cells
+                .iter()
+                .zip(widths)
+                .zip(RIGHT_ALIGNED)
+                .map(|((cell, width), right_aligned)| match right_aligned {
+                    true => format!("{:>width$}", cell),
+                    false => format!("{:<width$}", cell),
+                })
+                .collect::<Vec<_>>()
+                .join(" ");
+            line.truncate(line.trim_end().len());
+            line
+        };
+
+        let mut table = format_row(HEADER);
+        table.push('\n');
+        table.push_str(&"-".repeat(widths.iter().sum::<usize>() + widths.len() - 1));
+
+        for row in &rows {
+            table.push('\n');
+            table.push_str(&format_row(row.each_ref().map(|cell| cell.as_str())));
+        }
+
+        table
+    }
+
+    /// Build a static DASH manifest of the adaptive streams for players like dash.js or ExoPlayer,
+    /// which switch between the qualities by themselves. `duration` is the duration of the video.
+    ///
+    /// Streams are grouped into an `AdaptationSet` per type, codec and audio track, and each one is
+    /// addressed with its `init_range` and `index_range`, so streams without them, like OTF streams
+    /// and the ones from a DASH manifest, are left out. Fails if a stream still has a signature,
+    /// resolve them with `Cipher::resolve_streams` first.
+    pub fn to_dash_manifest(&self, duration: Duration) -> Result<String> {
+        // The `AdaptationSet` of each group of streams, in the order of their first stream.
+        let mut adaptation_sets: Vec<(DashAdaptationSetKey, Vec<String>)> = Vec::new();
+        let mut representation_ids = Vec::new();
+
+        for stream in self.streams.iter().filter(|stream| !stream.is_muxed()) {
+            let (Some(init_range), Some(index_range)) = (stream.init_range, stream.index_range)
+            else {
+                continue;
+            };
+
+            let url = match &stream.source {
+                YtStreamSource::URL(url) => url,
+                YtStreamSource::Signature(_) => bail!(
+                    "Stream {} has a signature that has to be deciphered before it goes into a DASH manifest, resolve the streams with `Cipher::resolve_streams` first.",
+                    stream.itag.as_deref().unwrap_or("?")
+                ),
+            };
+
+            // Dubs and DRC audio share the `itag` of the regular stream.
+            let mut id = stream.itag.clone().unwrap_or_default();
+            if let Some(track) = &stream.audio_track {
+                id.push_str(&format!("-{}", track.id));
+            }
+            if stream.is_drc {
+                id.push_str("-drc");
+            }
+            // The same stream can come from more than one client.
+            if representation_ids.contains(&id) {
+                continue;
+            }
+
+            let codec = match stream.kind {
+                YtStreamKind::AdaptiveAudio => stream.audio_codec.as_deref(),
+                _ => stream.video_codec.as_deref(),
+            }
+            .unwrap_or_default();
+            let content_type = match stream.kind {
+                YtStreamKind::AdaptiveAudio => "audio",
+                _ => "video",
+            };
+            let mime_type = stream
+                .mime_type
+                .as_deref()
+                .and_then(|mime_type| mime_type.split(';').next())
+                .map(|mime_type| mime_type.trim().to_string())
+                .unwrap_or_else(|| {
+                    format!(
+                        "{}/{}",
+                        content_type,
+                        stream.container.as_deref().unwrap_or("mp4")
+                    )
+                });
+            let language = stream
+                .audio_track
+                .as_ref()
+                .and_then(|track| track.language.as_deref());
+
+            let mut representation = format!(
+                "      <Representation id=\"{}\" codecs=\"{}\" bandwidth=\"{}\"",
+                escape_xml(&id),
+                escape_xml(codec),
+                stream.bitrate.unwrap_or(stream.tbr as u64)
+            );
+            match stream.kind {
+                YtStreamKind::AdaptiveAudio => {
+                    if let Some(asr) = stream.asr {
+                        representation.push_str(&format!(" audioSamplingRate=\"{}\"", asr));
+                    }
+                    representation.push_str(">\n");
+                    if let Some(audio_channels) = stream.audio_channels {
+                        representation.push_str(&format!(
+                            "        <AudioChannelConfiguration schemeIdUri=\"urn:mpeg:dash:23003:3:audio_channel_configuration:2011\" value=\"{}\"/>\n",
+                            audio_channels
+                        ));
+                    }
+                }
+                _ => {
+                    for (attribute, value) in [
+                        ("width", stream.width),
+                        ("height", stream.height),
+                        ("frameRate", stream.fps),
+                    ] {
+                        if let Some(value) = value {
+                            representation.push_str(&format!(" {}=\"{}\"", attribute, value));
+                        }
+                    }
+                    representation.push_str(">\n");
+                }
+            }
+            representation.push_str(&format!(
+                "        <BaseURL>{}</BaseURL>\n        <SegmentBase indexRange=\"{}-{}\">\n          <Initialization range=\"{}-{}\"/>\n        </SegmentBase>\n      </Representation>\n",
+                escape_xml(url),
+                index_range.start,
+                index_range.end,
+                init_range.start,
+                init_range.end
+            ));
+
+            // Codecs of a family can be switched between, like `avc1.4d401f` and `avc1.640028`,
+            // but the dubs of a video can't.
+            let key = DashAdaptationSetKey {
+                content_type,
+                mime_type,
+                codec_family: codec.split('.').next().unwrap_or_default(),
+                language,
+            };
+            representation_ids.push(id);
+            match adaptation_sets.iter_mut().find(|(k, _)| *k == key) {
+                Some((_, representations)) => representations.push(representation),
+                None => adaptation_sets.push((key, vec![representation])),
+            }
+        }
+
+        if adaptation_sets.is_empty() {
+            bail!(
+                "None of the streams can go into a DASH manifest, which takes adaptive streams with an `initRange` and an `indexRange`."
+            );
+        }
+
+        let duration = format!("PT{:.3}S", duration.as_secs_f64());
+        let mut manifest = format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<MPD xmlns=\"urn:mpeg:dash:schema:mpd:2011\" profiles=\"urn:mpeg:dash:profile:isoff-on-demand:2011\" type=\"static\" mediaPresentationDuration=\"{}\" minBufferTime=\"PT1.500S\">\n  <Period duration=\"{}\">\n",
+            duration, duration
+        );
+
+        for (id, (key, representations)) in adaptation_sets.iter().enumerate() {
+            let lang = key
+                .language
+                .map(|language| format!(" lang=\"{}\"", escape_xml(language)))
+                .unwrap_or_default();
+            manifest.push_str(&format!(
+                "    <AdaptationSet id=\"{}\" contentType=\"{}\" mimeType=\"{}\"{} subsegmentAlignment=\"true\">\n",
+                id,
+                key.content_type,
+                escape_xml(&key.mime_type),
+                lang
+            ));
+            for representation in representations {
+                manifest.push_str(representation);
+            }
+            manifest.push_str("    </AdaptationSet>\n");
+        }
+
+        manifest.push_str("  </Period>\n</MPD>\n");
+        Ok(manifest)
+    }
+
+    /// Get how much downloading the video takes, the `best_video` and `best_audio` streams together,
+    /// or `best_muxed` if the video has no adaptive streams. See `YtStream::size_estimate`.
+    pub fn total_size_estimate(&self, video_duration: Duration) -> YtSizeEstimate {
+        match (self.best_video(), self.best_audio(), self.best_muxed()) {
+            (Some(video), Some(audio), _) => {
+                video.size_estimate(video_duration) + audio.size_estimate(video_duration)
+            }
+            (_, _, Some(muxed)) => muxed.size_estimate(video_duration),
+            _ => YtSizeEstimate::Unknown,
+        }
+    }
+
+    /// Get the URLs every stream can be downloaded from, in the order of `streams`.
+    pub async fn resolve_all(&self, ty: &Tydle) -> Result<Vec<String>> {
+        ty.resolve_stream_urls(&self.streams, self.player_url.clone())
+            .await
+    }
+}
+
+/// What the streams of an `AdaptationSet` of `YtStreamResponse::to_dash_manifest` have in common.
+#[derive(PartialEq)]
+struct DashAdaptationSetKey<'a> {
+    content_type: &'static str,
+    mime_type: String,
+    codec_family: &'a str,
+    language: Option<&'a str>,
+}
+
+/// What to prefer when picking a stream with `YtStreamResponse::best_audio_with` and the like.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BestStreamOptions {
+    /// Rank the streams with this codec, like `opus` or `avc1`, above all the others.
+    pub prefer_codec: Option<String>,
+    /// Skip video streams taller than this.
+    pub max_height: Option<u64>,
+    /// Rank the audio track in this language, like `en` or `hi-IN`, above the default one of dubbed videos.
+    pub language: Option<String>,
+    /// Rank DRC audio above the regular audio instead of below it, for playback on speakers
+    /// that can't handle loud and quiet parts far apart.
+    pub prefer_drc: bool,
+    /// Rank HDR video above SDR video of the same height and frame rate.
+    pub prefer_hdr: bool,
+    /// Rank the `Premium` variants above the regular ones of the same height and frame rate.
+    pub prefer_premium: bool,
+}
+
+fn compare_tbr(a: &YtStream, b: &YtStream) -> std::cmp::Ordering {
+    a.tbr
+        .partial_cmp(&b.tbr)
+        .unwrap_or(std::cmp::Ordering::Equal)
+}
+
+fn compare_video(a: &YtStream, b: &YtStream, options: &BestStreamOptions) -> std::cmp::Ordering {
+    a.height
+        .cmp(&b.height)
+        .then_with(|| a.fps.cmp(&b.fps))
+        .then_with(|| (options.prefer_hdr && a.is_hdr).cmp(&(options.prefer_hdr && b.is_hdr)))
+        .then_with(|| {
+            (options.prefer_premium && a.is_premium_bitrate)
+                .cmp(&(options.prefer_premium && b.is_premium_bitrate))
+        })
+        .then_with(|| compare_tbr(a, b))
+}
+
+#[cfg_attr(
+    target_arch = "wasm32",
+    derive(serde::Serialize, serde::Deserialize, tsify::Tsify,),
+    tsify(into_wasm_abi, from_wasm_abi),
+    serde(rename_all = "camelCase")
+)]
+#[derive(Debug)]
+pub struct YtManifest {
+    pub extracted_manifest: Vec<HashMap<String, Value>>,
+    /// URL of the player the manifest was extracted with, pass it to `Cipher::decipher_signature`.
+    pub player_url: String,
+    /// `ytInitialData` of the watch page, which has what the player responses don't, like the chapters.
+    pub initial_data: HashMap<String, Value>,
+    /// Client each of the player responses in `extracted_manifest` was requested with, in the same order.
+    pub clients: Vec<YtClient>,
+}
+
+/// The player YouTube serves for a video.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlayerInfo {
+    pub url: String,
+    /// The hash in the `/s/player/<hash>/` path of the URL, changes whenever YouTube rotates the player.
+    pub version: String,
+    /// When the player URL was looked up.
+    pub fetched_at: SystemTime,
+}
+
+impl YtManifest {
+    pub fn new(extracted_manifest: Vec<HashMap<String, Value>>, player_url: String) -> Self {
+        Self {
+            extracted_manifest,
+            player_url,
+            initial_data: HashMap::new(),
+            clients: Vec::new(),
+        }
+    }
+}
+
+#[cfg_attr(
+    target_arch = "wasm32",
     derive(serde::Serialize, serde::Deserialize, tsify::Tsify),
     tsify(into_wasm_abi, from_wasm_abi),
     serde(rename_all = "camelCase")
@@ -604,9 +2370,2250 @@ pub struct YtVideoInfo {
     /// Rounded-off duration of the video in seconds.
     pub duration: u64,
     pub view_count: u64,
+    /// Whether the video is a live stream that's currently broadcasting, its `duration` is meaningless then.
+    pub is_live: bool,
+    /// Whether the video is a premiere or live stream that hasn't started yet, which has no streams until then.
+    pub is_upcoming: bool,
+    /// When an upcoming video is scheduled to start, in seconds since the unix epoch.
+    pub scheduled_start_time: Option<u64>,
+    /// Whether YouTube requires confirming the viewer's age to watch the video.
+    pub is_age_restricted: bool,
     pub channel: YtChannel,
+    /// Details of the uploader, the ones besides the ID and name come from the watch page
+    /// and are missing when it couldn't be loaded.
+    pub channel_info: YtChannelInfo,
     pub keywords: Vec<String>,
     pub thumbnails: Vec<YtThumbnail>,
     pub media_type: YtMediaType,
     pub age_limit: YtAgeLimit,
+    /// Subtitles of the video, empty if it has none. Fetch the cues of one with `Tydle::get_caption_track`.
+    pub captions: Vec<YtCaptionTrack>,
+    /// Chapters of the video in their order, empty if it has none.
+    pub chapters: Vec<YtChapter>,
+    /// Sprite sheets of the thumbnails the player previews seeking with, from the smallest thumbnails
+    /// to the largest. Empty for live streams.
+    pub storyboards: Vec<YtStoryboardLevel>,
+    /// "Most replayed" markers spanning the video in their order, empty for videos without enough views to have them.
+    pub heatmap: Vec<YtHeatMarker>,
+    /// Songs used in the video as listed in its "Music" section, empty for videos without any.
+    pub music: Vec<YtMusicTrack>,
+    /// The first projection of the streams that isn't `Rectangular`, or else `Rectangular`.
+    pub projection: YtProjection,
+    /// How much louder than YouTube's target the video is in dB, negative for quiet videos.
+    /// See `normalized_gain`.
+    pub loudness_db: Option<f32>,
+}
+
+impl YtVideoInfo {
+    /// Get what to multiply the volume with to normalize the video like YouTube does, which only turns
+    /// loud videos down unless `allow_boost`. Missing if the `loudness_db` of the video isn't known.
+    pub fn normalized_gain(&self, allow_boost: bool) -> Option<f32> {
+        self.loudness_db
+            .map(|loudness_db| loudness_to_gain(loudness_db, allow_boost))
+    }
+
+    /// Whether the video is a 360° or VR video, which any of its streams not being rectangular means.
+    pub fn is_360(&self) -> bool {
+        self.projection != YtProjection::Rectangular
+    }
+
+    /// Describe the video in a short paragraph, like
+    /// `Never Gonna Give You Up by Rick Astley, 3:33 long with 1,234,567,890 views.`
+    pub fn summary(&self) -> String {
+        let mut summary = self.title.clone();
+
+        if !self.channel_info.name.is_empty() {
+            summary.push_str(&format!(" by {}", self.channel_info.name));
+        }
+
+        let length = if self.is_live {
+            "live now".to_string()
+        } else if self.is_upcoming {
+            match self.scheduled_start_time {
+                Some(start_time) => format!("upcoming at {} (unix time)", start_time),
+                None => "upcoming".to_string(),
+            }
+        } else {
+            format!("{} long", format_duration(self.duration))
+        };
+
+        summary.push_str(&format!(
+            ", {} with {} views.",
+            length,
+            format_count(self.view_count)
+        ));
+
+        if self.is_age_restricted {
+            summary.push_str(" Age-restricted.");
+        }
+
+        summary
+    }
+}
+
+#[cfg_attr(
+    target_arch = "wasm32",
+    derive(serde::Serialize, serde::Deserialize, tsify::Tsify),
+    tsify(into_wasm_abi, from_wasm_abi),
+    serde(rename_all = "lowercase")
+)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum YtPlaylistVisibility {
+    #[default]
+    Public,
+    Unlisted,
+    Private,
+}
+
+#[cfg_attr(
+    target_arch = "wasm32",
+    derive(serde::Serialize, serde::Deserialize, tsify::Tsify),
+    tsify(into_wasm_abi, from_wasm_abi),
+    serde(rename_all = "camelCase")
+)]
+#[derive(Debug)]
+pub struct YtPlaylistEntry {
+    pub video_id: VideoId,
+    /// `[Deleted video]` or `[Private video]` for entries that aren't available.
+    pub title: String,
+    /// Duration of the video in seconds, missing for unavailable videos and live streams.
+    pub duration: Option<u64>,
+    pub thumbnails: Vec<YtThumbnail>,
+    /// Deleted and private videos stay in playlists, they are included with this unset.
+    pub is_available: bool,
+}
+
+#[cfg_attr(
+    target_arch = "wasm32",
+    derive(serde::Serialize, serde::Deserialize, tsify::Tsify),
+    tsify(into_wasm_abi, from_wasm_abi),
+    serde(rename_all = "camelCase")
+)]
+#[derive(Debug)]
+pub struct YtPlaylist {
+    pub id: PlaylistId,
+    pub title: String,
+    /// Name of the channel that created the playlist, missing for auto-generated ones like albums.
+    pub author: Option<String>,
+    pub description: Option<String>,
+    pub visibility: YtPlaylistVisibility,
+    /// Number of videos YouTube reports for the playlist. Can be more than `entries`
+    /// if `TydleOptions::max_playlist_pages` stopped the extraction early.
+    pub video_count: Option<u64>,
+    /// Videos of the playlist in its order.
+    pub entries: Vec<YtPlaylistEntry>,
+}
+
+/// A page of the endless playlist YouTube generates from a video, known as a mix or radio.
+#[cfg_attr(
+    target_arch = "wasm32",
+    derive(serde::Serialize, serde::Deserialize, tsify::Tsify),
+    tsify(into_wasm_abi, from_wasm_abi),
+    serde(rename_all = "camelCase")
+)]
+#[derive(Debug)]
+pub struct YtMix {
+    /// `RD` followed by the ID of the video the mix is generated from.
+    pub id: PlaylistId,
+    pub title: String,
+    /// Videos of the page in their order, the first page starting with the video the mix is generated from.
+    pub entries: Vec<YtPlaylistEntry>,
+    /// Pass to `get_mix_continue` for the entries after the ones of this page.
+    #[cfg_attr(target_arch = "wasm32", tsify(type = "string | null"))]
+    pub continuation: Option<Continuation<YtPlaylistEntry>>,
+}
+
+/// Tabs of the trending page.
+#[cfg_attr(
+    target_arch = "wasm32",
+    derive(serde::Serialize, serde::Deserialize, tsify::Tsify),
+    tsify(into_wasm_abi, from_wasm_abi),
+    serde(rename_all = "lowercase")
+)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum YtTrendingCategory {
+    #[default]
+    Now,
+    Music,
+    Gaming,
+    Movies,
+}
+
+#[cfg_attr(
+    target_arch = "wasm32",
+    derive(serde::Serialize, serde::Deserialize, tsify::Tsify),
+    tsify(into_wasm_abi, from_wasm_abi),
+    serde(rename_all = "camelCase")
+)]
+#[derive(Debug)]
+pub struct YtTrendingVideo {
+    pub video_id: VideoId,
+    pub title: String,
+    pub channel_name: Option<String>,
+    /// `UC...` ID of the channel that uploaded the video.
+    pub channel_id: Option<String>,
+    /// View count as YouTube shows it, like `1,234,567 views`.
+    pub view_count_text: Option<String>,
+    /// When the video was uploaded relative to now as YouTube shows it, like `2 days ago`.
+    pub published_text: Option<String>,
+    /// Duration of the video in seconds, missing for live streams.
+    pub duration: Option<u64>,
+    pub thumbnails: Vec<YtThumbnail>,
+}
+
+/// A shelf of the trending page, like "Trending" or "Recently trending".
+#[cfg_attr(
+    target_arch = "wasm32",
+    derive(serde::Serialize, serde::Deserialize, tsify::Tsify),
+    tsify(into_wasm_abi, from_wasm_abi),
+    serde(rename_all = "camelCase")
+)]
+#[derive(Debug)]
+pub struct YtTrendingSection {
+    /// Missing for the first section of a tab, which has no heading.
+    pub title: Option<String>,
+    pub videos: Vec<YtTrendingVideo>,
+}
+
+#[cfg_attr(
+    target_arch = "wasm32",
+    derive(serde::Serialize, serde::Deserialize, tsify::Tsify),
+    tsify(into_wasm_abi, from_wasm_abi),
+    serde(rename_all = "camelCase")
+)]
+#[derive(Debug)]
+pub struct YtTrending {
+    /// The tab the sections are from, `Now` when the requested one doesn't exist in the country.
+    pub category: YtTrendingCategory,
+    pub sections: Vec<YtTrendingSection>,
+}
+
+/// Order of the videos of a channel, like the chips above its Videos tab.
+#[cfg_attr(
+    target_arch = "wasm32",
+    derive(serde::Serialize, serde::Deserialize, tsify::Tsify),
+    tsify(into_wasm_abi, from_wasm_abi),
+    serde(rename_all = "lowercase")
+)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum YtChannelVideosOrder {
+    #[default]
+    Latest,
+    Popular,
+    Oldest,
+}
+
+#[cfg_attr(
+    target_arch = "wasm32",
+    derive(serde::Serialize, serde::Deserialize, tsify::Tsify),
+    tsify(into_wasm_abi, from_wasm_abi),
+    serde(rename_all = "camelCase")
+)]
+#[derive(Debug)]
+pub struct YtChannelVideo {
+    pub video_id: VideoId,
+    pub title: String,
+    /// Upload date relative to now as YouTube shows it, like `3 weeks ago`.
+    pub published_text: Option<String>,
+    pub view_count: Option<u64>,
+    /// Duration of the video in seconds, missing for live streams and premieres.
+    pub duration: Option<u64>,
+    pub thumbnails: Vec<YtThumbnail>,
+}
+
+#[cfg_attr(
+    target_arch = "wasm32",
+    derive(serde::Serialize, serde::Deserialize, tsify::Tsify),
+    tsify(into_wasm_abi, from_wasm_abi),
+    serde(rename_all = "camelCase")
+)]
+#[derive(Debug)]
+pub struct YtChannelVideosPage {
+    pub videos: Vec<YtChannelVideo>,
+    /// Pass to `get_channel_videos_continuation` to get the next page, missing on the last one.
+    #[cfg_attr(target_arch = "wasm32", tsify(type = "string | null"))]
+    pub continuation: Option<Continuation<YtChannelVideo>>,
+}
+
+#[cfg_attr(
+    target_arch = "wasm32",
+    derive(serde::Serialize, serde::Deserialize, tsify::Tsify),
+    tsify(into_wasm_abi, from_wasm_abi),
+    serde(rename_all = "camelCase")
+)]
+#[derive(Debug)]
+pub struct YtChannelVideos {
+    /// Canonical `UC...` ID of the channel.
+    pub channel_id: String,
+    pub name: String,
+    pub avatar: Vec<YtThumbnail>,
+    /// Subscriber count as YouTube shows it, like `1.2M subscribers`. Missing if the channel hides it.
+    pub subscriber_text: Option<String>,
+    /// First page of the uploads, in the requested order.
+    pub videos: YtChannelVideosPage,
+}
+
+#[cfg_attr(
+    target_arch = "wasm32",
+    derive(serde::Serialize, serde::Deserialize, tsify::Tsify),
+    tsify(into_wasm_abi, from_wasm_abi),
+    serde(rename_all = "camelCase")
+)]
+#[derive(Debug)]
+pub struct YtSearchVideo {
+    pub video_id: VideoId,
+    pub title: String,
+    /// Name of the channel that uploaded the video.
+    pub channel_name: Option<String>,
+    /// Duration of the video in seconds, missing for live streams.
+    pub duration: Option<u64>,
+    pub view_count: Option<u64>,
+    pub thumbnails: Vec<YtThumbnail>,
+}
+
+#[cfg_attr(
+    target_arch = "wasm32",
+    derive(serde::Serialize, serde::Deserialize, tsify::Tsify),
+    tsify(into_wasm_abi, from_wasm_abi),
+    serde(rename_all = "camelCase")
+)]
+#[derive(Debug)]
+pub struct YtSearchChannel {
+    pub channel_id: ChannelId,
+    pub title: String,
+    /// Subscriber count as YouTube shows it, like `1.2M subscribers`.
+    pub subscriber_text: Option<String>,
+    pub thumbnails: Vec<YtThumbnail>,
+}
+
+#[cfg_attr(
+    target_arch = "wasm32",
+    derive(serde::Serialize, serde::Deserialize, tsify::Tsify),
+    tsify(into_wasm_abi, from_wasm_abi),
+    serde(rename_all = "camelCase")
+)]
+#[derive(Debug)]
+pub struct YtSearchPlaylist {
+    pub playlist_id: PlaylistId,
+    pub title: String,
+    pub video_count: Option<u64>,
+    pub thumbnails: Vec<YtThumbnail>,
+}
+
+#[cfg_attr(
+    target_arch = "wasm32",
+    derive(serde::Serialize, serde::Deserialize, tsify::Tsify),
+    tsify(into_wasm_abi, from_wasm_abi),
+    serde(rename_all = "lowercase")
+)]
+#[derive(Debug)]
+pub enum YtSearchResult {
+    Video(YtSearchVideo),
+    Channel(YtSearchChannel),
+    Playlist(YtSearchPlaylist),
+}
+
+/// Kinds of results a search can be limited to, the values are the ones of the `sp` protobuf.
+#[cfg_attr(
+    target_arch = "wasm32",
+    derive(serde::Serialize, serde::Deserialize, tsify::Tsify),
+    tsify(into_wasm_abi, from_wasm_abi),
+    serde(rename_all = "lowercase")
+)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum YtSearchType {
+    Video = 1,
+    Channel = 2,
+    Playlist = 3,
+    Movie = 4,
+}
+
+#[cfg_attr(
+    target_arch = "wasm32",
+    derive(serde::Serialize, serde::Deserialize, tsify::Tsify),
+    tsify(into_wasm_abi, from_wasm_abi),
+    serde(rename_all = "lowercase")
+)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum YtSearchDuration {
+    /// Under 4 minutes.
+    Short = 1,
+    /// Over 20 minutes.
+    Long = 2,
+    /// 4 to 20 minutes.
+    Medium = 3,
+}
+
+#[cfg_attr(
+    target_arch = "wasm32",
+    derive(serde::Serialize, serde::Deserialize, tsify::Tsify),
+    tsify(into_wasm_abi, from_wasm_abi),
+    serde(rename_all = "lowercase")
+)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum YtSearchUploadDate {
+    LastHour = 1,
+    Today = 2,
+    ThisWeek = 3,
+    ThisMonth = 4,
+    ThisYear = 5,
+}
+
+#[cfg_attr(
+    target_arch = "wasm32",
+    derive(serde::Serialize, serde::Deserialize, tsify::Tsify),
+    tsify(into_wasm_abi, from_wasm_abi),
+    serde(rename_all = "camelCase")
+)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum YtSearchSort {
+    #[default]
+    Relevance = 0,
+    Rating = 1,
+    UploadDate = 2,
+    ViewCount = 3,
+}
+
+/// Properties results can be required to have, the values are their field numbers in the `sp` protobuf.
+#[cfg_attr(
+    target_arch = "wasm32",
+    derive(serde::Serialize, serde::Deserialize, tsify::Tsify),
+    tsify(into_wasm_abi, from_wasm_abi),
+    serde(rename_all = "camelCase")
+)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum YtSearchFeature {
+    Hd = 4,
+    Subtitles = 5,
+    CreativeCommons = 6,
+    ThreeD = 7,
+    Live = 8,
+    Purchased = 9,
+    FourK = 14,
+    ThreeSixty = 15,
+    Location = 23,
+    Hdr = 25,
+    Vr180 = 26,
+}
+
+/// Filters of a search, like the ones in the filter menu of YouTube's search results.
+/// Filters YouTube can't combine aren't rejected, the results are whatever YouTube returns for them.
+#[cfg_attr(
+    target_arch = "wasm32",
+    derive(serde::Serialize, serde::Deserialize, tsify::Tsify),
+    tsify(into_wasm_abi, from_wasm_abi),
+    serde(rename_all = "camelCase"),
+    serde(default)
+)]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct YtSearchFilters {
+    pub result_type: Option<YtSearchType>,
+    pub duration: Option<YtSearchDuration>,
+    pub upload_date: Option<YtSearchUploadDate>,
+    pub sort: YtSearchSort,
+    pub features: Vec<YtSearchFeature>,
+}
+
+impl YtSearchFilters {
+    /// Encode the filters into the `sp` parameter of search URLs, a base64 protobuf like `EgIQAQ%3D%3D`
+    /// for videos only. Empty for no filters at all.
+    pub fn to_sp(&self) -> String {
+        // Every field is a varint, so its key is the field number shifted past the wire type 0.
+        fn push_varint(bytes: &mut Vec<u8>, mut value: u64) {
+            while value >= 0x80 {
+                bytes.push((value as u8) | 0x80);
+                value >>= 7;
+            }
+            bytes.push(value as u8);
+        }
+
+        let mut filters = Vec::new();
+
+        for (field, value) in [
+            (1, self.upload_date.map(|u| u as u64)),
+            (2, self.result_type.map(|t| t as u64)),
+            (3, self.duration.map(|d| d as u64)),
+        ] {
+            if let Some(value) = value {
+                push_varint(&mut filters, field << 3);
+                push_varint(&mut filters, value);
+            }
+        }
+
+        let mut features = self.features.clone();
+        features.sort_by_key(|feature| *feature as u64);
+        features.dedup();
+
+        for feature in features {
+            push_varint(&mut filters, (feature as u64) << 3);
+            push_varint(&mut filters, 1);
+        }
+
+        let mut params = Vec::new();
+
+        if self.sort != YtSearchSort::Relevance {
+            push_varint(&mut params, 1 << 3);
+            push_varint(&mut params, self.sort as u64);
+        }
+
+        if !filters.is_empty() {
+            // Field 2 is the message of the filters, length-delimited.
+            push_varint(&mut params, (2 << 3) | 2);
+            push_varint(&mut params, filters.len() as u64);
+            params.extend(filters);
+        }
+
+        BASE64
+            .encode(&params)
+            .replace('+', "%2B")
+            .replace('/', "%2F")
+            .replace('=', "%3D")
+    }
+}
+
+#[cfg_attr(
+    target_arch = "wasm32",
+    derive(serde::Serialize, serde::Deserialize, tsify::Tsify),
+    tsify(into_wasm_abi, from_wasm_abi),
+    serde(rename_all = "camelCase")
+)]
+#[derive(Debug)]
+pub struct YtSearchResponse {
+    /// Results in the order YouTube ranked them.
+    pub results: Vec<YtSearchResult>,
+    /// Pass to `search_continue` to get the next page of results, missing on the last one.
+    #[cfg_attr(target_arch = "wasm32", tsify(type = "string | null"))]
+    pub continuation: Option<Continuation<YtSearchResult>>,
+    /// YouTube's rough estimate of the total number of results, only sent with the first page.
+    pub estimated_results: Option<u64>,
+}
+
+#[cfg_attr(
+    target_arch = "wasm32",
+    derive(serde::Serialize, serde::Deserialize, tsify::Tsify),
+    tsify(into_wasm_abi, from_wasm_abi),
+    serde(rename_all = "camelCase")
+)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct YtCaptionTrack {
+    /// Language of the track, like `en` or `pt-BR`.
+    pub language_code: String,
+    /// Name of the track as YouTube shows it, like `English (auto-generated)`.
+    pub name: String,
+    /// Whether the track was generated with speech recognition rather than uploaded.
+    pub is_auto_generated: bool,
+    /// Whether YouTube can machine translate the track to other languages.
+    pub is_translatable: bool,
+    /// URL of the timedtext of the track.
+    pub base_url: String,
+}
+
+#[cfg_attr(
+    target_arch = "wasm32",
+    derive(serde::Serialize, serde::Deserialize, tsify::Tsify),
+    tsify(into_wasm_abi, from_wasm_abi),
+    serde(rename_all = "camelCase")
+)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct YtCaptionCue {
+    /// Start of the cue in milliseconds.
+    pub start_ms: u64,
+    pub duration_ms: u64,
+    pub text: String,
+}
+
+#[cfg_attr(
+    target_arch = "wasm32",
+    derive(serde::Serialize, serde::Deserialize, tsify::Tsify),
+    tsify(into_wasm_abi, from_wasm_abi),
+    serde(rename_all = "camelCase")
+)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct YtChapter {
+    pub title: String,
+    /// Start of the chapter in milliseconds.
+    pub start_ms: u64,
+    /// End of the chapter in milliseconds, the start of the next one or the end of the video.
+    /// Missing for the last chapter when the duration of the video isn't known, like during live streams.
+    pub end_ms: Option<u64>,
+}
+
+/// A song credited in a video.
+#[cfg_attr(
+    target_arch = "wasm32",
+    derive(serde::Serialize, serde::Deserialize, tsify::Tsify),
+    tsify(into_wasm_abi, from_wasm_abi),
+    serde(rename_all = "camelCase")
+)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct YtMusicTrack {
+    pub song: String,
+    pub artists: Vec<String>,
+    pub album: Option<String>,
+    /// Only known for auto-generated "Art Track" uploads.
+    pub release_year: Option<u32>,
+    /// Who licensed the song to YouTube, like `SME (on behalf of RCA Records Label)`.
+    pub licenses: Option<String>,
+}
+
+/// A part of the video on the "most replayed" graph above the player bar.
+#[cfg_attr(
+    target_arch = "wasm32",
+    derive(serde::Serialize, serde::Deserialize, tsify::Tsify),
+    tsify(into_wasm_abi, from_wasm_abi),
+    serde(rename_all = "camelCase")
+)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct YtHeatMarker {
+    pub start_ms: u64,
+    pub duration_ms: u64,
+    /// How often the part is replayed compared to the rest of the video, from 0.0 to 1.0 for the most replayed one.
+    pub intensity: f32,
+}
+
+/// A size of the thumbnails the player previews seeking with. The thumbnails are tiled into sprite sheets
+/// of `columns` by `rows`, one every `interval_ms` of the video.
+#[cfg_attr(
+    target_arch = "wasm32",
+    derive(serde::Serialize, serde::Deserialize, tsify::Tsify),
+    tsify(into_wasm_abi, from_wasm_abi),
+    serde(rename_all = "camelCase")
+)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct YtStoryboardLevel {
+    /// URL of the sheets with `$M` in place of the index of a sheet, use `url_for` to get the URL of one.
+    pub url_template: String,
+    /// Width of a thumbnail in pixels.
+    pub width: u64,
+    /// Height of a thumbnail in pixels.
+    pub height: u64,
+    pub columns: u64,
+    pub rows: u64,
+    /// Number of thumbnails across all sheets.
+    pub thumbnail_count: u64,
+    pub interval_ms: u64,
+}
+
+impl YtStoryboardLevel {
+    /// URL of the sheet at `index`, starting from 0.
+    pub fn url_for(&self, index: u64) -> String {
+        self.url_template.replace("$M", &index.to_string())
+    }
+
+    /// Number of sheets the thumbnails are tiled into.
+    pub fn sheet_count(&self) -> u64 {
+        self.thumbnail_count.div_ceil(self.columns * self.rows)
+    }
+}
+
+/// A quality of a live stream, from the master playlist of its HLS manifest.
+#[cfg_attr(
+    target_arch = "wasm32",
+    derive(serde::Serialize, serde::Deserialize, tsify::Tsify),
+    tsify(into_wasm_abi, from_wasm_abi),
+    serde(rename_all = "camelCase")
+)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct YtHlsVariant {
+    /// URL of the media playlist of the variant.
+    pub url: String,
+    pub itag: Option<String>,
+    /// Peak bitrate of the variant in bits per second.
+    pub bandwidth: u64,
+    pub width: Option<u64>,
+    pub height: Option<u64>,
+    pub codecs: Option<String>,
+    pub frame_rate: Option<f64>,
+    /// `GROUP-ID` of the `#EXT-X-MEDIA` audio renditions the variant plays with, missing if it has none.
+    pub audio_group: Option<String>,
+}
+
+#[cfg_attr(
+    target_arch = "wasm32",
+    derive(serde::Serialize, serde::Deserialize, tsify::Tsify),
+    tsify(into_wasm_abi, from_wasm_abi),
+    serde(rename_all = "camelCase")
+)]
+#[derive(Debug)]
+pub struct YtRelatedVideo {
+    pub video_id: VideoId,
+    pub title: String,
+    pub channel_name: Option<String>,
+    /// Duration of the video in seconds, missing for live streams.
+    pub duration: Option<u64>,
+    /// View count as YouTube shows it, like `1.2M views`.
+    pub view_count_text: Option<String>,
+    pub thumbnails: Vec<YtThumbnail>,
+}
+
+#[cfg_attr(
+    target_arch = "wasm32",
+    derive(serde::Serialize, serde::Deserialize, tsify::Tsify),
+    tsify(into_wasm_abi, from_wasm_abi),
+    serde(rename_all = "camelCase")
+)]
+#[derive(Debug)]
+pub struct YtRelatedVideos {
+    pub videos: Vec<YtRelatedVideo>,
+    /// Pass to `get_related_continue` to get more videos, missing when there are no more.
+    #[cfg_attr(target_arch = "wasm32", tsify(type = "string | null"))]
+    pub continuation: Option<Continuation<YtRelatedVideo>>,
+}
+
+#[cfg_attr(
+    target_arch = "wasm32",
+    derive(serde::Serialize, serde::Deserialize, tsify::Tsify),
+    tsify(into_wasm_abi, from_wasm_abi),
+    serde(rename_all = "lowercase")
+)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum YtCommentSort {
+    #[default]
+    Top,
+    Newest,
+}
+
+#[cfg_attr(
+    target_arch = "wasm32",
+    derive(serde::Serialize, serde::Deserialize, tsify::Tsify),
+    tsify(into_wasm_abi, from_wasm_abi),
+    serde(rename_all = "camelCase")
+)]
+#[derive(Debug)]
+pub struct YtComment {
+    pub comment_id: String,
+    pub author_name: String,
+    /// `UC...` ID of the author's channel.
+    pub author_channel_id: Option<String>,
+    pub author_avatar: Vec<YtThumbnail>,
+    /// Text of the comment, emojis included.
+    pub text: String,
+    pub like_count: Option<u64>,
+    /// When the comment was posted relative to now as YouTube shows it, like `2 days ago`.
+    pub published_text: Option<String>,
+    pub is_pinned: bool,
+    /// Whether the uploader hearted the comment.
+    pub is_hearted: bool,
+    pub reply_count: u64,
+    /// Pass to `get_comment_replies` to get the replies, missing for comments without any and for replies themselves.
+    #[cfg_attr(target_arch = "wasm32", tsify(type = "string | null"))]
+    pub replies_continuation: Option<Continuation<YtComment>>,
+}
+
+#[cfg_attr(
+    target_arch = "wasm32",
+    derive(serde::Serialize, serde::Deserialize, tsify::Tsify),
+    tsify(into_wasm_abi, from_wasm_abi),
+    serde(rename_all = "camelCase")
+)]
+#[derive(Debug)]
+pub struct YtCommentPage {
+    pub comments: Vec<YtComment>,
+    /// Pass to `get_comments_continue` or `get_comment_replies` for the next page, missing on the last one.
+    #[cfg_attr(target_arch = "wasm32", tsify(type = "string | null"))]
+    pub continuation: Option<Continuation<YtComment>>,
+}
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// Items of the listings YouTube paginates with continuation tokens, which `Continuation`s can page through.
+pub trait YtPaginated: sealed::Sealed + 'static {
+    /// Prefix of the serialized tokens of the listing, so they can't be redeemed for the wrong kind of items.
+    const KIND: &'static str;
+}
+
+macro_rules! impl_paginated {
+    ($($item:ty => $kind:literal),* $(,)?) => {
+        $(
+            impl sealed::Sealed for $item {}
+
+            impl YtPaginated for $item {
+                const KIND: &'static str = $kind;
+            }
+        )*
+    };
+}
+
+impl_paginated! {
+    YtChannelVideo => "channel_videos",
+    YtSearchResult => "search",
+    YtRelatedVideo => "related",
+    YtComment => "comments",
+    YtPlaylistEntry => "mix",
+}
+
+/// Token for the next page of a listing, redeemed with `continue_page` or the `_continue` method of the listing.
+///
+/// Converts to and from a plain string with `to_string` and `parse`, so it can be handed to a client
+/// and redeemed later, even by another `Ty`. Tokens that can't be redeemed anymore fail with `ContinuationExpired`.
+pub struct Continuation<T: YtPaginated> {
+    token: String,
+    item: PhantomData<fn() -> T>,
+}
+
+impl<T: YtPaginated> Continuation<T> {
+    pub(crate) fn new(token: String) -> Self {
+        Self {
+            token,
+            item: PhantomData,
+        }
+    }
+
+    /// The token YouTube sent, without the prefix of its listing.
+    pub fn token(&self) -> &str {
+        &self.token
+    }
+}
+
+impl<T: YtPaginated> Clone for Continuation<T> {
+    fn clone(&self) -> Self {
+        Self::new(self.token.clone())
+    }
+}
+
+impl<T: YtPaginated> PartialEq for Continuation<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.token == other.token
+    }
+}
+
+impl<T: YtPaginated> Eq for Continuation<T> {}
+
+impl<T: YtPaginated> fmt::Debug for Continuation<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Continuation")
+            .field(&T::KIND)
+            .field(&self.token)
+            .finish()
+    }
+}
+
+impl<T: YtPaginated> fmt::Display for Continuation<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", T::KIND, self.token)
+    }
+}
+
+impl<T: YtPaginated> FromStr for Continuation<T> {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            Some((kind, token)) if kind == T::KIND && !token.is_empty() => {
+                Ok(Self::new(token.to_string()))
+            }
+            _ => Err(ContinuationExpired {
+                token: s.to_string(),
+            }
+            .into()),
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+impl<T: YtPaginated> serde::Serialize for Continuation<T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+impl<'de, T: YtPaginated> serde::Deserialize<'de> for Continuation<T> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+/// A page of a listing, returned by `continue_page`.
+#[derive(Debug)]
+pub struct Page<T: YtPaginated> {
+    pub items: Vec<T>,
+    /// Pass to `continue_page` for the next page, missing on the last one.
+    pub continuation: Option<Continuation<T>>,
+}
+
+impl From<YtChannelVideosPage> for Page<YtChannelVideo> {
+    fn from(page: YtChannelVideosPage) -> Self {
+        Self {
+            items: page.videos,
+            continuation: page.continuation,
+        }
+    }
+}
+
+impl From<YtSearchResponse> for Page<YtSearchResult> {
+    fn from(response: YtSearchResponse) -> Self {
+        Self {
+            items: response.results,
+            continuation: response.continuation,
+        }
+    }
+}
+
+impl From<YtRelatedVideos> for Page<YtRelatedVideo> {
+    fn from(related: YtRelatedVideos) -> Self {
+        Self {
+            items: related.videos,
+            continuation: related.continuation,
+        }
+    }
+}
+
+impl From<YtCommentPage> for Page<YtComment> {
+    fn from(page: YtCommentPage) -> Self {
+        Self {
+            items: page.comments,
+            continuation: page.continuation,
+        }
+    }
+}
+
+impl From<YtMix> for Page<YtPlaylistEntry> {
+    fn from(mix: YtMix) -> Self {
+        Self {
+            items: mix.entries,
+            continuation: mix.continuation,
+        }
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use crate::{
+        extractor::extract::InfoExtractor, test_server::test_extractor, tydle::TydleOptions,
+    };
+
+    /// The streams of `player_formats.json`, once for each of `clients` as if each of them had sent it.
+    fn fixture_response(clients: &[YtClient]) -> YtStreamResponse {
+        response_of(
+            include_str!("../tests/fixtures/player_formats.json"),
+            clients,
+        )
+    }
+
+    fn response_of(player_response: &str, clients: &[YtClient]) -> YtStreamResponse {
+        let player_response: HashMap<String, Value> =
+            serde_json::from_str(player_response).unwrap();
+        let streams = test_extractor(TydleOptions::default(), reqwest::Client::new())
+            .extract_formats(vec![player_response; clients.len()], clients)
+            .unwrap();
+
+        YtStreamResponse::new(String::new(), streams)
+    }
+
+    fn itags(streams: &[&YtStream]) -> Vec<String> {
+        streams
+            .iter()
+            .map(|stream| stream.itag().unwrap().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn signature_cipher_decodes_its_parameters() {
+        let cipher: SignatureCipher = "s=AOq0QJ8wRQIhAK%3DNdTq+Zx7%2FvWj%3D%3DA&sp=sig&url=https%3A%2F%2Frr3---sn-4g5e6nsz.googlevideo.com%2Fvideoplayback%3Fexpire%3D1700000000%26itag%3D251%26n%3DxKAwXyUbcN9p"
+            .parse()
+            .unwrap();
+
+        assert_eq!(cipher.s, "AOq0QJ8wRQIhAK=NdTq Zx7/vWj==A");
+        assert_eq!(cipher.sp.as_deref(), Some("sig"));
+        assert_eq!(
+            cipher.url,
+            "https://rr3---sn-4g5e6nsz.googlevideo.com/videoplayback?expire=1700000000&itag=251&n=xKAwXyUbcN9p"
+        );
+    }
+
+    #[test]
+    fn signature_cipher_parameters_can_be_in_any_order_and_sp_missing() {
+        let cipher: SignatureCipher =
+            "url=https%3A%2F%2Fexample.com%2Fvideoplayback%3Fitag%3D18&s=ab+cd%2B"
+                .parse()
+                .unwrap();
+
+        assert_eq!(cipher.s, "ab cd+");
+        assert_eq!(cipher.sp, None);
+        assert_eq!(cipher.signature_param(), "signature");
+        assert_eq!(cipher.url, "https://example.com/videoplayback?itag=18");
+    }
+
+    #[test]
+    fn signature_cipher_round_trips_through_display() {
+        let cipher = SignatureCipher {
+            s: "a b=c/d".into(),
+            sp: Some("sig".into()),
+            url: "https://example.com/videoplayback?itag=18&n=abc".into(),
+        };
+
+        assert_eq!(
+            cipher.to_string().parse::<SignatureCipher>().unwrap(),
+            cipher
+        );
+    }
+
+    #[test]
+    fn malformed_signature_cipher_names_the_missing_component() {
+        let missing_s = "sp=sig&url=https%3A%2F%2Fexample.com"
+            .parse::<SignatureCipher>()
+            .unwrap_err();
+        let missing_url = "s=abc&sp=sig".parse::<SignatureCipher>().unwrap_err();
+
+        assert!(missing_s.to_string().contains("`s`"));
+        assert!(missing_url.to_string().contains("`url`"));
+    }
+
+    #[test]
+    fn search_filters_encode_to_the_sp_of_youtube() {
+        let filters = |filters: YtSearchFilters| filters.to_sp();
+        let result_type = |result_type| YtSearchFilters {
+            result_type: Some(result_type),
+            ..Default::default()
+        };
+        let duration = |duration| YtSearchFilters {
+            duration: Some(duration),
+            ..Default::default()
+        };
+        let upload_date = |upload_date| YtSearchFilters {
+            upload_date: Some(upload_date),
+            ..Default::default()
+        };
+        let sort = |sort| YtSearchFilters {
+            sort,
+            ..Default::default()
+        };
+
+        let cases = [
+            (YtSearchFilters::default(), ""),
+            (result_type(YtSearchType::Video), "EgIQAQ%3D%3D"),
+            (result_type(YtSearchType::Channel), "EgIQAg%3D%3D"),
+            (result_type(YtSearchType::Playlist), "EgIQAw%3D%3D"),
+            (result_type(YtSearchType::Movie), "EgIQBA%3D%3D"),
+            (duration(YtSearchDuration::Short), "EgIYAQ%3D%3D"),
+            (duration(YtSearchDuration::Long), "EgIYAg%3D%3D"),
+            (duration(YtSearchDuration::Medium), "EgIYAw%3D%3D"),
+            (upload_date(YtSearchUploadDate::LastHour), "EgIIAQ%3D%3D"),
+            (upload_date(YtSearchUploadDate::Today), "EgIIAg%3D%3D"),
+            (upload_date(YtSearchUploadDate::ThisWeek), "EgIIAw%3D%3D"),
+            (upload_date(YtSearchUploadDate::ThisMonth), "EgIIBA%3D%3D"),
+            (upload_date(YtSearchUploadDate::ThisYear), "EgIIBQ%3D%3D"),
+            (sort(YtSearchSort::Relevance), ""),
+            (sort(YtSearchSort::Rating), "CAE%3D"),
+            (sort(YtSearchSort::UploadDate), "CAI%3D"),
+            (sort(YtSearchSort::ViewCount), "CAM%3D"),
+        ];
+
+        for (search_filters, sp) in cases {
+            assert_eq!(filters(search_filters.clone()), sp, "{:?}", search_filters);
+        }
+    }
+
+    #[test]
+    fn search_features_encode_to_the_sp_of_youtube() {
+        let cases = [
+            (YtSearchFeature::Hd, "EgIgAQ%3D%3D"),
+            (YtSearchFeature::Subtitles, "EgIoAQ%3D%3D"),
+            (YtSearchFeature::CreativeCommons, "EgIwAQ%3D%3D"),
+            (YtSearchFeature::ThreeD, "EgI4AQ%3D%3D"),
+            (YtSearchFeature::Live, "EgJAAQ%3D%3D"),
+            (YtSearchFeature::Purchased, "EgJIAQ%3D%3D"),
+            (YtSearchFeature::FourK, "EgJwAQ%3D%3D"),
+            (YtSearchFeature::ThreeSixty, "EgJ4AQ%3D%3D"),
+            (YtSearchFeature::Location, "EgO4AQE%3D"),
+            (YtSearchFeature::Hdr, "EgPIAQE%3D"),
+            (YtSearchFeature::Vr180, "EgPQAQE%3D"),
+        ];
+
+        for (feature, sp) in cases {
+            let filters = YtSearchFilters {
+                features: vec![feature],
+                ..Default::default()
+            };
+
+            assert_eq!(filters.to_sp(), sp, "{:?}", feature);
+        }
+    }
+
+    #[test]
+    fn combined_search_filters_encode_in_field_order() {
+        let filters = YtSearchFilters {
+            result_type: Some(YtSearchType::Video),
+            duration: Some(YtSearchDuration::Short),
+            upload_date: Some(YtSearchUploadDate::ThisWeek),
+            sort: YtSearchSort::ViewCount,
+            features: vec![],
+        };
+        assert_eq!(filters.to_sp(), "CAMSBggDEAEYAQ%3D%3D");
+
+        // Features are deduplicated and ordered by their field, whatever order they were given in.
+        let features = YtSearchFilters {
+            features: vec![
+                YtSearchFeature::FourK,
+                YtSearchFeature::Subtitles,
+                YtSearchFeature::FourK,
+            ],
+            ..Default::default()
+        };
+        assert_eq!(features.to_sp(), "EgQoAXAB");
+    }
+
+    #[test]
+    fn streams_sort_by_quality_with_the_itag_breaking_ties() {
+        let response = fixture_response(&[YtClient::Web]);
+
+        // 247 and 136 are both 720p25 with the same bitrate, so only their itag orders them.
+        assert_eq!(
+            itags(&response.sorted_by_quality()),
+            ["137", "248", "399", "247", "136", "18", "140", "251"]
+        );
+        assert_eq!(
+            itags(&response.sorted_by_bitrate()),
+            ["137", "248", "399", "247", "136", "18", "140", "251"]
+        );
+    }
+
+    #[test]
+    fn quality_sort_is_total_and_stable() {
+        let clients = [YtClient::Web, YtClient::Android];
+        let response = fixture_response(&clients);
+        let mut reversed = fixture_response(&clients);
+        reversed.streams.0.reverse();
+
+        for (response, first_client) in [(&response, YtClient::Web), (&reversed, YtClient::Android)]
+        {
+            let sorted = response.sorted_by_quality();
+            assert_eq!(sorted.len(), response.streams.len());
+
+            for pair in sorted.windows(2) {
+                let (a, b) = (pair[0].quality_key(), pair[1].quality_key());
+                assert!(a >= b, "{:?} is sorted before {:?}", a, b);
+
+                // Only the same stream from two clients compares equal, and it stays in the order it came in.
+                if a == b {
+                    assert_eq!(pair[0].itag(), pair[1].itag());
+                    assert_eq!(pair[0].client, Some(first_client));
+                }
+            }
+
+            // Sorting doesn't depend on the order the streams came in.
+            assert_eq!(
+                itags(&sorted),
+                itags(&fixture_response(&clients).sorted_by_quality())
+            );
+            assert_eq!(
+                itags(&response.sorted_by_bitrate()),
+                itags(&fixture_response(&clients).sorted_by_bitrate())
+            );
+        }
+
+        // The response itself is left as it was.
+        assert_eq!(
+            response.streams.first().and_then(|stream| stream.itag()),
+            Some("18")
+        );
+    }
+
+    #[test]
+    fn fixture_formats_expose_each_field() {
+        let response = fixture_response(&[YtClient::Web]);
+        let stream = |itag: &str| {
+            response
+                .streams
+                .iter()
+                .find(|stream| stream.itag() == Some(itag))
+                .unwrap()
+        };
+
+        let muxed = stream("18");
+        assert_eq!(muxed.kind, YtStreamKind::Muxed);
+        assert_eq!(
+            (muxed.width, muxed.height, muxed.fps),
+            (Some(640), Some(360), Some(25))
+        );
+        assert_eq!(
+            (muxed.bitrate, muxed.average_bitrate),
+            (Some(493163), Some(493111))
+        );
+        assert_eq!(muxed.file_size, Some(13072829));
+        assert_eq!(
+            muxed.mime_type.as_deref(),
+            Some("video/mp4; codecs=\"avc1.42001E, mp4a.40.2\"")
+        );
+        assert_eq!(muxed.container.as_deref(), Some("mp4"));
+        assert_eq!(muxed.video_codec.as_deref(), Some("avc1.42001E"));
+        assert_eq!(muxed.audio_codec.as_deref(), Some("mp4a.40.2"));
+        assert_eq!(muxed.quality_label.as_deref(), Some("360p"));
+        assert_eq!(muxed.audio_quality.as_deref(), Some("audio_quality_low"));
+        assert_eq!((muxed.asr, muxed.audio_channels), (Some(44100), Some(2)));
+        assert_eq!(
+            muxed.expires_at,
+            Some(SystemTime::UNIX_EPOCH + Duration::from_secs(1760000000))
+        );
+
+        let video = stream("137");
+        assert_eq!(video.kind, YtStreamKind::AdaptiveVideo);
+        assert_eq!(
+            (video.width, video.height, video.fps),
+            (Some(1920), Some(1080), Some(25))
+        );
+        assert_eq!(
+            (video.bitrate, video.average_bitrate),
+            (Some(4389000), Some(2959154))
+        );
+        assert_eq!(video.tbr, 2959154.0);
+        assert_eq!(video.file_size, Some(78432614));
+        assert_eq!(video.container.as_deref(), Some("mp4"));
+        assert_eq!(video.video_codec.as_deref(), Some("avc1.640028"));
+        assert_eq!(video.audio_codec, None);
+        assert_eq!(video.quality_label.as_deref(), Some("1080p"));
+        assert_eq!(
+            (
+                video.audio_quality.as_deref(),
+                video.asr,
+                video.audio_channels
+            ),
+            (None, None, None)
+        );
+        assert_eq!(video.init_range, Some(YtByteRange { start: 0, end: 740 }));
+        assert_eq!(
+            video.index_range,
+            Some(YtByteRange {
+                start: 741,
+                end: 1260
+            })
+        );
+        assert_eq!(video.resolution_source, YtFieldSource::Manifest);
+
+        let aac = stream("140");
+        assert_eq!(aac.kind, YtStreamKind::AdaptiveAudio);
+        assert_eq!((aac.width, aac.height, aac.fps), (None, None, None));
+        assert_eq!(
+            (aac.bitrate, aac.average_bitrate),
+            (Some(130530), Some(129502))
+        );
+        assert_eq!(aac.file_size, Some(3433514));
+        assert_eq!(aac.container.as_deref(), Some("mp4"));
+        assert_eq!(aac.video_codec, None);
+        assert_eq!(aac.audio_codec.as_deref(), Some("mp4a.40.2"));
+        assert_eq!(aac.quality_label, None);
+        assert_eq!(aac.audio_quality.as_deref(), Some("audio_quality_medium"));
+        assert_eq!((aac.asr, aac.audio_channels), (Some(44100), Some(2)));
+        assert_eq!(aac.loudness_db, Some(-7.1));
+
+        let opus = stream("251");
+        assert_eq!(opus.kind, YtStreamKind::AdaptiveAudio);
+        assert_eq!((opus.width, opus.height, opus.fps), (None, None, None));
+        assert_eq!(
+            (opus.bitrate, opus.average_bitrate),
+            (Some(139652), Some(129161))
+        );
+        assert_eq!(opus.file_size, Some(3423818));
+        assert_eq!(
+            opus.mime_type.as_deref(),
+            Some("audio/webm; codecs=\"opus\"")
+        );
+        assert_eq!(opus.container.as_deref(), Some("webm"));
+        assert_eq!(opus.audio_codec.as_deref(), Some("opus"));
+        assert_eq!(opus.audio_quality.as_deref(), Some("audio_quality_medium"));
+        assert_eq!((opus.asr, opus.audio_channels), (Some(48000), Some(2)));
+        assert!(!opus.is_drc);
+    }
+
+    /// `approxDurationMs` of `player_formats.json` in whole seconds, like `YtVideoInfo::duration`.
+    const FIXTURE_DURATION: Duration = Duration::from_secs(212);
+
+    fn itags_of(streams: YtStreamList) -> Vec<String> {
+        itags(&streams.iter().collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn filter_answers_representative_queries() {
+        let response = fixture_response(&[YtClient::Web]);
+
+        assert_eq!(
+            itags_of(
+                response
+                    .filter()
+                    .mime_contains("mp4")
+                    .video_only()
+                    .max_height(1080)
+                    .collect()
+            ),
+            ["137", "399", "136"]
+        );
+        assert_eq!(
+            itags_of(
+                response
+                    .filter()
+                    .codecs_contain("opus")
+                    .audio_only()
+                    .collect()
+            ),
+            ["251"]
+        );
+        assert_eq!(itags_of(response.filter().muxed().collect()), ["18"]);
+        assert_eq!(
+            itags_of(
+                response
+                    .filter()
+                    .video_only()
+                    .min_height(1080)
+                    .codecs_contain("vp9")
+                    .collect()
+            ),
+            ["248"]
+        );
+        // Only video has a height, so audio never makes it past a height condition.
+        assert_eq!(
+            itags_of(response.filter().max_height(720).collect()),
+            ["18", "136", "247"]
+        );
+        assert_eq!(
+            itags_of(response.filter().max_bitrate(500_000.0).collect()),
+            ["18", "140", "251"]
+        );
+        // Everything is 25 frames a second.
+        assert!(response.filter().min_fps(30).collect().is_empty());
+        assert_eq!(
+            itags_of(
+                response
+                    .filter()
+                    .video_only()
+                    .max_fps(30)
+                    .max_height(720)
+                    .collect()
+            ),
+            ["136", "247"]
+        );
+        assert_eq!(
+            itags_of(response.filter().collect()),
+            ["18", "137", "248", "399", "136", "247", "140", "251"]
+        );
+    }
+
+    #[test]
+    fn filter_by_frame_rate_keeps_the_sixty_fps_streams() {
+        let response = sixty_fps_response();
+
+        assert_eq!(
+            itags_of(
+                response
+                    .filter()
+                    .mime_contains("mp4")
+                    .video_only()
+                    .max_height(1080)
+                    .min_fps(30)
+                    .collect()
+            ),
+            ["616", "299", "298", "135"]
+        );
+        assert_eq!(
+            itags_of(
+                response
+                    .filter()
+                    .video_only()
+                    .min_fps(48)
+                    .max_height(720)
+                    .collect()
+            ),
+            ["298", "302"]
+        );
+    }
+
+    #[test]
+    fn filter_treats_streams_behind_a_signature_the_same() {
+        let response = fixture_response(&[YtClient::Web]);
+        let mut ciphered = fixture_response(&[YtClient::Web]);
+        for stream in ciphered.streams.iter_mut() {
+            let YtStreamSource::URL(url) = &stream.source else {
+                unreachable!()
+            };
+            stream.source = YtStreamSource::Signature(SignatureCipher {
+                s: "AOq0QJ8wRQIhAK".into(),
+                sp: Some("sig".into()),
+                url: url.clone(),
+            });
+        }
+
+        for query in [
+            |r: &YtStreamResponse| r.filter().mime_contains("mp4").video_only().collect(),
+            |r: &YtStreamResponse| r.filter().audio_only().max_bitrate(129_200.0).collect(),
+            |r: &YtStreamResponse| r.filter().muxed().collect(),
+        ] {
+            let expected = query(&response);
+            let filtered = query(&ciphered);
+
+            assert!(!filtered.is_empty());
+            assert!(
+                filtered
+                    .iter()
+                    .all(|s| matches!(s.source, YtStreamSource::Signature(_)))
+            );
+            assert_eq!(itags_of(filtered), itags_of(expected));
+        }
+    }
+
+    #[test]
+    fn size_estimates_are_within_one_percent_of_the_content_length() {
+        let response = fixture_response(&[YtClient::Web]);
+
+        for stream in &response.streams {
+            let content_length = stream.file_size.unwrap();
+            assert_eq!(
+                stream.size_estimate(FIXTURE_DURATION),
+                YtSizeEstimate::Exact(content_length)
+            );
+
+            let without_size = YtStream {
+                file_size: None,
+                ..stream.clone()
+            };
+            let YtSizeEstimate::Approx(estimate) = without_size.size_estimate(FIXTURE_DURATION)
+            else {
+                panic!("itag {:?} has no estimate", stream.itag());
+            };
+            let deviation = (estimate as f64 - content_length as f64).abs() / content_length as f64;
+
+            assert!(
+                deviation < 0.01,
+                "itag {:?} estimated {} for {} bytes",
+                stream.itag(),
+                estimate,
+                content_length
+            );
+        }
+    }
+
+    #[test]
+    fn size_estimate_falls_back_to_the_bitrate_and_is_unknown_for_live_streams() {
+        let response = fixture_response(&[YtClient::Web]);
+        let stream = response
+            .streams
+            .iter()
+            .find(|stream| stream.itag() == Some("140"))
+            .unwrap();
+        let peak_only = YtStream {
+            file_size: None,
+            average_bitrate: None,
+            ..stream.clone()
+        };
+
+        assert_eq!(
+            peak_only.size_estimate(FIXTURE_DURATION),
+            YtSizeEstimate::Approx(130530 * 212 / 8)
+        );
+        assert_eq!(
+            peak_only.size_estimate(Duration::ZERO),
+            YtSizeEstimate::Unknown
+        );
+        assert_eq!(
+            YtStream {
+                bitrate: None,
+                ..peak_only
+            }
+            .size_estimate(FIXTURE_DURATION),
+            YtSizeEstimate::Unknown
+        );
+    }
+
+    #[test]
+    fn total_size_estimate_adds_up_the_best_video_and_audio() {
+        let response = fixture_response(&[YtClient::Web]);
+        let best_sizes = response.best_video().unwrap().file_size.unwrap()
+            + response.best_audio().unwrap().file_size.unwrap();
+
+        assert_eq!(
+            response.total_size_estimate(FIXTURE_DURATION),
+            YtSizeEstimate::Exact(best_sizes)
+        );
+    }
+
+    #[test]
+    fn dash_manifest_of_the_fixture_matches_the_golden_file() {
+        let mpd = fixture_response(&[YtClient::Web])
+            .to_dash_manifest(Duration::from_millis(212091))
+            .unwrap();
+
+        assert_eq!(mpd, include_str!("../tests/fixtures/player_formats.mpd"));
+    }
+
+    #[test]
+    fn streams_with_a_signature_are_refused_in_a_dash_manifest() {
+        let mut response = fixture_response(&[YtClient::Web]);
+        let stream = response
+            .streams
+            .iter_mut()
+            .find(|stream| stream.itag() == Some("251"))
+            .unwrap();
+        stream.source = YtStreamSource::Signature(
+            "s=AOq0QJ8wRQIhAK&sp=sig&url=https%3A%2F%2Frr3---sn-4g5e6nsz.googlevideo.com%2Fvideoplayback%3Fitag%3D251"
+                .parse()
+                .unwrap(),
+        );
+
+        let error = response
+            .to_dash_manifest(Duration::from_millis(212091))
+            .unwrap_err();
+
+        assert!(error.to_string().contains("Stream 251 has a signature"));
+    }
+
+    fn stream_url(url: &str) -> StreamUrl {
+        url.parse().unwrap()
+    }
+
+    #[test]
+    fn range_is_appended_to_a_url_without_one() {
+        let url = stream_url(
+            "https://rr3---sn-8pxuuxa-i5oz.googlevideo.com/videoplayback?itag=18&n=bcdefa&sig=AOq0QJ8w%3D%3D",
+        );
+
+        assert_eq!(
+            url.with_range(0, 1048575).as_str(),
+            "https://rr3---sn-8pxuuxa-i5oz.googlevideo.com/videoplayback?itag=18&n=bcdefa&sig=AOq0QJ8w%3D%3D&range=0-1048575"
+        );
+        assert_eq!(
+            stream_url("https://rr3---sn-8pxuuxa-i5oz.googlevideo.com/videoplayback")
+                .with_range(10, 20)
+                .as_str(),
+            "https://rr3---sn-8pxuuxa-i5oz.googlevideo.com/videoplayback?range=10-20"
+        );
+    }
+
+    #[test]
+    fn existing_range_is_replaced_where_it_is() {
+        let url = stream_url(
+            "https://rr3---sn-8pxuuxa-i5oz.googlevideo.com/videoplayback?itag=137&range=0-99&sig=AOq0QJ8w%3D%3D&n=bcdefa",
+        );
+
+        let url = url.with_range(100, 199);
+        assert_eq!(
+            url.as_str(),
+            "https://rr3---sn-8pxuuxa-i5oz.googlevideo.com/videoplayback?itag=137&range=100-199&sig=AOq0QJ8w%3D%3D&n=bcdefa"
+        );
+
+        // Setting it again doesn't add a second one, and duplicates collapse into the first.
+        assert_eq!(url.clone().with_range(100, 199), url);
+        assert_eq!(
+            stream_url("https://a.googlevideo.com/videoplayback?range=0-1&itag=137&range=2-3")
+                .with_range(4, 5)
+                .as_str(),
+            "https://a.googlevideo.com/videoplayback?range=4-5&itag=137"
+        );
+    }
+
+    #[test]
+    fn segment_is_appended_or_replaced() {
+        let url = stream_url(
+            "https://rr3---sn-8pxuuxa-i5oz.googlevideo.com/videoplayback?itag=137&otf=1&sig=AOq0QJ8w%3D%3D",
+        );
+
+        let url = url.with_segment(0);
+        assert_eq!(
+            url.as_str(),
+            "https://rr3---sn-8pxuuxa-i5oz.googlevideo.com/videoplayback?itag=137&otf=1&sig=AOq0QJ8w%3D%3D&sq=0"
+        );
+        assert_eq!(
+            url.with_segment(12).as_str(),
+            "https://rr3---sn-8pxuuxa-i5oz.googlevideo.com/videoplayback?itag=137&otf=1&sig=AOq0QJ8w%3D%3D&sq=12"
+        );
+    }
+
+    #[test]
+    fn ratebypass_is_appended_or_replaced() {
+        assert_eq!(
+            stream_url("https://a.googlevideo.com/videoplayback?itag=18&sig=AB%2FC&n=bcdefa")
+                .with_ratebypass()
+                .as_str(),
+            "https://a.googlevideo.com/videoplayback?itag=18&sig=AB%2FC&n=bcdefa&ratebypass=yes"
+        );
+        assert_eq!(
+            stream_url("https://a.googlevideo.com/videoplayback?ratebypass=no&itag=18&sig=AB%2FC")
+                .with_ratebypass()
+                .with_range(0, 9)
+                .as_str(),
+            "https://a.googlevideo.com/videoplayback?ratebypass=yes&itag=18&sig=AB%2FC&range=0-9"
+        );
+    }
+
+    #[test]
+    fn encoded_ampersand_in_a_value_is_left_alone() {
+        // The `%26` is part of the value of `title`, not the start of a `range` parameter.
+        let url = stream_url(
+            "https://a.googlevideo.com/videoplayback?title=Rock%26range%3D1-2&sig=AB%26C&n=bcdefa#t=10",
+        );
+
+        assert_eq!(
+            url.with_range(0, 9).with_ratebypass().as_str(),
+            "https://a.googlevideo.com/videoplayback?title=Rock%26range%3D1-2&sig=AB%26C&n=bcdefa&range=0-9&ratebypass=yes#t=10"
+        );
+    }
+
+    fn video_quality(height: u32, fps: Option<u32>, hdr: bool, premium: bool) -> YtQuality {
+        YtQuality::Video {
+            height,
+            fps,
+            hdr,
+            premium,
+        }
+    }
+
+    #[test]
+    fn quality_labels_seen_in_the_wild_parse() {
+        let cases = [
+            ("144p", video_quality(144, None, false, false)),
+            ("144p15", video_quality(144, Some(15), false, false)),
+            ("240p", video_quality(240, None, false, false)),
+            ("360p", video_quality(360, None, false, false)),
+            ("480p", video_quality(480, None, false, false)),
+            ("720p", video_quality(720, None, false, false)),
+            ("720p50", video_quality(720, Some(50), false, false)),
+            ("720p60", video_quality(720, Some(60), false, false)),
+            ("720p60 HDR", video_quality(720, Some(60), true, false)),
+            ("1080p", video_quality(1080, None, false, false)),
+            ("1080p60", video_quality(1080, Some(60), false, false)),
+            ("1080p Premium", video_quality(1080, None, false, true)),
+            ("1080p60 HDR", video_quality(1080, Some(60), true, false)),
+            ("1440p", video_quality(1440, None, false, false)),
+            ("1440p60 HDR", video_quality(1440, Some(60), true, false)),
+            ("2160p", video_quality(2160, None, false, false)),
+            ("2160p60 HDR", video_quality(2160, Some(60), true, false)),
+            ("4320p60", video_quality(4320, Some(60), false, false)),
+        ];
+
+        for (label, quality) in cases {
+            assert_eq!(YtQuality::from(label), quality, "{}", label);
+        }
+    }
+
+    #[test]
+    fn labels_that_arent_a_resolution_are_kept_raw() {
+        for label in [
+            "tiny",
+            "small",
+            "medium",
+            "large",
+            "hd720",
+            "hd1080",
+            "hd2160",
+            "highres",
+            "audio only",
+            "audio_quality_medium",
+            "",
+            "p60",
+            "1080p60 3D",
+            "1080pHDR",
+            "p",
+        ] {
+            assert_eq!(
+                YtQuality::from(label),
+                YtQuality::Raw(label.to_string()),
+                "{}",
+                label
+            );
+        }
+    }
+
+    #[test]
+    fn qualities_order_by_height_then_fps_then_hdr_then_premium() {
+        let ordered = [
+            "audio only",
+            "144p",
+            "240p",
+            "360p",
+            "480p",
+            "720p",
+            "720p60",
+            "720p60 HDR",
+            "1080p",
+            "1080p Premium",
+            "1080p50",
+            "1080p60",
+            "1080p60 HDR",
+            "1440p60",
+            "2160p",
+            "2160p60 HDR",
+            "4320p60",
+        ]
+        .map(YtQuality::from);
+
+        for pair in ordered.windows(2) {
+            assert!(pair[0] < pair[1], "{:?} < {:?}", pair[0], pair[1]);
+        }
+        assert!(YtQuality::from("144p") < YtQuality::from("2160p60 HDR"));
+        assert!(YtQuality::from("tiny") < YtQuality::from("144p"));
+
+        let response = fixture_response(&[YtClient::Web]);
+        let parsed_quality = |itag: &str| {
+            response
+                .streams
+                .iter()
+                .find(|stream| stream.itag() == Some(itag))
+                .and_then(|stream| stream.parsed_quality.clone())
+        };
+        assert_eq!(
+            parsed_quality("137"),
+            Some(video_quality(1080, None, false, false))
+        );
+        assert_eq!(
+            parsed_quality("140"),
+            Some(YtQuality::Raw("audio_quality_medium".into()))
+        );
+    }
+
+    /// A video in HDR up to `2160p60 HDR`, next to SDR `1080p60` and `720p60`.
+    fn hdr_response() -> YtStreamResponse {
+        response_of(
+            include_str!("../tests/fixtures/player_hdr.json"),
+            &[YtClient::Web],
+        )
+    }
+
+    /// A video in SDR at 60 frames a second from `720p60` up, with a `1080p60 Premium` variant.
+    fn sixty_fps_response() -> YtStreamResponse {
+        response_of(
+            include_str!("../tests/fixtures/player_60fps.json"),
+            &[YtClient::Web],
+        )
+    }
+
+    fn stream_with_itag<'a>(response: &'a YtStreamResponse, itag: &str) -> &'a YtStream {
+        response
+            .streams
+            .iter()
+            .find(|stream| stream.itag() == Some(itag))
+            .unwrap()
+    }
+
+    #[test]
+    fn hdr_is_read_from_the_color_info_or_the_quality_label() {
+        let response = hdr_response();
+
+        let hdr = stream_with_itag(&response, "335");
+        assert!(hdr.is_hdr);
+        assert_eq!(
+            hdr.color_info,
+            Some(YtColorInfo {
+                primaries: Some("COLOR_PRIMARIES_BT2020".into()),
+                transfer_characteristics: Some("COLOR_TRANSFER_CHARACTERISTICS_SMPTEST2084".into()),
+                matrix_coefficients: Some("COLOR_MATRIX_COEFFICIENTS_BT2020_NCL".into()),
+            })
+        );
+
+        // `720p60 HDR` came without a `colorInfo`.
+        let labelled = stream_with_itag(&response, "334");
+        assert!(labelled.is_hdr);
+        assert_eq!(labelled.color_info, None);
+
+        let sdr = stream_with_itag(&response, "303");
+        assert!(!sdr.is_hdr);
+        assert_eq!(
+            sdr.color_info.as_ref().unwrap().primaries.as_deref(),
+            Some("COLOR_PRIMARIES_BT709")
+        );
+
+        assert_eq!(
+            itags(
+                &response
+                    .streams
+                    .iter()
+                    .filter(|s| s.is_hdr)
+                    .collect::<Vec<_>>()
+            ),
+            ["337", "336", "335", "334"]
+        );
+        assert!(response.video_only().iter().all(|s| s.is_high_fps()));
+        assert!(!response.streams.iter().any(|s| s.is_premium_bitrate));
+    }
+
+    #[test]
+    fn hybrid_log_gamma_counts_as_hdr() {
+        let hlg = YtColorInfo {
+            primaries: Some("COLOR_PRIMARIES_BT709".into()),
+            transfer_characteristics: Some("COLOR_TRANSFER_CHARACTERISTICS_ARIB_STD_B67".into()),
+            matrix_coefficients: None,
+        };
+
+        assert!(hlg.is_hdr());
+        assert!(!YtColorInfo::default().is_hdr());
+    }
+
+    #[test]
+    fn high_fps_and_premium_are_read_from_a_sixty_fps_video() {
+        let response = sixty_fps_response();
+
+        assert_eq!(
+            itags(
+                &response
+                    .streams
+                    .iter()
+                    .filter(|s| s.is_high_fps())
+                    .collect::<Vec<_>>()
+            ),
+            ["616", "299", "303", "298", "302"]
+        );
+        // 30 frames a second, and audio without any.
+        assert!(!stream_with_itag(&response, "135").is_high_fps());
+        assert!(!stream_with_itag(&response, "140").is_high_fps());
+
+        assert_eq!(
+            itags(
+                &response
+                    .streams
+                    .iter()
+                    .filter(|s| s.is_premium_bitrate)
+                    .collect::<Vec<_>>()
+            ),
+            ["616"]
+        );
+        assert!(!response.streams.iter().any(|s| s.is_hdr));
+    }
+
+    #[test]
+    fn hdr_only_wins_a_tie_when_preferred() {
+        let response = hdr_response();
+        let at_most_1080p = BestStreamOptions {
+            max_height: Some(1080),
+            ..Default::default()
+        };
+
+        // The SDR `avc1` stream has the highest bitrate of the `1080p60` ones.
+        assert_eq!(
+            response.best_video_with(&at_most_1080p).unwrap().itag(),
+            Some("299")
+        );
+        assert_eq!(
+            response
+                .best_video_with(&BestStreamOptions {
+                    prefer_hdr: true,
+                    ..at_most_1080p.clone()
+                })
+                .unwrap()
+                .itag(),
+            Some("335")
+        );
+        // Also among the `720p60` ones, where the HDR stream came without a `colorInfo`.
+        assert_eq!(
+            response
+                .best_video_with(&BestStreamOptions {
+                    max_height: Some(720),
+                    prefer_hdr: true,
+                    ..Default::default()
+                })
+                .unwrap()
+                .itag(),
+            Some("334")
+        );
+    }
+
+    #[test]
+    fn premium_only_wins_a_tie_when_preferred() {
+        let mut response = sixty_fps_response();
+        // Make the `Premium` variant lose on bitrate so that only the preference can rank it first.
+        response
+            .streams
+            .iter_mut()
+            .find(|stream| stream.itag() == Some("616"))
+            .unwrap()
+            .tbr = 1.0;
+
+        assert_eq!(response.best_video().unwrap().itag(), Some("299"));
+        assert_eq!(
+            response
+                .best_video_with(&BestStreamOptions {
+                    prefer_premium: true,
+                    ..Default::default()
+                })
+                .unwrap()
+                .itag(),
+            Some("616")
+        );
+    }
+
+    #[test]
+    fn audio_streams_carry_their_own_loudness() {
+        let quiet = response_of(
+            include_str!("../tests/fixtures/player_quiet.json"),
+            &[YtClient::Web],
+        );
+        let loud = response_of(
+            include_str!("../tests/fixtures/player_loud.json"),
+            &[YtClient::Web],
+        );
+        let quiet = stream_with_itag(&quiet, "251");
+        // Sent as the whole number `6`.
+        let loud = stream_with_itag(&loud, "251");
+
+        assert_eq!(quiet.loudness_db, Some(-8.53));
+        assert_eq!(quiet.normalized_gain(false), Some(1.0));
+        let boost = quiet.normalized_gain(true).unwrap();
+        assert!((boost - 2.6699).abs() < 1e-3, "{}", boost);
+
+        assert_eq!(loud.loudness_db, Some(6.0));
+        let gain = loud.normalized_gain(false).unwrap();
+        assert!((gain - 0.5012).abs() < 1e-3, "{}", gain);
+        assert_eq!(loud.normalized_gain(true), Some(gain));
+
+        // Video streams have no loudness of their own.
+        let response = fixture_response(&[YtClient::Web]);
+        assert_eq!(
+            stream_with_itag(&response, "137").normalized_gain(true),
+            None
+        );
+    }
+
+    /// A video with its original English audio and four dubs, each in `140` and `251`.
+    fn dubbed_response() -> YtStreamResponse {
+        response_of(
+            include_str!("../tests/fixtures/player_dubbed.json"),
+            &[YtClient::Web],
+        )
+    }
+
+    fn audio_track_of(stream: Option<&YtStream>) -> Option<&str> {
+        Some(stream?.audio_track.as_ref()?.id.as_str())
+    }
+
+    #[test]
+    fn audio_tracks_of_a_dubbed_video_are_listed_once_each() {
+        let response = dubbed_response();
+        let tracks = response.audio_tracks();
+
+        assert_eq!(
+            tracks
+                .iter()
+                .map(|track| (track.id.as_str(), track.language.as_deref().unwrap()))
+                .collect::<Vec<_>>(),
+            [
+                ("en.4", "en"),
+                ("es.3", "es"),
+                ("hi.3", "hi"),
+                ("pt-BR.3", "pt-BR"),
+                ("fr-FR.3", "fr-FR"),
+            ]
+        );
+        assert_eq!(tracks[0].display_name, "English original");
+        assert_eq!(
+            tracks
+                .iter()
+                .filter(|track| track.is_default)
+                .map(|track| track.id.as_str())
+                .collect::<Vec<_>>(),
+            ["en.4"]
+        );
+        assert_eq!(response.audio_only().len(), 10);
+        assert!(fixture_response(&[YtClient::Web]).audio_tracks().is_empty());
+    }
+
+    #[test]
+    fn language_is_the_part_of_the_track_id_before_the_dot() {
+        let track = |id: &str| YtAudioTrack::new(id.into(), String::new(), false);
+
+        assert_eq!(track("en.4").language.as_deref(), Some("en"));
+        assert_eq!(track("pt-BR.3").language.as_deref(), Some("pt-BR"));
+        assert_eq!(track("hi").language, None);
+        assert_eq!(track(".4").language, None);
+
+        assert!(track("pt-BR.3").is_in_language("pt"));
+        assert!(track("pt-BR.3").is_in_language("PT-br"));
+        assert!(!track("pt-BR.3").is_in_language("pt-PT"));
+        assert!(!track("en.4").is_in_language("es"));
+    }
+
+    #[test]
+    fn best_audio_picks_the_default_track_unless_a_language_is_asked_for() {
+        let response = dubbed_response();
+        let in_language = |language: &str| {
+            response.best_audio_with(&BestStreamOptions {
+                language: Some(language.into()),
+                ..Default::default()
+            })
+        };
+
+        // The Spanish dub has the highest bitrate, but the original is the default.
+        let best = response.best_audio();
+        assert_eq!(audio_track_of(best), Some("en.4"));
+        assert_eq!(best.unwrap().itag(), Some("251"));
+
+        assert_eq!(audio_track_of(in_language("es")), Some("es.3"));
+        assert_eq!(audio_track_of(in_language("hi")), Some("hi.3"));
+        // Any variant of a language will do.
+        assert_eq!(audio_track_of(in_language("pt")), Some("pt-BR.3"));
+        assert_eq!(audio_track_of(in_language("fr-FR")), Some("fr-FR.3"));
+        // The video isn't dubbed in German, so it's the original.
+        assert_eq!(audio_track_of(in_language("de")), Some("en.4"));
+
+        // The language outranks the codec, which then picks between the formats of the track.
+        let hindi_aac = response
+            .best_audio_with(&BestStreamOptions {
+                language: Some("hi".into()),
+                prefer_codec: Some("mp4a".into()),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(audio_track_of(Some(hindi_aac)), Some("hi.3"));
+        assert_eq!(hindi_aac.itag(), Some("140"));
+    }
+
+    #[test]
+    fn best_video_ranks_by_height_then_fps_then_bitrate() {
+        let response = fixture_response(&[YtClient::Web]);
+        let best_video_with =
+            |options: BestStreamOptions| response.best_video_with(&options).and_then(|s| s.itag());
+
+        // `137` has the highest bitrate of the `1080p` streams.
+        assert_eq!(response.best_video().unwrap().itag(), Some("137"));
+        // The codec outranks everything else.
+        assert_eq!(
+            best_video_with(BestStreamOptions {
+                prefer_codec: Some("av01".into()),
+                ..Default::default()
+            }),
+            Some("399")
+        );
+        assert_eq!(
+            best_video_with(BestStreamOptions {
+                prefer_codec: Some("avc1".into()),
+                max_height: Some(720),
+                ..Default::default()
+            }),
+            Some("136")
+        );
+        // `136` and `247` tie on everything, which goes to the later one.
+        assert_eq!(
+            best_video_with(BestStreamOptions {
+                max_height: Some(720),
+                ..Default::default()
+            }),
+            Some("247")
+        );
+        assert_eq!(
+            best_video_with(BestStreamOptions {
+                max_height: Some(480),
+                ..Default::default()
+            }),
+            None
+        );
+
+        // A higher frame rate beats a higher bitrate, and a taller video beats both.
+        let sixty_fps = sixty_fps_response();
+        let mut fast_1080p25 = stream_with_itag(&response, "137").clone();
+        fast_1080p25.tbr = 20_000_000.0;
+        let mixed = YtStreamResponse::new(
+            String::new(),
+            vec![
+                fast_1080p25,
+                stream_with_itag(&sixty_fps, "303").clone(),
+                stream_with_itag(&sixty_fps, "298").clone(),
+            ],
+        );
+        assert_eq!(mixed.best_video().unwrap().itag(), Some("303"));
+        assert_eq!(
+            mixed
+                .best_video_with(&BestStreamOptions {
+                    max_height: Some(720),
+                    ..Default::default()
+                })
+                .unwrap()
+                .itag(),
+            Some("298")
+        );
+    }
+
+    #[test]
+    fn best_muxed_only_looks_at_muxed_streams() {
+        let response = fixture_response(&[YtClient::Web]);
+
+        assert_eq!(response.best_muxed().unwrap().itag(), Some("18"));
+        assert!(
+            response
+                .best_muxed_with(&BestStreamOptions {
+                    max_height: Some(240),
+                    ..Default::default()
+                })
+                .is_none()
+        );
+        assert!(hdr_response().best_muxed().is_none());
+        assert_eq!(
+            sixty_fps_response().best_muxed().unwrap().itag(),
+            Some("18")
+        );
+    }
+
+    #[test]
+    fn best_audio_ranks_by_bitrate_and_not_by_drc() {
+        let response = fixture_response(&[YtClient::Web]);
+
+        // `140` has a slightly higher average bitrate than `251`.
+        assert_eq!(response.best_audio().unwrap().itag(), Some("140"));
+        assert_eq!(
+            response
+                .best_audio_with(&BestStreamOptions {
+                    prefer_codec: Some("opus".into()),
+                    ..Default::default()
+                })
+                .unwrap()
+                .itag(),
+            Some("251")
+        );
+
+        // The DRC variant has the highest bitrate of all, and still loses.
+        let drc = drc_response();
+        let highest_bitrate = drc
+            .audio_only()
+            .into_iter()
+            .max_by(|a, b| compare_tbr(a, b))
+            .unwrap();
+        assert!(highest_bitrate.is_drc);
+        assert!(!drc.best_audio().unwrap().is_drc);
+    }
+
+    #[test]
+    fn best_streams_are_picked_before_deciphering() {
+        let mut response = fixture_response(&[YtClient::Web]);
+        for stream in response.streams.iter_mut() {
+            let YtStreamSource::URL(url) = &stream.source else {
+                unreachable!()
+            };
+            stream.source = YtStreamSource::Signature(SignatureCipher {
+                s: "AOq0QJ8wRQIhAK".into(),
+                sp: Some("sig".into()),
+                url: url.clone(),
+            });
+        }
+
+        assert_eq!(response.best_video().unwrap().itag(), Some("137"));
+        assert_eq!(response.best_audio().unwrap().itag(), Some("140"));
+        assert_eq!(response.best_muxed().unwrap().itag(), Some("18"));
+    }
+
+    fn drc_response() -> YtStreamResponse {
+        response_of(
+            include_str!("../tests/fixtures/player_drc_audio.json"),
+            &[YtClient::Web],
+        )
+    }
+
+    /// The `xtags` of the stream, which are distinct for each of the 251 streams of `player_drc_audio.json`.
+    fn xtags_of(stream: Option<&YtStream>) -> Option<&str> {
+        stream.and_then(|stream| stream.xtags.as_deref())
+    }
+
+    #[test]
+    fn audio_only_keeps_the_drc_duplicates() {
+        let response = drc_response();
+        let audio = response.audio_only();
+
+        // Two tracks, each with a regular and a DRC variant sharing the `itag`.
+        assert_eq!(audio.len(), 4);
+        assert!(audio.iter().all(|s| s.itag() == Some("251")));
+        assert_eq!(audio.iter().filter(|s| s.is_drc).count(), 2);
+        // Views of `streams` rather than copies, in their order.
+        for (stream, view) in response
+            .streams
+            .iter()
+            .filter(|s| s.is_audio_only())
+            .zip(&audio)
+        {
+            assert!(std::ptr::eq(stream, *view));
+        }
+        // `best_audio` is where they're told apart.
+        assert!(!response.best_audio().unwrap().is_drc);
+    }
+
+    #[test]
+    fn video_only_and_muxed_split_the_video_streams() {
+        let response = fixture_response(&[YtClient::Web]);
+
+        assert_eq!(
+            itags(&response.video_only()),
+            ["137", "248", "399", "136", "247"]
+        );
+        assert_eq!(itags(&response.muxed()), ["18"]);
+        assert_eq!(itags(&response.audio_only()), ["140", "251"]);
+        // Both agree with the filter.
+        assert_eq!(
+            itags(&response.video_only()),
+            itags_of(response.filter().video_only().collect())
+        );
+        assert_eq!(
+            itags(&response.audio_only()),
+            itags_of(response.filter().audio_only().collect())
+        );
+    }
+
+    #[test]
+    fn drc_is_read_from_is_drc_and_xtags() {
+        let response = drc_response();
+        let drc: Vec<(Option<&str>, bool)> = response
+            .streams
+            .iter()
+            .map(|stream| (stream.xtag("lang"), stream.is_drc))
+            .collect();
+
+        // The Spanish DRC stream only says so in its `xtags`.
+        assert_eq!(
+            drc,
+            [
+                (Some("en-US"), true),
+                (Some("en-US"), false),
+                (Some("es-ES"), false),
+                (Some("es-ES"), true),
+            ]
+        );
+        assert_eq!(
+            response.streams[0].xtags.as_deref(),
+            Some("acont=original:drc=1:lang=en-US")
+        );
+        assert_eq!(response.streams[0].xtag("acont"), Some("original"));
+        assert_eq!(response.streams[1].xtag("drc"), None);
+    }
+
+    #[test]
+    fn best_audio_skips_drc_unless_asked_for() {
+        let response = drc_response();
+
+        // The DRC streams have the higher bitrate, which would win otherwise.
+        assert_eq!(
+            xtags_of(response.best_audio()),
+            Some("acont=original:lang=en-US")
+        );
+        assert_eq!(
+            xtags_of(response.best_audio_with(&BestStreamOptions {
+                prefer_drc: true,
+                ..Default::default()
+            })),
+            Some("acont=original:drc=1:lang=en-US")
+        );
+        assert_eq!(
+            xtags_of(response.best_audio_with(&BestStreamOptions {
+                language: Some("es".into()),
+                ..Default::default()
+            })),
+            Some("acont=dubbed:lang=es-ES")
+        );
+        assert_eq!(
+            xtags_of(response.best_audio_with(&BestStreamOptions {
+                language: Some("es".into()),
+                prefer_drc: true,
+                ..Default::default()
+            })),
+            Some("acont=dubbed:drc=1:lang=es-ES")
+        );
+    }
+
+    #[test]
+    fn audio_tracks_dont_list_drc_variants_again() {
+        let response = drc_response();
+        let tracks: Vec<&str> = response
+            .audio_tracks()
+            .iter()
+            .map(|track| track.id.as_str())
+            .collect();
+
+        assert_eq!(tracks, ["en-US.4", "es-ES.3"]);
+        assert_eq!(response.audio_only().len(), 4);
+    }
 }
@@ -0,0 +1,7 @@
+var _yt_player={};(function(g){var window=this;
+var Zq="split;reverse;splice;join;".split(";");
+var Wd={Rt:function(a){a[Zq[1]]()},
+Pm:function(a,b){a[Zq[2]](0,b)}};
+Jh=function(a){a=a[Zq[0]](Zq[4]);Wd.Rt(a,3);Wd.Pm(a,1);return a[Zq[3]](Zq[4])};
+g.Vr=function(a,b,c,d){c&&d.set(b,encodeURIComponent(Jh(c)));return d};
+})(_yt_player);
@@ -0,0 +1,5 @@
+var _yt_player={};(function(g){var window=this;
+var Pq={Lk:function(a,b){a.splice(0,b)}};
+Mq=function(z,w){z=z.split("");w(z);Pq.Lk(z,2);return z.join("")};
+g.Hx=function(a,b){return Mq(a,b)};
+})(_yt_player);
@@ -0,0 +1,7 @@
+var _yt_player={};(function(g){var window=this;
+var XY={ab:function(a,b){a.splice(0,b)},
+cd:function(a){a.reverse()},
+ef:function(a,b){var c=a[0];a[0]=a[b%a.length];a[b%a.length]=c}};
+Kx=function(a){a=a.split("");XY.cd(a,1);XY.ef(a,3);XY.ab(a,2);return a.join("")};
+g.Qz=function(a,b,c){c&&(c=Kx(decodeURIComponent(c)));a.set(b,encodeURIComponent(c));return a};
+})(_yt_player);
@@ -0,0 +1,9 @@
+var _yt_player={};(function(g){var window=this;
+var Tk={Vw:function(a,b){var c=a[0];a[0]=a[b%a.length];a[b%a.length]=c},
+Xy:function(a){a.reverse()}};
+Yb=function(a){var b=Array.from(a);Tk.Xy(b);Tk.Vw(b,2);return b.join("")};
+var Nq=[Ow];
+function Ow(a){var b=a.split("");b.push(b.shift());return b.join("")}
+g.Fm=function(h){var m;m=Yb(decodeURIComponent(h.s));return m};
+g.Gn=function(a){var b;(b=a.get("n"))&&(b=Nq[0](b),a.set("n",b));return a};
+})(_yt_player);